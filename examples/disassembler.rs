@@ -0,0 +1,15 @@
+use noak::disassembler::disassemble;
+use noak::reader::Class;
+
+fn main() {
+    let path = std::env::args().nth(1).expect("usage: `disassembler MyClass.class`");
+    let bytes = std::fs::read(&path).expect("could not read file");
+
+    match Class::new(&bytes).and_then(|class| disassemble(&class)) {
+        Ok(text) => print!("{}", text),
+        Err(err) => {
+            eprintln!("Error in file `{}`: {}", path, err);
+            std::process::exit(1);
+        }
+    }
+}
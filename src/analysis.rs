@@ -0,0 +1,12 @@
+//! Analyses of the code of methods.
+
+mod cfg;
+mod dominators;
+mod frames;
+pub(crate) mod interpreter;
+mod loops;
+
+pub use cfg::*;
+pub use dominators::*;
+pub use frames::*;
+pub use loops::*;
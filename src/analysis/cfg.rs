@@ -0,0 +1,447 @@
+use crate::error::*;
+use crate::reader::attributes::{target, Code, Index, RawInstruction};
+use crate::reader::cpool;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The control flow graph of a code attribute.
+///
+/// The blocks partition the code, so unreachable code forms blocks without predecessors.
+/// A block ends after an instruction which transfers control elsewhere, before every jump target
+/// and at the bounds of every exception handler range, so that each block is either protected
+/// by a handler or not at all.
+///
+/// # Examples
+/// ```
+/// use noak::analysis::ControlFlowGraph;
+/// use noak::reader::attributes::AttributeContent;
+/// use noak::reader::Class;
+///
+/// let bytes = noak::assembler::assemble(concat!(
+///     ".class public super A\n",
+///     ".super java/lang/Object\n",
+///     ".method static abs (I)I\n",
+///     "    .limit stack 1\n",
+///     "    .limit locals 1\n",
+///     "    iload_0\n",
+///     "    ifge done\n",
+///     "    iload_0\n",
+///     "    ineg\n",
+///     "    ireturn\n",
+///     "done:\n",
+///     "    iload_0\n",
+///     "    ireturn\n",
+///     ".end method\n",
+/// ))?;
+/// let class = Class::new(&bytes)?;
+/// let method = class.methods().iter().next().unwrap()?;
+/// let attribute = method.attributes().iter().next().unwrap()?;
+/// let AttributeContent::Code(code) = attribute.read_content(class.pool())? else {
+///     unreachable!();
+/// };
+///
+/// let cfg = ControlFlowGraph::new(&code)?;
+/// assert_eq!(cfg.blocks().count(), 3);
+/// assert_eq!(cfg.entry().successors().len(), 2);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ControlFlowGraph<'input> {
+    blocks: BTreeMap<Index, BasicBlock<'input>>,
+}
+
+impl<'input> ControlFlowGraph<'input> {
+    /// Builds the graph of a code attribute.
+    ///
+    /// An error is returned if the code can't be decoded, if it is empty, if a jump target or the bounds
+    /// of an exception handler aren't at the start of an instruction or if execution can fall off the end of the code.
+    pub fn new(code: &Code<'input>) -> Result<ControlFlowGraph<'input>, DecodeError> {
+        let instructions = code.raw_instructions().collect::<Result<Vec<_>, _>>()?;
+        if instructions.is_empty() {
+            return Err(invalid());
+        }
+        let is_start = |index: Index| instructions.binary_search_by_key(&index, |(i, _)| *i).is_ok();
+        let code_end = Index::new(code.code_length());
+
+        let mut leaders = BTreeSet::new();
+        leaders.insert(instructions[0].0);
+        for (i, (position, instruction)) in instructions.iter().enumerate() {
+            for target in jump_targets(*position, instruction)? {
+                if !is_start(target) {
+                    return Err(invalid());
+                }
+                leaders.insert(target);
+            }
+            if instruction.ends_block() {
+                if let Some((next, _)) = instructions.get(i + 1) {
+                    leaders.insert(*next);
+                }
+            }
+        }
+
+        let handlers = code.exception_handlers().collect::<Vec<_>>();
+        for handler in &handlers {
+            if handler.start() >= handler.end()
+                || !is_start(handler.start())
+                || !(is_start(handler.end()) || handler.end() == code_end)
+                || !is_start(handler.handler())
+            {
+                return Err(invalid());
+            }
+            leaders.insert(handler.start());
+            leaders.insert(handler.handler());
+            if handler.end() != code_end {
+                leaders.insert(handler.end());
+            }
+        }
+
+        let mut blocks = BTreeMap::new();
+        let mut rets = Vec::new();
+        // the return sites of the `jsr` instructions calling each subroutine
+        let mut subroutines: BTreeMap<Index, Vec<Index>> = BTreeMap::new();
+        let mut current = Vec::new();
+        for (i, (position, instruction)) in instructions.iter().enumerate() {
+            current.push(*position);
+            let next = instructions.get(i + 1).map(|(next, _)| *next);
+            if next.is_some_and(|next| !leaders.contains(&next)) {
+                continue;
+            }
+
+            let start = current[0];
+            let mut successors = Vec::new();
+            let kind = match instruction {
+                RawInstruction::LookupSwitch(_) | RawInstruction::TableSwitch(_) => EdgeKind::Switch,
+                RawInstruction::JSr { .. } | RawInstruction::JSrW { .. } => EdgeKind::Jsr,
+                _ => EdgeKind::Branch,
+            };
+            for target in jump_targets(*position, instruction)? {
+                push_edge(&mut successors, Edge::new(start, target, kind));
+            }
+            match instruction {
+                RawInstruction::JSr { .. } | RawInstruction::JSrW { .. } => {
+                    if let Some(next) = next {
+                        for target in jump_targets(*position, instruction)? {
+                            subroutines.entry(target).or_default().push(next);
+                        }
+                    }
+                }
+                RawInstruction::Ret { .. } | RawInstruction::RetW { .. } => rets.push(start),
+                _ if instruction.falls_through() => match next {
+                    Some(next) => push_edge(&mut successors, Edge::new(start, next, EdgeKind::FallThrough)),
+                    None => return Err(invalid()),
+                },
+                _ => {}
+            }
+
+            let end = next.unwrap_or(code_end);
+            blocks.insert(
+                start,
+                BasicBlock {
+                    start,
+                    end,
+                    instructions: std::mem::take(&mut current),
+                    successors,
+                    predecessors: Vec::new(),
+                },
+            );
+        }
+
+        let mut graph = ControlFlowGraph { blocks };
+        for (subroutine, return_sites) in &subroutines {
+            for ret in graph.subroutine_blocks(*subroutine) {
+                if !rets.contains(&ret) {
+                    continue;
+                }
+                let successors = &mut graph.blocks.get_mut(&ret).unwrap().successors;
+                for return_site in return_sites {
+                    push_edge(successors, Edge::new(ret, *return_site, EdgeKind::Ret));
+                }
+            }
+        }
+
+        for handler in &handlers {
+            let kind = EdgeKind::Exception {
+                catch_type: handler.catch_type(),
+            };
+            for block in graph
+                .blocks
+                .range_mut(handler.start()..handler.end())
+                .map(|(_, block)| block)
+            {
+                push_edge(&mut block.successors, Edge::new(block.start, handler.handler(), kind));
+            }
+        }
+
+        let edges: Vec<Edge<'input>> = graph
+            .blocks
+            .values()
+            .flat_map(|block| block.successors.iter().copied())
+            .collect();
+        for edge in edges {
+            graph.blocks.get_mut(&edge.target).unwrap().predecessors.push(edge);
+        }
+
+        Ok(graph)
+    }
+
+    /// Returns the blocks which are reachable from the start of a subroutine without leaving it,
+    /// treating calls of other subroutines as if they returned immediately.
+    fn subroutine_blocks(&self, subroutine: Index) -> Vec<Index> {
+        let mut visited = BTreeSet::new();
+        let mut stack = vec![subroutine];
+        while let Some(start) = stack.pop() {
+            if !visited.insert(start) {
+                continue;
+            }
+            let block = &self.blocks[&start];
+            for edge in &block.successors {
+                match edge.kind {
+                    EdgeKind::FallThrough | EdgeKind::Branch | EdgeKind::Switch => stack.push(edge.target),
+                    EdgeKind::Jsr if self.blocks.contains_key(&block.end) => stack.push(block.end),
+                    _ => {}
+                }
+            }
+        }
+        visited.into_iter().collect()
+    }
+
+    /// Returns the block at the start of the code.
+    #[must_use]
+    pub fn entry(&self) -> &BasicBlock<'input> {
+        self.blocks.values().next().unwrap()
+    }
+
+    /// Returns all blocks, ordered by their position in the code.
+    pub fn blocks(&self) -> impl Iterator<Item = &BasicBlock<'input>> {
+        self.blocks.values()
+    }
+
+    /// Returns the block starting at an index.
+    #[must_use]
+    pub fn block(&self, start: Index) -> Option<&BasicBlock<'input>> {
+        self.blocks.get(&start)
+    }
+
+    /// Returns the block containing the instruction at an index.
+    #[must_use]
+    pub fn block_containing(&self, index: Index) -> Option<&BasicBlock<'input>> {
+        self.blocks
+            .range(..=index)
+            .next_back()
+            .map(|(_, block)| block)
+            .filter(|block| index < block.end)
+    }
+}
+
+/// A sequence of instructions which is only entered at its first instruction.
+#[derive(Debug, Clone)]
+pub struct BasicBlock<'input> {
+    start: Index,
+    end: Index,
+    instructions: Vec<Index>,
+    successors: Vec<Edge<'input>>,
+    predecessors: Vec<Edge<'input>>,
+}
+
+impl<'input> BasicBlock<'input> {
+    /// The index of the first instruction, which identifies the block.
+    #[must_use]
+    pub fn start(&self) -> Index {
+        self.start
+    }
+
+    /// The index after the last instruction.
+    #[must_use]
+    pub fn end(&self) -> Index {
+        self.end
+    }
+
+    /// The indices of the instructions in the block.
+    #[must_use]
+    pub fn instructions(&self) -> &[Index] {
+        &self.instructions
+    }
+
+    /// The edges leaving this block, with the normal ones before the exceptional ones.
+    #[must_use]
+    pub fn successors(&self) -> &[Edge<'input>] {
+        &self.successors
+    }
+
+    /// The edges entering this block.
+    #[must_use]
+    pub fn predecessors(&self) -> &[Edge<'input>] {
+        &self.predecessors
+    }
+}
+
+/// A transfer of control from one block to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge<'input> {
+    source: Index,
+    target: Index,
+    kind: EdgeKind<'input>,
+}
+
+impl<'input> Edge<'input> {
+    fn new(source: Index, target: Index, kind: EdgeKind<'input>) -> Edge<'input> {
+        Edge { source, target, kind }
+    }
+
+    /// The start of the block the edge leaves.
+    #[must_use]
+    pub fn source(&self) -> Index {
+        self.source
+    }
+
+    /// The start of the block the edge enters.
+    #[must_use]
+    pub fn target(&self) -> Index {
+        self.target
+    }
+
+    /// How control is transferred.
+    #[must_use]
+    pub fn kind(&self) -> EdgeKind<'input> {
+        self.kind
+    }
+}
+
+/// The ways in which control can be transferred between blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind<'input> {
+    /// Execution continues with the next instruction.
+    FallThrough,
+    /// A `goto` or a taken conditional branch.
+    Branch,
+    /// A case or the default of a `lookupswitch` or `tableswitch`.
+    Switch,
+    /// A `jsr` enters a subroutine.
+    ///
+    /// The instruction after the `jsr` is reached through [`Ret`](EdgeKind::Ret) edges instead of falling through.
+    Jsr,
+    /// A `ret` returns to the instruction after a `jsr` calling its subroutine.
+    Ret,
+    /// An exception thrown in the block is caught by a handler.
+    Exception {
+        /// The caught class, or `None` if the handler catches every exception.
+        catch_type: Option<cpool::Index<cpool::Class<'input>>>,
+    },
+}
+
+fn push_edge<'input>(edges: &mut Vec<Edge<'input>>, edge: Edge<'input>) {
+    if !edges.contains(&edge) {
+        edges.push(edge);
+    }
+}
+
+fn jump_targets(position: Index, instruction: &RawInstruction<'_>) -> Result<Vec<Index>, DecodeError> {
+    use RawInstruction::*;
+
+    let targets = match instruction {
+        Goto { offset }
+        | JSr { offset }
+        | IfACmpEq { offset }
+        | IfACmpNe { offset }
+        | IfICmpEq { offset }
+        | IfICmpNe { offset }
+        | IfICmpLt { offset }
+        | IfICmpGe { offset }
+        | IfICmpGt { offset }
+        | IfICmpLe { offset }
+        | IfEq { offset }
+        | IfNe { offset }
+        | IfLt { offset }
+        | IfGe { offset }
+        | IfGt { offset }
+        | IfLe { offset }
+        | IfNonNull { offset }
+        | IfNull { offset } => vec![target(position, i32::from(*offset))?],
+        GotoW { offset } | JSrW { offset } => vec![target(position, *offset)?],
+        LookupSwitch(lookupswitch) => {
+            let mut targets = vec![target(position, lookupswitch.default_offset())?];
+            for pair in lookupswitch.pairs() {
+                targets.push(target(position, pair.offset())?);
+            }
+            targets
+        }
+        TableSwitch(tableswitch) => {
+            let mut targets = vec![target(position, tableswitch.default_offset())?];
+            for pair in tableswitch.pairs() {
+                targets.push(target(position, pair.offset())?);
+            }
+            targets
+        }
+        _ => Vec::new(),
+    };
+    Ok(targets)
+}
+
+fn invalid() -> DecodeError {
+    DecodeError::with_context(DecodeErrorKind::InvalidInstruction, Context::Code)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assembler::assemble;
+    use crate::reader::attributes::AttributeContent;
+    use crate::reader::Class;
+
+    #[test]
+    fn subroutines_and_handlers() {
+        let bytes = assemble(concat!(
+            ".version 49 0\n",
+            ".class public super A\n",
+            ".super java/lang/Object\n",
+            ".method static m ()V\n",
+            "    .limit stack 1\n",
+            "    .limit locals 2\n",
+            "a:  invokestatic A/f()V\n",
+            "b:  jsr f\n",
+            "    return\n",
+            "h:  astore_0\n",
+            "    jsr f\n",
+            "    aload_0\n",
+            "    athrow\n",
+            "f:  astore_1\n",
+            "    invokestatic A/g()V\n",
+            "    ret 1\n",
+            "    .catch all from a to b using h\n",
+            ".end method\n",
+        ))
+        .unwrap();
+        let class = Class::new(&bytes).unwrap();
+        let method = class.methods().iter().next().unwrap().unwrap();
+        let attribute = method.attributes().iter().next().unwrap().unwrap();
+        let Ok(AttributeContent::Code(code)) = attribute.read_content(class.pool()) else {
+            panic!("expected a code attribute");
+        };
+
+        let cfg = ControlFlowGraph::new(&code).unwrap();
+        let successors = |start: u32| {
+            cfg.block(Index::new(start))
+                .unwrap()
+                .successors()
+                .iter()
+                .map(|edge| (edge.target().as_u32(), edge.kind()))
+                .collect::<Vec<_>>()
+        };
+        let starts = cfg.blocks().map(|block| block.start().as_u32()).collect::<Vec<_>>();
+        assert_eq!(starts, [0, 3, 6, 7, 11, 13]);
+        assert_eq!(
+            successors(0),
+            [
+                (3, EdgeKind::FallThrough),
+                (7, EdgeKind::Exception { catch_type: None })
+            ]
+        );
+        assert_eq!(successors(3), [(13, EdgeKind::Jsr)]);
+        assert_eq!(successors(6), []);
+        assert_eq!(successors(7), [(13, EdgeKind::Jsr)]);
+        assert_eq!(successors(11), []);
+        assert_eq!(successors(13), [(6, EdgeKind::Ret), (11, EdgeKind::Ret)]);
+        assert_eq!(cfg.block(Index::new(13)).unwrap().predecessors().len(), 2);
+        assert_eq!(cfg.block_containing(Index::new(15)).unwrap().start().as_u32(), 13);
+        assert!(cfg.block_containing(Index::new(19)).is_none());
+    }
+}
@@ -0,0 +1,275 @@
+use crate::analysis::ControlFlowGraph;
+use crate::reader::attributes::Index;
+use std::collections::BTreeMap;
+
+/// A dominator or post-dominator tree of the blocks of a [`ControlFlowGraph`], together with the dominance frontiers.
+///
+/// A block dominates another one if every path from the entry to the other block passes through it,
+/// and it post-dominates another one if every path from the other block to an exit passes through it.
+/// In a post-dominator tree, all methods talk about post-dominators instead of dominators.
+///
+/// Blocks are identified by their start. Blocks which can't be reached from the entry, or which can't reach an exit
+/// in case of post-dominators, are not part of the tree. Exceptional edges are treated like normal ones.
+#[derive(Debug, Clone)]
+pub struct DominatorTree {
+    nodes: BTreeMap<Index, Node>,
+    roots: Vec<Index>,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    parent: Option<Index>,
+    children: Vec<Index>,
+    frontier: Vec<Index>,
+}
+
+impl DominatorTree {
+    /// Computes the dominator tree, which has the entry as its only root.
+    #[must_use]
+    pub fn dominators(cfg: &ControlFlowGraph<'_>) -> DominatorTree {
+        let successors = cfg
+            .blocks()
+            .map(|block| block.successors().iter().map(|edge| edge.target()).collect())
+            .collect();
+        DominatorTree::compute(cfg, successors, vec![cfg.entry().start()])
+    }
+
+    /// Computes the post-dominator tree.
+    ///
+    /// The exits are the blocks without successors, which end by returning or by throwing an exception
+    /// that isn't caught. Each of them is a root of the tree.
+    #[must_use]
+    pub fn post_dominators(cfg: &ControlFlowGraph<'_>) -> DominatorTree {
+        let predecessors = cfg
+            .blocks()
+            .map(|block| block.predecessors().iter().map(|edge| edge.source()).collect())
+            .collect();
+        let exits = cfg
+            .blocks()
+            .filter(|block| block.successors().is_empty())
+            .map(|block| block.start())
+            .collect();
+        DominatorTree::compute(cfg, predecessors, exits)
+    }
+
+    /// Implements "A Simple, Fast Dominance Algorithm" by Cooper, Harvey and Kennedy,
+    /// with a virtual root whose successors are the roots.
+    fn compute(cfg: &ControlFlowGraph<'_>, successors: Vec<Vec<Index>>, roots: Vec<Index>) -> DominatorTree {
+        let blocks: Vec<Index> = cfg.blocks().map(|block| block.start()).collect();
+        let id = |index: &Index| blocks.binary_search(index).unwrap();
+        let root = blocks.len();
+        let mut successors: Vec<Vec<usize>> = successors
+            .iter()
+            .map(|targets| targets.iter().map(id).collect())
+            .collect();
+        successors.push(roots.iter().map(id).collect());
+
+        let mut postorder = Vec::new();
+        let mut order = vec![usize::MAX; root + 1];
+        let mut visited = vec![false; root + 1];
+        visited[root] = true;
+        let mut stack = vec![(root, 0)];
+        while let Some((node, next)) = stack.pop() {
+            if let Some(&successor) = successors[node].get(next) {
+                stack.push((node, next + 1));
+                if !visited[successor] {
+                    visited[successor] = true;
+                    stack.push((successor, 0));
+                }
+            } else {
+                order[node] = postorder.len();
+                postorder.push(node);
+            }
+        }
+
+        let mut predecessors = vec![Vec::new(); root + 1];
+        for &node in &postorder {
+            for &successor in &successors[node] {
+                if !predecessors[successor].contains(&node) {
+                    predecessors[successor].push(node);
+                }
+            }
+        }
+
+        let mut idom = vec![None; root + 1];
+        idom[root] = Some(root);
+        let intersect = |idom: &[Option<usize>], mut a: usize, mut b: usize| {
+            while a != b {
+                while order[a] < order[b] {
+                    a = idom[a].unwrap();
+                }
+                while order[b] < order[a] {
+                    b = idom[b].unwrap();
+                }
+            }
+            a
+        };
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &node in postorder.iter().rev().skip(1) {
+                let mut new = None;
+                for &predecessor in &predecessors[node] {
+                    if idom[predecessor].is_some() {
+                        new = Some(match new {
+                            Some(current) => intersect(&idom, predecessor, current),
+                            None => predecessor,
+                        });
+                    }
+                }
+                if idom[node] != new {
+                    idom[node] = new;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut frontiers = vec![Vec::new(); root];
+        for &node in &postorder {
+            if node == root || predecessors[node].len() < 2 {
+                continue;
+            }
+            for &predecessor in &predecessors[node] {
+                let mut runner = predecessor;
+                while Some(runner) != idom[node] {
+                    if !frontiers[runner].contains(&node) {
+                        frontiers[runner].push(node);
+                    }
+                    runner = idom[runner].unwrap();
+                }
+            }
+        }
+
+        let mut nodes: BTreeMap<Index, Node> = BTreeMap::new();
+        let mut tree_roots = Vec::new();
+        for node in (0..root).filter(|&node| visited[node]) {
+            let parent = idom[node].filter(|&parent| parent != root).map(|parent| blocks[parent]);
+            let mut frontier: Vec<Index> = frontiers[node].iter().map(|&node| blocks[node]).collect();
+            frontier.sort();
+            nodes.insert(
+                blocks[node],
+                Node {
+                    parent,
+                    children: Vec::new(),
+                    frontier,
+                },
+            );
+        }
+        let parents: Vec<(Index, Option<Index>)> = nodes.iter().map(|(&block, node)| (block, node.parent)).collect();
+        for (block, parent) in parents {
+            match parent {
+                Some(parent) => nodes.get_mut(&parent).unwrap().children.push(block),
+                None => tree_roots.push(block),
+            }
+        }
+
+        DominatorTree {
+            nodes,
+            roots: tree_roots,
+        }
+    }
+
+    /// The blocks without an immediate dominator.
+    #[must_use]
+    pub fn roots(&self) -> &[Index] {
+        &self.roots
+    }
+
+    /// Whether a block is part of the tree.
+    #[must_use]
+    pub fn contains(&self, block: Index) -> bool {
+        self.nodes.contains_key(&block)
+    }
+
+    /// Returns the immediate dominator of a block, which is its parent in the tree.
+    #[must_use]
+    pub fn immediate_dominator(&self, block: Index) -> Option<Index> {
+        self.nodes.get(&block)?.parent
+    }
+
+    /// Returns the blocks which a block immediately dominates, ordered by their position in the code.
+    #[must_use]
+    pub fn children(&self, block: Index) -> &[Index] {
+        self.nodes.get(&block).map_or(&[], |node| &node.children)
+    }
+
+    /// Whether the first block dominates the second one. Every block in the tree dominates itself.
+    #[must_use]
+    pub fn dominates(&self, dominator: Index, block: Index) -> bool {
+        let mut current = Some(block);
+        while let Some(block) = current {
+            if block == dominator {
+                return self.contains(block);
+            }
+            current = self.immediate_dominator(block);
+        }
+        false
+    }
+
+    /// Returns the dominance frontier of a block, ordered by position.
+    ///
+    /// These are the blocks which the block doesn't strictly dominate, but which have a predecessor it dominates.
+    /// For post-dominators, these are the blocks whose branches decide whether the block is executed.
+    #[must_use]
+    pub fn frontier(&self, block: Index) -> &[Index] {
+        self.nodes.get(&block).map_or(&[], |node| &node.frontier)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assembler::assemble;
+    use crate::reader::attributes::AttributeContent;
+    use crate::reader::Class;
+
+    #[test]
+    fn nested_loops() {
+        let bytes = assemble(concat!(
+            ".class public super A\n",
+            ".super java/lang/Object\n",
+            ".method static m (I)V\n",
+            "    .limit stack 2\n",
+            "    .limit locals 2\n",
+            "    iconst_0\n",
+            "    istore_1\n",
+            "a:  iinc 1 1\n",
+            "b:  iinc 0 -1\n",
+            "    iload_0\n",
+            "    ifgt b\n",
+            "    iload_1\n",
+            "    bipush 10\n",
+            "    if_icmplt a\n",
+            "    return\n",
+            ".end method\n",
+        ))
+        .unwrap();
+        let class = Class::new(&bytes).unwrap();
+        let method = class.methods().iter().next().unwrap().unwrap();
+        let attribute = method.attributes().iter().next().unwrap().unwrap();
+        let Ok(AttributeContent::Code(code)) = attribute.read_content(class.pool()) else {
+            panic!("expected a code attribute");
+        };
+        let cfg = ControlFlowGraph::new(&code).unwrap();
+        let starts = |indices: &[Index]| indices.iter().map(|index| index.as_u32()).collect::<Vec<_>>();
+
+        let dominators = DominatorTree::dominators(&cfg);
+        assert_eq!(starts(dominators.roots()), [0]);
+        assert_eq!(dominators.immediate_dominator(Index::new(12)), Some(Index::new(5)));
+        assert_eq!(starts(dominators.children(Index::new(2))), [5]);
+        assert!(dominators.dominates(Index::new(2), Index::new(18)));
+        assert!(!dominators.dominates(Index::new(18), Index::new(2)));
+        assert_eq!(starts(dominators.frontier(Index::new(2))), [2]);
+        assert_eq!(starts(dominators.frontier(Index::new(5))), [2, 5]);
+        assert_eq!(starts(dominators.frontier(Index::new(12))), [2]);
+
+        let post_dominators = DominatorTree::post_dominators(&cfg);
+        assert_eq!(starts(post_dominators.roots()), [18]);
+        assert_eq!(post_dominators.immediate_dominator(Index::new(0)), Some(Index::new(2)));
+        assert_eq!(post_dominators.immediate_dominator(Index::new(2)), Some(Index::new(5)));
+        assert!(post_dominators.dominates(Index::new(12), Index::new(0)));
+        assert_eq!(starts(post_dominators.frontier(Index::new(0))), []);
+        assert_eq!(starts(post_dominators.frontier(Index::new(2))), [12]);
+        assert_eq!(starts(post_dominators.frontier(Index::new(5))), [5, 12]);
+    }
+}
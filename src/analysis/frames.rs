@@ -0,0 +1,571 @@
+use crate::analysis::interpreter::{initial_frame, Constants, Interpreter, Loadable};
+use crate::analysis::{ControlFlowGraph, EdgeKind};
+use crate::descriptor::MethodDescriptor;
+use crate::error::*;
+use crate::hierarchy::ClassHierarchy;
+use crate::mutf8::{MStr, MString};
+use crate::reader::attributes::{self, member_name_and_type, Code, Index};
+use crate::reader::cpool::{self, value, ConstantPool};
+use crate::reader::{Class, Method};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The type of a local variable or operand stack slot as computed by [`Frames`].
+///
+/// Unlike the types of stack map frames, classes are referred to by their names,
+/// as merged classes and the types of descriptors may not be in the constant pool.
+/// [`VerificationType::from_stack_map`] and [`VerificationType::to_stack_map`] convert between the two.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationType {
+    Top,
+    Null,
+    UninitializedThis,
+    Object(MString),
+    /// An object created by the `new` instruction at the index, whose constructor wasn't called yet.
+    UninitializedVariable(Index),
+    Integer,
+    Long,
+    Float,
+    Double,
+}
+
+impl VerificationType {
+    /// Converts a type of a stack map frame, looking up the names of classes in the constant pool.
+    pub fn from_stack_map(
+        ty: attributes::VerificationType<'_>,
+        pool: &ConstantPool<'_>,
+    ) -> Result<VerificationType, DecodeError> {
+        use attributes::VerificationType as Stack;
+
+        Ok(match ty {
+            Stack::Top => VerificationType::Top,
+            Stack::Null => VerificationType::Null,
+            Stack::UninitializedThis => VerificationType::UninitializedThis,
+            Stack::Object(class) => VerificationType::Object(pool.retrieve(class)?.name.to_owned()),
+            Stack::UninitializedVariable(index) => VerificationType::UninitializedVariable(index),
+            Stack::Integer => VerificationType::Integer,
+            Stack::Long => VerificationType::Long,
+            Stack::Float => VerificationType::Float,
+            Stack::Double => VerificationType::Double,
+        })
+    }
+
+    /// Converts the type to a type of a stack map frame, looking up classes in the constant pool.
+    ///
+    /// Returns `None` if the class of an object type isn't in the constant pool.
+    #[must_use]
+    pub fn to_stack_map<'input>(&self, pool: &ConstantPool<'input>) -> Option<attributes::VerificationType<'input>> {
+        use attributes::VerificationType as Stack;
+
+        Some(match self {
+            VerificationType::Top => Stack::Top,
+            VerificationType::Null => Stack::Null,
+            VerificationType::UninitializedThis => Stack::UninitializedThis,
+            VerificationType::Object(name) => {
+                let index = pool.iter_indices().find_map(|(index, item)| match item {
+                    cpool::Item::Class(class) if pool.retrieve(class.name).ok()? == &**name => Some(index),
+                    _ => None,
+                })?;
+                Stack::Object(cpool::Index::new(index.as_u16()).ok()?)
+            }
+            VerificationType::UninitializedVariable(index) => Stack::UninitializedVariable(*index),
+            VerificationType::Integer => Stack::Integer,
+            VerificationType::Long => Stack::Long,
+            VerificationType::Float => Stack::Float,
+            VerificationType::Double => Stack::Double,
+        })
+    }
+
+    pub(crate) fn is_wide(&self) -> bool {
+        matches!(self, VerificationType::Long | VerificationType::Double)
+    }
+}
+
+/// The types of the local variables and of the operand stack before an instruction is executed.
+///
+/// Values of type `long` or `double` take up two slots, the second one being `Top`.
+/// The return addresses pushed by `jsr` are represented by `Top` as well.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub(crate) locals: Vec<VerificationType>,
+    pub(crate) stack: Vec<VerificationType>,
+}
+
+impl Frame {
+    /// The types of all local variables, with unused ones being `Top`.
+    #[must_use]
+    pub fn locals(&self) -> &[VerificationType] {
+        &self.locals
+    }
+
+    /// The types of the operand stack, from the bottom to the top.
+    #[must_use]
+    pub fn stack(&self) -> &[VerificationType] {
+        &self.stack
+    }
+
+    pub(crate) fn push(&mut self, ty: VerificationType) {
+        let wide = ty.is_wide();
+        self.stack.push(ty);
+        if wide {
+            self.stack.push(VerificationType::Top);
+        }
+    }
+
+    /// Stores a value in a local variable, adding the local variables up to it if they are missing.
+    pub(crate) fn store(&mut self, index: usize, ty: VerificationType) {
+        let wide = ty.is_wide();
+        let len = index + if wide { 2 } else { 1 };
+        if self.locals.len() < len {
+            self.locals.resize(len, VerificationType::Top);
+        }
+
+        // a value taking up two slots gets invalidated if its second slot is overwritten
+        if index > 0 && self.locals[index - 1].is_wide() {
+            self.locals[index - 1] = VerificationType::Top;
+        }
+        self.locals[index] = ty;
+        if wide {
+            self.locals[index + 1] = VerificationType::Top;
+        }
+    }
+}
+
+/// The frames of all instructions of a code attribute, computed by a dataflow analysis similar to the one of the verifier.
+///
+/// At join points, equal types stay the same, `null` and an object type become the object type
+/// and two object types become their nearest common super class as given by the [`ClassHierarchy`].
+/// All other pairs of types become `Top`.
+///
+/// Subroutines are analyzed with the merged frames of all `jsr` instructions calling them,
+/// and their `ret` instructions continue at the instructions after all of these calls.
+///
+/// # Examples
+/// ```
+/// use noak::analysis::{Frames, VerificationType};
+/// use noak::hierarchy::ClassHierarchy;
+/// use noak::reader::attributes::AttributeContent;
+/// use noak::reader::Class;
+/// use noak::{MStr, MString};
+///
+/// struct NoHierarchy;
+///
+/// impl ClassHierarchy for NoHierarchy {
+///     fn super_class(&self, _: &MStr) -> Option<MString> {
+///         None
+///     }
+///
+///     fn is_interface(&self, _: &MStr) -> bool {
+///         false
+///     }
+/// }
+///
+/// let bytes = noak::assembler::assemble(concat!(
+///     ".class public super A\n",
+///     ".super java/lang/Object\n",
+///     ".method static text (J)Ljava/lang/String;\n",
+///     "    .limit stack 2\n",
+///     "    .limit locals 2\n",
+///     "    lload_0\n",
+///     "    invokestatic java/lang/Long/toString(J)Ljava/lang/String;\n",
+///     "    areturn\n",
+///     ".end method\n",
+/// ))?;
+/// let class = Class::new(&bytes)?;
+/// let method = class.methods().iter().next().unwrap()?;
+/// let attribute = method.attributes().iter().next().unwrap()?;
+/// let AttributeContent::Code(code) = attribute.read_content(class.pool())? else {
+///     unreachable!();
+/// };
+///
+/// let frames = Frames::new(&class, &method, &code, &NoHierarchy)?;
+/// let (index, frame) = frames.iter().nth(2).unwrap();
+/// assert_eq!(index.as_u32(), 4);
+/// assert_eq!(frame.locals(), [VerificationType::Long, VerificationType::Top]);
+/// assert_eq!(frame.stack(), [VerificationType::Object(MString::from("java/lang/String"))]);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Frames {
+    frames: BTreeMap<Index, Frame>,
+}
+
+impl Frames {
+    /// Analyzes the code of a method of a class.
+    ///
+    /// An error is returned if the code can't be decoded or if the types of the operand stack don't fit together,
+    /// such as when a value is popped from an empty stack or when the stack sizes differ at a join point.
+    /// Apart from that, the types are not checked against the instructions.
+    pub fn new<'input>(
+        class: &Class<'input>,
+        method: &Method<'input>,
+        code: &Code<'input>,
+        hierarchy: &dyn ClassHierarchy,
+    ) -> Result<Frames, DecodeError> {
+        let pool = class.pool();
+        let this_class = pool.retrieve(class.this_class())?.name;
+        let name = pool.retrieve(method.name())?;
+        let descriptor = MethodDescriptor::parse(pool.retrieve(method.descriptor())?)?;
+
+        let cfg = ControlFlowGraph::new(code)?;
+        let instructions = code.raw_instructions().collect::<Result<Vec<_>, _>>()?;
+        let interpreter = Interpreter {
+            constants: pool,
+            this_class,
+            hierarchy,
+            instructions: &instructions,
+        };
+
+        let max_locals = usize::from(code.max_locals());
+        let mut initial = initial_frame(this_class, method.access_flags(), name, &descriptor);
+        if initial.locals.len() > max_locals {
+            return Err(invalid_local());
+        }
+        initial.locals.resize(max_locals, VerificationType::Top);
+
+        let mut frames = BTreeMap::new();
+        let mut entries = BTreeMap::new();
+        let mut queue = BTreeSet::new();
+        merge(&interpreter, &mut entries, &mut queue, cfg.entry().start(), initial)?;
+
+        while let Some(start) = queue.pop_first() {
+            let block = cfg.block(start).expect("only the starts of blocks are queued");
+            let first = interpreter
+                .position_of(start)
+                .ok_or_else(|| DecodeError::with_context(DecodeErrorKind::InvalidInstruction, Context::Code))?;
+            let mut frame = entries[&start].clone();
+            for (index, instruction) in &instructions[first..first + block.instructions().len()] {
+                if let Some((local, size)) = instruction.local_variable() {
+                    if usize::from(local) + usize::from(size) > max_locals {
+                        return Err(invalid_local());
+                    }
+                }
+                let mut next = frame.clone();
+                interpreter.execute(*index, instruction, &mut next)?;
+                if next.stack.len() > code.max_stack().into() {
+                    return Err(invalid_stack());
+                }
+
+                for edge in block.successors() {
+                    if let EdgeKind::Exception { catch_type } = edge.kind() {
+                        let catch_type = match catch_type {
+                            Some(catch_type) => pool.retrieve(catch_type)?.name.to_owned(),
+                            None => MString::from("java/lang/Throwable"),
+                        };
+                        // stores are checked against the handler both before and after they are executed
+                        for locals in [&frame.locals, &next.locals] {
+                            let handler_frame = Frame {
+                                locals: locals.clone(),
+                                stack: vec![VerificationType::Object(catch_type.clone())],
+                            };
+                            merge(&interpreter, &mut entries, &mut queue, edge.target(), handler_frame)?;
+                        }
+                    }
+                }
+
+                // the last time a block is analyzed, it starts with its final frame
+                frames.insert(*index, std::mem::replace(&mut frame, next));
+            }
+
+            for edge in block.successors() {
+                if !matches!(edge.kind(), EdgeKind::Exception { .. }) {
+                    merge(&interpreter, &mut entries, &mut queue, edge.target(), frame.clone())?;
+                }
+            }
+        }
+
+        Ok(Frames { frames })
+    }
+
+    /// Returns the frame before the instruction at an index, or `None` if the instruction is never executed.
+    #[must_use]
+    pub fn get(&self, index: Index) -> Option<&Frame> {
+        self.frames.get(&index)
+    }
+
+    /// Returns the frames of all instructions which are executed, ordered by their index.
+    pub fn iter(&self) -> impl Iterator<Item = (Index, &Frame)> {
+        self.frames.iter().map(|(index, frame)| (*index, frame))
+    }
+}
+
+/// Merges a frame into the frame at the start of a block and queues the block if that frame changed.
+fn merge(
+    interpreter: &Interpreter<'_, ConstantPool<'_>>,
+    entries: &mut BTreeMap<Index, Frame>,
+    queue: &mut BTreeSet<Index>,
+    start: Index,
+    frame: Frame,
+) -> Result<(), DecodeError> {
+    match entries.get_mut(&start) {
+        Some(current) => {
+            if interpreter.merge(current, &frame)? {
+                queue.insert(start);
+            }
+        }
+        None => {
+            entries.insert(start, frame);
+            queue.insert(start);
+        }
+    }
+    Ok(())
+}
+
+impl<'input> Constants for ConstantPool<'input> {
+    type Error = DecodeError;
+
+    fn invalid_stack(&self) -> DecodeError {
+        invalid_stack()
+    }
+
+    fn invalid_descriptor(&self, error: DecodeError) -> DecodeError {
+        error
+    }
+
+    fn loadable(&self, index: u16) -> Result<Loadable<'_>, DecodeError> {
+        Ok(match self.retrieve(cpool::Index::<cpool::Item<'input>>::new(index)?)? {
+            value::Item::Integer(_) => Loadable::Integer,
+            value::Item::Float(_) => Loadable::Float,
+            value::Item::Long(_) => Loadable::Long,
+            value::Item::Double(_) => Loadable::Double,
+            value::Item::String(_) => Loadable::String,
+            value::Item::Class(_) => Loadable::Class,
+            value::Item::MethodType(_) => Loadable::MethodType,
+            value::Item::MethodHandle(_) => Loadable::MethodHandle,
+            value::Item::Dynamic(dynamic) => Loadable::Dynamic(dynamic.name_and_type.descriptor),
+            _ => return Err(DecodeError::with_context(DecodeErrorKind::TagMismatch, Context::Code)),
+        })
+    }
+
+    fn member(&self, index: u16) -> Result<(&MStr, &MStr), DecodeError> {
+        let name_and_type = member_name_and_type(self, index)?;
+        Ok((name_and_type.name, name_and_type.descriptor))
+    }
+
+    fn class_name(&self, index: u16) -> Result<&MStr, DecodeError> {
+        Ok(self.retrieve(cpool::Index::<cpool::Class<'input>>::new(index)?)?.name)
+    }
+}
+
+fn invalid_stack() -> DecodeError {
+    DecodeError::with_context(DecodeErrorKind::InvalidStackState, Context::Code)
+}
+
+fn invalid_local() -> DecodeError {
+    DecodeError::with_context(DecodeErrorKind::InvalidInstruction, Context::Code)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assembler::assemble;
+    use crate::testing::first_code;
+
+    struct Hierarchy;
+
+    impl ClassHierarchy for Hierarchy {
+        fn super_class(&self, class: &MStr) -> Option<MString> {
+            match class.as_bytes() {
+                b"java/lang/StringBuilder" => Some(MString::from("java/lang/AbstractStringBuilder")),
+                b"Left" | b"Right" => Some(MString::from("Base")),
+                b"Deep" => Some(MString::from("Left")),
+                b"java/lang/AbstractStringBuilder" | b"java/lang/String" | b"Base" | b"Shape" => {
+                    Some(MString::from("java/lang/Object"))
+                }
+                _ => None,
+            }
+        }
+
+        fn is_interface(&self, class: &MStr) -> bool {
+            class == "Shape"
+        }
+    }
+
+    /// Analyzes the first method of a class `A` with the given methods.
+    fn analyze(methods: &str) -> Frames {
+        let source = format!(".class public super A\n.super java/lang/Object\n{methods}");
+        let bytes = assemble(&source).unwrap();
+        let class = Class::new(&bytes).unwrap();
+        let (method, code) = first_code(&class);
+        Frames::new(&class, &method, &code, &Hierarchy).unwrap()
+    }
+
+    fn object(name: &str) -> VerificationType {
+        VerificationType::Object(MString::from(name))
+    }
+
+    #[test]
+    fn initializes_and_merges() {
+        let frames = analyze(concat!(
+            ".method static m (Z)Ljava/lang/Object;\n",
+            "    .limit stack 2\n",
+            "    .limit locals 2\n",
+            "    new java/lang/StringBuilder\n",
+            "    dup\n",
+            "    invokespecial java/lang/StringBuilder/<init>()V\n",
+            "    astore_1\n",
+            "    iload_0\n",
+            "    ifeq b\n",
+            "    ldc \"text\"\n",
+            "    goto c\n",
+            "b:  aload_1\n",
+            "c:  areturn\n",
+            ".end method\n",
+        ));
+        let uninitialized = VerificationType::UninitializedVariable(Index::new(0));
+
+        assert_eq!(frames.iter().count(), 10);
+        let before_init = frames.get(Index::new(4)).unwrap();
+        assert_eq!(before_init.locals(), [VerificationType::Integer, VerificationType::Top]);
+        assert_eq!(before_init.stack(), [uninitialized.clone(), uninitialized]);
+        assert_eq!(
+            frames.get(Index::new(7)).unwrap().stack(),
+            [object("java/lang/StringBuilder")]
+        );
+
+        let join = frames.get(Index::new(18)).unwrap();
+        assert_eq!(
+            join.locals(),
+            [VerificationType::Integer, object("java/lang/StringBuilder")]
+        );
+        assert_eq!(join.stack(), [object("java/lang/Object")]);
+    }
+
+    #[test]
+    fn tracks_uninitialized_objects_by_their_new_instruction() {
+        let frames = analyze(concat!(
+            ".method <init> ()V\n",
+            "    .limit stack 3\n",
+            "    .limit locals 2\n",
+            "    new java/lang/StringBuilder\n",
+            "    new java/lang/StringBuilder\n",
+            "    dup\n",
+            "    invokespecial java/lang/StringBuilder/<init>()V\n",
+            "    astore_1\n",
+            "    aload_0\n",
+            "    invokespecial java/lang/Object/<init>()V\n",
+            "    pop\n",
+            "    return\n",
+            ".end method\n",
+        ));
+        let first = VerificationType::UninitializedVariable(Index::new(0));
+        let second = VerificationType::UninitializedVariable(Index::new(3));
+
+        let before_init = frames.get(Index::new(7)).unwrap();
+        assert_eq!(
+            before_init.locals(),
+            [VerificationType::UninitializedThis, VerificationType::Top]
+        );
+        assert_eq!(before_init.stack(), [first.clone(), second.clone(), second]);
+
+        // only the object created by the second `new` is initialized
+        let after_init = frames.get(Index::new(10)).unwrap();
+        assert_eq!(after_init.stack(), [first.clone(), object("java/lang/StringBuilder")]);
+
+        let after_super = frames.get(Index::new(15)).unwrap();
+        assert_eq!(after_super.locals(), [object("A"), object("java/lang/StringBuilder")]);
+        assert_eq!(after_super.stack(), [first]);
+    }
+
+    #[test]
+    fn wide_values_take_up_two_locals() {
+        use VerificationType::*;
+
+        let frames = analyze(concat!(
+            ".method static m (JD)V\n",
+            "    .limit stack 2\n",
+            "    .limit locals 6\n",
+            "    lload_0\n",
+            "    lstore 4\n",
+            "    iconst_0\n",
+            "    istore_3\n",
+            "    return\n",
+            ".end method\n",
+        ));
+
+        let initial = frames.get(Index::new(0)).unwrap();
+        assert_eq!(initial.locals(), [Long, Top, Double, Top, Top, Top]);
+        assert_eq!(frames.get(Index::new(1)).unwrap().stack(), [Long, Top]);
+        assert_eq!(
+            frames.get(Index::new(3)).unwrap().locals(),
+            [Long, Top, Double, Top, Long, Top]
+        );
+        // overwriting the second slot of the double invalidates it
+        assert_eq!(
+            frames.get(Index::new(5)).unwrap().locals(),
+            [Long, Top, Top, Integer, Long, Top]
+        );
+    }
+
+    #[test]
+    fn merges_with_the_class_hierarchy() {
+        let frames = analyze(concat!(
+            ".method static m (Z)[Ljava/lang/Object;\n",
+            "    .limit stack 1\n",
+            "    .limit locals 4\n",
+            "    aconst_null\n",
+            "    checkcast Deep\n",
+            "    astore_1\n",
+            "    fconst_0\n",
+            "    fstore_2\n",
+            "    aconst_null\n",
+            "    checkcast Shape\n",
+            "    astore_3\n",
+            "    iload_0\n",
+            "    ifeq b\n",
+            "    aconst_null\n",
+            "    checkcast Right\n",
+            "    astore_1\n",
+            "    iconst_0\n",
+            "    istore_2\n",
+            "    aconst_null\n",
+            "    checkcast Left\n",
+            "    astore_3\n",
+            "    iconst_1\n",
+            "    anewarray Right\n",
+            "    goto c\n",
+            "b:  iconst_1\n",
+            "    anewarray Deep\n",
+            "c:  areturn\n",
+            ".end method\n",
+        ));
+
+        let join = frames.get(Index::new(39)).unwrap();
+        assert_eq!(
+            join.locals(),
+            [
+                VerificationType::Integer,
+                object("Base"),
+                VerificationType::Top,
+                // merging with an interface gives `Object`
+                object("java/lang/Object"),
+            ]
+        );
+        assert_eq!(join.stack(), [object("[LBase;")]);
+    }
+
+    #[test]
+    fn converts_stack_map_types() {
+        let bytes = assemble(".class public super A\n.super java/lang/Object\n").unwrap();
+        let class = Class::new(&bytes).unwrap();
+        let pool = class.pool();
+
+        let object = VerificationType::Object(MString::from("java/lang/Object"));
+        let converted = object.to_stack_map(pool).unwrap();
+        assert!(matches!(
+            converted,
+            attributes::VerificationType::Object(class) if pool.retrieve(class).unwrap().name == "java/lang/Object"
+        ));
+        assert_eq!(VerificationType::from_stack_map(converted, pool).unwrap(), object);
+
+        let uninitialized = VerificationType::UninitializedVariable(Index::new(3));
+        let converted = uninitialized.to_stack_map(pool).unwrap();
+        assert_eq!(
+            VerificationType::from_stack_map(converted, pool).unwrap(),
+            uninitialized
+        );
+
+        // merged classes may not be in the constant pool
+        let missing = VerificationType::Object(MString::from("java/lang/String"));
+        assert!(missing.to_stack_map(pool).is_none());
+    }
+}
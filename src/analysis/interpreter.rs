@@ -0,0 +1,330 @@
+use crate::analysis::{Frame, VerificationType};
+use crate::descriptor::{BaseType, MethodDescriptor, TypeDescriptor};
+use crate::error::DecodeError;
+use crate::header::AccessFlags;
+use crate::hierarchy::{array_of_class, merge_classes, ClassHierarchy};
+use crate::mutf8::{MStr, MString};
+use crate::reader::attributes::{ArrayType, Index, RawInstruction};
+
+/// The constants referenced by instructions, which are either read from a class or were written to one.
+pub(crate) trait Constants {
+    type Error;
+
+    /// The error for values popped from an empty stack or stacks which can't be merged.
+    fn invalid_stack(&self) -> Self::Error;
+
+    /// The error for invalid descriptors of the referenced members.
+    fn invalid_descriptor(&self, error: DecodeError) -> Self::Error;
+
+    /// Returns the kind of constant loaded by `ldc`.
+    fn loadable(&self, index: u16) -> Result<Loadable<'_>, Self::Error>;
+
+    /// Returns the name and the descriptor of a field or method reference or of a dynamically-computed constant
+    /// or call site.
+    fn member(&self, index: u16) -> Result<(&MStr, &MStr), Self::Error>;
+
+    /// Returns the name of a class constant.
+    fn class_name(&self, index: u16) -> Result<&MStr, Self::Error>;
+}
+
+/// A constant which can be loaded by `ldc`.
+pub(crate) enum Loadable<'a> {
+    Integer,
+    Float,
+    Long,
+    Double,
+    String,
+    Class,
+    MethodType,
+    MethodHandle,
+    /// A dynamically-computed constant with the descriptor.
+    Dynamic(&'a MStr),
+}
+
+/// Simulates the execution of instructions on frames, as shared by [`Frames`](crate::analysis::Frames) and the
+/// frame computation of the code writer.
+///
+/// How many slots an instruction pops and pushes is taken from [`RawInstruction::stack_effect`],
+/// only the types of the pushed values are decided here.
+pub(crate) struct Interpreter<'a, C> {
+    pub(crate) constants: &'a C,
+    pub(crate) this_class: &'a MStr,
+    pub(crate) hierarchy: &'a dyn ClassHierarchy,
+    pub(crate) instructions: &'a [(Index, RawInstruction<'a>)],
+}
+
+impl<'a, C: Constants> Interpreter<'a, C> {
+    /// Returns the position of the instruction at an index.
+    pub(crate) fn position_of(&self, index: Index) -> Option<usize> {
+        self.instructions.binary_search_by_key(&index, |&(index, _)| index).ok()
+    }
+
+    /// Merges a frame into another one and returns whether that one changed.
+    ///
+    /// Local variables which are only present in one of the frames are removed.
+    pub(crate) fn merge(&self, current: &mut Frame, frame: &Frame) -> Result<bool, C::Error> {
+        if current.stack.len() != frame.stack.len() {
+            return Err(self.constants.invalid_stack());
+        }
+
+        let mut changed = false;
+        if current.locals.len() > frame.locals.len() {
+            current.locals.truncate(frame.locals.len());
+            changed = true;
+        }
+        let pairs = current
+            .locals
+            .iter_mut()
+            .zip(&frame.locals)
+            .chain(current.stack.iter_mut().zip(&frame.stack));
+        for (current, new) in pairs {
+            let merged = self.merge_types(current, new);
+            if *current != merged {
+                *current = merged;
+                changed = true;
+            }
+        }
+        Ok(changed)
+    }
+
+    fn merge_types(&self, a: &VerificationType, b: &VerificationType) -> VerificationType {
+        use VerificationType::*;
+
+        match (a, b) {
+            _ if a == b => a.clone(),
+            (Null, Object(_)) => b.clone(),
+            (Object(_), Null) => a.clone(),
+            (Object(a), Object(b)) => Object(merge_classes(self.hierarchy, a, b)),
+            _ => Top,
+        }
+    }
+
+    /// Simulates the execution of an instruction on the frame.
+    ///
+    /// Local variables which are stored to are added to the frame if it doesn't have them yet,
+    /// while loading a missing local variable loads `Top`.
+    /// The return address pushed by `jsr` is `Top` as well, and `ret` doesn't change the frame.
+    pub(crate) fn execute(
+        &self,
+        index: Index,
+        instruction: &RawInstruction<'_>,
+        frame: &mut Frame,
+    ) -> Result<(), C::Error> {
+        use RawInstruction::*;
+        use VerificationType::*;
+
+        let member = match instruction.member_index() {
+            Some(index) => Some(self.constants.member(index)?),
+            None => None,
+        };
+        let effect = instruction
+            .stack_effect_with(member.map(|(_, descriptor)| descriptor))
+            .map_err(|err| self.constants.invalid_descriptor(err))?;
+        // the popped values, starting at the top of the stack
+        let mut popped = Vec::with_capacity(effect.popped.into());
+        for _ in 0..effect.popped {
+            popped.push(frame.stack.pop().ok_or_else(|| self.constants.invalid_stack())?);
+        }
+
+        let pushed = match *instruction {
+            Dup | DupX1 | DupX2 | Dup2 | Dup2X1 | Dup2X2 | Swap => {
+                // the popped values to push again, starting at the bottom of the stack
+                let order: &[usize] = match instruction {
+                    Dup => &[0, 0],
+                    DupX1 => &[0, 1, 0],
+                    DupX2 => &[0, 2, 1, 0],
+                    Dup2 => &[1, 0, 1, 0],
+                    Dup2X1 => &[1, 0, 2, 1, 0],
+                    Dup2X2 => &[1, 0, 3, 2, 1, 0],
+                    _ => &[0, 1],
+                };
+                frame.stack.extend(order.iter().map(|&value| popped[value].clone()));
+                return Ok(());
+            }
+            ALoad { .. } | ALoadW { .. } | ALoad0 | ALoad1 | ALoad2 | ALoad3 => {
+                let (local, _) = instruction.local_variable().expect("loads access a local variable");
+                Some(frame.locals.get(usize::from(local)).cloned().unwrap_or(Top))
+            }
+            IStore { .. } | IStoreW { .. } | IStore0 | IStore1 | IStore2 | IStore3 => {
+                store(instruction, frame, Integer);
+                None
+            }
+            LStore { .. } | LStoreW { .. } | LStore0 | LStore1 | LStore2 | LStore3 => {
+                store(instruction, frame, Long);
+                None
+            }
+            FStore { .. } | FStoreW { .. } | FStore0 | FStore1 | FStore2 | FStore3 => {
+                store(instruction, frame, Float);
+                None
+            }
+            DStore { .. } | DStoreW { .. } | DStore0 | DStore1 | DStore2 | DStore3 => {
+                store(instruction, frame, Double);
+                None
+            }
+            AStore { .. } | AStoreW { .. } | AStore0 | AStore1 | AStore2 | AStore3 => {
+                store(instruction, frame, popped[0].clone());
+                None
+            }
+            AALoad => Some(match &popped[1] {
+                Object(name) if name.as_bytes().first() == Some(&b'[') => {
+                    verification_type(&self.parse_type(&name[1..])?)
+                }
+                Null => Null,
+                _ => Top,
+            }),
+            AConstNull => Some(Null),
+            JSr { .. } | JSrW { .. } => Some(Top),
+            IConstM1 | IConst0 | IConst1 | IConst2 | IConst3 | IConst4 | IConst5 | BIPush { .. } | SIPush { .. } => {
+                Some(Integer)
+            }
+            ILoad { .. } | ILoadW { .. } | ILoad0 | ILoad1 | ILoad2 | ILoad3 => Some(Integer),
+            IALoad | BALoad | CALoad | SALoad | ArrayLength | InstanceOf { .. } => Some(Integer),
+            IAdd | ISub | IMul | IDiv | IRem | IAnd | IOr | IXor | IShL | IShR | IUShR | INeg => Some(Integer),
+            I2B | I2C | I2S | L2I | F2I | D2I | LCmp | FCmpL | FCmpG | DCmpL | DCmpG => Some(Integer),
+            LConst0 | LConst1 | LLoad { .. } | LLoadW { .. } | LLoad0 | LLoad1 | LLoad2 | LLoad3 | LALoad => Some(Long),
+            LAdd | LSub | LMul | LDiv | LRem | LAnd | LOr | LXor | LShL | LShR | LUShR | LNeg => Some(Long),
+            I2L | F2L | D2L => Some(Long),
+            FConst0 | FConst1 | FConst2 | FLoad { .. } | FLoadW { .. } | FLoad0 | FLoad1 | FLoad2 | FLoad3 => {
+                Some(Float)
+            }
+            FALoad | FAdd | FSub | FMul | FDiv | FRem | FNeg | I2F | L2F | D2F => Some(Float),
+            DConst0 | DConst1 | DLoad { .. } | DLoadW { .. } | DLoad0 | DLoad1 | DLoad2 | DLoad3 | DALoad => {
+                Some(Double)
+            }
+            DAdd | DSub | DMul | DDiv | DRem | DNeg | I2D | L2D | F2D => Some(Double),
+            LdC { index } | LdCW { index } | LdC2W { index } => {
+                Some(match self.constants.loadable(index.as_u16())? {
+                    Loadable::Integer => Integer,
+                    Loadable::Float => Float,
+                    Loadable::Long => Long,
+                    Loadable::Double => Double,
+                    Loadable::String => object("java/lang/String"),
+                    Loadable::Class => object("java/lang/Class"),
+                    Loadable::MethodType => object("java/lang/invoke/MethodType"),
+                    Loadable::MethodHandle => object("java/lang/invoke/MethodHandle"),
+                    Loadable::Dynamic(descriptor) => verification_type(&self.parse_type(descriptor)?),
+                })
+            }
+            GetStatic { .. } | GetField { .. } => {
+                let (_, descriptor) = member.expect("field instructions reference a member");
+                Some(verification_type(&self.parse_type(descriptor)?))
+            }
+            InvokeVirtual { .. }
+            | InvokeSpecial { .. }
+            | InvokeInterface { .. }
+            | InvokeStatic { .. }
+            | InvokeDynamic { .. } => {
+                let (name, descriptor) = member.expect("invocations reference a member");
+                if matches!(instruction, InvokeSpecial { .. }) && name == "<init>" {
+                    // the receiver is the deepest popped value
+                    let receiver = popped.last().ok_or_else(|| self.constants.invalid_stack())?;
+                    self.initialize(frame, receiver)?;
+                }
+                let descriptor =
+                    MethodDescriptor::parse(descriptor).map_err(|err| self.constants.invalid_descriptor(err))?;
+                descriptor
+                    .return_type()
+                    .map(|return_type| verification_type(&return_type))
+            }
+            New { .. } => Some(UninitializedVariable(index)),
+            NewArray { atype } => Some(object(match atype {
+                ArrayType::Boolean => "[Z",
+                ArrayType::Char => "[C",
+                ArrayType::Float => "[F",
+                ArrayType::Double => "[D",
+                ArrayType::Byte => "[B",
+                ArrayType::Short => "[S",
+                ArrayType::Int => "[I",
+                ArrayType::Long => "[J",
+            })),
+            ANewArray { index } => Some(Object(array_of_class(self.constants.class_name(index.as_u16())?))),
+            CheckCast { index } | MultiANewArray { index, .. } => {
+                Some(Object(self.constants.class_name(index.as_u16())?.to_owned()))
+            }
+            _ => None,
+        };
+
+        if let Some(ty) = pushed {
+            frame.push(ty);
+        }
+        Ok(())
+    }
+
+    /// Replaces all occurrences of an uninitialized type after its constructor was called.
+    fn initialize(&self, frame: &mut Frame, receiver: &VerificationType) -> Result<(), C::Error> {
+        let initialized = match receiver {
+            VerificationType::UninitializedThis => VerificationType::Object(self.this_class.to_owned()),
+            VerificationType::UninitializedVariable(index) => {
+                let instruction = self.position_of(*index).map(|position| &self.instructions[position].1);
+                match instruction {
+                    Some(RawInstruction::New { index }) => {
+                        VerificationType::Object(self.constants.class_name(index.as_u16())?.to_owned())
+                    }
+                    _ => return Err(self.constants.invalid_stack()),
+                }
+            }
+            _ => return Ok(()),
+        };
+
+        for ty in frame.locals.iter_mut().chain(&mut frame.stack) {
+            if ty == receiver {
+                *ty = initialized.clone();
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_type<'d>(&self, descriptor: &'d MStr) -> Result<TypeDescriptor<'d>, C::Error> {
+        TypeDescriptor::parse(descriptor).map_err(|err| self.constants.invalid_descriptor(err))
+    }
+}
+
+/// Returns the frame at the start of a method, where the local variables only hold `this` and the parameters.
+pub(crate) fn initial_frame(
+    this_class: &MStr,
+    access_flags: AccessFlags,
+    name: &MStr,
+    descriptor: &MethodDescriptor<'_>,
+) -> Frame {
+    let mut initial = Frame {
+        locals: Vec::new(),
+        stack: Vec::new(),
+    };
+    if !access_flags.contains(AccessFlags::STATIC) {
+        if name == "<init>" && this_class != "java/lang/Object" {
+            initial.locals.push(VerificationType::UninitializedThis);
+        } else {
+            initial.locals.push(VerificationType::Object(this_class.to_owned()));
+        }
+    }
+    for parameter in descriptor.parameters() {
+        let index = initial.locals.len();
+        initial.store(index, verification_type(&parameter));
+    }
+    initial
+}
+
+fn store(instruction: &RawInstruction<'_>, frame: &mut Frame, ty: VerificationType) {
+    let (local, _) = instruction.local_variable().expect("stores access a local variable");
+    frame.store(local.into(), ty);
+}
+
+fn object(name: &str) -> VerificationType {
+    VerificationType::Object(MString::from(name))
+}
+
+fn verification_type(descriptor: &TypeDescriptor<'_>) -> VerificationType {
+    if descriptor.dimensions > 0 {
+        return VerificationType::Object(descriptor.to_mstring());
+    }
+
+    match descriptor.base {
+        BaseType::Boolean | BaseType::Byte | BaseType::Short | BaseType::Char | BaseType::Integer => {
+            VerificationType::Integer
+        }
+        BaseType::Long => VerificationType::Long,
+        BaseType::Float => VerificationType::Float,
+        BaseType::Double => VerificationType::Double,
+        BaseType::Object(class) => VerificationType::Object(class.to_owned()),
+    }
+}
@@ -0,0 +1,235 @@
+use crate::analysis::{ControlFlowGraph, DominatorTree};
+use crate::reader::attributes::Index;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The natural loops of a [`ControlFlowGraph`] and how they are nested.
+///
+/// Loops are ordered so that every loop comes before the loops nested in it,
+/// and loops at the same level are ordered by the position of their headers.
+#[derive(Debug, Clone)]
+pub struct Loops {
+    loops: Vec<Loop>,
+}
+
+impl Loops {
+    /// Finds the loops using the dominator tree of the graph.
+    ///
+    /// Every edge to a block which dominates the source of the edge is a back edge.
+    /// All back edges to the same header form one loop, so irreducible control flow doesn't form loops.
+    #[must_use]
+    pub fn new(cfg: &ControlFlowGraph<'_>, dominators: &DominatorTree) -> Loops {
+        let mut latches: BTreeMap<Index, Vec<Index>> = BTreeMap::new();
+        for block in cfg.blocks().filter(|block| dominators.contains(block.start())) {
+            for edge in block.successors() {
+                if dominators.dominates(edge.target(), edge.source()) {
+                    let sources = latches.entry(edge.target()).or_default();
+                    if !sources.contains(&edge.source()) {
+                        sources.push(edge.source());
+                    }
+                }
+            }
+        }
+
+        let mut loops: Vec<Loop> = latches
+            .into_iter()
+            .map(|(header, latches)| {
+                let mut blocks = BTreeSet::from([header]);
+                let mut stack = latches.clone();
+                while let Some(block) = stack.pop() {
+                    if blocks.insert(block) {
+                        let block = cfg.block(block).unwrap();
+                        stack.extend(
+                            block
+                                .predecessors()
+                                .iter()
+                                .map(|edge| edge.source())
+                                .filter(|&source| dominators.contains(source)),
+                        );
+                    }
+                }
+                Loop {
+                    header,
+                    latches,
+                    blocks,
+                    parent: None,
+                    children: Vec::new(),
+                    depth: 1,
+                }
+            })
+            .collect();
+
+        // natural loops with different headers are either disjoint or nested,
+        // so the parent of a loop is the smallest other loop containing its header
+        let parents: Vec<Option<usize>> = loops
+            .iter()
+            .map(|inner| {
+                (0..loops.len())
+                    .filter(|&outer| {
+                        loops[outer].blocks.len() > inner.blocks.len() && loops[outer].contains(inner.header)
+                    })
+                    .min_by_key(|&outer| loops[outer].blocks.len())
+            })
+            .collect();
+
+        let mut order = Vec::new();
+        let mut stack: Vec<usize> = (0..loops.len()).rev().filter(|&i| parents[i].is_none()).collect();
+        while let Some(current) = stack.pop() {
+            order.push(current);
+            stack.extend((0..loops.len()).rev().filter(|&i| parents[i] == Some(current)));
+        }
+        let mut positions = vec![0; loops.len()];
+        for (position, &i) in order.iter().enumerate() {
+            positions[i] = position;
+        }
+
+        let mut sorted: Vec<Option<Loop>> = loops.drain(..).map(Some).collect();
+        for &i in &order {
+            let mut current = sorted[i].take().unwrap();
+            if let Some(parent) = parents[i].map(|parent| positions[parent]) {
+                current.parent = Some(parent);
+                current.depth = loops[parent].depth + 1;
+                let position = loops.len();
+                loops[parent].children.push(position);
+            }
+            loops.push(current);
+        }
+
+        Loops { loops }
+    }
+
+    /// Returns all loops.
+    #[must_use]
+    pub fn loops(&self) -> &[Loop] {
+        &self.loops
+    }
+
+    /// Returns the loop at a position in [`loops`](Loops::loops).
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&Loop> {
+        self.loops.get(index)
+    }
+
+    /// Returns the innermost loop containing a block.
+    #[must_use]
+    pub fn innermost(&self, block: Index) -> Option<&Loop> {
+        self.loops
+            .iter()
+            .filter(|current| current.contains(block))
+            .max_by_key(|current| current.depth)
+    }
+
+    /// Returns the number of loops containing a block.
+    #[must_use]
+    pub fn depth(&self, block: Index) -> usize {
+        self.innermost(block).map_or(0, |current| current.depth)
+    }
+}
+
+/// A natural loop, which consists of a header and all blocks that can reach a back edge to it without passing it.
+#[derive(Debug, Clone)]
+pub struct Loop {
+    header: Index,
+    latches: Vec<Index>,
+    blocks: BTreeSet<Index>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    depth: usize,
+}
+
+impl Loop {
+    /// The only block through which the loop can be entered, which dominates all blocks of the loop.
+    #[must_use]
+    pub fn header(&self) -> Index {
+        self.header
+    }
+
+    /// The sources of the back edges to the header.
+    #[must_use]
+    pub fn latches(&self) -> &[Index] {
+        &self.latches
+    }
+
+    /// Returns the blocks of the loop, including the ones of nested loops, ordered by position.
+    pub fn blocks(&self) -> impl Iterator<Item = Index> + '_ {
+        self.blocks.iter().copied()
+    }
+
+    /// Whether a block is part of the loop or of a nested loop.
+    #[must_use]
+    pub fn contains(&self, block: Index) -> bool {
+        self.blocks.contains(&block)
+    }
+
+    /// The position of the directly enclosing loop in [`Loops::loops`].
+    #[must_use]
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
+    /// The positions of the directly nested loops in [`Loops::loops`].
+    #[must_use]
+    pub fn children(&self) -> &[usize] {
+        &self.children
+    }
+
+    /// The number of loops containing this loop, including itself.
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assembler::assemble;
+    use crate::reader::attributes::AttributeContent;
+    use crate::reader::Class;
+
+    #[test]
+    fn nesting() {
+        let bytes = assemble(concat!(
+            ".class public super A\n",
+            ".super java/lang/Object\n",
+            ".method static m (I)V\n",
+            "    .limit stack 2\n",
+            "    .limit locals 2\n",
+            "    iconst_0\n",
+            "    istore_1\n",
+            "a:  iload_1\n",
+            "    bipush 10\n",
+            "    if_icmpge c\n",
+            "b:  iinc 0 -1\n",
+            "    iload_0\n",
+            "    ifgt b\n",
+            "    iinc 1 1\n",
+            "    goto a\n",
+            "c:  return\n",
+            ".end method\n",
+        ))
+        .unwrap();
+        let class = Class::new(&bytes).unwrap();
+        let method = class.methods().iter().next().unwrap().unwrap();
+        let attribute = method.attributes().iter().next().unwrap().unwrap();
+        let Ok(AttributeContent::Code(code)) = attribute.read_content(class.pool()) else {
+            panic!("expected a code attribute");
+        };
+        let cfg = ControlFlowGraph::new(&code).unwrap();
+        let loops = Loops::new(&cfg, &DominatorTree::dominators(&cfg));
+
+        let [outer, inner] = loops.loops() else {
+            panic!("expected two loops");
+        };
+        assert_eq!(outer.header().as_u32(), 2);
+        assert_eq!(outer.latches(), [Index::new(15)]);
+        assert_eq!(outer.blocks().map(Index::as_u32).collect::<Vec<_>>(), [2, 8, 15]);
+        assert_eq!(outer.children(), [1]);
+        assert_eq!(inner.header().as_u32(), 8);
+        assert_eq!(inner.latches(), [Index::new(8)]);
+        assert_eq!(inner.parent(), Some(0));
+        assert_eq!(inner.depth(), 2);
+        assert_eq!(loops.innermost(Index::new(8)).unwrap().header().as_u32(), 8);
+        assert_eq!(loops.depth(Index::new(15)), 1);
+        assert_eq!(loops.depth(Index::new(0)), 0);
+    }
+}
@@ -0,0 +1,137 @@
+//! Assembles class files from a textual representation similar to the one of Jasmin.
+//!
+//! ```text
+//! ; comments start with a semicolon at the start of a word
+//! .version 52 0
+//! .class public super com/example/Hello
+//! .super java/lang/Object
+//! .implements java/lang/Runnable
+//! .source Hello.java
+//!
+//! .field private static final GREETING Ljava/lang/String; = "Hello, World!"
+//!
+//! .method public static main ([Ljava/lang/String;)V
+//!     .limit stack 2
+//!     .limit locals 1
+//!     .line 3
+//! start:
+//!     getstatic java/lang/System/out Ljava/io/PrintStream;
+//!     ldc "Hello, World!"
+//!     invokevirtual java/io/PrintStream/println(Ljava/lang/String;)V
+//!     return
+//! end:
+//!     .var 0 is args [Ljava/lang/String; from start to end
+//! .end method
+//! ```
+//!
+//! The directives are:
+//!
+//! - `.version <major> [<minor>]`, which defaults to Java 8
+//! - `.class <flags...> <name>`
+//! - `.super [<name>]`, which defaults to `java/lang/Object` and leaves out the super class if the name is missing
+//! - `.implements <name>`
+//! - `.field <flags...> <name> <descriptor> [= <constant>]`
+//! - `.record_component <name> <descriptor>`, which adds a component to the `Record` attribute
+//! - `.method <flags...> <name> <descriptor>`, where the name may be directly followed by the descriptor,
+//!   and `.end method`
+//! - `.source <file>`, `.signature <signature>`, `.deprecated`, `.synthetic` and `.attribute <name> <hex bytes...>`,
+//!   which add an attribute to the field or record component directly preceding them, to the method containing them,
+//!   or else to the class
+//! - `.annotation visible|invisible <type> [{ <name> = <value>... }]`, which adds an annotation in the same way,
+//!   and `.annotation visible|invisible parameter <index> ...` for an annotation of a method parameter
+//! - `.parameter_annotations visible|invisible <count>`, which sets the number of parameters with annotations
+//! - `.annotation_default <value>`
+//! - `.parameter <flags...> [<name>]`, which adds a parameter to the `MethodParameters` of the method
+//! - `.throws <class>`, which adds the class to the exceptions of the method
+//! - `.inner_class <flags...> <class> [outer <class>] [name <name>]`, `.enclosing_method <class> [<name> <descriptor>]`,
+//!   `.nest_host <class>`, `.nest_member <class>` and `.permitted_subclass <class>`, which always apply to the class
+//!
+//! Annotation values are written with a tag, like `int 1`, `string "text"`, `class Ljava/lang/Object;`,
+//! `enum <type> <constant>`, `annotation <type> { ... }` or `array { <value>... }`,
+//! where the tags `boolean`, `byte`, `short`, `char`, `long`, `float` and `double` work like `int`.
+//!
+//! Inside of methods:
+//!
+//! - `.limit stack <n>` and `.limit locals <n>`, which are computed if both are left out
+//! - `<label>:`, which may be followed by an instruction on the same line
+//! - `.line <n>`, which marks the start of a line in the `LineNumberTable`
+//! - `.catch <class> from <label> to <label> using <label>`, where the class `all` catches every exception
+//! - `.var <index> is <name> <descriptor> [signature <signature>] from <label> to <label>`
+//! - `.frame <type>`, which adds a stack map frame at the next instruction, where the type is `same`,
+//!   `same_extended`, `same1 <type>`, `same1_extended <type>`, `chop <n>`, `append <types...>` or
+//!   `full locals <types...> stack <types...>`, and the verification types are `top`, `int`, `float`, `long`,
+//!   `double`, `null`, `uninitialized_this`, `object <class>` or `uninitialized <label>`
+//!
+//! Instructions are written with their usual names.
+//! Fields are referenced as `class/name descriptor` and methods as `class/name(descriptor)`, followed by the argument
+//! count for `invokeinterface` which is computed if it is left out.
+//! `invokespecial` and `invokestatic` call methods of interfaces if the method is preceded by `interface`.
+//! Local variable indices which don't fit into a byte use the `wide` form, which can also be requested by
+//! writing `wide` before the instruction.
+//!
+//! The constants of `ldc` and `ldc_w` are integers, floats, strings or class names, and the ones of `ldc2_w` are
+//! longs or doubles. The type of a number can be given with `int`, `long`, `float` or `double` before it.
+//! Other constants are written as `class <name>`, `method_type <descriptor>`, `handle <handle>` or
+//! `dynamic <name> <descriptor> <bootstrap>`.
+//! Method handles are written as their kind (like `getstatic` or `invokestatic`), optionally `interface`,
+//! and the referenced field or method, and bootstrap methods as a method handle followed by their arguments:
+//!
+//! ```text
+//! invokedynamic run ()Ljava/lang/Runnable; invokestatic java/lang/invoke/LambdaMetafactory/metafactory(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/invoke/MethodType;Ljava/lang/invoke/MethodType;Ljava/lang/invoke/MethodHandle;Ljava/lang/invoke/MethodType;)Ljava/lang/invoke/CallSite; {
+//!     method_type ()V
+//!     handle invokestatic Example/lambda$main$0()V
+//!     method_type ()V
+//! }
+//! ```
+//!
+//! Switches are written as:
+//!
+//! ```text
+//! tableswitch 1
+//!     one
+//!     two
+//!     default : other
+//! lookupswitch
+//!     1 : one
+//!     10 : ten
+//!     default : other
+//! ```
+//!
+//! Stack map frames can either be written with `.frame` or computed with [`assemble_with_frames`]
+//! for methods without `.frame` and `.limit` directives.
+
+mod emit;
+mod instructions;
+mod lexer;
+mod parser;
+
+use crate::error::*;
+use crate::hierarchy::ClassHierarchy;
+
+/// Assembles a class file.
+///
+/// # Examples
+/// ```
+/// let class = noak::assembler::assemble(
+///     r#"
+///     .class public super Example
+///     .method public static answer ()I
+///         bipush 42
+///         ireturn
+///     .end method
+///     "#,
+/// )?;
+/// assert_eq!(&class[..4], &[0xCA, 0xFE, 0xBA, 0xBE]);
+/// # Ok::<(), noak::error::AssembleError>(())
+/// ```
+pub fn assemble(source: &str) -> Result<Vec<u8>, AssembleError> {
+    let class = parser::parse(source)?;
+    emit::emit(&class, None)
+}
+
+/// Assembles a class file and computes the stack map frames of methods without `.limit` directives,
+/// if the version requires them.
+pub fn assemble_with_frames(source: &str, hierarchy: &dyn ClassHierarchy) -> Result<Vec<u8>, AssembleError> {
+    let class = parser::parse(source)?;
+    emit::emit(&class, Some(hierarchy))
+}
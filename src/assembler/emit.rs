@@ -0,0 +1,813 @@
+use std::cell::Cell;
+
+use crate::descriptor::MethodDescriptor;
+use crate::error::*;
+use crate::hierarchy::ClassHierarchy;
+use crate::writer::{
+    attributes::{
+        code::{
+            stack_map::{StackMapTableWriter, VerificationTypeWriter, VerificationTypeWriterState},
+            CodeWriter, CodeWriterState, Label, LabelRef,
+        },
+        AnnotationWriter, AnnotationWriterState, AttributeWriter, AttributeWriterState, ElementValueWriter,
+        ElementValueWriterState, ParameterAnnotationsWriter,
+    },
+    cpool,
+    encoding::{EncoderContext, ManyWriter},
+    ClassWriter, ClassWriterState,
+};
+use crate::MString;
+
+use super::instructions::Writer;
+use super::lexer::Position;
+use super::parser::*;
+
+type MethodsContext = ClassWriter<ClassWriterState::Methods>;
+
+pub(super) fn emit(class: &Class, hierarchy: Option<&dyn ClassHierarchy>) -> Result<Vec<u8>, AssembleError> {
+    let emitter = Emitter {
+        class,
+        hierarchy,
+        position: Cell::new(Position { line: 1, column: 1 }),
+    };
+    // encoding errors are reported at the item which was written last
+    emitter
+        .class()
+        .map_err(|err| emitter.position.get().error(AssembleErrorKind::Encode(err)))
+}
+
+struct Emitter<'a> {
+    class: &'a Class,
+    hierarchy: Option<&'a dyn ClassHierarchy>,
+    position: Cell<Position>,
+}
+
+impl<'a> Emitter<'a> {
+    fn class(&self) -> Result<Vec<u8>, EncodeError> {
+        let class = self.class;
+        let writer = ClassWriter::new()
+            .version(class.version)?
+            .access_flags(class.access_flags)?
+            .this_class(&*class.name)?;
+        let writer = match &class.super_class {
+            Some(super_class) => writer.super_class(&**super_class)?,
+            None => writer.no_super_class()?,
+        };
+        writer
+            .interfaces(|interfaces| {
+                for interface in &class.interfaces {
+                    interfaces.begin(|writer| writer.interface(&**interface))?;
+                }
+                Ok(())
+            })?
+            .fields(|fields| {
+                for field in &class.fields {
+                    self.position.set(field.position);
+                    fields.begin(|writer| {
+                        writer
+                            .access_flags(field.access_flags)?
+                            .name(&*field.name)?
+                            .descriptor(&*field.descriptor)?
+                            .attributes(|attributes| self.attributes(attributes, &field.attributes))
+                    })?;
+                }
+                Ok(())
+            })?
+            .methods(|methods| {
+                for method in &class.methods {
+                    self.position.set(method.position);
+                    methods.begin(|writer| {
+                        writer
+                            .access_flags(method.access_flags)?
+                            .name(&*method.name)?
+                            .descriptor(&*method.descriptor)?
+                            .attributes(|attributes| {
+                                if let Some(code) = &method.code {
+                                    attributes.begin(|writer| writer.code(|writer| self.code(writer, method, code)))?;
+                                }
+                                self.attributes(attributes, &method.attributes)
+                            })
+                    })?;
+                }
+                Ok(())
+            })?
+            .attributes(|attributes| self.attributes(attributes, &class.attributes))?
+            .into_bytes()
+    }
+
+    fn attributes<Ctx: EncoderContext>(
+        &self,
+        writer: &mut ManyWriter<AttributeWriter<Ctx, AttributeWriterState::Start>, u16>,
+        attributes: &[Attribute],
+    ) -> Result<(), EncodeError> {
+        for attribute in attributes {
+            self.position.set(attribute.position);
+            writer.begin(|writer| match &attribute.kind {
+                AttributeKind::SourceFile(name) => writer.source_file(&**name),
+                AttributeKind::Signature(signature) => writer.signature(&**signature),
+                AttributeKind::Deprecated => writer.deprecated(),
+                AttributeKind::Synthetic => writer.synthetic(),
+                AttributeKind::ConstantValue(constant) => writer.constant_value(constant.clone()),
+                AttributeKind::Exceptions(classes) => writer.exceptions(|exceptions| {
+                    for class in classes {
+                        exceptions.begin(|writer| writer.exception(&**class))?;
+                    }
+                    Ok(())
+                }),
+                AttributeKind::InnerClasses(classes) => writer.inner_classes(|inner_classes| {
+                    for class in classes {
+                        inner_classes.begin(|writer| {
+                            let writer = writer.inner_class(&*class.inner)?;
+                            let writer = match &class.outer {
+                                Some(outer) => writer.outer_class(&**outer)?,
+                                None => writer.no_outer_class::<MString>()?,
+                            };
+                            let writer = match &class.name {
+                                Some(name) => writer.inner_name(&**name)?,
+                                None => writer.no_inner_name::<MString>()?,
+                            };
+                            writer.inner_access_flags(class.access_flags)
+                        })?;
+                    }
+                    Ok(())
+                }),
+                AttributeKind::EnclosingMethod { class, method } => writer.enclosing_method(|writer| {
+                    writer
+                        .class(&**class)?
+                        .method(method.as_ref().map(|(name, descriptor)| (&**name, &**descriptor)))
+                }),
+                AttributeKind::NestHost(class) => writer.nest_host(&**class),
+                AttributeKind::NestMembers(classes) => writer.nest_members(|members| {
+                    for class in classes {
+                        members.begin(|writer| writer.member(&**class))?;
+                    }
+                    Ok(())
+                }),
+                AttributeKind::PermittedSubclasses(classes) => writer.permitted_subclasses(|subclasses| {
+                    for class in classes {
+                        subclasses.begin(|writer| writer.subclass(&**class))?;
+                    }
+                    Ok(())
+                }),
+                AttributeKind::Record(components) => writer.record(|record| {
+                    for component in components {
+                        record.begin(|writer| {
+                            writer
+                                .name(&*component.name)?
+                                .descriptor(&*component.descriptor)?
+                                .attributes(|attributes| self.attributes(attributes, &component.attributes))
+                        })?;
+                    }
+                    Ok(())
+                }),
+                AttributeKind::MethodParameters(parameters) => writer.method_parameters(|writer| {
+                    for parameter in parameters {
+                        writer.begin(|writer| {
+                            let writer = match &parameter.name {
+                                Some(name) => writer.name(&**name)?,
+                                None => writer.no_name()?,
+                            };
+                            writer.access_flags(parameter.access_flags)
+                        })?;
+                    }
+                    Ok(())
+                }),
+                AttributeKind::Annotations { visible, annotations } => {
+                    let f = |writer: &mut ManyWriter<_, u16>| {
+                        for annotation in annotations {
+                            writer.begin(|writer| annotation_into(writer, annotation))?;
+                        }
+                        Ok(())
+                    };
+                    if *visible {
+                        writer.runtime_visible_annotations(f)
+                    } else {
+                        writer.runtime_invisible_annotations(f)
+                    }
+                }
+                AttributeKind::ParameterAnnotations { visible, parameters } => {
+                    let f = |writer: &mut ManyWriter<ParameterAnnotationsWriter<_, _>, u8>| {
+                        for annotations in parameters {
+                            writer.begin(|writer| {
+                                writer.annotations(|writer| {
+                                    for annotation in annotations {
+                                        writer.begin(|writer| annotation_into(writer, annotation))?;
+                                    }
+                                    Ok(())
+                                })
+                            })?;
+                        }
+                        Ok(())
+                    };
+                    if *visible {
+                        writer.runtime_visible_parameter_annotations(f)
+                    } else {
+                        writer.runtime_invisible_parameter_annotations(f)
+                    }
+                }
+                AttributeKind::AnnotationDefault(value) => {
+                    writer.annotation_default(|writer| element_value_into(writer, value))
+                }
+                AttributeKind::Raw(name, bytes) => writer.raw_attribute(&**name, bytes),
+            })?;
+        }
+        Ok(())
+    }
+
+    fn code(
+        &self,
+        mut writer: CodeWriter<MethodsContext, CodeWriterState::MaxStack>,
+        method: &Method,
+        code: &Code,
+    ) -> Result<CodeWriter<MethodsContext, CodeWriterState::End>, EncodeError> {
+        let mut labels: Vec<Option<Label>> = Vec::with_capacity(code.labels.len());
+        let mut label_refs: Vec<LabelRef> = Vec::with_capacity(code.labels.len());
+        for _ in &code.labels {
+            let (label, label_ref) = writer.new_label()?;
+            labels.push(Some(label));
+            label_refs.push(label_ref);
+        }
+
+        let mut body = |writer: CodeWriter<MethodsContext, CodeWriterState::Instructions>| {
+            writer
+                .instructions(|writer| {
+                    for statement in &code.statements {
+                        self.position.set(statement.position);
+                        match &statement.kind {
+                            StatementKind::Label(index) => {
+                                // the parser makes sure that each label is defined only once
+                                if let Some(label) = labels[*index].take() {
+                                    writer.label(label)?;
+                                }
+                            }
+                            StatementKind::Line(line) => {
+                                writer.line(*line)?;
+                            }
+                            StatementKind::Instruction(instruction) => {
+                                instruction_into(writer, instruction, &label_refs)?;
+                            }
+                        }
+                    }
+                    Ok(())
+                })?
+                .exceptions(|exceptions| {
+                    for catch in &code.catches {
+                        self.position.set(catch.position);
+                        exceptions.begin(|writer| {
+                            let writer = writer
+                                .start(label_refs[catch.start])?
+                                .end(label_refs[catch.end])?
+                                .handler(label_refs[catch.handler])?;
+                            match &catch.class {
+                                Some(class) => writer.catch_type(&**class),
+                                None => writer.catch_any(),
+                            }
+                        })?;
+                    }
+                    Ok(())
+                })
+        };
+
+        let writer = match (code.max_stack, code.max_locals) {
+            (Some(max_stack), Some(max_locals)) => body(writer.max_stack(max_stack)?.max_locals(max_locals)?)?,
+            (None, None) => {
+                let descriptor = MethodDescriptor::parse(&method.descriptor)
+                    .map_err(|err| EncodeError::from_err(err, Context::Code))?;
+                match self.hierarchy {
+                    // explicit frames replace the computed ones
+                    Some(hierarchy) if self.class.version.major >= 50 && code.frames.is_empty() => body(
+                        writer.compute_frames(&*self.class.name, method.access_flags, &method.name, &descriptor)?,
+                    )?
+                    .frames(hierarchy)?,
+                    _ => body(writer.compute_maxs(method.access_flags, &descriptor)?)?,
+                }
+            }
+            _ => return Err(EncodeError::with_context(EncodeErrorKind::ValuesMissing, Context::Code)),
+        };
+
+        writer.attributes(|attributes| {
+            if !code.variables.is_empty() {
+                self.position.set(code.variables[0].position);
+                attributes.begin(|writer| {
+                    writer.local_variable_table(|table| {
+                        for variable in &code.variables {
+                            self.position.set(variable.position);
+                            table.begin(|writer| {
+                                writer
+                                    .start(label_refs[variable.start])?
+                                    .end(label_refs[variable.end])?
+                                    .name(&*variable.name)?
+                                    .descriptor(&*variable.descriptor)?
+                                    .index(variable.index)
+                            })?;
+                        }
+                        Ok(())
+                    })
+                })?;
+            }
+            if code.variables.iter().any(|variable| variable.signature.is_some()) {
+                attributes.begin(|writer| {
+                    writer.local_variable_type_table(|table| {
+                        for variable in &code.variables {
+                            if let Some(signature) = &variable.signature {
+                                self.position.set(variable.position);
+                                table.begin(|writer| {
+                                    writer
+                                        .start(label_refs[variable.start])?
+                                        .end(label_refs[variable.end])?
+                                        .name(&*variable.name)?
+                                        .signature(&**signature)?
+                                        .index(variable.index)
+                                })?;
+                            }
+                        }
+                        Ok(())
+                    })
+                })?;
+            }
+            if !code.frames.is_empty() {
+                self.position.set(code.frames[0].position);
+                attributes.begin(|writer| {
+                    writer.stack_map_table(|table| {
+                        for frame in &code.frames {
+                            self.position.set(frame.position);
+                            frame_into(table, frame, &label_refs)?;
+                        }
+                        Ok(())
+                    })
+                })?;
+            }
+            Ok(())
+        })
+    }
+}
+
+fn frame_into<Ctx: EncoderContext>(
+    table: &mut StackMapTableWriter<Ctx>,
+    frame: &Frame,
+    labels: &[LabelRef],
+) -> Result<(), EncodeError> {
+    let label = labels[frame.label];
+    match &frame.kind {
+        FrameKind::Same => table.same(label),
+        FrameKind::SameExtended => table.same_extended(label),
+        FrameKind::Same1(stack) => table.same1(label, |writer| {
+            writer.stack_item(|writer| verification_type_into(writer, stack, labels))
+        }),
+        FrameKind::Same1Extended(stack) => table.same1_extended(label, |writer| {
+            writer.stack_item(|writer| verification_type_into(writer, stack, labels))
+        }),
+        &FrameKind::Chop(count) => table.chop(label, count),
+        FrameKind::Append(locals) => table.append(label, |mut writer| {
+            for local in locals {
+                writer = writer.local(|writer| verification_type_into(writer, local, labels))?;
+            }
+            Ok(writer)
+        }),
+        FrameKind::Full { locals, stack } => table.full(label, |writer| {
+            writer
+                .locals(|writer| {
+                    for local in locals {
+                        writer.begin(|writer| verification_type_into(writer, local, labels))?;
+                    }
+                    Ok(())
+                })?
+                .stack(|writer| {
+                    for item in stack {
+                        writer.begin(|writer| verification_type_into(writer, item, labels))?;
+                    }
+                    Ok(())
+                })
+        }),
+    }
+}
+
+fn verification_type_into<'ctx, Ctx: EncoderContext>(
+    writer: VerificationTypeWriter<'ctx, Ctx, VerificationTypeWriterState::Start>,
+    verification_type: &VerificationType,
+    labels: &[LabelRef],
+) -> Result<VerificationTypeWriter<'ctx, Ctx, VerificationTypeWriterState::End>, EncodeError> {
+    match verification_type {
+        VerificationType::Top => writer.top(),
+        VerificationType::Integer => writer.integer(),
+        VerificationType::Float => writer.float(),
+        VerificationType::Long => writer.long(),
+        VerificationType::Double => writer.double(),
+        VerificationType::Null => writer.null(),
+        VerificationType::UninitializedThis => writer.uninitialized_this(),
+        VerificationType::Object(class) => writer.object(&**class),
+        &VerificationType::Uninitialized(label) => writer.uninitialized(labels[label]),
+    }
+}
+
+fn annotation_into<Ctx: EncoderContext>(
+    writer: AnnotationWriter<Ctx, AnnotationWriterState::Type>,
+    annotation: &Annotation,
+) -> Result<AnnotationWriter<Ctx, AnnotationWriterState::End>, EncodeError> {
+    writer.type_(&*annotation.type_)?.pairs(|pairs| {
+        for (name, value) in &annotation.pairs {
+            pairs.begin(|writer| writer.name(&**name)?.value(|writer| element_value_into(writer, value)))?;
+        }
+        Ok(())
+    })
+}
+
+fn element_value_into<Ctx: EncoderContext>(
+    writer: ElementValueWriter<Ctx, ElementValueWriterState::Start>,
+    value: &ElementValue,
+) -> Result<ElementValueWriter<Ctx, ElementValueWriterState::End>, EncodeError> {
+    match value {
+        &ElementValue::Boolean(value) => writer.boolean(value),
+        &ElementValue::Byte(value) => writer.byte(value),
+        &ElementValue::Short(value) => writer.short(value),
+        &ElementValue::Char(value) => writer.char(value),
+        &ElementValue::Int(value) => writer.int(value),
+        &ElementValue::Long(value) => writer.long(value),
+        &ElementValue::Float(value) => writer.float(value),
+        &ElementValue::Double(value) => writer.double(value),
+        ElementValue::String(value) => writer.string(&**value),
+        ElementValue::Class(descriptor) => writer.class(&**descriptor),
+        ElementValue::Enum(type_name, const_name) => writer.enum_constant(&**type_name, &**const_name),
+        ElementValue::Annotation(annotation) => writer.annotation(|writer| annotation_into(writer, annotation)),
+        ElementValue::Array(values) => writer.array(|writer| {
+            for value in values {
+                writer.begin(|writer| element_value_into(writer, value))?;
+            }
+            Ok(())
+        }),
+    }
+}
+
+fn instruction_into(writer: &mut Writer, instruction: &Instruction, labels: &[LabelRef]) -> Result<(), EncodeError> {
+    match instruction {
+        Instruction::None(write) => write(writer)?,
+        &Instruction::Local(write, write_wide, index) => match u8::try_from(index) {
+            Ok(index) => write(writer, index)?,
+            Err(_) => write_wide(writer, index)?,
+        },
+        &Instruction::WideLocal(write, index) => write(writer, index)?,
+        &Instruction::WideIinc(index, value) => writer.iinc_wide(index, value)?,
+        &Instruction::Iinc(index, value) => match (u8::try_from(index), i8::try_from(value)) {
+            (Ok(index), Ok(value)) => writer.iinc(index, value)?,
+            _ => writer.iinc_wide(index, value)?,
+        },
+        &Instruction::Byte(write, value) => write(writer, value)?,
+        &Instruction::Short(write, value) => write(writer, value)?,
+        &Instruction::Label(write, label) => write(writer, labels[label])?,
+        Instruction::Class(write, class) => write(writer, class.clone())?,
+        Instruction::MultiANewArray(class, dimensions) => writer.multianewarray(&**class, *dimensions)?,
+        Instruction::NewArray(array_type) => writer.newarray(*array_type)?,
+        Instruction::Field(write, field) => write(
+            writer,
+            cpool::FieldRef::by(field.class.clone(), (field.name.clone(), field.descriptor.clone())),
+        )?,
+        Instruction::Method(write, method) => write(
+            writer,
+            cpool::MethodRef::by(method.class.clone(), (method.name.clone(), method.descriptor.clone())),
+        )?,
+        Instruction::InterfaceMethod(write, method) => write(
+            writer,
+            cpool::InterfaceMethodRef::by(method.class.clone(), (method.name.clone(), method.descriptor.clone())),
+        )?,
+        Instruction::InvokeDynamic(dynamic) => writer.invokedynamic(cpool::InvokeDynamic::by(
+            dynamic.bootstrap.clone(),
+            (dynamic.name.clone(), dynamic.descriptor.clone()),
+        ))?,
+        Instruction::InvokeInterface(method, count) => writer.invokeinterface(
+            cpool::InterfaceMethodRef::by(&*method.class, (&*method.name, &*method.descriptor)),
+            *count,
+        )?,
+        Instruction::Constant(_, constant) | Instruction::WideConstant(constant)
+            if constant.is_wide() != matches!(instruction, Instruction::WideConstant(_)) =>
+        {
+            return Err(EncodeError::with_context(
+                EncodeErrorKind::InvalidConstant,
+                Context::Code,
+            ));
+        }
+        Instruction::Constant(write, constant) => write(writer, constant.clone())?,
+        Instruction::WideConstant(constant) => writer.ldc2w(constant.clone())?,
+        Instruction::TableSwitch {
+            low,
+            labels: targets,
+            default,
+        } => writer.tableswitch(|writer| {
+            let high = i32::try_from(i64::from(*low) + targets.len() as i64 - 1)
+                .map_err(|_| EncodeError::with_context(EncodeErrorKind::IncorrectBounds, Context::Code))?;
+            let mut writer = writer.default(labels[*default])?.low(*low)?.high(high)?;
+            for &target in targets {
+                writer = writer.jump(labels[target])?;
+            }
+            Ok(writer)
+        })?,
+        Instruction::LookupSwitch { pairs, default } => writer.lookupswitch(|writer| {
+            let mut writer = writer.default(labels[*default])?;
+            let mut sorted = pairs.clone();
+            sorted.sort_by_key(|&(key, _)| key);
+            for (key, target) in sorted {
+                writer = writer.pair(key, labels[target])?;
+            }
+            Ok(writer)
+        })?,
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::assembler::assemble;
+    use crate::error::{AssembleErrorKind, EncodeErrorKind};
+    use crate::reader::attributes::{AttributeContent, RawInstruction, StackMapFrame, VerificationType};
+    use crate::reader::cpool::{ConstantPool, Item};
+    use crate::reader::Class;
+    use crate::testing::{attribute, first_code};
+
+    /// Assembles a class `A` with the given methods.
+    fn class(methods: &str) -> Vec<u8> {
+        let source = format!(".class public super A\n.super java/lang/Object\n{methods}");
+        assemble(&source).unwrap()
+    }
+
+    /// Returns the position of the error of assembling a class `A` with the given methods, which has to be an
+    /// encoding error of the kind.
+    fn error_at(methods: &str, kind: fn(&EncodeErrorKind) -> bool) -> (usize, usize) {
+        let source = format!(".class public super A\n.super java/lang/Object\n{methods}");
+        let error = assemble(&source).unwrap_err();
+        assert!(
+            matches!(error.kind(), AssembleErrorKind::Encode(encode) if kind(encode.kind())),
+            "unexpected error {error}"
+        );
+        (error.line(), error.column())
+    }
+
+    fn verification_type(pool: &ConstantPool<'_>, ty: VerificationType<'_>) -> String {
+        match ty {
+            VerificationType::Object(class) => {
+                format!("object {}", pool.retrieve(class).unwrap().name.display())
+            }
+            VerificationType::UninitializedVariable(index) => format!("uninitialized {}", index.as_u32()),
+            ty => format!("{ty:?}"),
+        }
+    }
+
+    #[test]
+    fn code_directives() {
+        let bytes = class(concat!(
+            ".method public static m (I)I\n",
+            "    .limit stack 1\n",
+            "    .limit locals 2\n",
+            "    .catch java/lang/RuntimeException from start to end using handler\n",
+            "    .catch all from start to end using handler\n",
+            "start:\n",
+            "    .line 3\n",
+            "    iload_0\n",
+            "    istore_1\n",
+            "    .line 4\n",
+            "    iload_1\n",
+            "end:\n",
+            "    ireturn\n",
+            "handler:\n",
+            "    .frame full locals int stack object java/lang/Throwable\n",
+            "    pop\n",
+            "    .frame same\n",
+            "    iconst_0\n",
+            "    ireturn\n",
+            "    .var 1 is x I from start to end\n",
+            ".end method\n",
+        ));
+        let class = Class::new(&bytes).unwrap();
+        let pool = class.pool();
+        let (_, code) = first_code(&class);
+
+        let handlers: Vec<_> = code
+            .exception_handlers()
+            .map(|handler| {
+                let catch_type = handler
+                    .catch_type()
+                    .map(|class| pool.retrieve(class).unwrap().name.display().to_string());
+                (
+                    handler.start().as_u32(),
+                    handler.end().as_u32(),
+                    handler.handler().as_u32(),
+                    catch_type,
+                )
+            })
+            .collect();
+        assert_eq!(
+            handlers,
+            [
+                (0, 3, 4, Some("java/lang/RuntimeException".to_owned())),
+                (0, 3, 4, None),
+            ]
+        );
+
+        let AttributeContent::LineNumberTable(table) = attribute(&class, code.attributes(), "LineNumberTable") else {
+            unreachable!();
+        };
+        let lines: Vec<_> = table
+            .lines()
+            .iter()
+            .map(|line| {
+                let line = line.unwrap();
+                (line.start().as_u32(), line.line_number())
+            })
+            .collect();
+        assert_eq!(lines, [(0, 3), (2, 4)]);
+
+        let AttributeContent::LocalVariableTable(table) = attribute(&class, code.attributes(), "LocalVariableTable")
+        else {
+            unreachable!();
+        };
+        let variables: Vec<_> = table
+            .locals()
+            .iter()
+            .map(|variable| {
+                let variable = variable.unwrap();
+                let range = variable.range();
+                (
+                    variable.index(),
+                    pool.retrieve(variable.name()).unwrap().display().to_string(),
+                    pool.retrieve(variable.descriptor()).unwrap().display().to_string(),
+                    range.start.as_u32()..range.end.as_u32(),
+                )
+            })
+            .collect();
+        assert_eq!(variables, [(1, "x".to_owned(), "I".to_owned(), 0..3)]);
+
+        let AttributeContent::StackMapTable(table) = attribute(&class, code.attributes(), "StackMapTable") else {
+            unreachable!();
+        };
+        let frames: Vec<_> = table
+            .iter()
+            .map(|frame| {
+                let (offset, frame) = frame.unwrap();
+                let frame = match frame {
+                    StackMapFrame::Full { locals, stack } => {
+                        let locals: Vec<_> = locals.map(|ty| verification_type(pool, ty.unwrap())).collect();
+                        let stack: Vec<_> = stack.map(|ty| verification_type(pool, ty.unwrap())).collect();
+                        format!("full {locals:?} {stack:?}")
+                    }
+                    frame => format!("{frame:?}"),
+                };
+                (offset.as_u32(), frame)
+            })
+            .collect();
+        assert_eq!(
+            frames,
+            [
+                (4, r#"full ["Integer"] ["object java/lang/Throwable"]"#.to_owned()),
+                (5, "Same".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn constants_are_typed_by_the_instruction() {
+        let bytes = class(concat!(
+            ".method public static m ()V\n",
+            "    ldc 1\n",
+            "    ldc 1.5\n",
+            "    ldc float 2\n",
+            "    ldc_w -3\n",
+            "    ldc \"text\"\n",
+            "    ldc java/lang/String\n",
+            "    ldc2_w 1\n",
+            "    ldc2_w 1.5\n",
+            "    ldc2_w double 2\n",
+            "    return\n",
+            ".end method\n",
+        ));
+        let class = Class::new(&bytes).unwrap();
+        let pool = class.pool();
+        let (_, code) = first_code(&class);
+
+        let constants: Vec<String> = code
+            .raw_instructions()
+            .filter_map(|instruction| match instruction.unwrap().1 {
+                RawInstruction::LdC { index } | RawInstruction::LdCW { index } | RawInstruction::LdC2W { index } => {
+                    Some(match pool.get(index).unwrap() {
+                        Item::Integer(integer) => format!("int {}", integer.value),
+                        Item::Long(long) => format!("long {}", long.value),
+                        Item::Float(float) => format!("float {}", float.value),
+                        Item::Double(double) => format!("double {}", double.value),
+                        Item::String(string) => format!("string {}", pool.retrieve(string.string).unwrap().display()),
+                        Item::Class(class) => format!("class {}", pool.retrieve(class.name).unwrap().display()),
+                        item => panic!("unexpected constant {item:?}"),
+                    })
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            constants,
+            [
+                "int 1",
+                "float 1.5",
+                "float 2",
+                "int -3",
+                "string text",
+                "class java/lang/String",
+                "long 1",
+                "double 1.5",
+                "double 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn invokeinterface_counts_the_argument_slots() {
+        let bytes = class(concat!(
+            ".method public static m (LI;)V\n",
+            "    .limit stack 6\n",
+            "    .limit locals 1\n",
+            "    aload_0\n",
+            "    lconst_0\n",
+            "    dconst_0\n",
+            "    iconst_0\n",
+            "    invokeinterface I/m(JDI)V\n",
+            "    aload_0\n",
+            "    invokeinterface I/n()V 3\n",
+            "    return\n",
+            ".end method\n",
+        ));
+        let class = Class::new(&bytes).unwrap();
+        let (_, code) = first_code(&class);
+
+        let counts: Vec<u8> = code
+            .raw_instructions()
+            .filter_map(|instruction| match instruction.unwrap().1 {
+                RawInstruction::InvokeInterface { count, .. } => Some(count),
+                _ => None,
+            })
+            .collect();
+        // the receiver and the slots of the arguments, while an explicit count is kept
+        assert_eq!(counts, [6, 3]);
+    }
+
+    #[test]
+    fn errors_are_reported_at_the_item_written_last() {
+        assert_eq!(
+            error_at(
+                concat!(
+                    ".method public static m (I)V\n",
+                    "    .limit stack 1\n",
+                    "    .limit locals 1\n",
+                    "    iload_0\n",
+                    "    lookupswitch\n",
+                    "        1 : a\n",
+                    "        1 : a\n",
+                    "        default : a\n",
+                    "a:  return\n",
+                    ".end method\n",
+                ),
+                |kind| matches!(kind, EncodeErrorKind::InvalidKeyOrder)
+            ),
+            (7, 5)
+        );
+        assert_eq!(
+            error_at(
+                concat!(
+                    ".method public static m ()V\n",
+                    "    .limit stack 2\n",
+                    "    .limit locals 0\n",
+                    "    ldc long 1\n",
+                    "    return\n",
+                    ".end method\n",
+                ),
+                |kind| matches!(kind, EncodeErrorKind::InvalidConstant)
+            ),
+            (6, 5)
+        );
+        assert_eq!(
+            error_at(
+                concat!(
+                    ".method public static m ()V\n",
+                    "    .limit stack 2\n",
+                    "    .limit locals 0\n",
+                    "    ldc 1\n",
+                    "    pop\n",
+                    "    ldc2_w \"text\"\n",
+                    "    return\n",
+                    ".end method\n",
+                ),
+                |kind| matches!(kind, EncodeErrorKind::InvalidConstant)
+            ),
+            (8, 5)
+        );
+        assert_eq!(
+            error_at(
+                concat!(
+                    ".method public static m ()V\n",
+                    "    .limit stack 0\n",
+                    "    .limit locals 1\n",
+                    "a:  nop\n",
+                    "b:  return\n",
+                    "    .var 0 is x I from b to a\n",
+                    ".end method\n",
+                ),
+                |kind| matches!(kind, EncodeErrorKind::NegativeOffset)
+            ),
+            (8, 5)
+        );
+    }
+}
@@ -0,0 +1,395 @@
+use crate::error::*;
+use crate::mutf8::MString;
+use crate::reader::attributes::ArrayType;
+use crate::writer::{
+    attributes::code::{InstructionWriter, LabelRef},
+    cpool,
+    encoding::EncoderContext,
+    ClassWriter, ClassWriterState,
+};
+
+pub(super) type Writer = InstructionWriter<ClassWriter<ClassWriterState::Methods>>;
+pub(super) type WriteNone = for<'w> fn(&'w mut Writer) -> Result<&'w mut Writer, EncodeError>;
+pub(super) type Write<T> = for<'w> fn(&'w mut Writer, T) -> Result<&'w mut Writer, EncodeError>;
+pub(super) type FieldRef = cpool::FieldRefInserter<MString, (MString, MString)>;
+pub(super) type MethodRef = cpool::MethodRefInserter<MString, (MString, MString)>;
+pub(super) type InterfaceMethodRef = cpool::InterfaceMethodRefInserter<MString, (MString, MString)>;
+
+/// How the operands of an instruction are written, and the methods writing it.
+#[derive(Clone, Copy)]
+pub(super) enum Shape {
+    None(WriteNone),
+    /// A local variable index, with the method for indices which fit into a byte and the one for the `wide` form.
+    Local(Write<u8>, Write<u16>),
+    Iinc,
+    Byte(Write<i8>),
+    Short(Write<i16>),
+    Label(Write<LabelRef>),
+    Class(Write<MString>),
+    MultiANewArray,
+    NewArray,
+    Field(Write<FieldRef>),
+    /// A method, with the method for interface methods if the instruction may also invoke them.
+    Method(Write<MethodRef>, Option<Write<InterfaceMethodRef>>),
+    InvokeInterface,
+    InvokeDynamic,
+    /// `ldc` and `ldc_w`, which take 32-bit constants.
+    Constant(Write<Constant>),
+    /// `ldc2_w`, which takes 64-bit constants.
+    WideConstant,
+    TableSwitch,
+    LookupSwitch,
+}
+
+/// A constant loaded by `ldc`, `ldc_w` or `ldc2_w`, or a static argument of a bootstrap method.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Constant {
+    Integer(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(MString),
+    Class(MString),
+    MethodType(MString),
+    MethodHandle(Handle),
+    Dynamic(Box<Dynamic>),
+}
+
+impl Constant {
+    /// Returns whether the constant takes up two stack slots, so that it is loaded by `ldc2_w`.
+    pub(super) fn is_wide(&self) -> bool {
+        match self {
+            Constant::Long(_) | Constant::Double(_) => true,
+            Constant::Dynamic(dynamic) => matches!(dynamic.descriptor.as_bytes(), b"J" | b"D"),
+            _ => false,
+        }
+    }
+}
+
+impl cpool::Insertable<cpool::Item> for Constant {
+    fn insert<Ctx: EncoderContext>(self, context: &mut Ctx) -> Result<cpool::Index<cpool::Item>, EncodeError> {
+        match self {
+            Constant::Integer(value) => value.insert(context),
+            Constant::Long(value) => value.insert(context),
+            Constant::Float(value) => value.insert(context),
+            Constant::Double(value) => value.insert(context),
+            Constant::String(value) => cpool::String::by(value).insert(context),
+            Constant::Class(value) => cpool::Class::by(value).insert(context),
+            Constant::MethodType(descriptor) => cpool::MethodType::by(descriptor).insert(context),
+            Constant::MethodHandle(handle) => {
+                Ok(cpool::Insertable::<cpool::MethodHandle>::insert(handle, context)?.as_item())
+            }
+            Constant::Dynamic(dynamic) => {
+                let Dynamic {
+                    name,
+                    descriptor,
+                    bootstrap,
+                } = *dynamic;
+                cpool::Dynamic::by(bootstrap, (name, descriptor)).insert(context)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct MemberRef {
+    pub(super) class: MString,
+    pub(super) name: MString,
+    pub(super) descriptor: MString,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Handle {
+    pub(super) kind: cpool::MethodKind,
+    /// Whether the referenced method is declared by an interface.
+    pub(super) interface: bool,
+    pub(super) member: MemberRef,
+}
+
+impl cpool::Insertable<cpool::MethodHandle> for Handle {
+    fn insert<Ctx: EncoderContext>(self, context: &mut Ctx) -> Result<cpool::Index<cpool::MethodHandle>, EncodeError> {
+        use cpool::MethodKind::*;
+
+        let MemberRef {
+            class,
+            name,
+            descriptor,
+        } = self.member;
+        let reference: cpool::Index<cpool::Item> = match self.kind {
+            GetField | GetStatic | PutField | PutStatic => {
+                cpool::FieldRef::by(class, (name, descriptor)).insert(context)?
+            }
+            _ if self.interface => cpool::InterfaceMethodRef::by(class, (name, descriptor)).insert(context)?,
+            _ => cpool::MethodRef::by(class, (name, descriptor)).insert(context)?,
+        };
+        cpool::MethodHandle::by(self.kind, reference).insert(context)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Bootstrap {
+    pub(super) handle: Handle,
+    pub(super) arguments: Vec<Constant>,
+}
+
+impl cpool::InsertableBootstrapMethod for Bootstrap {
+    fn insert<Ctx: EncoderContext>(self, context: &mut Ctx) -> Result<u16, EncodeError> {
+        cpool::InsertableBootstrapMethod::insert(cpool::BootstrapMethod::by(self.handle, self.arguments), context)
+    }
+}
+
+/// A dynamically-computed constant.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Dynamic {
+    pub(super) name: MString,
+    pub(super) descriptor: MString,
+    pub(super) bootstrap: Bootstrap,
+}
+
+pub(super) fn array_type(name: &str) -> Option<ArrayType> {
+    Some(match name {
+        "boolean" => ArrayType::Boolean,
+        "char" => ArrayType::Char,
+        "float" => ArrayType::Float,
+        "double" => ArrayType::Double,
+        "byte" => ArrayType::Byte,
+        "short" => ArrayType::Short,
+        "int" => ArrayType::Int,
+        "long" => ArrayType::Long,
+        _ => return None,
+    })
+}
+
+pub(super) fn shape(mnemonic: &str) -> Option<Shape> {
+    INSTRUCTIONS
+        .binary_search_by_key(&mnemonic, |&(name, _)| name)
+        .ok()
+        .map(|index| INSTRUCTIONS[index].1)
+}
+
+/// All instructions apart from `wide`, sorted by their names.
+const INSTRUCTIONS: &[(&str, Shape)] = &[
+    ("aaload", Shape::None(Writer::aaload)),
+    ("aastore", Shape::None(Writer::aastore)),
+    ("aconst_null", Shape::None(Writer::aconstnull)),
+    ("aload", Shape::Local(Writer::aload, Writer::aload_wide)),
+    ("aload_0", Shape::None(Writer::aload0)),
+    ("aload_1", Shape::None(Writer::aload1)),
+    ("aload_2", Shape::None(Writer::aload2)),
+    ("aload_3", Shape::None(Writer::aload3)),
+    ("anewarray", Shape::Class(Writer::anewarray::<MString>)),
+    ("areturn", Shape::None(Writer::areturn)),
+    ("arraylength", Shape::None(Writer::arraylength)),
+    ("astore", Shape::Local(Writer::astore, Writer::astore_wide)),
+    ("astore_0", Shape::None(Writer::astore0)),
+    ("astore_1", Shape::None(Writer::astore1)),
+    ("astore_2", Shape::None(Writer::astore2)),
+    ("astore_3", Shape::None(Writer::astore3)),
+    ("athrow", Shape::None(Writer::athrow)),
+    ("baload", Shape::None(Writer::baload)),
+    ("bastore", Shape::None(Writer::bastore)),
+    ("bipush", Shape::Byte(Writer::bipush)),
+    ("caload", Shape::None(Writer::caload)),
+    ("castore", Shape::None(Writer::castore)),
+    ("checkcast", Shape::Class(Writer::checkcast::<MString>)),
+    ("d2f", Shape::None(Writer::d2f)),
+    ("d2i", Shape::None(Writer::d2i)),
+    ("d2l", Shape::None(Writer::d2l)),
+    ("dadd", Shape::None(Writer::dadd)),
+    ("daload", Shape::None(Writer::daload)),
+    ("dastore", Shape::None(Writer::dastore)),
+    ("dcmpg", Shape::None(Writer::dcmpg)),
+    ("dcmpl", Shape::None(Writer::dcmpl)),
+    ("dconst_0", Shape::None(Writer::dconst0)),
+    ("dconst_1", Shape::None(Writer::dconst1)),
+    ("ddiv", Shape::None(Writer::ddiv)),
+    ("dload", Shape::Local(Writer::dload, Writer::dload_wide)),
+    ("dload_0", Shape::None(Writer::dload0)),
+    ("dload_1", Shape::None(Writer::dload1)),
+    ("dload_2", Shape::None(Writer::dload2)),
+    ("dload_3", Shape::None(Writer::dload3)),
+    ("dmul", Shape::None(Writer::dmul)),
+    ("dneg", Shape::None(Writer::dneg)),
+    ("drem", Shape::None(Writer::drem)),
+    ("dreturn", Shape::None(Writer::dreturn)),
+    ("dstore", Shape::Local(Writer::dstore, Writer::dstore_wide)),
+    ("dstore_0", Shape::None(Writer::dstore0)),
+    ("dstore_1", Shape::None(Writer::dstore1)),
+    ("dstore_2", Shape::None(Writer::dstore2)),
+    ("dstore_3", Shape::None(Writer::dstore3)),
+    ("dsub", Shape::None(Writer::dsub)),
+    ("dup", Shape::None(Writer::dup)),
+    ("dup2", Shape::None(Writer::dup2)),
+    ("dup2_x1", Shape::None(Writer::dup2x1)),
+    ("dup2_x2", Shape::None(Writer::dup2x2)),
+    ("dup_x1", Shape::None(Writer::dupx1)),
+    ("dup_x2", Shape::None(Writer::dupx2)),
+    ("f2d", Shape::None(Writer::f2d)),
+    ("f2i", Shape::None(Writer::f2i)),
+    ("f2l", Shape::None(Writer::f2l)),
+    ("fadd", Shape::None(Writer::fadd)),
+    ("faload", Shape::None(Writer::faload)),
+    ("fastore", Shape::None(Writer::fastore)),
+    ("fcmpg", Shape::None(Writer::fcmpg)),
+    ("fcmpl", Shape::None(Writer::fcmpl)),
+    ("fconst_0", Shape::None(Writer::fconst0)),
+    ("fconst_1", Shape::None(Writer::fconst1)),
+    ("fconst_2", Shape::None(Writer::fconst2)),
+    ("fdiv", Shape::None(Writer::fdiv)),
+    ("fload", Shape::Local(Writer::fload, Writer::fload_wide)),
+    ("fload_0", Shape::None(Writer::fload0)),
+    ("fload_1", Shape::None(Writer::fload1)),
+    ("fload_2", Shape::None(Writer::fload2)),
+    ("fload_3", Shape::None(Writer::fload3)),
+    ("fmul", Shape::None(Writer::fmul)),
+    ("fneg", Shape::None(Writer::fneg)),
+    ("frem", Shape::None(Writer::frem)),
+    ("freturn", Shape::None(Writer::freturn)),
+    ("fstore", Shape::Local(Writer::fstore, Writer::fstore_wide)),
+    ("fstore_0", Shape::None(Writer::fstore0)),
+    ("fstore_1", Shape::None(Writer::fstore1)),
+    ("fstore_2", Shape::None(Writer::fstore2)),
+    ("fstore_3", Shape::None(Writer::fstore3)),
+    ("fsub", Shape::None(Writer::fsub)),
+    ("getfield", Shape::Field(Writer::getfield::<FieldRef>)),
+    ("getstatic", Shape::Field(Writer::getstatic::<FieldRef>)),
+    ("goto", Shape::Label(Writer::goto)),
+    ("goto_w", Shape::Label(Writer::gotow)),
+    ("i2b", Shape::None(Writer::i2b)),
+    ("i2c", Shape::None(Writer::i2c)),
+    ("i2d", Shape::None(Writer::i2d)),
+    ("i2f", Shape::None(Writer::i2f)),
+    ("i2l", Shape::None(Writer::i2l)),
+    ("i2s", Shape::None(Writer::i2s)),
+    ("iadd", Shape::None(Writer::iadd)),
+    ("iaload", Shape::None(Writer::iaload)),
+    ("iand", Shape::None(Writer::iand)),
+    ("iastore", Shape::None(Writer::iastore)),
+    ("iconst_0", Shape::None(Writer::iconst0)),
+    ("iconst_1", Shape::None(Writer::iconst1)),
+    ("iconst_2", Shape::None(Writer::iconst2)),
+    ("iconst_3", Shape::None(Writer::iconst3)),
+    ("iconst_4", Shape::None(Writer::iconst4)),
+    ("iconst_5", Shape::None(Writer::iconst5)),
+    ("iconst_m1", Shape::None(Writer::iconstm1)),
+    ("idiv", Shape::None(Writer::idiv)),
+    ("if_acmpeq", Shape::Label(Writer::ifacmpeq)),
+    ("if_acmpne", Shape::Label(Writer::ifacmpne)),
+    ("if_icmpeq", Shape::Label(Writer::ificmpeq)),
+    ("if_icmpge", Shape::Label(Writer::ificmpge)),
+    ("if_icmpgt", Shape::Label(Writer::ificmpgt)),
+    ("if_icmple", Shape::Label(Writer::ificmple)),
+    ("if_icmplt", Shape::Label(Writer::ificmplt)),
+    ("if_icmpne", Shape::Label(Writer::ificmpne)),
+    ("ifeq", Shape::Label(Writer::ifeq)),
+    ("ifge", Shape::Label(Writer::ifge)),
+    ("ifgt", Shape::Label(Writer::ifgt)),
+    ("ifle", Shape::Label(Writer::ifle)),
+    ("iflt", Shape::Label(Writer::iflt)),
+    ("ifne", Shape::Label(Writer::ifne)),
+    ("ifnonnull", Shape::Label(Writer::ifnonnull)),
+    ("ifnull", Shape::Label(Writer::ifnull)),
+    ("iinc", Shape::Iinc),
+    ("iload", Shape::Local(Writer::iload, Writer::iload_wide)),
+    ("iload_0", Shape::None(Writer::iload0)),
+    ("iload_1", Shape::None(Writer::iload1)),
+    ("iload_2", Shape::None(Writer::iload2)),
+    ("iload_3", Shape::None(Writer::iload3)),
+    ("imul", Shape::None(Writer::imul)),
+    ("ineg", Shape::None(Writer::ineg)),
+    ("instanceof", Shape::Class(Writer::instanceof::<MString>)),
+    ("invokedynamic", Shape::InvokeDynamic),
+    ("invokeinterface", Shape::InvokeInterface),
+    (
+        "invokespecial",
+        Shape::Method(
+            Writer::invokespecial::<MethodRef>,
+            Some(Writer::invokespecial::<InterfaceMethodRef>),
+        ),
+    ),
+    (
+        "invokestatic",
+        Shape::Method(
+            Writer::invokestatic::<MethodRef>,
+            Some(Writer::invokestatic::<InterfaceMethodRef>),
+        ),
+    ),
+    ("invokevirtual", Shape::Method(Writer::invokevirtual::<MethodRef>, None)),
+    ("ior", Shape::None(Writer::ior)),
+    ("irem", Shape::None(Writer::irem)),
+    ("ireturn", Shape::None(Writer::ireturn)),
+    ("ishl", Shape::None(Writer::ishl)),
+    ("ishr", Shape::None(Writer::ishr)),
+    ("istore", Shape::Local(Writer::istore, Writer::istore_wide)),
+    ("istore_0", Shape::None(Writer::istore0)),
+    ("istore_1", Shape::None(Writer::istore1)),
+    ("istore_2", Shape::None(Writer::istore2)),
+    ("istore_3", Shape::None(Writer::istore3)),
+    ("isub", Shape::None(Writer::isub)),
+    ("iushr", Shape::None(Writer::iushr)),
+    ("ixor", Shape::None(Writer::ixor)),
+    ("jsr", Shape::Label(Writer::jsr)),
+    ("jsr_w", Shape::Label(Writer::jsrw)),
+    ("l2d", Shape::None(Writer::l2d)),
+    ("l2f", Shape::None(Writer::l2f)),
+    ("l2i", Shape::None(Writer::l2i)),
+    ("ladd", Shape::None(Writer::ladd)),
+    ("laload", Shape::None(Writer::laload)),
+    ("land", Shape::None(Writer::land)),
+    ("lastore", Shape::None(Writer::lastore)),
+    ("lcmp", Shape::None(Writer::lcmp)),
+    ("lconst_0", Shape::None(Writer::lconst0)),
+    ("lconst_1", Shape::None(Writer::lconst1)),
+    ("ldc", Shape::Constant(Writer::ldc::<Constant>)),
+    ("ldc2_w", Shape::WideConstant),
+    ("ldc_w", Shape::Constant(Writer::ldcw::<Constant>)),
+    ("ldiv", Shape::None(Writer::ldiv)),
+    ("lload", Shape::Local(Writer::lload, Writer::lload_wide)),
+    ("lload_0", Shape::None(Writer::lload0)),
+    ("lload_1", Shape::None(Writer::lload1)),
+    ("lload_2", Shape::None(Writer::lload2)),
+    ("lload_3", Shape::None(Writer::lload3)),
+    ("lmul", Shape::None(Writer::lmul)),
+    ("lneg", Shape::None(Writer::lneg)),
+    ("lookupswitch", Shape::LookupSwitch),
+    ("lor", Shape::None(Writer::lor)),
+    ("lrem", Shape::None(Writer::lrem)),
+    ("lreturn", Shape::None(Writer::lreturn)),
+    ("lshl", Shape::None(Writer::lshl)),
+    ("lshr", Shape::None(Writer::lshr)),
+    ("lstore", Shape::Local(Writer::lstore, Writer::lstore_wide)),
+    ("lstore_0", Shape::None(Writer::lstore0)),
+    ("lstore_1", Shape::None(Writer::lstore1)),
+    ("lstore_2", Shape::None(Writer::lstore2)),
+    ("lstore_3", Shape::None(Writer::lstore3)),
+    ("lsub", Shape::None(Writer::lsub)),
+    ("lushr", Shape::None(Writer::lushr)),
+    ("lxor", Shape::None(Writer::lxor)),
+    ("monitorenter", Shape::None(Writer::monitorenter)),
+    ("monitorexit", Shape::None(Writer::monitorexit)),
+    ("multianewarray", Shape::MultiANewArray),
+    ("new", Shape::Class(Writer::new::<MString>)),
+    ("newarray", Shape::NewArray),
+    ("nop", Shape::None(Writer::nop)),
+    ("pop", Shape::None(Writer::pop)),
+    ("pop2", Shape::None(Writer::pop2)),
+    ("putfield", Shape::Field(Writer::putfield::<FieldRef>)),
+    ("putstatic", Shape::Field(Writer::putstatic::<FieldRef>)),
+    ("ret", Shape::Local(Writer::ret, Writer::ret_wide)),
+    ("return", Shape::None(Writer::return_)),
+    ("saload", Shape::None(Writer::saload)),
+    ("sastore", Shape::None(Writer::sastore)),
+    ("sipush", Shape::Short(Writer::sipush)),
+    ("swap", Shape::None(Writer::swap)),
+    ("tableswitch", Shape::TableSwitch),
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sorted() {
+        assert!(INSTRUCTIONS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+}
@@ -0,0 +1,148 @@
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use crate::error::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum TokenKind<'a> {
+    /// A run of characters up to the next whitespace, colon or string.
+    Word(&'a str),
+    /// A string literal with its escape sequences resolved.
+    String(String),
+    Colon,
+    Newline,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Token<'a> {
+    pub(super) kind: TokenKind<'a>,
+    pub(super) position: Position,
+}
+
+/// A position in the source, where both the line and column start at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Position {
+    pub(super) line: usize,
+    pub(super) column: usize,
+}
+
+impl Position {
+    pub(super) fn error(self, kind: AssembleErrorKind) -> AssembleError {
+        AssembleError::new(kind, self.line, self.column)
+    }
+}
+
+/// Splits the source into tokens, leaving out whitespace and comments.
+pub(super) fn tokenize(source: &str) -> Result<Vec<Token<'_>>, AssembleError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    let mut position = Position { line: 1, column: 1 };
+
+    while let Some(&(start, c)) = chars.peek() {
+        let token_position = position;
+        match c {
+            '\n' => {
+                chars.next();
+                tokens.push(Token {
+                    kind: TokenKind::Newline,
+                    position,
+                });
+                position = Position {
+                    line: position.line + 1,
+                    column: 1,
+                };
+            }
+            ';' => while chars.next_if(|&(_, c)| c != '\n').is_some() {},
+            ':' => {
+                chars.next();
+                position.column += 1;
+                tokens.push(Token {
+                    kind: TokenKind::Colon,
+                    position: token_position,
+                });
+            }
+            '"' => {
+                chars.next();
+                position.column += 1;
+                let mut content = String::new();
+                loop {
+                    let (_, c) = chars
+                        .next()
+                        .ok_or_else(|| position.error(AssembleErrorKind::UnexpectedEnd))?;
+                    let escape_position = position;
+                    position.column += 1;
+                    match c {
+                        '"' => break,
+                        '\n' => return Err(escape_position.error(AssembleErrorKind::InvalidString)),
+                        '\\' => {
+                            let (_, escape) = chars
+                                .next()
+                                .ok_or_else(|| position.error(AssembleErrorKind::UnexpectedEnd))?;
+                            position.column += 1;
+                            let c = match escape {
+                                'n' => '\n',
+                                'r' => '\r',
+                                't' => '\t',
+                                '0' => '\0',
+                                '\\' | '"' | '\'' => escape,
+                                'u' => {
+                                    let invalid = || escape_position.error(AssembleErrorKind::InvalidString);
+                                    let mut code = code_unit(&mut chars, &mut position).ok_or_else(invalid)?;
+                                    // characters outside of the BMP are written as a surrogate pair
+                                    if (0xD800..0xDC00).contains(&code) {
+                                        if chars.next_if(|&(_, c)| c == '\\').is_none()
+                                            || chars.next_if(|&(_, c)| c == 'u').is_none()
+                                        {
+                                            return Err(invalid());
+                                        }
+                                        position.column += 2;
+                                        let low = code_unit(&mut chars, &mut position)
+                                            .filter(|low| (0xDC00..0xE000).contains(low))
+                                            .ok_or_else(invalid)?;
+                                        code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                                    }
+                                    char::from_u32(code).ok_or_else(invalid)?
+                                }
+                                _ => return Err(escape_position.error(AssembleErrorKind::InvalidString)),
+                            };
+                            content.push(c);
+                        }
+                        c => content.push(c),
+                    }
+                }
+                tokens.push(Token {
+                    kind: TokenKind::String(content),
+                    position: token_position,
+                });
+            }
+            c if c.is_whitespace() => {
+                chars.next();
+                position.column += 1;
+            }
+            _ => {
+                let mut end = start;
+                while let Some((index, c)) = chars.next_if(|&(_, c)| !c.is_whitespace() && c != ':' && c != '"') {
+                    end = index + c.len_utf8();
+                    position.column += 1;
+                }
+                tokens.push(Token {
+                    kind: TokenKind::Word(&source[start..end]),
+                    position: token_position,
+                });
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Reads the four hexadecimal digits of a `\u` escape sequence.
+fn code_unit(chars: &mut Peekable<CharIndices<'_>>, position: &mut Position) -> Option<u32> {
+    let mut code = 0;
+    for _ in 0..4 {
+        let digit = chars.next()?.1.to_digit(16)?;
+        position.column += 1;
+        code = code * 16 + digit;
+    }
+    Some(code)
+}
@@ -0,0 +1,1402 @@
+use std::str::FromStr;
+
+use crate::descriptor::{BaseType, MethodDescriptor, TypeDescriptor};
+use crate::error::*;
+use crate::header::{AccessFlags, Version};
+use crate::mutf8::{MStr, MString};
+use crate::reader::attributes::ArrayType;
+use crate::writer::attributes::code::LabelRef;
+use crate::writer::cpool::MethodKind;
+
+use super::instructions::{self, Bootstrap, Constant, Dynamic, Handle, MemberRef, Shape, Write, WriteNone};
+use super::lexer::{self, Position, Token, TokenKind};
+
+pub(super) struct Class {
+    pub(super) version: Version,
+    pub(super) access_flags: AccessFlags,
+    pub(super) name: MString,
+    /// The super class, which is only missing for `java/lang/Object` and modules.
+    pub(super) super_class: Option<MString>,
+    pub(super) interfaces: Vec<MString>,
+    pub(super) fields: Vec<Field>,
+    pub(super) methods: Vec<Method>,
+    pub(super) attributes: Vec<Attribute>,
+}
+
+pub(super) struct Field {
+    pub(super) position: Position,
+    pub(super) access_flags: AccessFlags,
+    pub(super) name: MString,
+    pub(super) descriptor: MString,
+    pub(super) attributes: Vec<Attribute>,
+}
+
+pub(super) struct Method {
+    pub(super) position: Position,
+    pub(super) access_flags: AccessFlags,
+    pub(super) name: MString,
+    pub(super) descriptor: MString,
+    pub(super) attributes: Vec<Attribute>,
+    /// The code, unless the method has neither instructions nor limits.
+    pub(super) code: Option<Code>,
+}
+
+pub(super) struct Attribute {
+    pub(super) position: Position,
+    pub(super) kind: AttributeKind,
+}
+
+pub(super) enum AttributeKind {
+    SourceFile(MString),
+    Signature(MString),
+    Deprecated,
+    Synthetic,
+    ConstantValue(Constant),
+    Exceptions(Vec<MString>),
+    InnerClasses(Vec<InnerClass>),
+    EnclosingMethod {
+        class: MString,
+        /// The name and descriptor of the method.
+        method: Option<(MString, MString)>,
+    },
+    NestHost(MString),
+    NestMembers(Vec<MString>),
+    PermittedSubclasses(Vec<MString>),
+    Record(Vec<RecordComponent>),
+    MethodParameters(Vec<Parameter>),
+    Annotations {
+        visible: bool,
+        annotations: Vec<Annotation>,
+    },
+    ParameterAnnotations {
+        visible: bool,
+        parameters: Vec<Vec<Annotation>>,
+    },
+    AnnotationDefault(ElementValue),
+    Raw(MString, Vec<u8>),
+}
+
+impl AttributeKind {
+    /// Whether the attribute only applies to classes, even if it directly follows a field.
+    fn is_class_only(&self) -> bool {
+        matches!(
+            self,
+            AttributeKind::SourceFile(_)
+                | AttributeKind::InnerClasses(_)
+                | AttributeKind::EnclosingMethod { .. }
+                | AttributeKind::NestHost(_)
+                | AttributeKind::NestMembers(_)
+                | AttributeKind::PermittedSubclasses(_)
+                | AttributeKind::Record(_)
+        )
+    }
+
+    /// Adds the entries of an attribute of the same kind, or returns the other attribute if they can't be merged.
+    fn merge(&mut self, other: AttributeKind) -> Option<AttributeKind> {
+        use AttributeKind::*;
+
+        match (self, other) {
+            (Exceptions(classes), Exceptions(other))
+            | (NestMembers(classes), NestMembers(other))
+            | (PermittedSubclasses(classes), PermittedSubclasses(other)) => classes.extend(other),
+            (InnerClasses(classes), InnerClasses(other)) => classes.extend(other),
+            (Record(components), Record(other)) => components.extend(other),
+            (MethodParameters(parameters), MethodParameters(other)) => parameters.extend(other),
+            (
+                Annotations { visible, annotations },
+                Annotations {
+                    visible: other_visible,
+                    annotations: other,
+                },
+            ) if *visible == other_visible => annotations.extend(other),
+            (
+                ParameterAnnotations { visible, parameters },
+                ParameterAnnotations {
+                    visible: other_visible,
+                    parameters: other,
+                },
+            ) if *visible == other_visible => {
+                if parameters.len() < other.len() {
+                    parameters.resize_with(other.len(), Vec::new);
+                }
+                for (annotations, other) in parameters.iter_mut().zip(other) {
+                    annotations.extend(other);
+                }
+            }
+            (_, other) => return Some(other),
+        }
+        None
+    }
+}
+
+/// Adds an attribute, merging it into an existing one if both are written by repeated directives.
+fn add_attribute(attributes: &mut Vec<Attribute>, attribute: Attribute) {
+    let mut kind = attribute.kind;
+    for existing in attributes.iter_mut() {
+        match existing.kind.merge(kind) {
+            Some(other) => kind = other,
+            None => return,
+        }
+    }
+    attributes.push(Attribute {
+        position: attribute.position,
+        kind,
+    });
+}
+
+pub(super) struct InnerClass {
+    pub(super) access_flags: AccessFlags,
+    pub(super) inner: MString,
+    pub(super) outer: Option<MString>,
+    pub(super) name: Option<MString>,
+}
+
+pub(super) struct RecordComponent {
+    pub(super) name: MString,
+    pub(super) descriptor: MString,
+    pub(super) attributes: Vec<Attribute>,
+}
+
+pub(super) struct Parameter {
+    pub(super) access_flags: AccessFlags,
+    pub(super) name: Option<MString>,
+}
+
+#[derive(Clone)]
+pub(super) struct Annotation {
+    pub(super) type_: MString,
+    pub(super) pairs: Vec<(MString, ElementValue)>,
+}
+
+#[derive(Clone)]
+pub(super) enum ElementValue {
+    Boolean(bool),
+    Byte(i8),
+    Short(i16),
+    Char(u16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(MString),
+    Class(MString),
+    Enum(MString, MString),
+    Annotation(Annotation),
+    Array(Vec<ElementValue>),
+}
+
+#[derive(Default)]
+pub(super) struct Code {
+    pub(super) max_stack: Option<u16>,
+    pub(super) max_locals: Option<u16>,
+    /// The names of all labels defined in the method.
+    pub(super) labels: Vec<String>,
+    pub(super) statements: Vec<Statement>,
+    pub(super) catches: Vec<Catch>,
+    pub(super) variables: Vec<Variable>,
+    pub(super) frames: Vec<Frame>,
+}
+
+pub(super) struct Statement {
+    pub(super) position: Position,
+    pub(super) kind: StatementKind,
+}
+
+pub(super) enum StatementKind {
+    /// The label at an index of [`Code::labels`].
+    Label(usize),
+    Line(u16),
+    Instruction(Instruction),
+}
+
+/// An instruction with its operands, where labels are indices of [`Code::labels`].
+pub(super) enum Instruction {
+    None(WriteNone),
+    Local(Write<u8>, Write<u16>, u16),
+    WideLocal(Write<u16>, u16),
+    Iinc(u16, i16),
+    WideIinc(u16, i16),
+    Byte(Write<i8>, i8),
+    Short(Write<i16>, i16),
+    Label(Write<LabelRef>, usize),
+    Class(Write<MString>, MString),
+    MultiANewArray(MString, u8),
+    NewArray(ArrayType),
+    Field(Write<instructions::FieldRef>, MemberRef),
+    Method(Write<instructions::MethodRef>, MemberRef),
+    InterfaceMethod(Write<instructions::InterfaceMethodRef>, MemberRef),
+    InvokeInterface(MemberRef, u8),
+    InvokeDynamic(Dynamic),
+    Constant(Write<Constant>, Constant),
+    WideConstant(Constant),
+    TableSwitch {
+        low: i32,
+        labels: Vec<usize>,
+        default: usize,
+    },
+    LookupSwitch {
+        pairs: Vec<(i32, usize)>,
+        default: usize,
+    },
+}
+
+pub(super) struct Catch {
+    pub(super) position: Position,
+    /// The caught class, or `None` if every exception is caught.
+    pub(super) class: Option<MString>,
+    pub(super) start: usize,
+    pub(super) end: usize,
+    pub(super) handler: usize,
+}
+
+pub(super) struct Variable {
+    pub(super) position: Position,
+    pub(super) index: u16,
+    pub(super) name: MString,
+    pub(super) descriptor: MString,
+    pub(super) signature: Option<MString>,
+    pub(super) start: usize,
+    pub(super) end: usize,
+}
+
+pub(super) struct Frame {
+    pub(super) position: Position,
+    /// The label at the instruction the frame applies to.
+    pub(super) label: usize,
+    pub(super) kind: FrameKind,
+}
+
+pub(super) enum FrameKind {
+    Same,
+    SameExtended,
+    Same1(VerificationType),
+    Same1Extended(VerificationType),
+    Chop(u16),
+    Append(Vec<VerificationType>),
+    Full {
+        locals: Vec<VerificationType>,
+        stack: Vec<VerificationType>,
+    },
+}
+
+pub(super) enum VerificationType {
+    Top,
+    Integer,
+    Float,
+    Long,
+    Double,
+    Null,
+    UninitializedThis,
+    Object(MString),
+    /// An object created by the `new` instruction at the label.
+    Uninitialized(usize),
+}
+
+/// What directives which add attributes apply to.
+#[derive(Clone, Copy)]
+enum Member {
+    Class,
+    Field,
+    RecordComponent,
+}
+
+pub(super) fn parse(source: &str) -> Result<Class, AssembleError> {
+    let tokens = lexer::tokenize(source)?;
+    let end = tokens
+        .last()
+        .map_or(Position { line: 1, column: 1 }, |token| token.position);
+    Parser { tokens, index: 0, end }.class()
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    index: usize,
+    /// The position of the last token, at which a missing token is reported.
+    end: Position,
+}
+
+impl<'a> Parser<'a> {
+    fn class(&mut self) -> Result<Class, AssembleError> {
+        let mut version = Version::V8;
+        let mut class: Option<(AccessFlags, MString)> = None;
+        let mut super_class = None;
+        let mut interfaces = Vec::new();
+        let mut fields: Vec<Field> = Vec::new();
+        let mut member = Member::Class;
+        let mut methods = Vec::new();
+        let mut attributes = Vec::new();
+
+        while let Some((directive, position)) = self.next_line()? {
+            match directive {
+                ".version" => {
+                    let major = self.number("a major version")?;
+                    let minor = if self.at_line_end() {
+                        0
+                    } else {
+                        self.number("a minor version")?
+                    };
+                    version = Version { major, minor };
+                }
+                ".class" => {
+                    let (flags, mut names) = self.flags_and_names()?;
+                    let name = names.pop().ok_or_else(|| self.expected("a class name"))?;
+                    if let Some(name) = names.first() {
+                        return Err(name.1.error(AssembleErrorKind::UnknownAccessFlag));
+                    }
+                    class = Some((flags, name.0));
+                }
+                ".super" => {
+                    super_class = Some(if self.at_line_end() {
+                        None
+                    } else {
+                        Some(self.name("a class name")?)
+                    });
+                }
+                ".implements" => interfaces.push(self.name("a class name")?),
+                ".field" => {
+                    fields.push(self.field(position)?);
+                    member = Member::Field;
+                }
+                ".method" => {
+                    methods.push(self.method(position)?);
+                    member = Member::Class;
+                }
+                ".record_component" => {
+                    let component = self.record_component()?;
+                    let kind = AttributeKind::Record(vec![component]);
+                    add_attribute(&mut attributes, Attribute { position, kind });
+                    member = Member::RecordComponent;
+                }
+                _ => {
+                    let attribute = self
+                        .attribute(directive, position)?
+                        .ok_or_else(|| position.error(AssembleErrorKind::UnknownDirective))?;
+                    // attributes directly following a field or record component belong to it
+                    let target = match member {
+                        _ if attribute.kind.is_class_only() => None,
+                        Member::Class => None,
+                        Member::Field => fields.last_mut().map(|field| &mut field.attributes),
+                        Member::RecordComponent => attributes
+                            .iter_mut()
+                            .find_map(|attribute| match &mut attribute.kind {
+                                AttributeKind::Record(components) => components.last_mut(),
+                                _ => None,
+                            })
+                            .map(|component| &mut component.attributes),
+                    };
+                    match target {
+                        Some(target) => add_attribute(target, attribute),
+                        None => add_attribute(&mut attributes, attribute),
+                    }
+                }
+            }
+            self.line_end()?;
+        }
+
+        let (access_flags, name) = class.ok_or_else(|| self.end.error(AssembleErrorKind::Expected("`.class`")))?;
+        Ok(Class {
+            version,
+            access_flags,
+            name,
+            super_class: super_class.unwrap_or_else(|| Some(MString::from("java/lang/Object"))),
+            interfaces,
+            fields,
+            methods,
+            attributes,
+        })
+    }
+
+    fn field(&mut self, position: Position) -> Result<Field, AssembleError> {
+        let (access_flags, mut names) = self.flags_and_names()?;
+        let (descriptor, descriptor_position) = names.pop().ok_or_else(|| self.expected("a field descriptor"))?;
+        let name = names.pop().ok_or_else(|| self.expected("a field name"))?;
+        if let Some(name) = names.first() {
+            return Err(name.1.error(AssembleErrorKind::UnknownAccessFlag));
+        }
+        check_descriptor(&descriptor, descriptor_position, |d| TypeDescriptor::parse(d).is_ok())?;
+
+        let constant = if self.eat_word("=") {
+            let wide = *descriptor == *"J" || *descriptor == *"D";
+            // integers are also accepted as the values of floating point fields
+            Some(match self.constant(wide)? {
+                Constant::Integer(value) if *descriptor == *"F" => Constant::Float(value as f32),
+                Constant::Long(value) if *descriptor == *"D" => Constant::Double(value as f64),
+                constant => constant,
+            })
+        } else {
+            None
+        };
+
+        let attributes = constant
+            .map(|constant| Attribute {
+                position,
+                kind: AttributeKind::ConstantValue(constant),
+            })
+            .into_iter()
+            .collect();
+        Ok(Field {
+            position,
+            access_flags,
+            name: name.0,
+            descriptor,
+            attributes,
+        })
+    }
+
+    fn record_component(&mut self) -> Result<RecordComponent, AssembleError> {
+        let name = self.name("a component name")?;
+        let descriptor = self.field_descriptor()?;
+        Ok(RecordComponent {
+            name,
+            descriptor,
+            attributes: Vec::new(),
+        })
+    }
+
+    fn method(&mut self, position: Position) -> Result<Method, AssembleError> {
+        let (access_flags, mut names) = self.flags_and_names()?;
+        let (last, last_position) = names.pop().ok_or_else(|| self.expected("a method name"))?;
+        let glued = last.to_str().and_then(|last| last.find('(')).filter(|&at| at > 0);
+        let (name, descriptor) = match glued {
+            Some(at) => (MString::from(&last[..at]), MString::from(&last[at..])),
+            None => (names.pop().ok_or_else(|| self.expected("a method name"))?.0, last),
+        };
+        if let Some(name) = names.first() {
+            return Err(name.1.error(AssembleErrorKind::UnknownAccessFlag));
+        }
+        check_descriptor(&descriptor, last_position, |d| MethodDescriptor::parse(d).is_ok())?;
+        self.line_end()?;
+
+        let mut method = Method {
+            position,
+            access_flags,
+            name,
+            descriptor,
+            attributes: Vec::new(),
+            code: None,
+        };
+        let mut labels = Labels::default();
+        let mut limit_position = None;
+
+        loop {
+            self.skip_newlines();
+            let token = self
+                .next()
+                .ok_or_else(|| self.end.error(AssembleErrorKind::Expected("`.end method`")))?;
+            let position = token.position;
+            match token.kind {
+                TokenKind::Word(".end") => {
+                    self.keyword("method")?;
+                    break;
+                }
+                TokenKind::Word(".limit") => {
+                    limit_position = Some(position);
+                    let (kind, kind_position) = self.word("`stack` or `locals`")?;
+                    let value = Some(self.number("a limit")?);
+                    match kind {
+                        "stack" => labels.code.max_stack = value,
+                        "locals" => labels.code.max_locals = value,
+                        _ => return Err(kind_position.error(AssembleErrorKind::Expected("`stack` or `locals`"))),
+                    }
+                }
+                TokenKind::Word(".line") => {
+                    let line = self.number("a line number")?;
+                    labels.code.statements.push(Statement {
+                        position,
+                        kind: StatementKind::Line(line),
+                    });
+                }
+                TokenKind::Word(".catch") => {
+                    let class = if self.eat_word("all") {
+                        None
+                    } else {
+                        Some(self.name("a class name or `all`")?)
+                    };
+                    self.keyword("from")?;
+                    let start = self.label_reference(&mut labels)?;
+                    self.keyword("to")?;
+                    let end = self.label_reference(&mut labels)?;
+                    self.keyword("using")?;
+                    let handler = self.label_reference(&mut labels)?;
+                    labels.code.catches.push(Catch {
+                        position,
+                        class,
+                        start,
+                        end,
+                        handler,
+                    });
+                }
+                TokenKind::Word(".var") => {
+                    let index = self.number("a local variable index")?;
+                    self.keyword("is")?;
+                    let name = self.name("a local variable name")?;
+                    let descriptor = self.field_descriptor()?;
+                    let signature = if self.eat_word("signature") {
+                        Some(self.name("a signature")?)
+                    } else {
+                        None
+                    };
+                    self.keyword("from")?;
+                    let start = self.label_reference(&mut labels)?;
+                    self.keyword("to")?;
+                    let end = self.label_reference(&mut labels)?;
+                    labels.code.variables.push(Variable {
+                        position,
+                        index,
+                        name,
+                        descriptor,
+                        signature,
+                        start,
+                        end,
+                    });
+                }
+                TokenKind::Word(".throws") => {
+                    let kind = AttributeKind::Exceptions(vec![self.name("a class name")?]);
+                    add_attribute(&mut method.attributes, Attribute { position, kind });
+                }
+                TokenKind::Word(".frame") => self.frame(position, &mut labels)?,
+                TokenKind::Word(directive) if directive.starts_with('.') => {
+                    let attribute = self
+                        .attribute(directive, position)?
+                        .ok_or_else(|| position.error(AssembleErrorKind::UnknownDirective))?;
+                    add_attribute(&mut method.attributes, attribute);
+                }
+                TokenKind::Word(word) => {
+                    if self.peek().is_some_and(|token| token.kind == TokenKind::Colon) {
+                        self.next();
+                        let label = labels.define(word, position)?;
+                        labels.code.statements.push(Statement {
+                            position,
+                            kind: StatementKind::Label(label),
+                        });
+                        if self.at_line_end() {
+                            continue;
+                        }
+                        let (word, position) = self.word("an instruction")?;
+                        self.instruction(word, position, &mut labels)?;
+                    } else {
+                        self.instruction(word, position, &mut labels)?;
+                    }
+                }
+                _ => return Err(position.error(AssembleErrorKind::Expected("an instruction or directive"))),
+            }
+            self.line_end()?;
+        }
+
+        let Labels {
+            code,
+            defined,
+            references,
+        } = labels;
+        if let Some(&(_, position)) = references.iter().find(|&&(label, _)| !defined[label]) {
+            return Err(position.error(AssembleErrorKind::LabelNotFound));
+        }
+        if let Some(position) = limit_position {
+            if code.max_stack.is_none() || code.max_locals.is_none() {
+                return Err(position.error(AssembleErrorKind::Expected("both `.limit stack` and `.limit locals`")));
+            }
+        }
+        if !code.statements.is_empty() || limit_position.is_some() {
+            method.code = Some(code);
+        }
+        Ok(method)
+    }
+
+    fn instruction(&mut self, mnemonic: &str, position: Position, labels: &mut Labels) -> Result<(), AssembleError> {
+        let instruction = if mnemonic == "wide" {
+            let (mnemonic, position) = self.word("an instruction")?;
+            match instructions::shape(mnemonic) {
+                Some(Shape::Local(_, write)) => Instruction::WideLocal(write, self.number("a local variable index")?),
+                Some(Shape::Iinc) => {
+                    Instruction::WideIinc(self.number("a local variable index")?, self.number("an increment")?)
+                }
+                _ => return Err(position.error(AssembleErrorKind::Expected("an instruction with a wide form"))),
+            }
+        } else {
+            self.operands(mnemonic, position, labels)?
+        };
+
+        labels.code.statements.push(Statement {
+            position,
+            kind: StatementKind::Instruction(instruction),
+        });
+        Ok(())
+    }
+
+    fn operands(
+        &mut self,
+        mnemonic: &str,
+        position: Position,
+        labels: &mut Labels,
+    ) -> Result<Instruction, AssembleError> {
+        let shape =
+            instructions::shape(mnemonic).ok_or_else(|| position.error(AssembleErrorKind::UnknownInstruction))?;
+        Ok(match shape {
+            Shape::None(write) => Instruction::None(write),
+            Shape::Local(write, write_wide) => {
+                Instruction::Local(write, write_wide, self.number("a local variable index")?)
+            }
+            Shape::Iinc => Instruction::Iinc(self.number("a local variable index")?, self.number("an increment")?),
+            Shape::Byte(write) => Instruction::Byte(write, self.number("a byte")?),
+            Shape::Short(write) => Instruction::Short(write, self.number("a short")?),
+            Shape::Label(write) => Instruction::Label(write, self.label_reference(labels)?),
+            Shape::Class(write) => Instruction::Class(write, self.name("a class name")?),
+            Shape::MultiANewArray => {
+                Instruction::MultiANewArray(self.name("a class name")?, self.number("dimensions")?)
+            }
+            Shape::NewArray => {
+                let (name, position) = self.word("an array type")?;
+                let array_type = instructions::array_type(name)
+                    .ok_or_else(|| position.error(AssembleErrorKind::Expected("an array type")))?;
+                Instruction::NewArray(array_type)
+            }
+            Shape::Field(write) => Instruction::Field(write, self.field_ref()?),
+            Shape::Method(write, write_interface) => {
+                let interface_position = self.position();
+                let interface = self.eat_word("interface");
+                let method = self.method_ref()?;
+                match (interface, write_interface) {
+                    (false, _) => Instruction::Method(write, method),
+                    (true, Some(write)) => Instruction::InterfaceMethod(write, method),
+                    (true, None) => return Err(interface_position.error(AssembleErrorKind::Expected("a method"))),
+                }
+            }
+            Shape::InvokeInterface => {
+                let (method, argument_slots) = self.method_ref_with_slots()?;
+                let count = if self.at_line_end() {
+                    u8::try_from(argument_slots + 1)
+                        .map_err(|_| position.error(AssembleErrorKind::InvalidDescriptor))?
+                } else {
+                    self.number("an argument count")?
+                };
+                Instruction::InvokeInterface(method, count)
+            }
+            Shape::InvokeDynamic => {
+                let name = self.name("a method name")?;
+                let descriptor = self.method_descriptor()?;
+                let bootstrap = self.bootstrap()?;
+                Instruction::InvokeDynamic(Dynamic {
+                    name,
+                    descriptor,
+                    bootstrap,
+                })
+            }
+            Shape::Constant(write) => Instruction::Constant(write, self.constant(false)?),
+            Shape::WideConstant => Instruction::WideConstant(self.constant(true)?),
+            Shape::TableSwitch => {
+                let low = self.number("the lowest key")?;
+                let high: Option<(i32, Position)> = if self.at_line_end() {
+                    None
+                } else {
+                    let position = self.position();
+                    Some((self.number("the highest key")?, position))
+                };
+                let mut targets = Vec::new();
+                let default = loop {
+                    self.skip_newlines();
+                    if self.eat_word("default") {
+                        self.colon()?;
+                        break self.label_reference(labels)?;
+                    }
+                    targets.push(self.label_reference(labels)?);
+                };
+                if let Some((high, position)) = high {
+                    if i64::from(high) - i64::from(low) + 1 != targets.len() as i64 {
+                        return Err(position.error(AssembleErrorKind::Expected("as many labels as keys")));
+                    }
+                }
+                Instruction::TableSwitch {
+                    low,
+                    labels: targets,
+                    default,
+                }
+            }
+            Shape::LookupSwitch => {
+                let mut pairs = Vec::new();
+                let default = loop {
+                    self.skip_newlines();
+                    if self.eat_word("default") {
+                        self.colon()?;
+                        break self.label_reference(labels)?;
+                    }
+                    let key = self.number("a key")?;
+                    self.colon()?;
+                    pairs.push((key, self.label_reference(labels)?));
+                };
+                Instruction::LookupSwitch { pairs, default }
+            }
+        })
+    }
+
+    /// Parses a stack map frame, which applies to the next instruction.
+    fn frame(&mut self, position: Position, labels: &mut Labels) -> Result<(), AssembleError> {
+        let (kind, kind_position) = self.word("a frame type")?;
+        let kind = match kind {
+            "same" => FrameKind::Same,
+            "same_extended" => FrameKind::SameExtended,
+            "same1" => FrameKind::Same1(self.verification_type(labels)?),
+            "same1_extended" => FrameKind::Same1Extended(self.verification_type(labels)?),
+            "chop" => FrameKind::Chop(self.number("a number of locals")?),
+            "append" => {
+                let mut locals = Vec::new();
+                while !self.at_line_end() {
+                    locals.push(self.verification_type(labels)?);
+                }
+                FrameKind::Append(locals)
+            }
+            "full" => {
+                self.keyword("locals")?;
+                let mut locals = Vec::new();
+                while !self.eat_word("stack") {
+                    locals.push(self.verification_type(labels)?);
+                }
+                let mut stack = Vec::new();
+                while !self.at_line_end() {
+                    stack.push(self.verification_type(labels)?);
+                }
+                FrameKind::Full { locals, stack }
+            }
+            _ => return Err(kind_position.error(AssembleErrorKind::Expected("a frame type"))),
+        };
+
+        let label = labels.anonymous();
+        labels.code.statements.push(Statement {
+            position,
+            kind: StatementKind::Label(label),
+        });
+        labels.code.frames.push(Frame { position, label, kind });
+        Ok(())
+    }
+
+    fn verification_type(&mut self, labels: &mut Labels) -> Result<VerificationType, AssembleError> {
+        let (word, position) = self.word("a verification type")?;
+        Ok(match word {
+            "top" => VerificationType::Top,
+            "int" => VerificationType::Integer,
+            "float" => VerificationType::Float,
+            "long" => VerificationType::Long,
+            "double" => VerificationType::Double,
+            "null" => VerificationType::Null,
+            "uninitialized_this" => VerificationType::UninitializedThis,
+            "object" => VerificationType::Object(self.name("a class name")?),
+            "uninitialized" => VerificationType::Uninitialized(self.label_reference(labels)?),
+            _ => return Err(position.error(AssembleErrorKind::Expected("a verification type"))),
+        })
+    }
+
+    /// Parses an attribute directive, or returns `None` if the directive does not describe an attribute.
+    fn attribute(&mut self, directive: &str, position: Position) -> Result<Option<Attribute>, AssembleError> {
+        let kind = match directive {
+            ".source" => AttributeKind::SourceFile(self.name("a file name")?),
+            ".signature" => AttributeKind::Signature(self.name("a signature")?),
+            ".deprecated" => AttributeKind::Deprecated,
+            ".synthetic" => AttributeKind::Synthetic,
+            ".inner_class" => {
+                let access_flags = self.access_flags();
+                let inner = self.name("a class name")?;
+                let outer = if self.eat_word("outer") {
+                    Some(self.name("a class name")?)
+                } else {
+                    None
+                };
+                let name = if self.eat_word("name") {
+                    Some(self.name("a simple name")?)
+                } else {
+                    None
+                };
+                AttributeKind::InnerClasses(vec![InnerClass {
+                    access_flags,
+                    inner,
+                    outer,
+                    name,
+                }])
+            }
+            ".enclosing_method" => {
+                let class = self.name("a class name")?;
+                let method = if self.at_line_end() {
+                    None
+                } else {
+                    Some((self.name("a method name")?, self.method_descriptor()?))
+                };
+                AttributeKind::EnclosingMethod { class, method }
+            }
+            ".nest_host" => AttributeKind::NestHost(self.name("a class name")?),
+            ".nest_member" => AttributeKind::NestMembers(vec![self.name("a class name")?]),
+            ".permitted_subclass" => AttributeKind::PermittedSubclasses(vec![self.name("a class name")?]),
+            ".parameter" => {
+                let access_flags = self.access_flags();
+                let name = if self.at_line_end() {
+                    None
+                } else {
+                    Some(self.name("a parameter name")?)
+                };
+                AttributeKind::MethodParameters(vec![Parameter { access_flags, name }])
+            }
+            ".annotation" => {
+                let visible = self.visibility()?;
+                if self.eat_word("parameter") {
+                    let index: u8 = self.number("a parameter index")?;
+                    let mut parameters = vec![Vec::new(); usize::from(index) + 1];
+                    parameters[usize::from(index)].push(self.annotation()?);
+                    AttributeKind::ParameterAnnotations { visible, parameters }
+                } else {
+                    let annotations = vec![self.annotation()?];
+                    AttributeKind::Annotations { visible, annotations }
+                }
+            }
+            ".parameter_annotations" => {
+                let visible = self.visibility()?;
+                let count: u8 = self.number("a number of parameters")?;
+                let parameters = vec![Vec::new(); usize::from(count)];
+                AttributeKind::ParameterAnnotations { visible, parameters }
+            }
+            ".annotation_default" => AttributeKind::AnnotationDefault(self.element_value()?),
+            ".attribute" => {
+                let name = self.name("an attribute name")?;
+                let mut bytes = Vec::new();
+                while !self.at_line_end() {
+                    let (hex, position) = self.word("hexadecimal bytes")?;
+                    let invalid = || position.error(AssembleErrorKind::Expected("hexadecimal bytes"));
+                    if hex.len() % 2 != 0 {
+                        return Err(invalid());
+                    }
+                    for at in (0..hex.len()).step_by(2) {
+                        let byte = hex.get(at..at + 2).ok_or_else(invalid)?;
+                        bytes.push(u8::from_str_radix(byte, 16).map_err(|_| invalid())?);
+                    }
+                }
+                AttributeKind::Raw(name, bytes)
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(Attribute { position, kind }))
+    }
+
+    /// Parses whether annotations are `visible` or `invisible` at runtime.
+    fn visibility(&mut self) -> Result<bool, AssembleError> {
+        let (word, position) = self.word("`visible` or `invisible`")?;
+        match word {
+            "visible" => Ok(true),
+            "invisible" => Ok(false),
+            _ => Err(position.error(AssembleErrorKind::Expected("`visible` or `invisible`"))),
+        }
+    }
+
+    /// Parses an annotation type, optionally followed by its element value pairs in braces.
+    fn annotation(&mut self) -> Result<Annotation, AssembleError> {
+        let type_ = self.name("an annotation type")?;
+        let mut pairs = Vec::new();
+        if self.eat_word("{") {
+            while !self.at_closing_brace() {
+                let name = self.name("an element name")?;
+                self.keyword("=")?;
+                pairs.push((name, self.element_value()?));
+            }
+        }
+        Ok(Annotation { type_, pairs })
+    }
+
+    fn element_value(&mut self) -> Result<ElementValue, AssembleError> {
+        let (tag, position) = self.word("an element value")?;
+        Ok(match tag {
+            "boolean" => {
+                let (value, position) = self.word("`true` or `false`")?;
+                match value {
+                    "true" => ElementValue::Boolean(true),
+                    "false" => ElementValue::Boolean(false),
+                    _ => return Err(position.error(AssembleErrorKind::Expected("`true` or `false`"))),
+                }
+            }
+            "byte" => ElementValue::Byte(self.number("a byte")?),
+            "short" => ElementValue::Short(self.number("a short")?),
+            "char" => ElementValue::Char(self.number("a character")?),
+            "int" => ElementValue::Int(self.number("an integer")?),
+            "long" => ElementValue::Long(self.number("a long")?),
+            "float" => ElementValue::Float(self.float()?),
+            "double" => ElementValue::Double(self.float()?),
+            "string" => ElementValue::String(self.name("a string")?),
+            "class" => ElementValue::Class(self.name("a class descriptor")?),
+            "enum" => ElementValue::Enum(self.name("an enum type")?, self.name("an enum constant")?),
+            "annotation" => ElementValue::Annotation(self.annotation()?),
+            "array" => {
+                self.keyword("{")?;
+                let mut values = Vec::new();
+                while !self.at_closing_brace() {
+                    values.push(self.element_value()?);
+                }
+                ElementValue::Array(values)
+            }
+            _ => return Err(position.error(AssembleErrorKind::Expected("an element value"))),
+        })
+    }
+
+    /// Parses a constant, where untyped numbers are wide if they are loaded by `ldc2_w`.
+    fn constant(&mut self, wide: bool) -> Result<Constant, AssembleError> {
+        let token = self
+            .next()
+            .ok_or_else(|| self.end.error(AssembleErrorKind::UnexpectedEnd))?;
+        let word = match token.kind {
+            TokenKind::String(content) => return Ok(Constant::String(MString::from(content.as_str()))),
+            TokenKind::Word(word) => word,
+            _ => return Err(token.position.error(AssembleErrorKind::Expected("a constant"))),
+        };
+
+        let (word, position, wide, is_float) = match word {
+            "class" => return Ok(Constant::Class(self.name("a class name")?)),
+            "method_type" => return Ok(Constant::MethodType(self.method_descriptor()?)),
+            "handle" => return Ok(Constant::MethodHandle(self.handle()?)),
+            "dynamic" => {
+                let name = self.name("a constant name")?;
+                let descriptor = self.field_descriptor()?;
+                let bootstrap = self.bootstrap()?;
+                return Ok(Constant::Dynamic(Box::new(Dynamic {
+                    name,
+                    descriptor,
+                    bootstrap,
+                })));
+            }
+            "int" | "long" | "float" | "double" => {
+                let (value, position) = self.word("a number")?;
+                let wide = matches!(word, "long" | "double");
+                (value, position, wide, Some(matches!(word, "float" | "double")))
+            }
+            _ => (word, token.position, wide, None),
+        };
+
+        let is_number = word.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+' || c == '.');
+        if is_float.is_none() && !is_number && !matches!(word, "NaN" | "Infinity") {
+            return Ok(Constant::Class(MString::from(word)));
+        }
+
+        let invalid = || position.error(AssembleErrorKind::InvalidNumber);
+        let is_float = is_float.unwrap_or_else(|| {
+            matches!(word.trim_start_matches(['-', '+']), "NaN" | "Infinity")
+                || (!word.contains("0x") && word.contains(['.', 'e', 'E']))
+        });
+        Ok(match (is_float, wide) {
+            (false, false) => Constant::Integer(parse_integer(word).ok_or_else(invalid)?),
+            (false, true) => Constant::Long(parse_integer(word).ok_or_else(invalid)?),
+            (true, false) => Constant::Float(word.parse().map_err(|_| invalid())?),
+            (true, true) => Constant::Double(word.parse().map_err(|_| invalid())?),
+        })
+    }
+
+    /// Parses a method handle of the form `<kind> [interface] <member>`.
+    fn handle(&mut self) -> Result<Handle, AssembleError> {
+        use MethodKind::*;
+
+        let (word, position) = self.word("a method handle kind")?;
+        let kind = match word {
+            "getfield" => GetField,
+            "getstatic" => GetStatic,
+            "putfield" => PutField,
+            "putstatic" => PutStatic,
+            "invokevirtual" => InvokeVirtual,
+            "invokestatic" => InvokeStatic,
+            "invokespecial" => InvokeSpecial,
+            "newinvokespecial" => NewInvokeSpecial,
+            "invokeinterface" => InvokeInterface,
+            _ => return Err(position.error(AssembleErrorKind::Expected("a method handle kind"))),
+        };
+        let member = match kind {
+            GetField | GetStatic | PutField | PutStatic => self.field_ref()?,
+            _ => {
+                let interface = self.eat_word("interface") || kind == InvokeInterface;
+                let member = self.method_ref()?;
+                return Ok(Handle {
+                    kind,
+                    interface,
+                    member,
+                });
+            }
+        };
+        Ok(Handle {
+            kind,
+            interface: false,
+            member,
+        })
+    }
+
+    /// Parses the method handle of a bootstrap method followed by its static arguments in braces.
+    fn bootstrap(&mut self) -> Result<Bootstrap, AssembleError> {
+        let handle = self.handle()?;
+        self.keyword("{")?;
+        let mut arguments = Vec::new();
+        while !self.at_closing_brace() {
+            arguments.push(self.constant(false)?);
+        }
+        Ok(Bootstrap { handle, arguments })
+    }
+
+    /// Skips newlines and returns whether a closing brace followed, which is skipped as well.
+    fn at_closing_brace(&mut self) -> bool {
+        self.skip_newlines();
+        self.eat_word("}")
+    }
+
+    /// Parses a member reference of the form `class/name` and returns the class and the name.
+    fn member(&mut self, expected: &'static str) -> Result<(MString, MString), AssembleError> {
+        let (text, position) = self.text(expected)?;
+        match text.rfind('/') {
+            Some(at) if at > 0 && at + 1 < text.len() => {
+                Ok((MString::from(&text[..at]), MString::from(&text[at + 1..])))
+            }
+            _ => Err(position.error(AssembleErrorKind::Expected(expected))),
+        }
+    }
+
+    /// Parses a field reference of the form `class/name descriptor`.
+    fn field_ref(&mut self) -> Result<MemberRef, AssembleError> {
+        let (class, name) = self.member("a field")?;
+        let descriptor = self.field_descriptor()?;
+        Ok(MemberRef {
+            class,
+            name,
+            descriptor,
+        })
+    }
+
+    fn method_ref(&mut self) -> Result<MemberRef, AssembleError> {
+        Ok(self.method_ref_with_slots()?.0)
+    }
+
+    /// Parses a method reference of the form `class/name(descriptor)` and also returns the slots of its arguments.
+    fn method_ref_with_slots(&mut self) -> Result<(MemberRef, u32), AssembleError> {
+        let (text, position) = self.text("a method")?;
+        let at = text
+            .find('(')
+            .ok_or_else(|| position.error(AssembleErrorKind::Expected("a method descriptor")))?;
+        let descriptor = MString::from(&text[at..]);
+        let parsed =
+            MethodDescriptor::parse(&descriptor).map_err(|_| position.error(AssembleErrorKind::InvalidDescriptor))?;
+        let slots = parsed
+            .parameters()
+            .map(|parameter| {
+                if parameter.dimensions == 0 && matches!(parameter.base, BaseType::Long | BaseType::Double) {
+                    2
+                } else {
+                    1
+                }
+            })
+            .sum();
+
+        let owner = &text[..at];
+        match owner.rfind('/') {
+            Some(slash) if slash > 0 && slash + 1 < owner.len() => Ok((
+                MemberRef {
+                    class: MString::from(&owner[..slash]),
+                    name: MString::from(&owner[slash + 1..]),
+                    descriptor,
+                },
+                slots,
+            )),
+            _ => Err(position.error(AssembleErrorKind::Expected("a method"))),
+        }
+    }
+
+    fn field_descriptor(&mut self) -> Result<MString, AssembleError> {
+        let (descriptor, position) = self.name_with_position("a field descriptor")?;
+        check_descriptor(&descriptor, position, |d| TypeDescriptor::parse(d).is_ok())?;
+        Ok(descriptor)
+    }
+
+    fn method_descriptor(&mut self) -> Result<MString, AssembleError> {
+        let (descriptor, position) = self.name_with_position("a method descriptor")?;
+        check_descriptor(&descriptor, position, |d| MethodDescriptor::parse(d).is_ok())?;
+        Ok(descriptor)
+    }
+
+    /// Parses access flags followed by names until the end of the line or `=`.
+    fn flags_and_names(&mut self) -> Result<(AccessFlags, Vec<(MString, Position)>), AssembleError> {
+        let flags = self.access_flags();
+        let mut names = Vec::new();
+        while !self.at_line_end() && !self.peek().is_some_and(|token| token.kind == TokenKind::Word("=")) {
+            names.push(self.name_with_position("a name")?);
+        }
+        Ok((flags, names))
+    }
+
+    /// Parses access flags, which are only recognized if they aren't written as strings.
+    fn access_flags(&mut self) -> AccessFlags {
+        let mut flags = AccessFlags::empty();
+        while let Some(flag) = self.peek().and_then(|token| match token.kind {
+            TokenKind::Word(word) => access_flag(word),
+            _ => None,
+        }) {
+            flags |= flag;
+            self.index += 1;
+        }
+        flags
+    }
+
+    fn label_reference(&mut self, labels: &mut Labels) -> Result<usize, AssembleError> {
+        let (name, position) = self.word("a label")?;
+        Ok(labels.reference(name, position))
+    }
+
+    fn number<T: TryFrom<i64>>(&mut self, expected: &'static str) -> Result<T, AssembleError> {
+        let (word, position) = self.word(expected)?;
+        parse_integer(word).ok_or_else(|| position.error(AssembleErrorKind::InvalidNumber))
+    }
+
+    fn float<T: FromStr>(&mut self) -> Result<T, AssembleError> {
+        let (word, position) = self.word("a floating point number")?;
+        word.parse()
+            .map_err(|_| position.error(AssembleErrorKind::InvalidNumber))
+    }
+
+    fn name(&mut self, expected: &'static str) -> Result<MString, AssembleError> {
+        Ok(self.name_with_position(expected)?.0)
+    }
+
+    /// Parses a word or a string.
+    fn name_with_position(&mut self, expected: &'static str) -> Result<(MString, Position), AssembleError> {
+        let (text, position) = self.text(expected)?;
+        Ok((MString::from(text.as_str()), position))
+    }
+
+    /// Parses a word or a string and returns its content.
+    fn text(&mut self, expected: &'static str) -> Result<(String, Position), AssembleError> {
+        let token = self
+            .next()
+            .ok_or_else(|| self.end.error(AssembleErrorKind::UnexpectedEnd))?;
+        match token.kind {
+            TokenKind::Word(word) => Ok((word.to_owned(), token.position)),
+            TokenKind::String(content) => Ok((content, token.position)),
+            _ => Err(token.position.error(AssembleErrorKind::Expected(expected))),
+        }
+    }
+
+    fn word(&mut self, expected: &'static str) -> Result<(&'a str, Position), AssembleError> {
+        let token = self
+            .next()
+            .ok_or_else(|| self.end.error(AssembleErrorKind::UnexpectedEnd))?;
+        match token.kind {
+            TokenKind::Word(word) => Ok((word, token.position)),
+            _ => Err(token.position.error(AssembleErrorKind::Expected(expected))),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &'static str) -> Result<(), AssembleError> {
+        let (word, position) = self.word(keyword)?;
+        if word == keyword {
+            Ok(())
+        } else {
+            Err(position.error(AssembleErrorKind::Expected(keyword)))
+        }
+    }
+
+    fn colon(&mut self) -> Result<(), AssembleError> {
+        match self.next() {
+            Some(Token {
+                kind: TokenKind::Colon, ..
+            }) => Ok(()),
+            Some(token) => Err(token.position.error(AssembleErrorKind::Expected("`:`"))),
+            None => Err(self.end.error(AssembleErrorKind::UnexpectedEnd)),
+        }
+    }
+
+    fn eat_word(&mut self, expected: &str) -> bool {
+        let found = self.peek().is_some_and(|token| token.kind == TokenKind::Word(expected));
+        if found {
+            self.index += 1;
+        }
+        found
+    }
+
+    /// Skips empty lines and returns the directive starting the next line.
+    fn next_line(&mut self) -> Result<Option<(&'a str, Position)>, AssembleError> {
+        self.skip_newlines();
+        if self.peek().is_none() {
+            return Ok(None);
+        }
+        let (word, position) = self.word("a directive")?;
+        if word.starts_with('.') {
+            Ok(Some((word, position)))
+        } else {
+            Err(position.error(AssembleErrorKind::UnknownDirective))
+        }
+    }
+
+    fn line_end(&mut self) -> Result<(), AssembleError> {
+        match self.next() {
+            None
+            | Some(Token {
+                kind: TokenKind::Newline,
+                ..
+            }) => Ok(()),
+            Some(token) => Err(token.position.error(AssembleErrorKind::Expected("the end of the line"))),
+        }
+    }
+
+    fn at_line_end(&self) -> bool {
+        self.peek().is_none_or(|token| token.kind == TokenKind::Newline)
+    }
+
+    fn skip_newlines(&mut self) {
+        while self.peek().is_some_and(|token| token.kind == TokenKind::Newline) {
+            self.index += 1;
+        }
+    }
+
+    fn expected(&self, expected: &'static str) -> AssembleError {
+        self.position().error(AssembleErrorKind::Expected(expected))
+    }
+
+    /// The position of the next token.
+    fn position(&self) -> Position {
+        self.peek().map_or(self.end, |token| token.position)
+    }
+
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.index)
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.tokens.get(self.index).cloned();
+        if token.is_some() {
+            self.index += 1;
+        }
+        token
+    }
+}
+
+/// The code of a method being parsed, whose labels may be referenced before they are defined.
+#[derive(Default)]
+struct Labels {
+    code: Code,
+    /// Whether the label at the same index of [`Code::labels`] has been defined.
+    defined: Vec<bool>,
+    references: Vec<(usize, Position)>,
+}
+
+impl Labels {
+    fn define(&mut self, name: &str, position: Position) -> Result<usize, AssembleError> {
+        let index = self.index(name);
+        if self.defined[index] {
+            return Err(position.error(AssembleErrorKind::DuplicateLabel));
+        }
+        self.defined[index] = true;
+        Ok(index)
+    }
+
+    fn reference(&mut self, name: &str, position: Position) -> usize {
+        let index = self.index(name);
+        self.references.push((index, position));
+        index
+    }
+
+    /// Adds a label which can't be referenced by its name.
+    fn anonymous(&mut self) -> usize {
+        self.code.labels.push(String::new());
+        self.defined.push(true);
+        self.code.labels.len() - 1
+    }
+
+    fn index(&mut self, name: &str) -> usize {
+        match self.code.labels.iter().position(|label| label == name) {
+            Some(index) => index,
+            None => {
+                self.code.labels.push(name.to_owned());
+                self.defined.push(false);
+                self.code.labels.len() - 1
+            }
+        }
+    }
+}
+
+fn access_flag(name: &str) -> Option<AccessFlags> {
+    Some(match name {
+        "public" => AccessFlags::PUBLIC,
+        "private" => AccessFlags::PRIVATE,
+        "protected" => AccessFlags::PROTECTED,
+        "static" => AccessFlags::STATIC,
+        "final" => AccessFlags::FINAL,
+        "super" => AccessFlags::SUPER,
+        "synchronized" => AccessFlags::SYNCHRONIZED,
+        "open" => AccessFlags::OPEN,
+        "transitive" => AccessFlags::TRANSITIVE,
+        "volatile" => AccessFlags::VOLATILE,
+        "bridge" => AccessFlags::BRIDGE,
+        "static_phase" => AccessFlags::STATIC_PHASE,
+        "varargs" => AccessFlags::VARARGS,
+        "transient" => AccessFlags::TRANSIENT,
+        "native" => AccessFlags::NATIVE,
+        "interface" => AccessFlags::INTERFACE,
+        "abstract" => AccessFlags::ABSTRACT,
+        "strict" => AccessFlags::STRICT,
+        "synthetic" => AccessFlags::SYNTHETIC,
+        "annotation" => AccessFlags::ANNOTATION,
+        "enum" => AccessFlags::ENUM,
+        "mandated" => AccessFlags::MANDATED,
+        "module" => AccessFlags::MODULE,
+        _ => return None,
+    })
+}
+
+/// Parses a decimal or hexadecimal integer with an optional sign.
+fn parse_integer<T: TryFrom<i64>>(word: &str) -> Option<T> {
+    let (negative, digits) = match word.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, word.strip_prefix('+').unwrap_or(word)),
+    };
+    let value = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(hex) => i128::from_str_radix(hex, 16).ok()?,
+        None if digits.starts_with(|c: char| c.is_ascii_digit()) => digits.parse::<i128>().ok()?,
+        None => return None,
+    };
+    let value = if negative { -value } else { value };
+    T::try_from(i64::try_from(value).ok()?).ok()
+}
+
+fn check_descriptor(
+    descriptor: &MString,
+    position: Position,
+    is_valid: impl FnOnce(&MStr) -> bool,
+) -> Result<(), AssembleError> {
+    if is_valid(descriptor) {
+        Ok(())
+    } else {
+        Err(position.error(AssembleErrorKind::InvalidDescriptor))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn error_at(source: &str) -> (usize, usize) {
+        match parse(source) {
+            Ok(_) => panic!("parsing succeeded"),
+            Err(err) => (err.line(), err.column()),
+        }
+    }
+
+    #[test]
+    fn labels() {
+        let class = parse(".class A\n.method m ()V\n  goto end\nend: return\n.end method\n").unwrap();
+        let code = class.methods[0].code.as_ref().unwrap();
+        assert_eq!(code.labels, ["end"]);
+        assert_eq!(code.statements.len(), 3);
+
+        assert_eq!(
+            error_at(".class A\n.method m ()V\n  goto nowhere\n.end method\n"),
+            (3, 8)
+        );
+        assert_eq!(
+            error_at(".class A\n.method m ()V\na:\n  a: return\n.end method\n"),
+            (4, 3)
+        );
+    }
+
+    #[test]
+    fn error_positions() {
+        assert_eq!(
+            error_at(".class A\n.method m ()V\n  bipush 300\n.end method\n"),
+            (3, 10)
+        );
+        assert_eq!(error_at(".class A\n.method m (V\n.end method\n"), (2, 11));
+        assert_eq!(error_at(".class public foo A\n"), (1, 15));
+        assert_eq!(error_at(".class A\n.field x I = \"\\q\"\n"), (2, 15));
+    }
+}
@@ -1,5 +1,5 @@
 use crate::error::{DecodeError, DecodeErrorKind};
-use crate::mutf8::{CharsLossy, MStr};
+use crate::mutf8::{CharsLossy, MStr, MString};
 use std::fmt;
 
 /// A field type descriptor not wrapped within an array.
@@ -104,6 +104,25 @@ impl<'a> TypeDescriptor<'a> {
 
         Err(DecodeError::new(DecodeErrorKind::InvalidDescriptor))
     }
+
+    /// Returns the descriptor as it is stored in the constant pool.
+    ///
+    /// Unlike the [`Display`](fmt::Display) implementation, this keeps the names of classes as they are.
+    pub(crate) fn to_mstring(&self) -> MString {
+        let mut descriptor = MString::new();
+        for _ in 0..self.dimensions {
+            descriptor.push('[');
+        }
+        match self.base {
+            BaseType::Object(name) => {
+                descriptor.push('L');
+                descriptor.push_mstr(name);
+                descriptor.push(';');
+            }
+            ref base => descriptor.extend([base.to_string().as_str()]),
+        }
+        descriptor
+    }
 }
 
 impl<'a> fmt::Display for TypeDescriptor<'a> {
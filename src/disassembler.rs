@@ -0,0 +1,1039 @@
+//! Disassembles class files into the textual representation understood by the [assembler](crate::assembler).
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::error::*;
+use crate::header::AccessFlags;
+use crate::mutf8;
+use crate::reader::attributes::annotations::{Annotation, ElementValue};
+use crate::reader::attributes::{
+    ArrayType, AttributeContent, BootstrapMethod, Code, RawInstruction, StackMapFrame, VerificationType,
+};
+use crate::reader::cpool::{self, ConstantPool, Item, MethodKind};
+use crate::reader::decoding::DecodeMany;
+use crate::reader::{Attribute, Class};
+use crate::MStr;
+
+pub mod javap;
+
+/// Disassembles a class into the syntax of [`assemble`](crate::assembler::assemble).
+///
+/// Assembling the result gives an equivalent class, apart from attributes which can't be expressed:
+/// type annotations, module attributes and unknown attributes of the code are left out with a comment,
+/// and other unknown attributes are kept as raw bytes, so constant pool indices in them may become invalid.
+/// Strings containing unpaired surrogates are written with `\u` escapes, but can't be assembled again.
+///
+/// # Examples
+/// ```
+/// let bytes = noak::assembler::assemble(".class public super Example\n")?;
+/// let class = noak::reader::Class::new(&bytes)?;
+/// let text = noak::disassembler::disassemble(&class)?;
+/// assert!(text.contains(".class public super Example"));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn disassemble(class: &Class<'_>) -> Result<String, DecodeError> {
+    let pool = class.pool();
+    let mut bootstrap_methods = Vec::new();
+    for attribute in class.attributes().iter() {
+        if let Ok(AttributeContent::BootstrapMethods(methods)) = attribute?.read_content(pool) {
+            for method in methods.methods().iter() {
+                bootstrap_methods.push(method?);
+            }
+        }
+    }
+
+    let mut disassembler = Disassembler {
+        pool,
+        bootstrap_methods,
+        lines: Vec::new(),
+    };
+    disassembler.class(class)?;
+    Ok(disassembler.lines.join("\n") + "\n")
+}
+
+struct Disassembler<'a, 'input> {
+    pool: &'a ConstantPool<'input>,
+    bootstrap_methods: Vec<BootstrapMethod<'input>>,
+    lines: Vec<String>,
+}
+
+impl<'a, 'input> Disassembler<'a, 'input> {
+    fn class(&mut self, class: &Class<'input>) -> Result<(), DecodeError> {
+        let version = class.version();
+        self.lines.push(format!(".version {} {}", version.major, version.minor));
+        self.lines.push(format!(
+            ".class {}{}",
+            flags(class.access_flags(), CLASS_FLAGS),
+            self.class_name(class.this_class())?
+        ));
+        match class.super_class() {
+            Some(super_class) => self.lines.push(format!(".super {}", self.class_name(super_class)?)),
+            None => self.lines.push(".super".to_owned()),
+        }
+        for interface in class.interfaces().iter() {
+            self.lines.push(format!(".implements {}", self.class_name(interface?)?));
+        }
+
+        // record components come last, as the attributes following them belong to them
+        let mut records = Vec::new();
+        for attribute in class.attributes().iter() {
+            let attribute = attribute?;
+            match attribute.read_content(self.pool) {
+                Ok(AttributeContent::BootstrapMethods(_)) => {}
+                Ok(AttributeContent::Record(record)) => records.push(record),
+                _ => self.attribute(&attribute, "")?,
+            }
+        }
+        for record in records {
+            for component in record.components().iter() {
+                let component = component?;
+                self.lines.push(format!(
+                    ".record_component {} {}",
+                    self.utf8(component.name())?,
+                    self.utf8(component.descriptor())?
+                ));
+                self.attributes(&component.attributes(), "")?;
+            }
+        }
+
+        for field in class.fields().iter() {
+            let field = field?;
+            let descriptor = self.pool.retrieve(field.descriptor())?;
+            let mut constant = String::new();
+            let mut attributes = Vec::new();
+            for attribute in field.attributes().iter() {
+                let attribute = attribute?;
+                match attribute.read_content(self.pool) {
+                    Ok(AttributeContent::ConstantValue(value)) if constant.is_empty() => {
+                        let wide = *descriptor == *"J" || *descriptor == *"D";
+                        constant = format!(" = {}", self.constant(value.value(), wide)?);
+                    }
+                    _ => attributes.push(attribute),
+                }
+            }
+
+            self.lines.push(String::new());
+            self.lines.push(format!(
+                ".field {}{} {}{constant}",
+                flags(field.access_flags(), FIELD_FLAGS),
+                self.utf8(field.name())?,
+                text(&[descriptor])
+            ));
+            for attribute in &attributes {
+                self.attribute(attribute, "")?;
+            }
+        }
+
+        for method in class.methods().iter() {
+            let method = method?;
+            self.lines.push(String::new());
+            self.lines.push(format!(
+                ".method {}{} {}",
+                flags(method.access_flags(), METHOD_FLAGS),
+                self.utf8(method.name())?,
+                self.utf8(method.descriptor())?
+            ));
+            let mut code = None;
+            for attribute in method.attributes().iter() {
+                let attribute = attribute?;
+                match attribute.read_content(self.pool) {
+                    Ok(AttributeContent::Code(content)) if code.is_none() => code = Some(content),
+                    _ => self.attribute(&attribute, "    ")?,
+                }
+            }
+            if let Some(code) = code {
+                self.code(&code)?;
+            }
+            self.lines.push(".end method".to_owned());
+        }
+
+        Ok(())
+    }
+
+    fn attributes(
+        &mut self,
+        attributes: &DecodeMany<'input, Attribute<'input>, u16>,
+        indent: &str,
+    ) -> Result<(), DecodeError> {
+        for attribute in attributes.iter() {
+            self.attribute(&attribute?, indent)?;
+        }
+        Ok(())
+    }
+
+    fn attribute(&mut self, attribute: &Attribute<'input>, indent: &str) -> Result<(), DecodeError> {
+        let name = self.pool.retrieve(attribute.name())?;
+        let content = match attribute.read_content(self.pool) {
+            Ok(AttributeContent::SourceDebugExtension(_)) => None,
+            Ok(content) => Some(content),
+            Err(err) if err.kind() == DecodeErrorKind::UnknownAttributeName => None,
+            Err(err) => return Err(err),
+        };
+
+        let mut lines = Vec::new();
+        match content {
+            None => {
+                let bytes: String = attribute.content().iter().map(|byte| format!("{byte:02x}")).collect();
+                lines.push(format!(".attribute {} {bytes}", text(&[name])).trim_end().to_owned());
+            }
+            Some(AttributeContent::SourceFile(source)) => {
+                lines.push(format!(".source {}", self.utf8(source.source_file())?));
+            }
+            Some(AttributeContent::Signature(signature)) => {
+                lines.push(format!(".signature {}", self.utf8(signature.signature())?));
+            }
+            Some(AttributeContent::Deprecated(_)) => lines.push(".deprecated".to_owned()),
+            Some(AttributeContent::Synthetic(_)) => lines.push(".synthetic".to_owned()),
+            Some(AttributeContent::Exceptions(exceptions)) => {
+                for exception in exceptions.exceptions().iter() {
+                    lines.push(format!(".throws {}", self.class_name(exception?)?));
+                }
+            }
+            Some(AttributeContent::InnerClasses(classes)) => {
+                for class in classes.classes().iter() {
+                    let class = class?;
+                    let mut line = format!(
+                        ".inner_class {}{}",
+                        flags(class.inner_access_flags(), INNER_CLASS_FLAGS),
+                        self.class_name(class.inner_class())?
+                    );
+                    if let Some(outer) = class.outer_class() {
+                        line = format!("{line} outer {}", self.class_name(outer)?);
+                    }
+                    if let Some(name) = class.inner_name() {
+                        line = format!("{line} name {}", self.utf8(name)?);
+                    }
+                    lines.push(line);
+                }
+            }
+            Some(AttributeContent::EnclosingMethod(enclosing)) => {
+                let mut line = format!(".enclosing_method {}", self.class_name(enclosing.class())?);
+                if let Some(method) = enclosing.method() {
+                    let method = self.pool.retrieve(method)?;
+                    line = format!("{line} {} {}", text(&[method.name]), text(&[method.descriptor]));
+                }
+                lines.push(line);
+            }
+            Some(AttributeContent::NestHost(host)) => {
+                lines.push(format!(".nest_host {}", self.class_name(host.host_class())?));
+            }
+            Some(AttributeContent::NestMembers(members)) => {
+                for class in members.classes().iter() {
+                    lines.push(format!(".nest_member {}", self.class_name(class?)?));
+                }
+            }
+            Some(AttributeContent::PermittedSubclasses(subclasses)) => {
+                for class in subclasses.classes().iter() {
+                    lines.push(format!(".permitted_subclass {}", self.class_name(class?)?));
+                }
+            }
+            Some(AttributeContent::MethodParameters(parameters)) => {
+                for parameter in parameters.parameters().iter() {
+                    let parameter = parameter?;
+                    let mut line = format!(".parameter {}", flags(parameter.access_flags(), PARAMETER_FLAGS));
+                    if let Some(name) = parameter.name() {
+                        line += &self.utf8(name)?;
+                    }
+                    lines.push(line.trim_end().to_owned());
+                }
+            }
+            Some(AttributeContent::RuntimeVisibleAnnotations(annotations)) => {
+                for annotation in annotations.annotations().iter() {
+                    lines.push(format!(".annotation visible {}", self.annotation(&annotation?)?));
+                }
+            }
+            Some(AttributeContent::RuntimeInvisibleAnnotations(annotations)) => {
+                for annotation in annotations.annotations().iter() {
+                    lines.push(format!(".annotation invisible {}", self.annotation(&annotation?)?));
+                }
+            }
+            Some(AttributeContent::RuntimeVisibleParameterAnnotations(annotations)) => {
+                self.parameter_annotations(&mut lines, "visible", &annotations.parameters())?;
+            }
+            Some(AttributeContent::RuntimeInvisibleParameterAnnotations(annotations)) => {
+                self.parameter_annotations(&mut lines, "invisible", &annotations.parameters())?;
+            }
+            Some(AttributeContent::AnnotationDefault(default)) => {
+                lines.push(format!(".annotation_default {}", self.element_value(&default.value())?));
+            }
+            Some(_) => lines.push(format!("; the attribute {} is left out", text(&[name]))),
+        }
+
+        self.lines
+            .extend(lines.into_iter().map(|line| format!("{indent}{line}")));
+        Ok(())
+    }
+
+    fn parameter_annotations(
+        &self,
+        lines: &mut Vec<String>,
+        visibility: &str,
+        parameters: &DecodeMany<'input, crate::reader::attributes::annotations::ParameterAnnotations<'input>, u8>,
+    ) -> Result<(), DecodeError> {
+        let mut annotations = Vec::new();
+        let mut count = 0;
+        for (index, parameter) in parameters.iter().enumerate() {
+            for annotation in parameter?.annotations().iter() {
+                annotations.push(format!(
+                    ".annotation {visibility} parameter {index} {}",
+                    self.annotation(&annotation?)?
+                ));
+            }
+            count += 1;
+        }
+        lines.push(format!(".parameter_annotations {visibility} {count}"));
+        lines.extend(annotations);
+        Ok(())
+    }
+
+    fn annotation(&self, annotation: &Annotation<'input>) -> Result<String, DecodeError> {
+        let mut words = vec![self.utf8(annotation.type_())?];
+        let pairs = annotation.pairs();
+        if pairs.iter().next().is_some() {
+            words.push("{".to_owned());
+            for pair in pairs.iter() {
+                let pair = pair?;
+                words.push(format!(
+                    "{} = {}",
+                    self.utf8(pair.name())?,
+                    self.element_value(&pair.value())?
+                ));
+            }
+            words.push("}".to_owned());
+        }
+        Ok(words.join(" "))
+    }
+
+    fn element_value(&self, value: &ElementValue<'input>) -> Result<String, DecodeError> {
+        Ok(match value {
+            &ElementValue::Boolean(index) => {
+                let value = self.pool.get(index)?.value != 0;
+                format!("boolean {value}")
+            }
+            &ElementValue::Byte(index) => format!("byte {}", self.pool.get(index)?.value as i8),
+            &ElementValue::Short(index) => format!("short {}", self.pool.get(index)?.value as i16),
+            &ElementValue::Char(index) => format!("char {}", self.pool.get(index)?.value as u16),
+            &ElementValue::Int(index) => format!("int {}", self.pool.get(index)?.value),
+            &ElementValue::Long(index) => format!("long {}", self.pool.get(index)?.value),
+            &ElementValue::Float(index) => format!("float {}", float(self.pool.get(index)?.value)),
+            &ElementValue::Double(index) => format!("double {}", float(self.pool.get(index)?.value)),
+            &ElementValue::String(index) => format!("string {}", quote(self.pool.retrieve(index)?)),
+            &ElementValue::Class(index) => format!("class {}", self.utf8(index)?),
+            &ElementValue::Enum { type_name, const_name } => {
+                format!("enum {} {}", self.utf8(type_name)?, self.utf8(const_name)?)
+            }
+            ElementValue::Annotation(annotation) => format!("annotation {}", self.annotation(annotation)?),
+            ElementValue::Array(values) => {
+                let mut words = vec!["array {".to_owned()];
+                for value in values.iter() {
+                    words.push(self.element_value(&value?)?);
+                }
+                words.push("}".to_owned());
+                words.join(" ")
+            }
+        })
+    }
+
+    fn code(&mut self, code: &Code<'input>) -> Result<(), DecodeError> {
+        let mut labels = Labels {
+            offsets: BTreeSet::new(),
+            used: BTreeSet::new(),
+            end: code.code_length(),
+        };
+        let mut instructions = Vec::new();
+        for instruction in code.raw_instructions() {
+            let (offset, instruction) = instruction?;
+            labels.offsets.insert(offset.as_u32());
+            instructions.push((offset.as_u32(), instruction));
+        }
+
+        let mut lines = vec![
+            format!("    .limit stack {}", code.max_stack()),
+            format!("    .limit locals {}", code.max_locals()),
+        ];
+        for handler in code.exception_handlers() {
+            let class = match handler.catch_type() {
+                Some(class) => self.class_name(class)?,
+                None => "all".to_owned(),
+            };
+            lines.push(format!(
+                "    .catch {class} from {} to {} using {}",
+                labels.at(handler.start().as_u32())?,
+                labels.at(handler.end().as_u32())?,
+                labels.at(handler.handler().as_u32())?
+            ));
+        }
+
+        // the line numbers and frames of the instructions at an offset
+        let mut line_numbers: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+        let mut frames: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+        let mut variables = Vec::new();
+        let mut variable_types = Vec::new();
+        for attribute in code.attributes().iter() {
+            let attribute = attribute?;
+            match attribute.read_content(self.pool) {
+                Ok(AttributeContent::LineNumberTable(table)) => {
+                    for line in table.lines().iter() {
+                        let line = line?;
+                        let offset = labels.instruction(line.start().as_u32())?;
+                        line_numbers
+                            .entry(offset)
+                            .or_default()
+                            .push(format!(".line {}", line.line_number()));
+                    }
+                }
+                Ok(AttributeContent::LocalVariableTable(table)) => {
+                    for variable in table.locals().iter() {
+                        variables.push(variable?);
+                    }
+                }
+                Ok(AttributeContent::LocalVariableTypeTable(table)) => {
+                    for variable in table.locals().iter() {
+                        variable_types.push(Some(variable?));
+                    }
+                }
+                Ok(AttributeContent::StackMapTable(table)) => {
+                    for frame in table.iter() {
+                        let (offset, frame) = frame?;
+                        let offset = labels.instruction(offset.as_u32())?;
+                        let frame = self.frame(frame, &mut labels)?;
+                        frames.entry(offset).or_default().push(format!(".frame {frame}"));
+                    }
+                }
+                Ok(_) => lines.push(format!(
+                    "    ; the attribute {} is left out",
+                    text(&[self.pool.retrieve(attribute.name())?])
+                )),
+                Err(err) if err.kind() == DecodeErrorKind::UnknownAttributeName => lines.push(format!(
+                    "    ; the attribute {} is left out",
+                    text(&[self.pool.retrieve(attribute.name())?])
+                )),
+                Err(err) => return Err(err),
+            }
+        }
+
+        for variable in &variables {
+            let range = variable.range();
+            let mut line = format!(
+                "    .var {} is {} {}",
+                variable.index(),
+                self.utf8(variable.name())?,
+                self.utf8(variable.descriptor())?
+            );
+            let signature = variable_types.iter_mut().find(|variable_type| {
+                variable_type.as_ref().is_some_and(|variable_type| {
+                    variable_type.index() == variable.index() && variable_type.range() == range
+                })
+            });
+            if let Some(variable_type) = signature.and_then(Option::take) {
+                line = format!("{line} signature {}", self.utf8(variable_type.signature())?);
+            }
+            lines.push(format!(
+                "{line} from {} to {}",
+                labels.at(range.start.as_u32())?,
+                labels.at(range.end.as_u32())?
+            ));
+        }
+        for variable_type in variable_types.into_iter().flatten() {
+            lines.push(format!(
+                "    ; the generic local variable {} is left out, as it has no matching descriptor",
+                self.utf8(variable_type.name())?
+            ));
+        }
+
+        let mut body = Vec::new();
+        for (offset, instruction) in &instructions {
+            let text = self.instruction(*offset, instruction, &mut labels)?;
+            body.push((*offset, text));
+        }
+
+        self.lines.extend(lines);
+        for (offset, text) in body {
+            if labels.used.contains(&offset) {
+                self.lines.push(format!("L{offset}:"));
+            }
+            let line_numbers = line_numbers.remove(&offset).into_iter().flatten();
+            for directive in line_numbers.chain(frames.remove(&offset).into_iter().flatten()) {
+                self.lines.push(format!("    {directive}"));
+            }
+            self.lines.push(format!("    {text}"));
+        }
+        if labels.used.contains(&labels.end) {
+            self.lines.push(format!("L{}:", labels.end));
+        }
+        Ok(())
+    }
+
+    fn frame(&self, frame: StackMapFrame<'input>, labels: &mut Labels) -> Result<String, DecodeError> {
+        let mut words = Vec::new();
+        match frame {
+            StackMapFrame::Same => words.push("same".to_owned()),
+            StackMapFrame::SameExtended => words.push("same_extended".to_owned()),
+            StackMapFrame::Same1 { stack } => {
+                words.push("same1".to_owned());
+                words.push(self.verification_type(stack, labels)?);
+            }
+            StackMapFrame::Same1Extended { stack } => {
+                words.push("same1_extended".to_owned());
+                words.push(self.verification_type(stack, labels)?);
+            }
+            StackMapFrame::Chop { to_chop } => words.push(format!("chop {to_chop}")),
+            StackMapFrame::Append { locals } => {
+                words.push("append".to_owned());
+                for local in locals {
+                    words.push(self.verification_type(local?, labels)?);
+                }
+            }
+            StackMapFrame::Full { locals, stack } => {
+                words.push("full locals".to_owned());
+                for local in locals {
+                    words.push(self.verification_type(local?, labels)?);
+                }
+                words.push("stack".to_owned());
+                for item in stack {
+                    words.push(self.verification_type(item?, labels)?);
+                }
+            }
+        }
+        Ok(words.join(" "))
+    }
+
+    fn verification_type(
+        &self,
+        verification_type: VerificationType<'input>,
+        labels: &mut Labels,
+    ) -> Result<String, DecodeError> {
+        Ok(match verification_type {
+            VerificationType::Top => "top".to_owned(),
+            VerificationType::Integer => "int".to_owned(),
+            VerificationType::Float => "float".to_owned(),
+            VerificationType::Long => "long".to_owned(),
+            VerificationType::Double => "double".to_owned(),
+            VerificationType::Null => "null".to_owned(),
+            VerificationType::UninitializedThis => "uninitialized_this".to_owned(),
+            VerificationType::Object(class) => format!("object {}", self.class_name(class)?),
+            VerificationType::UninitializedVariable(offset) => {
+                format!("uninitialized {}", labels.at(labels.instruction(offset.as_u32())?)?)
+            }
+        })
+    }
+
+    fn instruction(
+        &self,
+        offset: u32,
+        instruction: &RawInstruction<'input>,
+        labels: &mut Labels,
+    ) -> Result<String, DecodeError> {
+        use RawInstruction::*;
+
+        let mnemonic = instruction.mnemonic();
+        let target = |labels: &mut Labels, relative: i32| labels.at_relative(offset, relative);
+        let operands = match instruction {
+            ALoad { index }
+            | AStore { index }
+            | DLoad { index }
+            | DStore { index }
+            | FLoad { index }
+            | FStore { index }
+            | ILoad { index }
+            | IStore { index }
+            | LLoad { index }
+            | LStore { index }
+            | Ret { index } => index.to_string(),
+            ALoadW { index }
+            | AStoreW { index }
+            | DLoadW { index }
+            | DStoreW { index }
+            | FLoadW { index }
+            | FStoreW { index }
+            | ILoadW { index }
+            | IStoreW { index }
+            | LLoadW { index }
+            | LStoreW { index }
+            | RetW { index } => return Ok(format!("wide {mnemonic} {index}")),
+            IInc { index, value } => format!("{index} {value}"),
+            IIncW { index, value } => return Ok(format!("wide {mnemonic} {index} {value}")),
+            BIPush { value } => value.to_string(),
+            SIPush { value } => value.to_string(),
+            Goto { offset }
+            | IfACmpEq { offset }
+            | IfACmpNe { offset }
+            | IfICmpEq { offset }
+            | IfICmpNe { offset }
+            | IfICmpLt { offset }
+            | IfICmpGe { offset }
+            | IfICmpGt { offset }
+            | IfICmpLe { offset }
+            | IfEq { offset }
+            | IfNe { offset }
+            | IfLt { offset }
+            | IfGe { offset }
+            | IfGt { offset }
+            | IfLe { offset }
+            | IfNonNull { offset }
+            | IfNull { offset }
+            | JSr { offset } => target(labels, (*offset).into())?,
+            GotoW { offset } | JSrW { offset } => target(labels, *offset)?,
+            ANewArray { index } | CheckCast { index } | InstanceOf { index } | New { index } => {
+                self.class_name(*index)?
+            }
+            MultiANewArray { index, dimensions } => format!("{} {dimensions}", self.class_name(*index)?),
+            NewArray { atype } => array_type(*atype).to_owned(),
+            GetField { index } | GetStatic { index } | PutField { index } | PutStatic { index } => {
+                let field = self.pool.get(*index)?;
+                self.field_ref(field.class, field.name_and_type)?
+            }
+            InvokeVirtual { index } => {
+                let method = self.pool.get(*index)?;
+                self.method_ref(method.class, method.name_and_type)?
+            }
+            InvokeSpecial { index } | InvokeStatic { index } => match self.pool.get(*index)? {
+                Item::MethodRef(method) => self.method_ref(method.class, method.name_and_type)?,
+                Item::InterfaceMethodRef(method) => {
+                    format!("interface {}", self.method_ref(method.class, method.name_and_type)?)
+                }
+                _ => return Err(DecodeError::with_context(DecodeErrorKind::TagMismatch, Context::Code)),
+            },
+            InvokeInterface { index, count } => {
+                let method = self.pool.get(*index)?;
+                format!("{} {count}", self.method_ref(method.class, method.name_and_type)?)
+            }
+            InvokeDynamic { index } => {
+                let invoke = self.pool.get(*index)?;
+                let name_and_type = self.pool.retrieve(invoke.name_and_type)?;
+                format!(
+                    "{} {} {}",
+                    text(&[name_and_type.name]),
+                    text(&[name_and_type.descriptor]),
+                    self.bootstrap(invoke.bootstrap_method_attr)?
+                )
+            }
+            LdC { index } | LdCW { index } => self.constant(*index, false)?,
+            LdC2W { index } => self.constant(*index, true)?,
+            TableSwitch(switch) => {
+                let mut lines = vec![format!("{} {}", switch.low(), switch.high())];
+                for pair in switch.pairs() {
+                    lines.push(format!("        {}", target(labels, pair.offset())?));
+                }
+                lines.push(format!(
+                    "        default : {}",
+                    target(labels, switch.default_offset())?
+                ));
+                lines.join("\n")
+            }
+            LookupSwitch(switch) => {
+                let mut lines = vec![String::new()];
+                for pair in switch.pairs() {
+                    lines.push(format!("        {} : {}", pair.key(), target(labels, pair.offset())?));
+                }
+                lines.push(format!(
+                    "        default : {}",
+                    target(labels, switch.default_offset())?
+                ));
+                lines.join("\n")
+            }
+            _ => return Ok(mnemonic.to_owned()),
+        };
+        Ok(format!("{mnemonic} {operands}"))
+    }
+
+    /// Returns a constant as it is written for `ldc`, where untyped numbers are wide if they are loaded by `ldc2_w`.
+    fn constant(&self, index: cpool::Index<Item<'input>>, wide: bool) -> Result<String, DecodeError> {
+        Ok(match self.pool.get(index)? {
+            Item::Integer(integer) if !wide => integer.value.to_string(),
+            Item::Integer(integer) => format!("int {}", integer.value),
+            Item::Long(long) if wide => long.value.to_string(),
+            Item::Long(long) => format!("long {}", long.value),
+            Item::Float(float_) if !wide => float(float_.value),
+            Item::Float(float_) => format!("float {}", float(float_.value)),
+            Item::Double(double) if wide => float(double.value),
+            Item::Double(double) => format!("double {}", float(double.value)),
+            Item::String(string) => quote(self.pool.retrieve(string.string)?),
+            Item::Class(class) => format!("class {}", self.utf8(class.name)?),
+            Item::MethodType(method_type) => format!("method_type {}", self.utf8(method_type.descriptor)?),
+            Item::MethodHandle(handle) => format!("handle {}", self.handle(handle)?),
+            Item::Dynamic(dynamic) => {
+                let name_and_type = self.pool.retrieve(dynamic.name_and_type)?;
+                format!(
+                    "dynamic {} {} {}",
+                    text(&[name_and_type.name]),
+                    text(&[name_and_type.descriptor]),
+                    self.bootstrap(dynamic.bootstrap_method_attr)?
+                )
+            }
+            _ => {
+                return Err(DecodeError::with_context(
+                    DecodeErrorKind::TagMismatch,
+                    Context::ConstantPool,
+                ))
+            }
+        })
+    }
+
+    fn handle(&self, handle: &cpool::MethodHandle<'input>) -> Result<String, DecodeError> {
+        let kind = match handle.kind {
+            MethodKind::GetField => "getfield",
+            MethodKind::GetStatic => "getstatic",
+            MethodKind::PutField => "putfield",
+            MethodKind::PutStatic => "putstatic",
+            MethodKind::InvokeVirtual => "invokevirtual",
+            MethodKind::InvokeStatic => "invokestatic",
+            MethodKind::InvokeSpecial => "invokespecial",
+            MethodKind::NewInvokeSpecial => "newinvokespecial",
+            MethodKind::InvokeInterface => "invokeinterface",
+        };
+        Ok(match self.pool.get(handle.reference)? {
+            Item::FieldRef(field) => format!("{kind} {}", self.field_ref(field.class, field.name_and_type)?),
+            Item::MethodRef(method) => format!("{kind} {}", self.method_ref(method.class, method.name_and_type)?),
+            Item::InterfaceMethodRef(method) if handle.kind == MethodKind::InvokeInterface => {
+                format!("{kind} {}", self.method_ref(method.class, method.name_and_type)?)
+            }
+            Item::InterfaceMethodRef(method) => {
+                format!(
+                    "{kind} interface {}",
+                    self.method_ref(method.class, method.name_and_type)?
+                )
+            }
+            _ => {
+                return Err(DecodeError::with_context(
+                    DecodeErrorKind::TagMismatch,
+                    Context::ConstantPool,
+                ))
+            }
+        })
+    }
+
+    /// Returns the method handle and the arguments of a bootstrap method.
+    fn bootstrap(&self, index: u16) -> Result<String, DecodeError> {
+        let method = self
+            .bootstrap_methods
+            .get(usize::from(index))
+            .ok_or_else(|| DecodeError::with_context(DecodeErrorKind::InvalidIndex, Context::Attributes))?;
+        let mut words = vec![self.handle(self.pool.get(method.method_ref())?)?, "{".to_owned()];
+        for argument in method.arguments().iter() {
+            words.push(self.constant(argument?, false)?);
+        }
+        words.push("}".to_owned());
+        Ok(words.join(" "))
+    }
+
+    fn field_ref(
+        &self,
+        class: cpool::Index<cpool::Class<'input>>,
+        name_and_type: cpool::Index<cpool::NameAndType<'input>>,
+    ) -> Result<String, DecodeError> {
+        let class = self.pool.retrieve(class)?;
+        let name_and_type = self.pool.retrieve(name_and_type)?;
+        Ok(format!(
+            "{} {}",
+            text(&[class.name, mutf8!("/"), name_and_type.name]),
+            text(&[name_and_type.descriptor])
+        ))
+    }
+
+    fn method_ref(
+        &self,
+        class: cpool::Index<cpool::Class<'input>>,
+        name_and_type: cpool::Index<cpool::NameAndType<'input>>,
+    ) -> Result<String, DecodeError> {
+        let class = self.pool.retrieve(class)?;
+        let name_and_type = self.pool.retrieve(name_and_type)?;
+        Ok(text(&[
+            class.name,
+            mutf8!("/"),
+            name_and_type.name,
+            name_and_type.descriptor,
+        ]))
+    }
+
+    fn class_name(&self, class: cpool::Index<cpool::Class<'input>>) -> Result<String, DecodeError> {
+        Ok(text(&[self.pool.retrieve(class)?.name]))
+    }
+
+    fn utf8(&self, index: cpool::Index<cpool::Utf8<'input>>) -> Result<String, DecodeError> {
+        Ok(text(&[self.pool.retrieve(index)?]))
+    }
+}
+
+/// The labels of a method, which are named after their offset.
+struct Labels {
+    /// The offsets of all instructions.
+    offsets: BTreeSet<u32>,
+    used: BTreeSet<u32>,
+    /// The length of the code, at which a label may be placed as well.
+    end: u32,
+}
+
+impl Labels {
+    /// Checks that an offset is the start of an instruction.
+    fn instruction(&self, offset: u32) -> Result<u32, DecodeError> {
+        if self.offsets.contains(&offset) {
+            Ok(offset)
+        } else {
+            Err(DecodeError::with_context(DecodeErrorKind::InvalidIndex, Context::Code))
+        }
+    }
+
+    fn at(&mut self, offset: u32) -> Result<String, DecodeError> {
+        if offset != self.end {
+            self.instruction(offset)?;
+        }
+        self.used.insert(offset);
+        Ok(format!("L{offset}"))
+    }
+
+    fn at_relative(&mut self, offset: u32, relative: i32) -> Result<String, DecodeError> {
+        let target = u32::try_from(i64::from(offset) + i64::from(relative))
+            .map_err(|_| DecodeError::with_context(DecodeErrorKind::InvalidIndex, Context::Code))?;
+        self.at(self.instruction(target)?)
+    }
+}
+
+const CLASS_FLAGS: &[(&str, AccessFlags)] = &[
+    ("public", AccessFlags::PUBLIC),
+    ("final", AccessFlags::FINAL),
+    ("super", AccessFlags::SUPER),
+    ("interface", AccessFlags::INTERFACE),
+    ("abstract", AccessFlags::ABSTRACT),
+    ("synthetic", AccessFlags::SYNTHETIC),
+    ("annotation", AccessFlags::ANNOTATION),
+    ("enum", AccessFlags::ENUM),
+    ("module", AccessFlags::MODULE),
+];
+
+const FIELD_FLAGS: &[(&str, AccessFlags)] = &[
+    ("public", AccessFlags::PUBLIC),
+    ("private", AccessFlags::PRIVATE),
+    ("protected", AccessFlags::PROTECTED),
+    ("static", AccessFlags::STATIC),
+    ("final", AccessFlags::FINAL),
+    ("volatile", AccessFlags::VOLATILE),
+    ("transient", AccessFlags::TRANSIENT),
+    ("synthetic", AccessFlags::SYNTHETIC),
+    ("enum", AccessFlags::ENUM),
+];
+
+const METHOD_FLAGS: &[(&str, AccessFlags)] = &[
+    ("public", AccessFlags::PUBLIC),
+    ("private", AccessFlags::PRIVATE),
+    ("protected", AccessFlags::PROTECTED),
+    ("static", AccessFlags::STATIC),
+    ("final", AccessFlags::FINAL),
+    ("synchronized", AccessFlags::SYNCHRONIZED),
+    ("bridge", AccessFlags::BRIDGE),
+    ("varargs", AccessFlags::VARARGS),
+    ("native", AccessFlags::NATIVE),
+    ("abstract", AccessFlags::ABSTRACT),
+    ("strict", AccessFlags::STRICT),
+    ("synthetic", AccessFlags::SYNTHETIC),
+];
+
+const INNER_CLASS_FLAGS: &[(&str, AccessFlags)] = &[
+    ("public", AccessFlags::PUBLIC),
+    ("private", AccessFlags::PRIVATE),
+    ("protected", AccessFlags::PROTECTED),
+    ("static", AccessFlags::STATIC),
+    ("final", AccessFlags::FINAL),
+    ("interface", AccessFlags::INTERFACE),
+    ("abstract", AccessFlags::ABSTRACT),
+    ("synthetic", AccessFlags::SYNTHETIC),
+    ("annotation", AccessFlags::ANNOTATION),
+    ("enum", AccessFlags::ENUM),
+];
+
+const PARAMETER_FLAGS: &[(&str, AccessFlags)] = &[
+    ("final", AccessFlags::FINAL),
+    ("synthetic", AccessFlags::SYNTHETIC),
+    ("mandated", AccessFlags::MANDATED),
+];
+
+/// A name for every flag, used for flags which have no meaning for an item.
+const ALL_FLAGS: &[(&str, AccessFlags)] = &[
+    ("public", AccessFlags::PUBLIC),
+    ("private", AccessFlags::PRIVATE),
+    ("protected", AccessFlags::PROTECTED),
+    ("static", AccessFlags::STATIC),
+    ("final", AccessFlags::FINAL),
+    ("super", AccessFlags::SUPER),
+    ("volatile", AccessFlags::VOLATILE),
+    ("transient", AccessFlags::TRANSIENT),
+    ("native", AccessFlags::NATIVE),
+    ("interface", AccessFlags::INTERFACE),
+    ("abstract", AccessFlags::ABSTRACT),
+    ("strict", AccessFlags::STRICT),
+    ("synthetic", AccessFlags::SYNTHETIC),
+    ("annotation", AccessFlags::ANNOTATION),
+    ("enum", AccessFlags::ENUM),
+    ("module", AccessFlags::MODULE),
+    ("open", AccessFlags::OPEN),
+    ("transitive", AccessFlags::TRANSITIVE),
+    ("static_phase", AccessFlags::STATIC_PHASE),
+    ("synchronized", AccessFlags::SYNCHRONIZED),
+    ("bridge", AccessFlags::BRIDGE),
+    ("varargs", AccessFlags::VARARGS),
+    ("mandated", AccessFlags::MANDATED),
+];
+
+/// Words which have a meaning in some position, so that names equal to them have to be quoted.
+const KEYWORDS: &[&str] = &[
+    "all",
+    "default",
+    "from",
+    "interface",
+    "invisible",
+    "is",
+    "locals",
+    "name",
+    "outer",
+    "parameter",
+    "signature",
+    "stack",
+    "to",
+    "using",
+    "visible",
+    "wide",
+    "=",
+    "{",
+    "}",
+];
+
+/// Returns the names of the flags, each followed by a space.
+fn flags(flags: AccessFlags, names: &[(&str, AccessFlags)]) -> String {
+    let mut remaining = flags;
+    let mut words = String::new();
+    for &(name, flag) in names.iter().chain(ALL_FLAGS) {
+        if remaining.contains(flag) {
+            remaining.remove(flag);
+            words = words + name + " ";
+        }
+    }
+    words
+}
+
+/// Formats a floating point number such that it is read as one again.
+fn float<T: std::fmt::Display>(value: T) -> String {
+    let text = value.to_string();
+    match text.as_str() {
+        "inf" => "Infinity".to_owned(),
+        "-inf" => "-Infinity".to_owned(),
+        _ if text.contains(['.', 'N']) => text,
+        _ => text + ".0",
+    }
+}
+
+fn array_type(array_type: ArrayType) -> &'static str {
+    match array_type {
+        ArrayType::Boolean => "boolean",
+        ArrayType::Char => "char",
+        ArrayType::Float => "float",
+        ArrayType::Double => "double",
+        ArrayType::Byte => "byte",
+        ArrayType::Short => "short",
+        ArrayType::Int => "int",
+        ArrayType::Long => "long",
+    }
+}
+
+/// Joins the parts into a single word, which is quoted if it could not be read as one.
+fn text(parts: &[&MStr]) -> String {
+    let mut word = String::new();
+    for part in parts {
+        match part.to_str() {
+            Some(part) => word += part,
+            None => return quote_parts(parts),
+        }
+    }
+
+    let plain = !word.is_empty()
+        && !word.starts_with(';')
+        && !word.contains(|c: char| c.is_whitespace() || c.is_control() || c == '"' || c == ':')
+        && !KEYWORDS.contains(&word.as_str())
+        && !ALL_FLAGS.iter().any(|&(name, _)| name == word);
+    if plain {
+        word
+    } else {
+        quote_parts(parts)
+    }
+}
+
+fn quote(string: &MStr) -> String {
+    quote_parts(&[string])
+}
+
+fn quote_parts(parts: &[&MStr]) -> String {
+    let mut quoted = String::from('"');
+    for c in parts.iter().flat_map(|part| part.chars()) {
+        match c {
+            Ok('"') => quoted += "\\\"",
+            Ok('\\') => quoted += "\\\\",
+            Ok('\n') => quoted += "\\n",
+            Ok('\r') => quoted += "\\r",
+            Ok('\t') => quoted += "\\t",
+            Ok('\0') => quoted += "\\0",
+            Ok(c) if c.is_control() => push_unicode_escape(&mut quoted, c.into()),
+            Ok(c) => quoted.push(c),
+            // an unpaired surrogate
+            Err(unit) => push_unicode_escape(&mut quoted, unit),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn push_unicode_escape(quoted: &mut String, unit: u32) {
+    quoted.push_str("\\u");
+    for shift in [12, 8, 4, 0] {
+        quoted.extend(char::from_digit((unit >> shift) & 0xF, 16).map(|c| c.to_ascii_uppercase()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assembler::assemble;
+
+    fn round_trip(source: &str) -> String {
+        let bytes = assemble(source).unwrap();
+        let text = disassemble(&Class::new(&bytes).unwrap()).unwrap();
+        let again = assemble(&text).unwrap();
+        assert_eq!(disassemble(&Class::new(&again).unwrap()).unwrap(), text);
+        text
+    }
+
+    #[test]
+    fn reassembles() {
+        let text = round_trip(concat!(
+            ".class public super \"odd name\"\n",
+            ".super java/lang/Object\n",
+            ".annotation visible LA; { v = array { int 1 string \"x\" } }\n",
+            ".field static final \"is\" J = 5\n",
+            ".field static final f F = NaN\n",
+            ".method public static m (I)I\n",
+            "    .limit stack 4\n",
+            "    .limit locals 2\n",
+            "    .catch all from a to b using b\n",
+            "a:  iload_0\n",
+            "    tableswitch 0 1\n",
+            "        a\n",
+            "        b\n",
+            "        default : b\n",
+            "b:\n",
+            "    .frame full locals int stack\n",
+            "    ldc \"\\\"\\ud83d\\ude00\\\"\"\n",
+            "    pop\n",
+            "    ldc2_w -1.5\n",
+            "    pop2\n",
+            "    wide iinc 1 1000\n",
+            "    invokestatic interface I/s()V\n",
+            "    iload_0\n",
+            "    ireturn\n",
+            ".end method\n",
+        ));
+        assert!(text.contains(".class public super \"odd name\""));
+        assert!(text.contains(".field static final \"is\" J = 5"));
+        assert!(text.contains(".field static final f F = NaN"));
+        assert!(text.contains("    ldc2_w -1.5"));
+        assert!(text.contains("    wide iinc 1 1000"));
+        assert!(text.contains("    invokestatic interface I/s()V"));
+    }
+}
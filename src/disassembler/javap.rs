@@ -0,0 +1,2180 @@
+//! Prints classes in the layout of `javap -v`, so that noak's view of a class can be compared with the one of the JDK.
+
+use std::fmt;
+
+use crate::error::*;
+use crate::header::AccessFlags;
+use crate::reader::attributes::annotations::{
+    Annotation, ElementValue, ParameterAnnotations, SuperTypeIndex, TargetInfo, TargetType, TypeAnnotation,
+    TypePathSegmentKind,
+};
+use crate::reader::attributes::{AttributeContent, Code, RawInstruction, StackMapFrame, VerificationType};
+use crate::reader::cpool::{self, ConstantPool, Item, MethodKind};
+use crate::reader::decoding::DecodeMany;
+use crate::reader::{Attribute, Class, Field, Method};
+use crate::MStr;
+
+use super::array_type;
+
+/// The members which are printed, like the access options of `javap`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    /// Only public members.
+    Public,
+    /// Public and protected members.
+    Protected,
+    /// All members except private ones, which is what `javap` prints by default.
+    #[default]
+    Package,
+    /// All members.
+    Private,
+}
+
+impl Visibility {
+    fn shows(self, flags: AccessFlags) -> bool {
+        match self {
+            Visibility::Public => flags.contains(AccessFlags::PUBLIC),
+            Visibility::Protected => flags.intersects(AccessFlags::PUBLIC | AccessFlags::PROTECTED),
+            Visibility::Package => !flags.contains(AccessFlags::PRIVATE),
+            Visibility::Private => true,
+        }
+    }
+}
+
+/// Prints a class like `javap -v` does.
+///
+/// The lines `javap` starts with, which describe the file the class was read from, are left out.
+/// Everything else is printed in the same layout, so that the output can be compared line by line.
+///
+/// # Examples
+/// ```
+/// use noak::disassembler::javap::{verbose, Visibility};
+///
+/// let bytes = noak::assembler::assemble(".class public super Example\n.super java/lang/Object\n")?;
+/// let class = noak::reader::Class::new(&bytes)?;
+/// let text = verbose(&class, Visibility::Package)?;
+/// assert!(text.starts_with("public class Example\n  minor version: 0\n"));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn verbose(class: &Class<'_>, visibility: Visibility) -> Result<String, DecodeError> {
+    let mut printer = Printer {
+        pool: class.pool(),
+        this_class: class.this_class(),
+        interface: class.access_flags().contains(AccessFlags::INTERFACE),
+        visibility,
+        args_size: 0,
+        out: String::new(),
+        line: String::new(),
+        pending: 0,
+        indent: 0,
+    };
+    printer.class(class)?;
+    Ok(printer.out)
+}
+
+/// Writes lines like the `BasicWriter` of `javap`.
+///
+/// Spaces are only written once something follows them on the same line, which drops trailing spaces
+/// and lets lines which are empty apart from their indentation become empty.
+struct Printer<'a, 'input> {
+    pool: &'a ConstantPool<'input>,
+    this_class: cpool::Index<cpool::Class<'input>>,
+    interface: bool,
+    visibility: Visibility,
+    /// The number of arguments of the method whose attributes are printed, including `this`.
+    args_size: usize,
+    out: String,
+    line: String,
+    pending: usize,
+    indent: usize,
+}
+
+impl<'a, 'input> Printer<'a, 'input> {
+    fn print(&mut self, text: &str) {
+        for c in text.chars() {
+            match c {
+                ' ' => self.pending += 1,
+                '\n' => self.newline(),
+                _ => {
+                    if self.line.is_empty() {
+                        self.pending += self.indent * 2;
+                    }
+                    self.line.extend(std::iter::repeat_n(' ', self.pending));
+                    self.pending = 0;
+                    self.line.push(c);
+                }
+            }
+        }
+    }
+
+    fn println(&mut self, text: &str) {
+        self.print(text);
+        self.newline();
+    }
+
+    fn newline(&mut self) {
+        self.out.push_str(&self.line);
+        self.out.push('\n');
+        self.line.clear();
+        self.pending = 0;
+    }
+
+    /// Moves to the column of comments, or by a single space if the line is already longer.
+    fn tab(&mut self) {
+        let column = self.indent * 2 + 40;
+        let length = self.line.chars().count();
+        self.pending += if column <= length { 1 } else { column - length };
+    }
+
+    /// Prints an index into the constant pool followed by a comment.
+    fn commented(&mut self, text: &str, comment: &str) {
+        self.print(text);
+        self.tab();
+        self.println(&format!("// {comment}"));
+    }
+
+    fn modifiers(&mut self, flags: AccessFlags, names: &[(&str, AccessFlags)]) {
+        for &(name, flag) in names {
+            if flags.contains(flag) {
+                self.print(name);
+                self.print(" ");
+            }
+        }
+    }
+
+    fn flags(&mut self, flags: AccessFlags, names: &[(&str, AccessFlags)]) {
+        let mut remaining = flags.bits();
+        let mut words = Vec::new();
+        for &(name, flag) in names {
+            if remaining & flag.bits() != 0 {
+                remaining &= !flag.bits();
+                words.push(name.to_owned());
+            }
+        }
+        while remaining != 0 {
+            let bit = 1 << (15 - remaining.leading_zeros());
+            remaining &= !bit;
+            words.push(format!("0x{bit:x}"));
+        }
+        self.println(&format!("flags: (0x{:04x}) {}", flags.bits(), words.join(", ")));
+    }
+
+    fn class(&mut self, class: &Class<'input>) -> Result<(), DecodeError> {
+        let flags = class.access_flags();
+        let mut source_file = None;
+        let mut signature = None;
+        let mut module = None;
+        for attribute in class.attributes().iter() {
+            match attribute?.read_content(self.pool) {
+                Ok(AttributeContent::SourceFile(source)) => source_file = Some(source.source_file()),
+                Ok(AttributeContent::Signature(content)) => signature = Some(content.signature()),
+                Ok(AttributeContent::Module(content)) => module = Some(content),
+                _ => {}
+            }
+        }
+
+        if let Some(source_file) = source_file {
+            self.indent = 1;
+            self.println(&format!(
+                "Compiled from \"{}\"",
+                lossy(self.pool.retrieve(source_file)?)
+            ));
+            self.indent = 0;
+        }
+
+        let this_name = java_name(self.pool.retrieve(self.this_class)?.name);
+        let modifiers = if self.interface {
+            &CLASS_MODIFIERS[..2]
+        } else {
+            CLASS_MODIFIERS
+        };
+        self.modifiers(flags, modifiers);
+        if flags.contains(AccessFlags::MODULE) {
+            if let Some(module) = &module {
+                if module.flags().contains(AccessFlags::OPEN) {
+                    self.print("open ");
+                }
+                self.print("module ");
+                self.print(&java_name(self.pool.retrieve(module.name())?.name));
+                if let Some(version) = module.version() {
+                    self.print(&format!("@{}", lossy(self.pool.retrieve(version)?)));
+                }
+            } else {
+                self.print(&format!("module {this_name}"));
+            }
+        } else {
+            self.print(if self.interface { "interface " } else { "class " });
+            self.print(&this_name);
+        }
+
+        match signature {
+            Some(signature) => {
+                let signature = class_signature(&lossy(self.pool.retrieve(signature)?)).ok_or_else(invalid)?;
+                if !signature.type_parameters.is_empty() {
+                    self.print(&format!("<{}>", signature.type_parameters.join(", ")));
+                }
+                if self.interface {
+                    if !signature.interfaces.is_empty() {
+                        self.print(&format!(" extends {}", signature.interfaces.join(", ")));
+                    }
+                } else {
+                    self.print(&format!(" extends {}", signature.super_class));
+                    if !signature.interfaces.is_empty() {
+                        self.print(&format!(" implements {}", signature.interfaces.join(", ")));
+                    }
+                }
+            }
+            None => {
+                if let (false, Some(super_class)) = (self.interface, class.super_class()) {
+                    let super_class = java_name(self.pool.retrieve(super_class)?.name);
+                    if super_class != "java.lang.Object" {
+                        self.print(&format!(" extends {super_class}"));
+                    }
+                }
+                for (i, interface) in class.interfaces().iter().enumerate() {
+                    if i == 0 {
+                        self.print(if self.interface { " extends " } else { " implements " });
+                    } else {
+                        self.print(",");
+                    }
+                    self.print(&java_name(self.pool.retrieve(interface?)?.name));
+                }
+            }
+        }
+        self.println("");
+
+        self.indent = 1;
+        let version = class.version();
+        self.println(&format!("minor version: {}", version.minor));
+        self.println(&format!("major version: {}", version.major));
+        self.flags(flags, CLASS_FLAGS);
+        let this_class = self.this_class.as_u16();
+        self.commented(&format!("this_class: #{this_class}"), &self.string_value(this_class)?);
+        match class.super_class() {
+            Some(super_class) => {
+                let super_class = super_class.as_u16();
+                self.commented(
+                    &format!("super_class: #{super_class}"),
+                    &self.string_value(super_class)?,
+                );
+            }
+            None => self.println("super_class: #0"),
+        }
+        self.println(&format!(
+            "interfaces: {}, fields: {}, methods: {}, attributes: {}",
+            class.interfaces().iter().count(),
+            class.fields().iter().count(),
+            class.methods().iter().count(),
+            class.attributes().iter().count()
+        ));
+        self.indent = 0;
+
+        self.constant_pool()?;
+
+        self.println("{");
+        for field in class.fields().iter() {
+            let field = field?;
+            if self.visibility.shows(field.access_flags()) {
+                self.field(&field)?;
+            }
+        }
+        let mut first = true;
+        for method in class.methods().iter() {
+            let method = method?;
+            if self.visibility.shows(method.access_flags()) {
+                if !first {
+                    self.println("");
+                }
+                first = false;
+                self.method(class, &method)?;
+            }
+        }
+        self.indent = 0;
+        self.println("}");
+
+        self.attributes(&class.attributes())
+    }
+
+    fn constant_pool(&mut self) -> Result<(), DecodeError> {
+        self.println("Constant pool:");
+        self.indent += 1;
+        let entries: Vec<_> = self.pool.iter_indices().collect();
+        let count = entries.last().map_or(1, |(index, item)| match item {
+            Item::Long(_) | Item::Double(_) => usize::from(index.as_u16()) + 2,
+            _ => usize::from(index.as_u16()) + 1,
+        });
+        let width = count.to_string().len() + 1;
+
+        for (index, item) in entries {
+            let index = format!("#{}", index.as_u16());
+            self.print(&format!("{index:>width$} = {:<18} ", pool_tag_name(item)));
+            let value = self.item_value(item)?;
+            match item {
+                Item::Class(class) => self.commented(&format!("#{}", class.name.as_u16()), &value),
+                Item::String(string) => self.commented(&format!("#{}", string.string.as_u16()), &value),
+                Item::Module(module) => self.commented(&format!("#{}", module.name.as_u16()), &value),
+                Item::Package(package) => self.commented(&format!("#{}", package.name.as_u16()), &value),
+                Item::FieldRef(cpool::FieldRef { class, name_and_type })
+                | Item::MethodRef(cpool::MethodRef { class, name_and_type })
+                | Item::InterfaceMethodRef(cpool::InterfaceMethodRef { class, name_and_type }) => {
+                    self.commented(&format!("#{}.#{}", class.as_u16(), name_and_type.as_u16()), &value)
+                }
+                Item::NameAndType(name_and_type) => self.commented(
+                    &format!(
+                        "#{}:#{}",
+                        name_and_type.name.as_u16(),
+                        name_and_type.descriptor.as_u16()
+                    ),
+                    &value,
+                ),
+                Item::Dynamic(cpool::Dynamic {
+                    bootstrap_method_attr,
+                    name_and_type,
+                })
+                | Item::InvokeDynamic(cpool::InvokeDynamic {
+                    bootstrap_method_attr,
+                    name_and_type,
+                }) => self.commented(&format!("#{bootstrap_method_attr}:#{}", name_and_type.as_u16()), &value),
+                Item::MethodHandle(handle) => self.commented(
+                    &format!("{}:#{}", method_kind_tag(handle.kind), handle.reference.as_u16()),
+                    &value,
+                ),
+                // the value is not the only one preceded by two spaces by accident, javap does the same
+                Item::MethodType(method_type) => {
+                    self.commented(&format!("#{}", method_type.descriptor.as_u16()), &format!(" {value}"));
+                }
+                Item::Utf8(_) | Item::Integer(_) | Item::Long(_) | Item::Float(_) | Item::Double(_) => {
+                    self.println(&value);
+                }
+            }
+        }
+        self.indent -= 1;
+        Ok(())
+    }
+
+    fn field(&mut self, field: &Field<'input>) -> Result<(), DecodeError> {
+        self.indent = 1;
+        let descriptor = self.pool.retrieve(field.descriptor())?;
+        let type_ = self.member_type(descriptor, &field.attributes())?;
+        self.modifiers(field.access_flags(), FIELD_MODIFIERS);
+        self.println(&format!("{type_} {};", lossy(self.pool.retrieve(field.name())?)));
+
+        self.indent = 2;
+        self.println(&format!("descriptor: {}", lossy(descriptor)));
+        self.flags(field.access_flags(), FIELD_FLAGS);
+        self.attributes(&field.attributes())?;
+        self.indent = 1;
+        self.println("");
+        Ok(())
+    }
+
+    /// Returns the type of a field or record component, which is taken from its signature if it has one.
+    fn member_type(
+        &self,
+        descriptor: &MStr,
+        attributes: &DecodeMany<'input, Attribute<'input>, u16>,
+    ) -> Result<String, DecodeError> {
+        let mut text = lossy(descriptor);
+        for attribute in attributes.iter() {
+            if let Ok(AttributeContent::Signature(signature)) = attribute?.read_content(self.pool) {
+                text = lossy(self.pool.retrieve(signature.signature())?);
+            }
+        }
+        field_type(&text).ok_or_else(invalid)
+    }
+
+    fn method(&mut self, class: &Class<'input>, method: &Method<'input>) -> Result<(), DecodeError> {
+        let flags = method.access_flags();
+        let name = lossy(self.pool.retrieve(method.name())?);
+        let descriptor = self.pool.retrieve(method.descriptor())?;
+        let erased = method_signature(&lossy(descriptor)).ok_or_else(invalid)?;
+
+        let mut signature = None;
+        let mut exceptions = None;
+        for attribute in method.attributes().iter() {
+            match attribute?.read_content(self.pool) {
+                Ok(AttributeContent::Signature(content)) => {
+                    let text = lossy(self.pool.retrieve(content.signature())?);
+                    signature = Some(method_signature(&text).ok_or_else(invalid)?);
+                }
+                Ok(AttributeContent::Exceptions(content)) => exceptions = Some(content),
+                _ => {}
+            }
+        }
+        let generic = signature.as_ref().unwrap_or(&erased);
+
+        self.indent = 1;
+        self.modifiers(flags, METHOD_MODIFIERS);
+        if self.interface
+            && !flags.intersects(AccessFlags::ABSTRACT | AccessFlags::STATIC | AccessFlags::PRIVATE)
+            && name != "<clinit>"
+            && class.version().major >= 52
+        {
+            self.print("default ");
+        }
+        if !generic.type_parameters.is_empty() {
+            self.print(&format!("<{}> ", generic.type_parameters.join(", ")));
+        }
+        let mut parameters = format!("({})", generic.parameters.join(", "));
+        if flags.contains(AccessFlags::VARARGS) {
+            if let Some(position) = parameters.rfind("[]") {
+                parameters.replace_range(position..position + 2, "...");
+            }
+        }
+        match name.as_str() {
+            "<init>" => self.print(&format!(
+                "{}{parameters}",
+                java_name(self.pool.retrieve(self.this_class)?.name)
+            )),
+            "<clinit>" => self.print("{}"),
+            _ => self.print(&format!("{} {name}{parameters}", generic.return_type)),
+        }
+        if let Some(exceptions) = exceptions {
+            self.print(" throws ");
+            if generic.throws.is_empty() {
+                let mut names = Vec::new();
+                for exception in exceptions.exceptions().iter() {
+                    names.push(java_name(self.pool.retrieve(exception?)?.name));
+                }
+                self.print(&names.join(", "));
+            } else {
+                self.print(&generic.throws.join(", "));
+            }
+        }
+        self.println(";");
+
+        self.indent = 2;
+        self.println(&format!("descriptor: {}", lossy(descriptor)));
+        self.flags(flags, METHOD_FLAGS);
+        self.args_size = erased.parameters.len() + usize::from(!flags.contains(AccessFlags::STATIC));
+        self.attributes(&method.attributes())?;
+        self.indent = 1;
+        Ok(())
+    }
+
+    fn attributes(&mut self, attributes: &DecodeMany<'input, Attribute<'input>, u16>) -> Result<(), DecodeError> {
+        for attribute in attributes.iter() {
+            self.attribute(&attribute?)?;
+        }
+        Ok(())
+    }
+
+    fn attribute(&mut self, attribute: &Attribute<'input>) -> Result<(), DecodeError> {
+        let name = self.pool.retrieve(attribute.name())?;
+        let content = match attribute.read_content(self.pool) {
+            Ok(content) => content,
+            Err(err) if err.kind() == DecodeErrorKind::UnknownAttributeName => {
+                let bytes = attribute.content();
+                self.println(&format!(
+                    "  {}: length = 0x{:X} (unknown attribute)",
+                    lossy(name),
+                    bytes.len()
+                ));
+                self.print("   ");
+                for (i, byte) in bytes.iter().enumerate() {
+                    self.print(&format!("{byte:02X}"));
+                    if i % 16 == 15 {
+                        self.println("");
+                        self.print("   ");
+                    } else {
+                        self.print(" ");
+                    }
+                }
+                self.println("");
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+
+        match content {
+            AttributeContent::AnnotationDefault(default) => {
+                self.println("AnnotationDefault:");
+                self.indent += 1;
+                let value = default.value();
+                self.println(&format!("default_value: {}", self.element_value(&value)?));
+                self.indent += 1;
+                self.resolved_element_value(&value)?;
+                self.println("");
+                self.indent -= 2;
+            }
+            AttributeContent::BootstrapMethods(methods) => {
+                self.println("BootstrapMethods:");
+                for (i, method) in methods.methods().iter().enumerate() {
+                    let method = method?;
+                    let handle = method.method_ref().as_u16();
+                    self.indent += 1;
+                    self.println(&format!("{i}: #{handle} {}", self.string_value(handle)?));
+                    self.indent += 1;
+                    self.println("Method arguments:");
+                    self.indent += 1;
+                    for argument in method.arguments().iter() {
+                        let argument = argument?.as_u16();
+                        self.println(&format!("#{argument} {}", self.string_value(argument)?));
+                    }
+                    self.indent -= 3;
+                }
+            }
+            AttributeContent::Code(code) => self.code(&code)?,
+            AttributeContent::ConstantValue(value) => {
+                self.println(&format!("ConstantValue: {}", self.constant(value.value().as_u16())?));
+            }
+            AttributeContent::Deprecated(_) => self.println("Deprecated: true"),
+            AttributeContent::EnclosingMethod(enclosing) => {
+                let class = enclosing.class();
+                let mut comment = java_name(self.pool.retrieve(class)?.name);
+                if let Some(method) = enclosing.method() {
+                    comment = format!("{comment}.{}", lossy(self.pool.retrieve(method)?.name));
+                }
+                let method = enclosing.method().map_or(0, cpool::Index::as_u16);
+                self.commented(&format!("EnclosingMethod: #{}.#{method}", class.as_u16()), &comment);
+            }
+            AttributeContent::Exceptions(exceptions) => {
+                self.println("Exceptions:");
+                let mut names = Vec::new();
+                for exception in exceptions.exceptions().iter() {
+                    names.push(java_name(self.pool.retrieve(exception?)?.name));
+                }
+                self.indent += 1;
+                self.println(&format!("throws {}", names.join(", ")));
+                self.indent -= 1;
+            }
+            AttributeContent::InnerClasses(classes) => {
+                let mut first = true;
+                for class in classes.classes().iter() {
+                    let class = class?;
+                    let flags = class.inner_access_flags();
+                    if !self.visibility.shows(flags) {
+                        continue;
+                    }
+                    if first {
+                        self.println("InnerClasses:");
+                        self.indent += 1;
+                        first = false;
+                    }
+                    let modifiers = if flags.contains(AccessFlags::INTERFACE) {
+                        &INNER_CLASS_MODIFIERS[..5]
+                    } else {
+                        INNER_CLASS_MODIFIERS
+                    };
+                    self.modifiers(flags, modifiers);
+                    let mut comment = String::new();
+                    if let Some(name) = class.inner_name() {
+                        self.print(&format!("#{}= ", name.as_u16()));
+                        comment = format!("{}=", lossy(self.pool.retrieve(name)?));
+                    }
+                    let inner = class.inner_class().as_u16();
+                    self.print(&format!("#{inner}"));
+                    comment += &self.constant(inner)?;
+                    if let Some(outer) = class.outer_class() {
+                        self.print(&format!(" of #{}", outer.as_u16()));
+                        comment = format!("{comment} of {}", self.constant(outer.as_u16())?);
+                    }
+                    self.commented(";", &comment);
+                }
+                if !first {
+                    self.indent -= 1;
+                }
+            }
+            AttributeContent::LineNumberTable(table) => {
+                self.println("LineNumberTable:");
+                self.indent += 1;
+                for line in table.lines().iter() {
+                    let line = line?;
+                    self.println(&format!("line {}: {}", line.line_number(), line.start().as_u32()));
+                }
+                self.indent -= 1;
+            }
+            AttributeContent::LocalVariableTable(table) => {
+                self.println("LocalVariableTable:");
+                self.indent += 1;
+                self.println("Start  Length  Slot  Name   Signature");
+                for variable in table.locals().iter() {
+                    let variable = variable?;
+                    let range = variable.range();
+                    self.println(&format!(
+                        "{:5} {:7} {:5} {:>5}   {}",
+                        range.start.as_u32(),
+                        range.end.as_u32() - range.start.as_u32(),
+                        variable.index(),
+                        self.string_value(variable.name().as_u16())?,
+                        self.string_value(variable.descriptor().as_u16())?
+                    ));
+                }
+                self.indent -= 1;
+            }
+            AttributeContent::LocalVariableTypeTable(table) => {
+                self.println("LocalVariableTypeTable:");
+                self.indent += 1;
+                self.println("Start  Length  Slot  Name   Signature");
+                for variable in table.locals().iter() {
+                    let variable = variable?;
+                    let range = variable.range();
+                    self.println(&format!(
+                        "{:5} {:7} {:5} {:>5}   {}",
+                        range.start.as_u32(),
+                        range.end.as_u32() - range.start.as_u32(),
+                        variable.index(),
+                        self.string_value(variable.name().as_u16())?,
+                        self.string_value(variable.signature().as_u16())?
+                    ));
+                }
+                self.indent -= 1;
+            }
+            AttributeContent::MethodParameters(parameters) => {
+                self.println("MethodParameters:");
+                self.indent += 1;
+                self.println(&format!("{:<31}{}", "Name", "Flags"));
+                for parameter in parameters.parameters().iter() {
+                    let parameter = parameter?;
+                    let name = match parameter.name() {
+                        Some(name) => self.string_value(name.as_u16())?,
+                        None => "<no name>".to_owned(),
+                    };
+                    let flags = parameter.access_flags();
+                    let mut words = String::new();
+                    for (word, flag) in [
+                        ("final ", AccessFlags::FINAL),
+                        ("mandated ", AccessFlags::MANDATED),
+                        ("synthetic", AccessFlags::SYNTHETIC),
+                    ] {
+                        if flags.contains(flag) {
+                            words += word;
+                        }
+                    }
+                    self.println(&format!("{name:<31}{words}"));
+                }
+                self.indent -= 1;
+            }
+            AttributeContent::Module(module) => {
+                self.println("Module:");
+                self.indent += 1;
+                let mut comment = self.string_value(module.name().as_u16())?;
+                for (word, flag) in [
+                    (" ACC_OPEN", AccessFlags::OPEN),
+                    (" ACC_MANDATED", AccessFlags::MANDATED),
+                    (" ACC_SYNTHETIC", AccessFlags::SYNTHETIC),
+                ] {
+                    if module.flags().contains(flag) {
+                        comment += word;
+                    }
+                }
+                self.commented(
+                    &format!("#{},{:x}", module.name().as_u16(), module.flags().bits()),
+                    &comment,
+                );
+                self.version(module.version())?;
+
+                self.commented(&module.requires().iter().count().to_string(), "requires");
+                self.indent += 1;
+                for require in module.requires().iter() {
+                    let require = require?;
+                    let mut comment = self.string_value(require.index().as_u16())?;
+                    for (word, flag) in [
+                        (" ACC_TRANSITIVE", AccessFlags::TRANSITIVE),
+                        (" ACC_STATIC_PHASE", AccessFlags::STATIC_PHASE),
+                        (" ACC_SYNTHETIC", AccessFlags::SYNTHETIC),
+                        (" ACC_MANDATED", AccessFlags::MANDATED),
+                    ] {
+                        if require.flags().contains(flag) {
+                            comment += word;
+                        }
+                    }
+                    self.commented(
+                        &format!("#{},{:x}", require.index().as_u16(), require.flags().bits()),
+                        &comment,
+                    );
+                    self.version(require.version())?;
+                }
+                self.indent -= 1;
+
+                self.commented(&module.exports().iter().count().to_string(), "exports");
+                self.indent += 1;
+                for export in module.exports().iter() {
+                    let export = export?;
+                    self.package_targets(export.index(), export.flags(), &export.exports_to())?;
+                }
+                self.indent -= 1;
+
+                self.commented(&module.opens().iter().count().to_string(), "opens");
+                self.indent += 1;
+                for open in module.opens().iter() {
+                    let open = open?;
+                    self.package_targets(open.index(), open.flags(), &open.opens_to())?;
+                }
+                self.indent -= 1;
+
+                self.commented(&module.uses().iter().count().to_string(), "uses");
+                self.indent += 1;
+                for class in module.uses().iter() {
+                    let class = class?.as_u16();
+                    self.commented(&format!("#{class}"), &self.string_value(class)?);
+                }
+                self.indent -= 1;
+
+                self.commented(&module.provides().iter().count().to_string(), "provides");
+                self.indent += 1;
+                for provide in module.provides().iter() {
+                    let provide = provide?;
+                    let class = provide.index().as_u16();
+                    let count = provide.provides_with().iter().count();
+                    self.commented(
+                        &format!("#{class}"),
+                        &format!("{} with ... {count}", self.string_value(class)?),
+                    );
+                    self.indent += 1;
+                    for with in provide.provides_with().iter() {
+                        let with = with?.as_u16();
+                        self.commented(&format!("#{with}"), &format!("... with {}", self.string_value(with)?));
+                    }
+                    self.indent -= 1;
+                }
+                self.indent -= 2;
+            }
+            AttributeContent::ModuleMainClass(main_class) => {
+                let class = main_class.main_class();
+                self.commented(
+                    &format!("ModuleMainClass: #{}", class.as_u16()),
+                    &java_name(self.pool.retrieve(class)?.name),
+                );
+            }
+            AttributeContent::ModulePackages(packages) => {
+                self.println("ModulePackages: ");
+                self.indent += 1;
+                for package in packages.packages().iter() {
+                    let package = package?;
+                    self.commented(
+                        &format!("#{}", package.as_u16()),
+                        &java_name(self.pool.retrieve(package)?.name),
+                    );
+                }
+                self.indent -= 1;
+            }
+            AttributeContent::NestHost(host) => {
+                self.println(&format!("NestHost: {}", self.constant(host.host_class().as_u16())?));
+            }
+            AttributeContent::NestMembers(members) => self.classes("NestMembers:", &members.classes())?,
+            AttributeContent::PermittedSubclasses(subclasses) => {
+                self.classes("PermittedSubclasses:", &subclasses.classes())?;
+            }
+            AttributeContent::Record(record) => {
+                self.println("Record:");
+                self.indent += 1;
+                for component in record.components().iter() {
+                    let component = component?;
+                    let descriptor = self.pool.retrieve(component.descriptor())?;
+                    let type_ = self.member_type(descriptor, &component.attributes())?;
+                    self.println(&format!("{type_} {};", lossy(self.pool.retrieve(component.name())?)));
+                    self.indent += 1;
+                    self.println(&format!("descriptor: {}", lossy(descriptor)));
+                    self.attributes(&component.attributes())?;
+                    self.println("");
+                    self.indent -= 1;
+                }
+                self.indent -= 1;
+            }
+            AttributeContent::RuntimeInvisibleAnnotations(annotations) => {
+                self.annotations("RuntimeInvisibleAnnotations:", &annotations.annotations())?;
+            }
+            AttributeContent::RuntimeVisibleAnnotations(annotations) => {
+                self.annotations("RuntimeVisibleAnnotations:", &annotations.annotations())?;
+            }
+            AttributeContent::RuntimeInvisibleParameterAnnotations(annotations) => {
+                self.parameter_annotations("RuntimeInvisibleParameterAnnotations:", &annotations.parameters())?;
+            }
+            AttributeContent::RuntimeVisibleParameterAnnotations(annotations) => {
+                self.parameter_annotations("RuntimeVisibleParameterAnnotations:", &annotations.parameters())?;
+            }
+            AttributeContent::RuntimeInvisibleTypeAnnotations(annotations) => {
+                self.type_annotations("RuntimeInvisibleTypeAnnotations:", &annotations.annotations())?;
+            }
+            AttributeContent::RuntimeVisibleTypeAnnotations(annotations) => {
+                self.type_annotations("RuntimeVisibleTypeAnnotations:", &annotations.annotations())?;
+            }
+            AttributeContent::Signature(signature) => {
+                let signature = signature.signature().as_u16();
+                self.commented(&format!("Signature: #{signature}"), &self.string_value(signature)?);
+            }
+            AttributeContent::SourceDebugExtension(extension) => {
+                self.println("SourceDebugExtension:");
+                self.indent += 1;
+                let content = lossy(extension.content());
+                for line in content.split(['\r', '\n']).filter(|line| !line.is_empty()) {
+                    self.println(line);
+                }
+                self.indent -= 1;
+            }
+            AttributeContent::SourceFile(source) => {
+                self.println(&format!(
+                    "SourceFile: \"{}\"",
+                    lossy(self.pool.retrieve(source.source_file())?)
+                ));
+            }
+            AttributeContent::StackMapTable(table) => {
+                let frames = table.iter().collect::<Result<Vec<_>, _>>()?;
+                self.println(&format!("StackMapTable: number_of_entries = {}", frames.len()));
+                self.indent += 1;
+                let mut previous = None;
+                for (offset, frame) in frames {
+                    let offset = offset.as_u32();
+                    let delta = match previous {
+                        Some(previous) => offset - previous - 1,
+                        None => offset,
+                    };
+                    previous = Some(offset);
+                    self.frame(delta, frame)?;
+                }
+                self.indent -= 1;
+            }
+            AttributeContent::Synthetic(_) => self.println("Synthetic: true"),
+        }
+        Ok(())
+    }
+
+    /// Prints the version of a module, which is a separate line in the `Module` attribute.
+    fn version(&mut self, version: Option<cpool::Index<cpool::Utf8<'input>>>) -> Result<(), DecodeError> {
+        match version {
+            Some(version) => {
+                let version = version.as_u16();
+                self.commented(&format!("#{version}"), &self.string_value(version)?);
+            }
+            None => self.println("#0"),
+        }
+        Ok(())
+    }
+
+    /// Prints an exported or opened package of a module.
+    fn package_targets(
+        &mut self,
+        package: cpool::Index<cpool::Package<'input>>,
+        flags: AccessFlags,
+        targets: &DecodeMany<'input, cpool::Index<cpool::Module<'input>>, u16>,
+    ) -> Result<(), DecodeError> {
+        let mut comment = self.string_value(package.as_u16())?;
+        for (word, flag) in [
+            (" ACC_MANDATED", AccessFlags::MANDATED),
+            (" ACC_SYNTHETIC", AccessFlags::SYNTHETIC),
+        ] {
+            if flags.contains(flag) {
+                comment += word;
+            }
+        }
+        let count = targets.iter().count();
+        if count != 0 {
+            comment = format!("{comment} to ... {count}");
+        }
+        self.commented(&format!("#{},{:x}", package.as_u16(), flags.bits()), &comment);
+        self.indent += 1;
+        for target in targets.iter() {
+            let target = target?.as_u16();
+            self.commented(&format!("#{target}"), &format!("... to {}", self.string_value(target)?));
+        }
+        self.indent -= 1;
+        Ok(())
+    }
+
+    fn classes(
+        &mut self,
+        header: &str,
+        classes: &DecodeMany<'input, cpool::Index<cpool::Class<'input>>, u16>,
+    ) -> Result<(), DecodeError> {
+        self.println(header);
+        self.indent += 1;
+        for class in classes.iter() {
+            self.println(&self.string_value(class?.as_u16())?);
+        }
+        self.indent -= 1;
+        Ok(())
+    }
+
+    fn code(&mut self, code: &Code<'input>) -> Result<(), DecodeError> {
+        self.println("Code:");
+        self.indent += 1;
+        self.println(&format!(
+            "stack={}, locals={}, args_size={}",
+            code.max_stack(),
+            code.max_locals(),
+            self.args_size
+        ));
+        for instruction in code.raw_instructions() {
+            let (offset, instruction) = instruction?;
+            self.instruction(offset.as_u32(), &instruction)?;
+        }
+
+        let mut handlers = code.exception_handlers().peekable();
+        if handlers.peek().is_some() {
+            self.println("Exception table:");
+            self.indent += 1;
+            self.println(" from    to  target type");
+            for handler in handlers {
+                let catch_type = match handler.catch_type() {
+                    Some(class) => format!("Class {}", self.string_value(class.as_u16())?),
+                    None => "any".to_owned(),
+                };
+                self.println(&format!(
+                    " {:5} {:5} {:5}   {catch_type}",
+                    handler.start().as_u32(),
+                    handler.end().as_u32(),
+                    handler.handler().as_u32()
+                ));
+            }
+            self.indent -= 1;
+        }
+
+        self.attributes(&code.attributes())?;
+        self.indent -= 1;
+        Ok(())
+    }
+
+    fn instruction(&mut self, offset: u32, instruction: &RawInstruction<'input>) -> Result<(), DecodeError> {
+        use RawInstruction::*;
+
+        let name = match instruction {
+            ALoadW { .. }
+            | AStoreW { .. }
+            | DLoadW { .. }
+            | DStoreW { .. }
+            | FLoadW { .. }
+            | FStoreW { .. }
+            | ILoadW { .. }
+            | IStoreW { .. }
+            | LLoadW { .. }
+            | LStoreW { .. }
+            | RetW { .. }
+            | IIncW { .. } => format!("{}_w", instruction.mnemonic()),
+            _ => instruction.mnemonic().to_owned(),
+        };
+        self.print(&format!("{offset:4}: {name:<13} "));
+
+        let target = |relative: i32| i64::from(offset) + i64::from(relative);
+        match instruction {
+            ALoad { index }
+            | AStore { index }
+            | DLoad { index }
+            | DStore { index }
+            | FLoad { index }
+            | FStore { index }
+            | ILoad { index }
+            | IStore { index }
+            | LLoad { index }
+            | LStore { index }
+            | Ret { index } => self.print(&index.to_string()),
+            ALoadW { index }
+            | AStoreW { index }
+            | DLoadW { index }
+            | DStoreW { index }
+            | FLoadW { index }
+            | FStoreW { index }
+            | ILoadW { index }
+            | IStoreW { index }
+            | LLoadW { index }
+            | LStoreW { index }
+            | RetW { index } => self.print(&index.to_string()),
+            IInc { index, value } => self.print(&format!("{index}, {value}")),
+            IIncW { index, value } => self.print(&format!("{index}, {value}")),
+            BIPush { value } => self.print(&value.to_string()),
+            SIPush { value } => self.print(&value.to_string()),
+            Goto { offset }
+            | IfACmpEq { offset }
+            | IfACmpNe { offset }
+            | IfICmpEq { offset }
+            | IfICmpNe { offset }
+            | IfICmpLt { offset }
+            | IfICmpGe { offset }
+            | IfICmpGt { offset }
+            | IfICmpLe { offset }
+            | IfEq { offset }
+            | IfNe { offset }
+            | IfLt { offset }
+            | IfGe { offset }
+            | IfGt { offset }
+            | IfLe { offset }
+            | IfNonNull { offset }
+            | IfNull { offset }
+            | JSr { offset } => self.print(&target((*offset).into()).to_string()),
+            GotoW { offset } | JSrW { offset } => self.print(&target(*offset).to_string()),
+            ANewArray { index } | CheckCast { index } | InstanceOf { index } | New { index } => {
+                self.operand(index.as_u16(), "")?;
+            }
+            GetField { index } | GetStatic { index } | PutField { index } | PutStatic { index } => {
+                self.operand(index.as_u16(), "")?;
+            }
+            InvokeVirtual { index } => self.operand(index.as_u16(), "")?,
+            InvokeSpecial { index } | InvokeStatic { index } | LdC { index } | LdCW { index } | LdC2W { index } => {
+                self.operand(index.as_u16(), "")?;
+            }
+            InvokeInterface { index, count } => self.operand(index.as_u16(), &format!(",  {count}"))?,
+            InvokeDynamic { index } => self.operand(index.as_u16(), ",  0")?,
+            MultiANewArray { index, dimensions } => self.operand(index.as_u16(), &format!(",  {dimensions}"))?,
+            NewArray { atype } => self.print(&format!(" {}", array_type(*atype))),
+            TableSwitch(switch) => {
+                self.print(&format!("{{ // {} to {}", switch.low(), switch.high()));
+                self.indent += 3;
+                for pair in switch.pairs() {
+                    self.print(&format!("\n{:12}: {}", pair.key(), target(pair.offset())));
+                }
+                self.print(&format!("\n     default: {}\n}}", target(switch.default_offset())));
+                self.indent -= 3;
+            }
+            LookupSwitch(switch) => {
+                self.print(&format!("{{ // {}", switch.pairs().count()));
+                self.indent += 3;
+                for pair in switch.pairs() {
+                    self.print(&format!("\n{:12}: {}", pair.key(), target(pair.offset())));
+                }
+                self.print(&format!("\n     default: {}\n}}", target(switch.default_offset())));
+                self.indent -= 3;
+            }
+            _ => {}
+        }
+        self.println("");
+        Ok(())
+    }
+
+    /// Prints an operand referring to the constant pool, followed by its value.
+    fn operand(&mut self, index: u16, suffix: &str) -> Result<(), DecodeError> {
+        let comment = self.constant(index)?;
+        self.print(&format!("#{index}{suffix}"));
+        self.tab();
+        self.print(&format!("// {comment}"));
+        Ok(())
+    }
+
+    fn frame(&mut self, delta: u32, frame: StackMapFrame<'input>) -> Result<(), DecodeError> {
+        let (frame_type, kind) = match &frame {
+            StackMapFrame::Same => (delta, "same"),
+            StackMapFrame::Same1 { .. } => (delta + 64, "same_locals_1_stack_item"),
+            StackMapFrame::Same1Extended { .. } => (247, "same_locals_1_stack_item_frame_extended"),
+            StackMapFrame::Chop { to_chop } => (251 - u32::from(*to_chop), "chop"),
+            StackMapFrame::SameExtended => (251, "same_frame_extended"),
+            StackMapFrame::Append { locals } => (251 + locals.clone().count() as u32, "append"),
+            StackMapFrame::Full { .. } => (255, "full_frame"),
+        };
+        self.println(&format!("frame_type = {frame_type} /* {kind} */"));
+        self.indent += 1;
+        if !matches!(frame, StackMapFrame::Same | StackMapFrame::Same1 { .. }) {
+            self.println(&format!("offset_delta = {delta}"));
+        }
+        match frame {
+            StackMapFrame::Same1 { stack } | StackMapFrame::Same1Extended { stack } => {
+                self.verification_types("stack", [Ok(stack)])?;
+            }
+            StackMapFrame::Append { locals } => self.verification_types("locals", locals)?,
+            StackMapFrame::Full { locals, stack } => {
+                self.verification_types("locals", locals)?;
+                self.verification_types("stack", stack)?;
+            }
+            StackMapFrame::Same | StackMapFrame::SameExtended | StackMapFrame::Chop { .. } => {}
+        }
+        self.indent -= 1;
+        Ok(())
+    }
+
+    fn verification_types(
+        &mut self,
+        name: &str,
+        types: impl IntoIterator<Item = Result<VerificationType<'input>, DecodeError>>,
+    ) -> Result<(), DecodeError> {
+        let mut words = Vec::new();
+        for verification_type in types {
+            words.push(match verification_type? {
+                VerificationType::Top => "top".to_owned(),
+                VerificationType::Integer => "int".to_owned(),
+                VerificationType::Float => "float".to_owned(),
+                VerificationType::Long => "long".to_owned(),
+                VerificationType::Double => "double".to_owned(),
+                VerificationType::Null => "null".to_owned(),
+                VerificationType::UninitializedThis => "this".to_owned(),
+                VerificationType::Object(class) => self.constant(class.as_u16())?,
+                VerificationType::UninitializedVariable(offset) => format!("uninitialized {}", offset.as_u32()),
+            });
+        }
+        if words.is_empty() {
+            self.println(&format!("{name} = []"));
+        } else {
+            self.println(&format!("{name} = [ {} ]", words.join(", ")));
+        }
+        Ok(())
+    }
+
+    fn annotations(
+        &mut self,
+        header: &str,
+        annotations: &DecodeMany<'input, Annotation<'input>, u16>,
+    ) -> Result<(), DecodeError> {
+        self.println(header);
+        self.indent += 1;
+        for (i, annotation) in annotations.iter().enumerate() {
+            let annotation = annotation?;
+            self.println(&format!("{i}: {}", self.annotation(&annotation)?));
+            self.indent += 1;
+            self.resolved_annotation(&annotation)?;
+            self.println("");
+            self.indent -= 1;
+        }
+        self.indent -= 1;
+        Ok(())
+    }
+
+    fn parameter_annotations(
+        &mut self,
+        header: &str,
+        parameters: &DecodeMany<'input, ParameterAnnotations<'input>, u8>,
+    ) -> Result<(), DecodeError> {
+        self.println(header);
+        self.indent += 1;
+        for (i, parameter) in parameters.iter().enumerate() {
+            self.println(&format!("parameter {i}: "));
+            self.indent += 1;
+            for (j, annotation) in parameter?.annotations().iter().enumerate() {
+                let annotation = annotation?;
+                self.println(&format!("{j}: {}", self.annotation(&annotation)?));
+                self.indent += 1;
+                self.resolved_annotation(&annotation)?;
+                self.println("");
+                self.indent -= 1;
+            }
+            self.indent -= 1;
+        }
+        self.indent -= 1;
+        Ok(())
+    }
+
+    fn type_annotations(
+        &mut self,
+        header: &str,
+        annotations: &DecodeMany<'input, TypeAnnotation<'input>, u16>,
+    ) -> Result<(), DecodeError> {
+        self.println(header);
+        self.indent += 1;
+        for (i, annotation) in annotations.iter().enumerate() {
+            let annotation = annotation?;
+            let mut pairs = Vec::new();
+            for pair in annotation.pairs().iter() {
+                let pair = pair?;
+                pairs.push(format!(
+                    "#{}={}",
+                    pair.name().as_u16(),
+                    self.element_value(&pair.value())?
+                ));
+            }
+            self.println(&format!(
+                "{i}: #{}({}): {}",
+                annotation.type_().as_u16(),
+                pairs.join(","),
+                type_annotation_position(&annotation)?
+            ));
+
+            self.indent += 1;
+            self.print(&self.annotation_type(annotation.type_())?);
+            self.resolved_pairs(&annotation.pairs())?;
+            self.println("");
+            self.indent -= 1;
+        }
+        self.indent -= 1;
+        Ok(())
+    }
+
+    /// Returns an annotation with the indices into the constant pool it consists of.
+    fn annotation(&self, annotation: &Annotation<'input>) -> Result<String, DecodeError> {
+        let mut pairs = Vec::new();
+        for pair in annotation.pairs().iter() {
+            let pair = pair?;
+            pairs.push(format!(
+                "#{}={}",
+                pair.name().as_u16(),
+                self.element_value(&pair.value())?
+            ));
+        }
+        Ok(format!("#{}({})", annotation.type_().as_u16(), pairs.join(",")))
+    }
+
+    fn element_value(&self, value: &ElementValue<'input>) -> Result<String, DecodeError> {
+        Ok(match value {
+            ElementValue::Boolean(index) => format!("Z#{}", index.as_u16()),
+            ElementValue::Byte(index) => format!("B#{}", index.as_u16()),
+            ElementValue::Short(index) => format!("S#{}", index.as_u16()),
+            ElementValue::Char(index) => format!("C#{}", index.as_u16()),
+            ElementValue::Int(index) => format!("I#{}", index.as_u16()),
+            ElementValue::Long(index) => format!("J#{}", index.as_u16()),
+            ElementValue::Float(index) => format!("F#{}", index.as_u16()),
+            ElementValue::Double(index) => format!("D#{}", index.as_u16()),
+            ElementValue::String(index) => format!("s#{}", index.as_u16()),
+            ElementValue::Class(index) => format!("c#{}", index.as_u16()),
+            ElementValue::Enum { type_name, const_name } => {
+                format!("e#{}.#{}", type_name.as_u16(), const_name.as_u16())
+            }
+            ElementValue::Annotation(annotation) => format!("@{}", self.annotation(annotation)?),
+            ElementValue::Array(values) => {
+                let mut words = Vec::new();
+                for value in values.iter() {
+                    words.push(self.element_value(&value?)?);
+                }
+                format!("[{}]", words.join(","))
+            }
+        })
+    }
+
+    /// Prints an annotation with its values, where pairs are written on separate lines.
+    fn resolved_annotation(&mut self, annotation: &Annotation<'input>) -> Result<(), DecodeError> {
+        self.print(&self.annotation_type(annotation.type_())?);
+        self.resolved_pairs(&annotation.pairs())
+    }
+
+    fn resolved_pairs(
+        &mut self,
+        pairs: &DecodeMany<'input, crate::reader::attributes::annotations::ElementValuePair<'input>, u16>,
+    ) -> Result<(), DecodeError> {
+        let pairs = pairs.iter().collect::<Result<Vec<_>, _>>()?;
+        if pairs.is_empty() {
+            return Ok(());
+        }
+        self.println("(");
+        self.indent += 1;
+        for pair in pairs {
+            self.print(&format!("{}=", self.string_value(pair.name().as_u16())?));
+            self.resolved_element_value(&pair.value())?;
+            self.println("");
+        }
+        self.indent -= 1;
+        self.print(")");
+        Ok(())
+    }
+
+    fn resolved_element_value(&mut self, value: &ElementValue<'input>) -> Result<(), DecodeError> {
+        match value {
+            ElementValue::Boolean(index) => {
+                let value = self.pool.get(*index)?.value != 0;
+                self.print(&value.to_string());
+            }
+            ElementValue::Byte(index) => self.print(&format!("(byte) {}", self.string_value(index.as_u16())?)),
+            ElementValue::Short(index) => self.print(&format!("(short) {}", self.string_value(index.as_u16())?)),
+            ElementValue::Char(index) => {
+                let value = self.pool.get(*index)?.value as u16;
+                let value = char::from_u32(value.into()).unwrap_or(char::REPLACEMENT_CHARACTER);
+                self.print(&format!("'{value}'"));
+            }
+            ElementValue::Int(index) => self.print(&self.string_value(index.as_u16())?),
+            ElementValue::Long(index) => self.print(&self.string_value(index.as_u16())?),
+            ElementValue::Float(index) => self.print(&self.string_value(index.as_u16())?),
+            ElementValue::Double(index) => self.print(&self.string_value(index.as_u16())?),
+            ElementValue::String(index) => self.print(&format!("\"{}\"", self.string_value(index.as_u16())?)),
+            ElementValue::Class(index) => self.print(&format!("class {}", self.string_value(index.as_u16())?)),
+            ElementValue::Enum { type_name, const_name } => self.print(&format!(
+                "{}.{}",
+                self.string_value(type_name.as_u16())?,
+                self.string_value(const_name.as_u16())?
+            )),
+            ElementValue::Annotation(annotation) => {
+                self.print("@");
+                self.resolved_annotation(annotation)?;
+            }
+            ElementValue::Array(values) => {
+                self.print("[");
+                for (i, value) in values.iter().enumerate() {
+                    if i != 0 {
+                        self.print(",");
+                    }
+                    self.resolved_element_value(&value?)?;
+                }
+                self.print("]");
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the type of an annotation in Java syntax, or its index if it is no valid descriptor.
+    fn annotation_type(&self, index: cpool::Index<cpool::Utf8<'input>>) -> Result<String, DecodeError> {
+        let descriptor = lossy(self.pool.retrieve(index)?);
+        Ok(field_type(&descriptor).unwrap_or_else(|| format!("#{}", index.as_u16())))
+    }
+
+    /// Returns a constant with its kind, where references to members of this class leave out the class.
+    fn constant(&self, index: u16) -> Result<String, DecodeError> {
+        let item = self.pool.get(cpool::Index::<Item<'input>>::new(index)?)?;
+        let value = match item {
+            Item::FieldRef(cpool::FieldRef { class, name_and_type })
+            | Item::MethodRef(cpool::MethodRef { class, name_and_type })
+            | Item::InterfaceMethodRef(cpool::InterfaceMethodRef { class, name_and_type })
+                if *class == self.this_class =>
+            {
+                self.string_value(name_and_type.as_u16())?
+            }
+            _ => self.item_value(item)?,
+        };
+        Ok(format!("{} {value}", constant_tag_name(item)))
+    }
+
+    fn string_value(&self, index: u16) -> Result<String, DecodeError> {
+        let item = self.pool.get(cpool::Index::<Item<'input>>::new(index)?)?;
+        self.item_value(item)
+    }
+
+    fn item_value(&self, item: &Item<'input>) -> Result<String, DecodeError> {
+        Ok(match item {
+            Item::Utf8(utf8) => escape(utf8.content),
+            Item::Integer(integer) => integer.value.to_string(),
+            Item::Long(long) => format!("{}l", long.value),
+            Item::Float(float) => format!("{}f", java_float(float.value)),
+            Item::Double(double) => format!("{}d", java_float(double.value)),
+            Item::Class(class) => checked_name(self.pool.retrieve(class.name)?),
+            Item::Module(module) => checked_name(self.pool.retrieve(module.name)?),
+            Item::Package(package) => checked_name(self.pool.retrieve(package.name)?),
+            Item::String(string) => self.string_value(string.string.as_u16())?,
+            Item::FieldRef(cpool::FieldRef { class, name_and_type })
+            | Item::MethodRef(cpool::MethodRef { class, name_and_type })
+            | Item::InterfaceMethodRef(cpool::InterfaceMethodRef { class, name_and_type }) => format!(
+                "{}.{}",
+                self.string_value(class.as_u16())?,
+                self.string_value(name_and_type.as_u16())?
+            ),
+            Item::NameAndType(name_and_type) => format!(
+                "{}:{}",
+                checked_name(self.pool.retrieve(name_and_type.name)?),
+                lossy(self.pool.retrieve(name_and_type.descriptor)?)
+            ),
+            Item::MethodHandle(handle) => format!(
+                "{} {}",
+                method_kind_name(handle.kind),
+                self.string_value(handle.reference.as_u16())?
+            ),
+            Item::MethodType(method_type) => self.string_value(method_type.descriptor.as_u16())?,
+            Item::Dynamic(cpool::Dynamic {
+                bootstrap_method_attr,
+                name_and_type,
+            })
+            | Item::InvokeDynamic(cpool::InvokeDynamic {
+                bootstrap_method_attr,
+                name_and_type,
+            }) => format!(
+                "#{bootstrap_method_attr}:{}",
+                self.string_value(name_and_type.as_u16())?
+            ),
+        })
+    }
+}
+
+const CLASS_MODIFIERS: &[(&str, AccessFlags)] = &[
+    ("public", AccessFlags::PUBLIC),
+    ("final", AccessFlags::FINAL),
+    ("abstract", AccessFlags::ABSTRACT),
+];
+
+const CLASS_FLAGS: &[(&str, AccessFlags)] = &[
+    ("ACC_PUBLIC", AccessFlags::PUBLIC),
+    ("ACC_FINAL", AccessFlags::FINAL),
+    ("ACC_SUPER", AccessFlags::SUPER),
+    ("ACC_INTERFACE", AccessFlags::INTERFACE),
+    ("ACC_ABSTRACT", AccessFlags::ABSTRACT),
+    ("ACC_SYNTHETIC", AccessFlags::SYNTHETIC),
+    ("ACC_ANNOTATION", AccessFlags::ANNOTATION),
+    ("ACC_ENUM", AccessFlags::ENUM),
+    ("ACC_MODULE", AccessFlags::MODULE),
+];
+
+const INNER_CLASS_MODIFIERS: &[(&str, AccessFlags)] = &[
+    ("public", AccessFlags::PUBLIC),
+    ("private", AccessFlags::PRIVATE),
+    ("protected", AccessFlags::PROTECTED),
+    ("static", AccessFlags::STATIC),
+    ("final", AccessFlags::FINAL),
+    ("abstract", AccessFlags::ABSTRACT),
+];
+
+const FIELD_MODIFIERS: &[(&str, AccessFlags)] = &[
+    ("public", AccessFlags::PUBLIC),
+    ("private", AccessFlags::PRIVATE),
+    ("protected", AccessFlags::PROTECTED),
+    ("static", AccessFlags::STATIC),
+    ("final", AccessFlags::FINAL),
+    ("volatile", AccessFlags::VOLATILE),
+    ("transient", AccessFlags::TRANSIENT),
+];
+
+const FIELD_FLAGS: &[(&str, AccessFlags)] = &[
+    ("ACC_PUBLIC", AccessFlags::PUBLIC),
+    ("ACC_PRIVATE", AccessFlags::PRIVATE),
+    ("ACC_PROTECTED", AccessFlags::PROTECTED),
+    ("ACC_STATIC", AccessFlags::STATIC),
+    ("ACC_FINAL", AccessFlags::FINAL),
+    ("ACC_VOLATILE", AccessFlags::VOLATILE),
+    ("ACC_TRANSIENT", AccessFlags::TRANSIENT),
+    ("ACC_SYNTHETIC", AccessFlags::SYNTHETIC),
+    ("ACC_ENUM", AccessFlags::ENUM),
+];
+
+const METHOD_MODIFIERS: &[(&str, AccessFlags)] = &[
+    ("public", AccessFlags::PUBLIC),
+    ("private", AccessFlags::PRIVATE),
+    ("protected", AccessFlags::PROTECTED),
+    ("static", AccessFlags::STATIC),
+    ("final", AccessFlags::FINAL),
+    ("synchronized", AccessFlags::SYNCHRONIZED),
+    ("native", AccessFlags::NATIVE),
+    ("abstract", AccessFlags::ABSTRACT),
+    ("strictfp", AccessFlags::STRICT),
+];
+
+const METHOD_FLAGS: &[(&str, AccessFlags)] = &[
+    ("ACC_PUBLIC", AccessFlags::PUBLIC),
+    ("ACC_PRIVATE", AccessFlags::PRIVATE),
+    ("ACC_PROTECTED", AccessFlags::PROTECTED),
+    ("ACC_STATIC", AccessFlags::STATIC),
+    ("ACC_FINAL", AccessFlags::FINAL),
+    ("ACC_SYNCHRONIZED", AccessFlags::SYNCHRONIZED),
+    ("ACC_BRIDGE", AccessFlags::BRIDGE),
+    ("ACC_VARARGS", AccessFlags::VARARGS),
+    ("ACC_NATIVE", AccessFlags::NATIVE),
+    ("ACC_ABSTRACT", AccessFlags::ABSTRACT),
+    ("ACC_STRICT", AccessFlags::STRICT),
+    ("ACC_SYNTHETIC", AccessFlags::SYNTHETIC),
+];
+
+/// The name of an entry in the listing of the constant pool.
+fn pool_tag_name(item: &Item<'_>) -> &'static str {
+    match item {
+        Item::Utf8(_) => "Utf8",
+        Item::Integer(_) => "Integer",
+        Item::Float(_) => "Float",
+        Item::Long(_) => "Long",
+        Item::Double(_) => "Double",
+        Item::Class(_) => "Class",
+        Item::String(_) => "String",
+        Item::FieldRef(_) => "Fieldref",
+        Item::MethodRef(_) => "Methodref",
+        Item::InterfaceMethodRef(_) => "InterfaceMethodref",
+        Item::NameAndType(_) => "NameAndType",
+        Item::MethodHandle(_) => "MethodHandle",
+        Item::MethodType(_) => "MethodType",
+        Item::Dynamic(_) => "Dynamic",
+        Item::InvokeDynamic(_) => "InvokeDynamic",
+        Item::Module(_) => "Module",
+        Item::Package(_) => "Package",
+    }
+}
+
+/// The name of a constant where it is referred to, such as in the comments after instructions.
+fn constant_tag_name(item: &Item<'_>) -> &'static str {
+    match item {
+        Item::Utf8(_) => "Utf8",
+        Item::Integer(_) => "int",
+        Item::Float(_) => "float",
+        Item::Long(_) => "long",
+        Item::Double(_) => "double",
+        Item::Class(_) => "class",
+        Item::String(_) => "String",
+        Item::FieldRef(_) => "Field",
+        Item::MethodRef(_) => "Method",
+        Item::InterfaceMethodRef(_) => "InterfaceMethod",
+        Item::NameAndType(_) => "NameAndType",
+        Item::MethodHandle(_) => "MethodHandle",
+        Item::MethodType(_) => "MethodType",
+        Item::Dynamic(_) => "Dynamic",
+        Item::InvokeDynamic(_) => "InvokeDynamic",
+        Item::Module(_) => "Module",
+        Item::Package(_) => "Package",
+    }
+}
+
+fn method_kind_tag(kind: MethodKind) -> u8 {
+    match kind {
+        MethodKind::GetField => 1,
+        MethodKind::GetStatic => 2,
+        MethodKind::PutField => 3,
+        MethodKind::PutStatic => 4,
+        MethodKind::InvokeVirtual => 5,
+        MethodKind::InvokeStatic => 6,
+        MethodKind::InvokeSpecial => 7,
+        MethodKind::NewInvokeSpecial => 8,
+        MethodKind::InvokeInterface => 9,
+    }
+}
+
+fn method_kind_name(kind: MethodKind) -> &'static str {
+    match kind {
+        MethodKind::GetField => "REF_getField",
+        MethodKind::GetStatic => "REF_getStatic",
+        MethodKind::PutField => "REF_putField",
+        MethodKind::PutStatic => "REF_putStatic",
+        MethodKind::InvokeVirtual => "REF_invokeVirtual",
+        MethodKind::InvokeStatic => "REF_invokeStatic",
+        MethodKind::InvokeSpecial => "REF_invokeSpecial",
+        MethodKind::NewInvokeSpecial => "REF_newInvokeSpecial",
+        MethodKind::InvokeInterface => "REF_invokeInterface",
+    }
+}
+
+/// Describes what a type annotation is applied to.
+fn type_annotation_position(annotation: &TypeAnnotation<'_>) -> Result<String, DecodeError> {
+    use TargetType::*;
+
+    let mut text = match annotation.target_type() {
+        ClassTypeParameter => "CLASS_TYPE_PARAMETER",
+        MethodTypeParameter => "METHOD_TYPE_PARAMETER",
+        ClassExtends => "CLASS_EXTENDS",
+        ClassTypeParameterBound => "CLASS_TYPE_PARAMETER_BOUND",
+        MethodTypeParameterBound => "METHOD_TYPE_PARAMETER_BOUND",
+        Field => "FIELD",
+        MethodReturn => "METHOD_RETURN",
+        MethodReceiver => "METHOD_RECEIVER",
+        MethodFormalParameter => "METHOD_FORMAL_PARAMETER",
+        Throws => "THROWS",
+        LocalVariable => "LOCAL_VARIABLE",
+        ResourceVariable => "RESOURCE_VARIABLE",
+        ExceptionParameter => "EXCEPTION_PARAMETER",
+        InstanceOf => "INSTANCEOF",
+        New => "NEW",
+        ConstructorReference => "CONSTRUCTOR_REFERENCE",
+        MethodReference => "METHOD_REFERENCE",
+        Cast => "CAST",
+        ConstructorInvocationTypeArgument => "CONSTRUCTOR_INVOCATION_TYPE_ARGUMENT",
+        MethodInvocationTypeArgument => "METHOD_INVOCATION_TYPE_ARGUMENT",
+        ConstructorReferenceTypeArgument => "CONSTRUCTOR_REFERENCE_TYPE_ARGUMENT",
+        MethodReferenceTypeArgument => "METHOD_REFERENCE_TYPE_ARGUMENT",
+    }
+    .to_owned();
+
+    let info = match annotation.target_info() {
+        TargetInfo::TypeParameter { parameter_index } => format!(", param_index={parameter_index}"),
+        TargetInfo::SuperType { supertype_index } => {
+            let index = match supertype_index {
+                SuperTypeIndex::Class => u16::MAX,
+                SuperTypeIndex::Interface { index } => *index,
+            };
+            format!(", type_index={index}")
+        }
+        TargetInfo::TypeParameterBound {
+            type_parameter_index,
+            bound_index,
+        } => format!(", param_index={type_parameter_index}, bound_index={bound_index}"),
+        TargetInfo::Empty => String::new(),
+        TargetInfo::FormalParameter { formal_parameter_index } => {
+            format!(", param_index={formal_parameter_index}")
+        }
+        TargetInfo::Throws { throws_type_index } => format!(", type_index={throws_type_index}"),
+        TargetInfo::LocalVariable { table } => {
+            let mut entries = Vec::new();
+            for variable in table.iter() {
+                let variable = variable?;
+                let range = variable.range();
+                entries.push(format!(
+                    "start_pc={}, length={}, index={}",
+                    range.start.as_u32(),
+                    range.end.as_u32() - range.start.as_u32(),
+                    variable.index()
+                ));
+            }
+            format!(", {{{}}}", entries.join("; "))
+        }
+        TargetInfo::Catch { exception_table_index } => {
+            format!(", exception_index={exception_table_index}")
+        }
+        TargetInfo::Offset { offset } => format!(", offset={}", offset.as_u32()),
+        TargetInfo::TypeArgument {
+            offset,
+            type_argument_index,
+        } => format!(", offset={}, type_index={type_argument_index}", offset.as_u32()),
+    };
+    text += &info;
+
+    let mut location = Vec::new();
+    for segment in annotation.target_path().iter() {
+        let segment = segment?;
+        location.push(match segment.kind() {
+            TypePathSegmentKind::ArrayElement => "ARRAY".to_owned(),
+            TypePathSegmentKind::InnerType => "INNER_TYPE".to_owned(),
+            TypePathSegmentKind::WildcardBound => "WILDCARD".to_owned(),
+            TypePathSegmentKind::TypeArgument => format!("TYPE_ARGUMENT({})", segment.type_argument_index()),
+        });
+    }
+    if !location.is_empty() {
+        text = format!("{text}, location=[{}]", location.join(", "));
+    }
+    Ok(text)
+}
+
+fn invalid() -> DecodeError {
+    DecodeError::new(DecodeErrorKind::InvalidDescriptor)
+}
+
+fn lossy(string: &MStr) -> String {
+    string.chars_lossy().collect()
+}
+
+/// Converts a binary class name into the dotted form of Java source code.
+fn java_name(name: &MStr) -> String {
+    lossy(name).replace('/', ".")
+}
+
+/// Escapes a string like `javap` prints strings in the constant pool.
+fn escape(string: &MStr) -> String {
+    let mut escaped = String::new();
+    for c in string.chars() {
+        match c {
+            Ok('\t') => escaped.push_str("\\t"),
+            Ok('\n') => escaped.push_str("\\n"),
+            Ok('\r') => escaped.push_str("\\r"),
+            Ok('\u{8}') => escaped.push_str("\\b"),
+            Ok('\u{c}') => escaped.push_str("\\f"),
+            Ok('"') => escaped.push_str("\\\""),
+            Ok('\'') => escaped.push_str("\\'"),
+            Ok('\\') => escaped.push_str("\\\\"),
+            Ok(c) if c.is_control() => push_unicode_escape(&mut escaped, c.into()),
+            Ok(c) => escaped.push(c),
+            Err(unit) => push_unicode_escape(&mut escaped, unit),
+        }
+    }
+    escaped
+}
+
+/// Writes a code unit as a `\u` escape sequence with lowercase digits, unlike the one of the assembler syntax.
+fn push_unicode_escape(escaped: &mut String, unit: u32) {
+    escaped.push_str("\\u");
+    for shift in [12, 8, 4, 0] {
+        escaped.extend(char::from_digit((unit >> shift) & 0xF, 16));
+    }
+}
+
+/// Returns a name as it is, unless it is no valid binary name, in which case it is quoted.
+fn checked_name(name: &MStr) -> String {
+    let text = lossy(name);
+    let mut previous = '/';
+    let valid = !text.is_empty()
+        && text.chars().all(|c| {
+            let valid = if previous == '/' {
+                c.is_alphabetic() || c == '_' || c == '$'
+            } else {
+                c == '/' || c.is_alphanumeric() || c == '_' || c == '$'
+            };
+            previous = c;
+            valid
+        });
+    if valid {
+        return text;
+    }
+
+    let mut quoted = String::from('"');
+    for c in text.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Formats a floating point number like `Float.toString` and `Double.toString` of Java.
+fn java_float<T: Copy + Into<f64> + fmt::Display + fmt::LowerExp>(value: T) -> String {
+    let magnitude = value.into().abs();
+    if value.into().is_nan() {
+        "NaN".to_owned()
+    } else if magnitude.is_infinite() {
+        if value.into() < 0.0 { "-Infinity" } else { "Infinity" }.to_owned()
+    } else if magnitude == 0.0 || (1e-3..1e7).contains(&magnitude) {
+        let text = value.to_string();
+        if text.contains('.') {
+            text
+        } else {
+            text + ".0"
+        }
+    } else {
+        // Java prints at least two digits, which are the closest ones if the shortest form has fewer
+        let mut text = format!("{value:e}");
+        if !text.contains('.') {
+            text = format!("{value:.1e}");
+        }
+        text.replace('e', "E")
+    }
+}
+
+struct ClassSignature {
+    type_parameters: Vec<String>,
+    super_class: String,
+    interfaces: Vec<String>,
+}
+
+struct MethodSignature {
+    type_parameters: Vec<String>,
+    parameters: Vec<String>,
+    return_type: String,
+    throws: Vec<String>,
+}
+
+fn field_type(signature: &str) -> Option<String> {
+    let mut reader = SignatureReader { rest: signature };
+    let type_ = reader.type_()?;
+    reader.rest.is_empty().then_some(type_)
+}
+
+fn class_signature(signature: &str) -> Option<ClassSignature> {
+    let mut reader = SignatureReader { rest: signature };
+    let type_parameters = reader.type_parameters()?;
+    let super_class = reader.type_()?;
+    let mut interfaces = Vec::new();
+    while !reader.rest.is_empty() {
+        interfaces.push(reader.type_()?);
+    }
+    Some(ClassSignature {
+        type_parameters,
+        super_class,
+        interfaces,
+    })
+}
+
+/// Reads the signature or the descriptor of a method.
+fn method_signature(signature: &str) -> Option<MethodSignature> {
+    let mut reader = SignatureReader { rest: signature };
+    let type_parameters = reader.type_parameters()?;
+    if !reader.eat('(') {
+        return None;
+    }
+    let mut parameters = Vec::new();
+    while !reader.eat(')') {
+        parameters.push(reader.type_()?);
+    }
+    let return_type = reader.type_()?;
+    let mut throws = Vec::new();
+    while reader.eat('^') {
+        throws.push(reader.type_()?);
+    }
+    reader.rest.is_empty().then_some(MethodSignature {
+        type_parameters,
+        parameters,
+        return_type,
+        throws,
+    })
+}
+
+/// Reads types from signatures and descriptors, and converts them into Java syntax.
+struct SignatureReader<'s> {
+    rest: &'s str,
+}
+
+impl<'s> SignatureReader<'s> {
+    fn eat(&mut self, c: char) -> bool {
+        match self.rest.strip_prefix(c) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reads up to a delimiter, which is left out.
+    fn until(&mut self, delimiter: char) -> Option<&'s str> {
+        let (text, rest) = self.rest.split_once(delimiter)?;
+        self.rest = rest;
+        Some(text)
+    }
+
+    fn type_(&mut self) -> Option<String> {
+        let c = self.rest.chars().next()?;
+        self.rest = &self.rest[c.len_utf8()..];
+        let name = match c {
+            'B' => "byte",
+            'C' => "char",
+            'D' => "double",
+            'F' => "float",
+            'I' => "int",
+            'J' => "long",
+            'S' => "short",
+            'Z' => "boolean",
+            'V' => "void",
+            '[' => return Some(self.type_()? + "[]"),
+            'T' => return self.until(';').map(str::to_owned),
+            'L' => return self.class_type(),
+            _ => return None,
+        };
+        Some(name.to_owned())
+    }
+
+    fn class_type(&mut self) -> Option<String> {
+        let mut text = String::new();
+        loop {
+            let end = self.rest.find(['<', '.', ';'])?;
+            text.push_str(&self.rest[..end].replace('/', "."));
+            self.rest = &self.rest[end..];
+            if self.eat('<') {
+                let mut arguments = Vec::new();
+                while !self.eat('>') {
+                    let argument = if self.eat('*') {
+                        "?".to_owned()
+                    } else if self.eat('+') {
+                        format!("? extends {}", self.type_()?)
+                    } else if self.eat('-') {
+                        format!("? super {}", self.type_()?)
+                    } else {
+                        self.type_()?
+                    };
+                    arguments.push(argument);
+                }
+                text = format!("{text}<{}>", arguments.join(", "));
+            }
+            if self.eat(';') {
+                return Some(text);
+            }
+            if !self.eat('.') {
+                return None;
+            }
+            text.push('.');
+        }
+    }
+
+    fn type_parameters(&mut self) -> Option<Vec<String>> {
+        let mut parameters = Vec::new();
+        if self.eat('<') {
+            while !self.eat('>') {
+                let name = self.until(':')?;
+                let mut bounds = Vec::new();
+                if !self.rest.starts_with(':') {
+                    bounds.push(self.type_()?);
+                }
+                while self.eat(':') {
+                    bounds.push(self.type_()?);
+                }
+                if bounds.is_empty() {
+                    parameters.push(name.to_owned());
+                } else {
+                    parameters.push(format!("{name} extends {}", bounds.join(" & ")));
+                }
+            }
+        }
+        Some(parameters)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assembler::assemble;
+
+    /// Assembles a class and prints it like `javap -v -p`.
+    /// The expected outputs are the ones of `javap -v -p` of JDK 17, without the lines about the class file.
+    fn javap(source: &str) -> String {
+        let bytes = assemble(source).unwrap();
+        verbose(&Class::new(&bytes).unwrap(), Visibility::Private).unwrap()
+    }
+
+    #[test]
+    fn matches_javap() {
+        let bytes = assemble(concat!(
+            ".class public super Example\n",
+            ".super java/lang/Object\n",
+            ".method public static m (I)I\n",
+            "    .limit stack 1\n",
+            "    .limit locals 1\n",
+            "    iload_0\n",
+            "    tableswitch 0 1\n",
+            "        a\n",
+            "        b\n",
+            "        default : b\n",
+            "a:  iconst_1\n",
+            "    ireturn\n",
+            "b:  iconst_0\n",
+            "    ireturn\n",
+            ".end method\n",
+        ))
+        .unwrap();
+        let text = verbose(&Class::new(&bytes).unwrap(), Visibility::Package).unwrap();
+        assert_eq!(
+            text,
+            concat!(
+                "public class Example\n",
+                "  minor version: 0\n",
+                "  major version: 52\n",
+                "  flags: (0x0021) ACC_PUBLIC, ACC_SUPER\n",
+                "  this_class: #2                          // Example\n",
+                "  super_class: #4                         // java/lang/Object\n",
+                "  interfaces: 0, fields: 0, methods: 1, attributes: 0\n",
+                "Constant pool:\n",
+                "  #1 = Utf8               Example\n",
+                "  #2 = Class              #1              // Example\n",
+                "  #3 = Utf8               java/lang/Object\n",
+                "  #4 = Class              #3              // java/lang/Object\n",
+                "  #5 = Utf8               m\n",
+                "  #6 = Utf8               (I)I\n",
+                "  #7 = Utf8               Code\n",
+                "{\n",
+                "  public static int m(int);\n",
+                "    descriptor: (I)I\n",
+                "    flags: (0x0009) ACC_PUBLIC, ACC_STATIC\n",
+                "    Code:\n",
+                "      stack=1, locals=1, args_size=1\n",
+                "         0: iload_0\n",
+                "         1: tableswitch   { // 0 to 1\n",
+                "                       0: 24\n",
+                "                       1: 26\n",
+                "                 default: 26\n",
+                "            }\n",
+                "        24: iconst_1\n",
+                "        25: ireturn\n",
+                "        26: iconst_0\n",
+                "        27: ireturn\n",
+                "}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn code_attributes_match_javap() {
+        assert_eq!(
+            javap(concat!(
+                ".class public super Example\n",
+                ".super java/lang/Object\n",
+                ".source Example.java\n",
+                ".method public static m (I)I\n",
+                "    .limit stack 1\n",
+                "    .limit locals 2\n",
+                "start:\n",
+                "    .line 3\n",
+                "    iload_0\n",
+                "    ifeq zero\n",
+                "    .line 4\n",
+                "    iconst_1\n",
+                "    istore_1\n",
+                "    goto end\n",
+                "zero:\n",
+                "    .frame same\n",
+                "    .line 6\n",
+                "    iconst_0\n",
+                "    istore_1\n",
+                "end:\n",
+                "    .frame append int\n",
+                "    .line 8\n",
+                "    iload_1\n",
+                "    ireturn\n",
+                "last:\n",
+                "    .var 0 is x I from start to last\n",
+                "    .var 1 is y I from zero to last\n",
+                ".end method\n",
+            )),
+            concat!(
+                "  Compiled from \"Example.java\"\n",
+                "public class Example\n",
+                "  minor version: 0\n",
+                "  major version: 52\n",
+                "  flags: (0x0021) ACC_PUBLIC, ACC_SUPER\n",
+                "  this_class: #2                          // Example\n",
+                "  super_class: #4                         // java/lang/Object\n",
+                "  interfaces: 0, fields: 0, methods: 1, attributes: 1\n",
+                "Constant pool:\n",
+                "   #1 = Utf8               Example\n",
+                "   #2 = Class              #1             // Example\n",
+                "   #3 = Utf8               java/lang/Object\n",
+                "   #4 = Class              #3             // java/lang/Object\n",
+                "   #5 = Utf8               m\n",
+                "   #6 = Utf8               (I)I\n",
+                "   #7 = Utf8               Code\n",
+                "   #8 = Utf8               LocalVariableTable\n",
+                "   #9 = Utf8               x\n",
+                "  #10 = Utf8               I\n",
+                "  #11 = Utf8               y\n",
+                "  #12 = Utf8               StackMapTable\n",
+                "  #13 = Utf8               LineNumberTable\n",
+                "  #14 = Utf8               SourceFile\n",
+                "  #15 = Utf8               Example.java\n",
+                "{\n",
+                "  public static int m(int);\n",
+                "    descriptor: (I)I\n",
+                "    flags: (0x0009) ACC_PUBLIC, ACC_STATIC\n",
+                "    Code:\n",
+                "      stack=1, locals=2, args_size=1\n",
+                "         0: iload_0\n",
+                "         1: ifeq          9\n",
+                "         4: iconst_1\n",
+                "         5: istore_1\n",
+                "         6: goto          11\n",
+                "         9: iconst_0\n",
+                "        10: istore_1\n",
+                "        11: iload_1\n",
+                "        12: ireturn\n",
+                "      LocalVariableTable:\n",
+                "        Start  Length  Slot  Name   Signature\n",
+                "            0      13     0     x   I\n",
+                "            9       4     1     y   I\n",
+                "      StackMapTable: number_of_entries = 2\n",
+                "        frame_type = 9 /* same */\n",
+                "        frame_type = 252 /* append */\n",
+                "          offset_delta = 1\n",
+                "          locals = [ int ]\n",
+                "      LineNumberTable:\n",
+                "        line 3: 0\n",
+                "        line 4: 4\n",
+                "        line 6: 9\n",
+                "        line 8: 11\n",
+                "}\n",
+                "SourceFile: \"Example.java\"\n",
+            )
+        );
+    }
+
+    #[test]
+    fn constant_values_match_javap() {
+        assert_eq!(
+            javap(concat!(
+                ".class public super Example\n",
+                ".super java/lang/Object\n",
+                ".field public static final A I = 42\n",
+                ".field public static final B J = long 10000000000\n",
+                ".field static final C F = float 1.5\n",
+                ".field private static final D D = double 2.25\n",
+                ".field public static final E Ljava/lang/String; = \"text\"\n",
+                ".field protected static final F Z = 1\n",
+            )),
+            concat!(
+                "public class Example\n",
+                "  minor version: 0\n",
+                "  major version: 52\n",
+                "  flags: (0x0021) ACC_PUBLIC, ACC_SUPER\n",
+                "  this_class: #2                          // Example\n",
+                "  super_class: #4                         // java/lang/Object\n",
+                "  interfaces: 0, fields: 6, methods: 0, attributes: 0\n",
+                "Constant pool:\n",
+                "   #1 = Utf8               Example\n",
+                "   #2 = Class              #1             // Example\n",
+                "   #3 = Utf8               java/lang/Object\n",
+                "   #4 = Class              #3             // java/lang/Object\n",
+                "   #5 = Utf8               A\n",
+                "   #6 = Utf8               I\n",
+                "   #7 = Utf8               ConstantValue\n",
+                "   #8 = Integer            42\n",
+                "   #9 = Utf8               B\n",
+                "  #10 = Utf8               J\n",
+                "  #11 = Long               10000000000l\n",
+                "  #13 = Utf8               C\n",
+                "  #14 = Utf8               F\n",
+                "  #15 = Float              1.5f\n",
+                "  #16 = Utf8               D\n",
+                "  #17 = Double             2.25d\n",
+                "  #19 = Utf8               E\n",
+                "  #20 = Utf8               Ljava/lang/String;\n",
+                "  #21 = Utf8               text\n",
+                "  #22 = String             #21            // text\n",
+                "  #23 = Utf8               Z\n",
+                "  #24 = Integer            1\n",
+                "{\n",
+                "  public static final int A;\n",
+                "    descriptor: I\n",
+                "    flags: (0x0019) ACC_PUBLIC, ACC_STATIC, ACC_FINAL\n",
+                "    ConstantValue: int 42\n",
+                "\n",
+                "  public static final long B;\n",
+                "    descriptor: J\n",
+                "    flags: (0x0019) ACC_PUBLIC, ACC_STATIC, ACC_FINAL\n",
+                "    ConstantValue: long 10000000000l\n",
+                "\n",
+                "  static final float C;\n",
+                "    descriptor: F\n",
+                "    flags: (0x0018) ACC_STATIC, ACC_FINAL\n",
+                "    ConstantValue: float 1.5f\n",
+                "\n",
+                "  private static final double D;\n",
+                "    descriptor: D\n",
+                "    flags: (0x001a) ACC_PRIVATE, ACC_STATIC, ACC_FINAL\n",
+                "    ConstantValue: double 2.25d\n",
+                "\n",
+                "  public static final java.lang.String E;\n",
+                "    descriptor: Ljava/lang/String;\n",
+                "    flags: (0x0019) ACC_PUBLIC, ACC_STATIC, ACC_FINAL\n",
+                "    ConstantValue: String text\n",
+                "\n",
+                "  protected static final boolean F;\n",
+                "    descriptor: Z\n",
+                "    flags: (0x001c) ACC_PROTECTED, ACC_STATIC, ACC_FINAL\n",
+                "    ConstantValue: int 1\n",
+                "\n",
+                "}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn annotations_match_javap() {
+        assert_eq!(
+            javap(concat!(
+                ".class public super Example\n",
+                ".super java/lang/Object\n",
+                ".annotation visible Ljava/lang/Deprecated; { since = string \"9\" forRemoval = boolean true }\n",
+                ".annotation invisible LMarker;\n",
+                ".method public m (I)V\n",
+                "    .annotation visible LValues; { ints = array { int 1 int 2 } kind = enum Ljava/lang/annotation/ElementType; FIELD type = class Ljava/lang/String; }\n",
+                "    .annotation invisible parameter 0 LNamed; { value = string \"n\" }\n",
+                "    .limit stack 0\n",
+                "    .limit locals 2\n",
+                "    return\n",
+                ".end method\n",
+            )),
+            concat!(
+                "public class Example\n",
+                "  minor version: 0\n",
+                "  major version: 52\n",
+                "  flags: (0x0021) ACC_PUBLIC, ACC_SUPER\n",
+                "  this_class: #2                          // Example\n",
+                "  super_class: #4                         // java/lang/Object\n",
+                "  interfaces: 0, fields: 0, methods: 1, attributes: 2\n",
+                "Constant pool:\n",
+                "   #1 = Utf8               Example\n",
+                "   #2 = Class              #1             // Example\n",
+                "   #3 = Utf8               java/lang/Object\n",
+                "   #4 = Class              #3             // java/lang/Object\n",
+                "   #5 = Utf8               m\n",
+                "   #6 = Utf8               (I)V\n",
+                "   #7 = Utf8               Code\n",
+                "   #8 = Utf8               RuntimeVisibleAnnotations\n",
+                "   #9 = Utf8               LValues;\n",
+                "  #10 = Utf8               ints\n",
+                "  #11 = Integer            1\n",
+                "  #12 = Integer            2\n",
+                "  #13 = Utf8               kind\n",
+                "  #14 = Utf8               Ljava/lang/annotation/ElementType;\n",
+                "  #15 = Utf8               FIELD\n",
+                "  #16 = Utf8               type\n",
+                "  #17 = Utf8               Ljava/lang/String;\n",
+                "  #18 = Utf8               RuntimeInvisibleParameterAnnotations\n",
+                "  #19 = Utf8               LNamed;\n",
+                "  #20 = Utf8               value\n",
+                "  #21 = Utf8               n\n",
+                "  #22 = Utf8               Ljava/lang/Deprecated;\n",
+                "  #23 = Utf8               since\n",
+                "  #24 = Utf8               9\n",
+                "  #25 = Utf8               forRemoval\n",
+                "  #26 = Utf8               RuntimeInvisibleAnnotations\n",
+                "  #27 = Utf8               LMarker;\n",
+                "{\n",
+                "  public void m(int);\n",
+                "    descriptor: (I)V\n",
+                "    flags: (0x0001) ACC_PUBLIC\n",
+                "    Code:\n",
+                "      stack=0, locals=2, args_size=2\n",
+                "         0: return\n",
+                "    RuntimeVisibleAnnotations:\n",
+                "      0: #9(#10=[I#11,I#12],#13=e#14.#15,#16=c#17)\n",
+                "        Values(\n",
+                "          ints=[1,2]\n",
+                "          kind=Ljava/lang/annotation/ElementType;.FIELD\n",
+                "          type=class Ljava/lang/String;\n",
+                "        )\n",
+                "    RuntimeInvisibleParameterAnnotations:\n",
+                "      parameter 0:\n",
+                "        0: #19(#20=s#21)\n",
+                "          Named(\n",
+                "            value=\"n\"\n",
+                "          )\n",
+                "}\n",
+                "RuntimeVisibleAnnotations:\n",
+                "  0: #22(#23=s#24,#25=Z#11)\n",
+                "    java.lang.Deprecated(\n",
+                "      since=\"9\"\n",
+                "      forRemoval=true\n",
+                "    )\n",
+                "RuntimeInvisibleAnnotations:\n",
+                "  0: #27()\n",
+                "    Marker\n",
+            )
+        );
+    }
+}
@@ -16,6 +16,7 @@ pub enum DecodeErrorKind {
     UnknownAttributeName,
     InvalidInstruction,
     AttributeNotFound,
+    InvalidStackState,
 }
 
 impl fmt::Display for DecodeErrorKind {
@@ -35,6 +36,7 @@ impl fmt::Display for DecodeErrorKind {
             UnknownAttributeName => write!(f, "unknown attribute name"),
             InvalidInstruction => write!(f, "invalid instruction"),
             AttributeNotFound => write!(f, "attribute was not found"),
+            InvalidStackState => write!(f, "the operand stack does not match the instruction or jump target"),
         }
     }
 }
@@ -127,6 +129,11 @@ pub enum EncodeErrorKind {
     NegativeOffset,
     IncorrectBounds,
     InvalidKeyOrder,
+    DuplicateKey,
+    InvalidConstant,
+    InvalidStackState,
+    UnsupportedInstruction,
+    LocalVariableRemoved,
     Other(Box<dyn Error + 'static>),
 }
 
@@ -150,6 +157,14 @@ impl fmt::Display for EncodeErrorKind {
                 f,
                 "the keys in the lookupswitch instruction must be in an increasing numerical order"
             ),
+            DuplicateKey => write!(f, "a key of the switch has multiple jump targets"),
+            InvalidConstant => write!(f, "an instruction references an invalid constant pool entry"),
+            InvalidStackState => write!(f, "the operand stack does not match the instruction or jump target"),
+            UnsupportedInstruction => write!(f, "the instruction is not supported when computing stack map frames"),
+            LocalVariableRemoved => write!(
+                f,
+                "a local variable was removed when optimizing, so the local variables can't be described by hand"
+            ),
             Other(err) => write!(f, "other: {}", err),
         }
     }
@@ -200,6 +215,89 @@ impl fmt::Display for EncodeError {
     }
 }
 
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum AssembleErrorKind {
+    UnexpectedEnd,
+    /// Something else was found where the described item was expected.
+    Expected(&'static str),
+    UnknownDirective,
+    UnknownInstruction,
+    UnknownAccessFlag,
+    InvalidNumber,
+    InvalidString,
+    InvalidDescriptor,
+    DuplicateLabel,
+    LabelNotFound,
+    /// Writing the class failed.
+    Encode(EncodeError),
+}
+
+impl fmt::Display for AssembleErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use AssembleErrorKind::*;
+
+        match self {
+            UnexpectedEnd => write!(f, "unexpected end of input"),
+            Expected(expected) => write!(f, "expected {}", expected),
+            UnknownDirective => write!(f, "unknown directive"),
+            UnknownInstruction => write!(f, "unknown instruction"),
+            UnknownAccessFlag => write!(f, "unknown access flag"),
+            InvalidNumber => write!(f, "invalid number"),
+            InvalidString => write!(f, "invalid string literal"),
+            InvalidDescriptor => write!(f, "invalid descriptor"),
+            DuplicateLabel => write!(f, "label is defined more than once"),
+            LabelNotFound => write!(f, "label was not found"),
+            Encode(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// An error in the source of the assembler, at a line and column which both start at 1.
+#[derive(Debug)]
+pub struct AssembleError {
+    kind: AssembleErrorKind,
+    line: usize,
+    column: usize,
+}
+
+impl AssembleError {
+    pub(crate) fn new(kind: AssembleErrorKind, line: usize, column: usize) -> AssembleError {
+        AssembleError { kind, line, column }
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> &AssembleErrorKind {
+        &self.kind
+    }
+
+    #[must_use]
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    #[must_use]
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl Error for AssembleError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        if let AssembleErrorKind::Encode(err) = self.kind() {
+            Some(err)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}:{}", self.kind(), self.line(), self.column())
+    }
+}
+
 /// The context in which a error occurred in.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[non_exhaustive]
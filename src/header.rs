@@ -55,8 +55,11 @@ bitflags! {
         const FINAL        = 1 << 4;
         const SUPER        = 1 << 5;
         const SYNCHRONIZED = 1 << 5;
+        const OPEN         = 1 << 5;
+        const TRANSITIVE   = 1 << 5;
         const BRIDGE       = 1 << 6;
         const VOLATILE     = 1 << 6;
+        const STATIC_PHASE = 1 << 6;
         const VARARGS      = 1 << 7;
         const TRANSIENT    = 1 << 7;
         const NATIVE       = 1 << 8;
@@ -0,0 +1,155 @@
+//! Information about the class hierarchy which is not part of a single class file.
+
+use crate::mutf8::{MStr, MString};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Provides the relationships between classes, which are required to merge object types
+/// when computing stack map frames.
+///
+/// All class names are internal names such as `java/lang/String`.
+pub trait ClassHierarchy {
+    /// Returns the name of the direct super class of a class.
+    ///
+    /// `None` is returned for `java/lang/Object` and for classes which are unknown.
+    fn super_class(&self, class: &MStr) -> Option<MString>;
+
+    /// Returns whether a class is an interface.
+    fn is_interface(&self, class: &MStr) -> bool;
+
+    /// Returns the name of the nearest common super class of two classes.
+    ///
+    /// Like the verifier, this treats interfaces as `java/lang/Object`.
+    fn common_super_class(&self, a: &MStr, b: &MStr) -> MString {
+        if a == b {
+            return a.to_owned();
+        }
+        if self.is_interface(a) || self.is_interface(b) {
+            return MString::from("java/lang/Object");
+        }
+
+        let mut ancestors = vec![a.to_owned()];
+        while let Some(super_class) = self.super_class(ancestors.last().unwrap()) {
+            if ancestors.contains(&super_class) {
+                break;
+            }
+            ancestors.push(super_class);
+        }
+
+        let mut visited = Vec::new();
+        let mut current = b.to_owned();
+        loop {
+            if ancestors.contains(&current) {
+                return current;
+            }
+            match self.super_class(&current) {
+                Some(super_class) if !visited.contains(&super_class) => {
+                    visited.push(current);
+                    current = super_class;
+                }
+                _ => return MString::from("java/lang/Object"),
+            }
+        }
+    }
+}
+
+impl<H: ClassHierarchy + ?Sized> ClassHierarchy for &H {
+    fn super_class(&self, class: &MStr) -> Option<MString> {
+        (**self).super_class(class)
+    }
+
+    fn is_interface(&self, class: &MStr) -> bool {
+        (**self).is_interface(class)
+    }
+
+    fn common_super_class(&self, a: &MStr, b: &MStr) -> MString {
+        (**self).common_super_class(a, b)
+    }
+}
+
+impl<H: ClassHierarchy + ?Sized> ClassHierarchy for Box<H> {
+    fn super_class(&self, class: &MStr) -> Option<MString> {
+        (**self).super_class(class)
+    }
+
+    fn is_interface(&self, class: &MStr) -> bool {
+        (**self).is_interface(class)
+    }
+
+    fn common_super_class(&self, a: &MStr, b: &MStr) -> MString {
+        (**self).common_super_class(a, b)
+    }
+}
+
+impl<H: ClassHierarchy + ?Sized> ClassHierarchy for Rc<H> {
+    fn super_class(&self, class: &MStr) -> Option<MString> {
+        (**self).super_class(class)
+    }
+
+    fn is_interface(&self, class: &MStr) -> bool {
+        (**self).is_interface(class)
+    }
+
+    fn common_super_class(&self, a: &MStr, b: &MStr) -> MString {
+        (**self).common_super_class(a, b)
+    }
+}
+
+impl<H: ClassHierarchy + ?Sized> ClassHierarchy for Arc<H> {
+    fn super_class(&self, class: &MStr) -> Option<MString> {
+        (**self).super_class(class)
+    }
+
+    fn is_interface(&self, class: &MStr) -> bool {
+        (**self).is_interface(class)
+    }
+
+    fn common_super_class(&self, a: &MStr, b: &MStr) -> MString {
+        (**self).common_super_class(a, b)
+    }
+}
+
+/// Returns the nearest common super type of two classes or array classes, like the verifier does when merging types.
+pub(crate) fn merge_classes(hierarchy: &dyn ClassHierarchy, a: &MStr, b: &MStr) -> MString {
+    if a == b {
+        return a.to_owned();
+    }
+
+    let is_array = |name: &MStr| name.as_bytes().first() == Some(&b'[');
+    if is_array(a) && is_array(b) {
+        let (a, b) = (&a[1..], &b[1..]);
+        let is_reference = |name: &MStr| matches!(name.as_bytes().first(), Some(b'L' | b'['));
+        if is_reference(a) && is_reference(b) {
+            let element = merge_classes(hierarchy, class_of_descriptor(a), class_of_descriptor(b));
+            return array_of_class(&element);
+        }
+        MString::from("java/lang/Object")
+    } else if is_array(a) || is_array(b) {
+        MString::from("java/lang/Object")
+    } else {
+        hierarchy.common_super_class(a, b)
+    }
+}
+
+/// Returns the name of the array class whose elements are of the class with the name as it is used in
+/// class constants.
+pub(crate) fn array_of_class(element: &MStr) -> MString {
+    let mut name = MString::from("[");
+    if element.as_bytes().first() == Some(&b'[') {
+        name.push_mstr(element);
+    } else {
+        name.push('L');
+        name.push_mstr(element);
+        name.push(';');
+    }
+    name
+}
+
+/// Converts a field descriptor of a reference type to the name of a class as it is used in class constants.
+pub(crate) fn class_of_descriptor(descriptor: &MStr) -> &MStr {
+    if descriptor.as_bytes().first() == Some(&b'L') {
+        &descriptor[1..descriptor.len() - 1]
+    } else {
+        descriptor
+    }
+}
@@ -30,7 +30,6 @@
     clippy::print_stderr,
     clippy::print_stdout,
     clippy::separated_literal_suffix,
-    clippy::string_to_string,
     clippy::todo,
     clippy::try_err,
     clippy::undocumented_unsafe_blocks,
@@ -39,12 +38,21 @@
     clippy::use_debug
 )]
 
+pub mod analysis;
+pub mod assembler;
 pub mod descriptor;
+pub mod disassembler;
 pub mod error;
 mod header;
+pub mod hierarchy;
 pub mod mutf8;
+mod opcode;
 pub mod reader;
 pub mod writer;
 
+#[cfg(test)]
+mod testing;
+
 pub use header::{AccessFlags, Version};
 pub use mutf8::{MStr, MString};
+pub use opcode::Opcode;
@@ -275,10 +275,10 @@ impl ops::Index<ops::RangeInclusive<usize>> for MStr {
 
     #[inline]
     fn index(&self, index: ops::RangeInclusive<usize>) -> &MStr {
-        if *index.end() == usize::max_value() {
+        if *index.end() == usize::MAX {
             panic!("cannot index mutf8 to maximum integer")
         } else {
-            #[allow(clippy::range_plus_one)]
+            #[allow(clippy::range_plus_one, reason = "the range is inclusive of the end byte")]
             &self[*index.start()..*index.end() + 1]
         }
     }
@@ -303,10 +303,10 @@ impl ops::Index<ops::RangeToInclusive<usize>> for MStr {
 
     #[inline]
     fn index(&self, index: ops::RangeToInclusive<usize>) -> &MStr {
-        if index.end == usize::max_value() {
+        if index.end == usize::MAX {
             panic!("cannot index mutf8 to maximum integer")
         } else {
-            #[allow(clippy::range_plus_one)]
+            #[allow(clippy::range_plus_one, reason = "the range is inclusive of the end byte")]
             &self[..index.end + 1]
         }
     }
@@ -455,6 +455,11 @@ impl MString {
         let size = encode_mutf8_char(ch, &mut buf);
         self.buf.extend_from_slice(&buf[..size]);
     }
+
+    /// Appends a string, which is always valid as modified UTF-8 encodes each character on its own.
+    pub(crate) fn push_mstr(&mut self, s: &MStr) {
+        self.buf.extend_from_slice(s.as_bytes());
+    }
 }
 
 impl Deref for MString {
@@ -856,7 +861,11 @@ fn encode_mutf8_char(ch: char, buf: &mut [u8]) -> usize {
 /// Dispatch conversion to a byte slice.
 /// Used from within the [`mutf8`] macro.
 #[doc(hidden)]
-#[allow(non_camel_case_types, missing_debug_implementations)]
+#[allow(
+    non_camel_case_types,
+    missing_debug_implementations,
+    reason = "only used from within the mutf8 macro"
+)]
 pub struct __private_MUtf8Literal<T>(pub T);
 
 impl __private_MUtf8Literal<&'static str> {
@@ -1019,12 +1028,17 @@ pub const fn __private_utf8_to_mutf8<const N: usize>(v: &[u8]) -> [u8; N] {
             }
             0b1111_0000..=0b1111_0111 => {
                 // U+10000 to U+10FFFF
+                let ch = (((v[i] & 0b0000_0111) as u32) << 18
+                    | ((v[i + 1] & 0b0011_1111) as u32) << 12
+                    | ((v[i + 2] & 0b0011_1111) as u32) << 6
+                    | (v[i + 3] & 0b0011_1111) as u32)
+                    - 0x10000;
                 out[m] = 0b1110_1101;
-                out[m + 1] = 0b1010_0000 | ((v[i] & 0b0000_0111) << 1) | ((v[i + 1] & 0b0010_0000) >> 5);
-                out[m + 2] = 0b1000_0000 | ((v[i + 1] & 0b0000_1111) << 2) | ((v[i + 2] & 0b0011_0000) >> 4);
+                out[m + 1] = 0b1010_0000 | ((ch >> 16) & 0b0000_1111) as u8;
+                out[m + 2] = 0b1000_0000 | ((ch >> 10) & 0b0011_1111) as u8;
                 out[m + 3] = 0b1110_1101;
-                out[m + 4] = 0b1011_0000 | (v[i + 2] & 0b0000_1111);
-                out[m + 5] = v[i + 3];
+                out[m + 4] = 0b1011_0000 | ((ch >> 6) & 0b0000_1111) as u8;
+                out[m + 5] = 0b1000_0000 | (ch & 0b0011_1111) as u8;
                 i += 4;
                 m += 6;
             }
@@ -1052,7 +1066,14 @@ macro_rules! mutf8 {
     ($s:literal) => {{
         // Ensure that the code is executed in a const context.
         const MSTR: &$crate::mutf8::MStr = {
-            const BYTES: &'static [u8] = $crate::mutf8::__private_MUtf8Literal($s).as_slice();
+            const LITERAL: &'static [u8] = $crate::mutf8::__private_MUtf8Literal($s).as_slice();
+            const LENGTH: usize = $crate::mutf8::__private_utf8_to_mutf8_length(LITERAL);
+            const CONVERTED: [u8; LENGTH] = $crate::mutf8::__private_utf8_to_mutf8(LITERAL);
+            const BYTES: &'static [u8] = if $crate::mutf8::__private_MUtf8Literal($s).is_str() {
+                &CONVERTED
+            } else {
+                LITERAL
+            };
             if !$crate::mutf8::__private_is_mutf8_valid(BYTES) {
                 panic!("literal is not a valid modified UTF-8 string.");
             }
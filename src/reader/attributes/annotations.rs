@@ -31,7 +31,7 @@ impl<'input> FromAttribute<'input> for RuntimeInvisibleAnnotations<'input> {
 
 dec_structure! {
     pub struct RuntimeInvisibleParameterAnnotations<'input> into {
-        annotations: DecodeMany<'input, Annotation<'input>, u8>,
+        parameters: DecodeMany<'input, ParameterAnnotations<'input>, u8>,
     }
 }
 
@@ -39,6 +39,13 @@ impl<'input> FromAttribute<'input> for RuntimeInvisibleParameterAnnotations<'inp
     const NAME: &'static MStr = mutf8!("RuntimeInvisibleParameterAnnotations");
 }
 
+dec_structure! {
+    /// The annotations of a single parameter.
+    pub struct ParameterAnnotations<'input> {
+        annotations: DecodeMany<'input, Annotation<'input>, u16>,
+    }
+}
+
 dec_structure! {
     pub struct RuntimeInvisibleTypeAnnotations<'input> into {
         annotations: DecodeMany<'input, TypeAnnotation<'input>, u16>,
@@ -61,7 +68,7 @@ impl<'input> FromAttribute<'input> for RuntimeVisibleAnnotations<'input> {
 
 dec_structure! {
     pub struct RuntimeVisibleParameterAnnotations<'input> into {
-        annotations: DecodeMany<'input, Annotation<'input>, u8>,
+        parameters: DecodeMany<'input, ParameterAnnotations<'input>, u8>,
     }
 }
 
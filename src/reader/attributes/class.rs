@@ -69,8 +69,8 @@ impl<'input> FromAttribute<'input> for BootstrapMethods<'input> {
 
 dec_structure! {
     pub struct BootstrapMethod<'input> {
-        method_ref: cpool::Index<cpool::MethodRef<'input>>,
-        arguments: DecodeMany<'input, cpool::Index<cpool::MethodHandle<'input>>, u16>
+        method_ref: cpool::Index<cpool::MethodHandle<'input>>,
+        arguments: DecodeMany<'input, cpool::Index<cpool::Item<'input>>, u16>,
     }
 }
 
@@ -80,6 +80,10 @@ dec_structure! {
     }
 }
 
+impl<'input> FromAttribute<'input> for Record<'input> {
+    const NAME: &'static MStr = mutf8!("Record");
+}
+
 dec_structure! {
     pub struct RecordComponent<'input> {
         name: cpool::Index<cpool::Utf8<'input>>,
@@ -93,3 +97,7 @@ dec_structure! {
         classes: DecodeMany<'input, cpool::Index<cpool::Class<'input>>, u16>,
     }
 }
+
+impl<'input> FromAttribute<'input> for PermittedSubclasses<'input> {
+    const NAME: &'static MStr = mutf8!("PermittedSubclasses");
+}
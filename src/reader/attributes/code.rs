@@ -1,9 +1,13 @@
 mod instructions;
 mod locals;
+mod metadata;
+mod resolved;
 mod stack_map;
 
 pub use instructions::*;
 pub use locals::*;
+pub use metadata::*;
+pub use resolved::*;
 pub use stack_map::*;
 
 use crate::reader::decoding::*;
@@ -33,6 +37,12 @@ impl<'input> Code<'input> {
         self.max_locals
     }
 
+    /// The length of the code table in bytes.
+    #[must_use]
+    pub fn code_length(&self) -> u32 {
+        self.raw_instructions.decoder.bytes_remaining() as u32
+    }
+
     #[must_use]
     pub fn raw_instructions(&self) -> RawInstructions<'input> {
         self.raw_instructions.clone()
@@ -44,6 +54,15 @@ impl<'input> Code<'input> {
         Ok(instructions)
     }
 
+    /// Returns the instructions with their operands resolved using the constant pool.
+    #[must_use]
+    pub fn instructions<'a>(&self, pool: &'a cpool::ConstantPool<'input>) -> Instructions<'a, 'input> {
+        Instructions {
+            raw_instructions: self.raw_instructions(),
+            pool,
+        }
+    }
+
     #[must_use]
     pub fn exception_handlers(&self) -> ExceptionHandlers<'input> {
         self.exception_handlers.clone()
@@ -185,7 +204,8 @@ impl Index {
 
 impl<'input> Decode<'input> for Index {
     fn decode(decoder: &mut Decoder<'input>) -> Result<Index, DecodeError> {
-        Ok(Index::new(decoder.read()?))
+        let index: u16 = decoder.read()?;
+        Ok(Index::new(index.into()))
     }
 }
 
@@ -2,6 +2,7 @@ use crate::error::*;
 use crate::reader::attributes::code;
 use crate::reader::cpool;
 use crate::reader::decoding::{Decode, Decoder};
+use crate::Opcode;
 use std::fmt;
 
 /// An iterator over the instructions and their indices into the code table
@@ -561,184 +562,185 @@ impl fmt::Debug for TablePair {
 impl<'input> RawInstruction<'input> {
     pub(crate) fn decode(decoder: &mut Decoder<'input>, instruction_start: usize) -> Result<Self, DecodeError> {
         use RawInstruction::*;
-        let opcode: u8 = decoder.read()?;
+        let opcode = Opcode::from_u8(decoder.read()?)
+            .ok_or_else(|| DecodeError::from_decoder(DecodeErrorKind::InvalidInstruction, decoder))?;
         let instruction = match opcode {
-            0x32 => AALoad,
-            0x53 => AAStore,
-            0x01 => AConstNull,
-            0x19 => ALoad { index: decoder.read()? },
-            0x2a => ALoad0,
-            0x2b => ALoad1,
-            0x2c => ALoad2,
-            0x2d => ALoad3,
-            0xbd => ANewArray { index: decoder.read()? },
-            0xb0 => AReturn,
-            0xbe => ArrayLength,
-            0x3a => AStore { index: decoder.read()? },
-            0x4b => AStore0,
-            0x4c => AStore1,
-            0x4d => AStore2,
-            0x4e => AStore3,
-            0xbf => AThrow,
-            0x33 => BALoad,
-            0x54 => BAStore,
-            0x10 => BIPush { value: decoder.read()? },
-            0x34 => CALoad,
-            0x55 => CAStore,
-            0xc0 => CheckCast { index: decoder.read()? },
-            0x90 => D2F,
-            0x8e => D2I,
-            0x8f => D2L,
-            0x63 => DAdd,
-            0x31 => DALoad,
-            0x52 => DAStore,
-            0x98 => DCmpG,
-            0x97 => DCmpL,
-            0x0e => DConst0,
-            0x0f => DConst1,
-            0x6f => DDiv,
-            0x18 => DLoad { index: decoder.read()? },
-            0x26 => DLoad0,
-            0x27 => DLoad1,
-            0x28 => DLoad2,
-            0x29 => DLoad3,
-            0x6b => DMul,
-            0x77 => DNeg,
-            0x73 => DRem,
-            0xaf => DReturn,
-            0x39 => DStore { index: decoder.read()? },
-            0x47 => DStore0,
-            0x48 => DStore1,
-            0x49 => DStore2,
-            0x4a => DStore3,
-            0x67 => DSub,
-            0x59 => Dup,
-            0x5a => DupX1,
-            0x5b => DupX2,
-            0x5c => Dup2,
-            0x5d => Dup2X1,
-            0x5e => Dup2X2,
-            0x8d => F2D,
-            0x8b => F2I,
-            0x8c => F2L,
-            0x62 => FAdd,
-            0x30 => FALoad,
-            0x51 => FAStore,
-            0x96 => FCmpG,
-            0x95 => FCmpL,
-            0x0b => FConst0,
-            0x0c => FConst1,
-            0x0d => FConst2,
-            0x6e => FDiv,
-            0x17 => FLoad { index: decoder.read()? },
-            0x22 => FLoad0,
-            0x23 => FLoad1,
-            0x24 => FLoad2,
-            0x25 => FLoad3,
-            0x6a => FMul,
-            0x76 => FNeg,
-            0x72 => FRem,
-            0xae => FReturn,
-            0x38 => FStore { index: decoder.read()? },
-            0x43 => FStore0,
-            0x44 => FStore1,
-            0x45 => FStore2,
-            0x46 => FStore3,
-            0x66 => FSub,
-            0xb4 => GetField { index: decoder.read()? },
-            0xb2 => GetStatic { index: decoder.read()? },
-            0xa7 => Goto {
+            Opcode::AALoad => AALoad,
+            Opcode::AAStore => AAStore,
+            Opcode::AConstNull => AConstNull,
+            Opcode::ALoad => ALoad { index: decoder.read()? },
+            Opcode::ALoad0 => ALoad0,
+            Opcode::ALoad1 => ALoad1,
+            Opcode::ALoad2 => ALoad2,
+            Opcode::ALoad3 => ALoad3,
+            Opcode::ANewArray => ANewArray { index: decoder.read()? },
+            Opcode::AReturn => AReturn,
+            Opcode::ArrayLength => ArrayLength,
+            Opcode::AStore => AStore { index: decoder.read()? },
+            Opcode::AStore0 => AStore0,
+            Opcode::AStore1 => AStore1,
+            Opcode::AStore2 => AStore2,
+            Opcode::AStore3 => AStore3,
+            Opcode::AThrow => AThrow,
+            Opcode::BALoad => BALoad,
+            Opcode::BAStore => BAStore,
+            Opcode::BIPush => BIPush { value: decoder.read()? },
+            Opcode::CALoad => CALoad,
+            Opcode::CAStore => CAStore,
+            Opcode::CheckCast => CheckCast { index: decoder.read()? },
+            Opcode::D2F => D2F,
+            Opcode::D2I => D2I,
+            Opcode::D2L => D2L,
+            Opcode::DAdd => DAdd,
+            Opcode::DALoad => DALoad,
+            Opcode::DAStore => DAStore,
+            Opcode::DCmpG => DCmpG,
+            Opcode::DCmpL => DCmpL,
+            Opcode::DConst0 => DConst0,
+            Opcode::DConst1 => DConst1,
+            Opcode::DDiv => DDiv,
+            Opcode::DLoad => DLoad { index: decoder.read()? },
+            Opcode::DLoad0 => DLoad0,
+            Opcode::DLoad1 => DLoad1,
+            Opcode::DLoad2 => DLoad2,
+            Opcode::DLoad3 => DLoad3,
+            Opcode::DMul => DMul,
+            Opcode::DNeg => DNeg,
+            Opcode::DRem => DRem,
+            Opcode::DReturn => DReturn,
+            Opcode::DStore => DStore { index: decoder.read()? },
+            Opcode::DStore0 => DStore0,
+            Opcode::DStore1 => DStore1,
+            Opcode::DStore2 => DStore2,
+            Opcode::DStore3 => DStore3,
+            Opcode::DSub => DSub,
+            Opcode::Dup => Dup,
+            Opcode::DupX1 => DupX1,
+            Opcode::DupX2 => DupX2,
+            Opcode::Dup2 => Dup2,
+            Opcode::Dup2X1 => Dup2X1,
+            Opcode::Dup2X2 => Dup2X2,
+            Opcode::F2D => F2D,
+            Opcode::F2I => F2I,
+            Opcode::F2L => F2L,
+            Opcode::FAdd => FAdd,
+            Opcode::FALoad => FALoad,
+            Opcode::FAStore => FAStore,
+            Opcode::FCmpG => FCmpG,
+            Opcode::FCmpL => FCmpL,
+            Opcode::FConst0 => FConst0,
+            Opcode::FConst1 => FConst1,
+            Opcode::FConst2 => FConst2,
+            Opcode::FDiv => FDiv,
+            Opcode::FLoad => FLoad { index: decoder.read()? },
+            Opcode::FLoad0 => FLoad0,
+            Opcode::FLoad1 => FLoad1,
+            Opcode::FLoad2 => FLoad2,
+            Opcode::FLoad3 => FLoad3,
+            Opcode::FMul => FMul,
+            Opcode::FNeg => FNeg,
+            Opcode::FRem => FRem,
+            Opcode::FReturn => FReturn,
+            Opcode::FStore => FStore { index: decoder.read()? },
+            Opcode::FStore0 => FStore0,
+            Opcode::FStore1 => FStore1,
+            Opcode::FStore2 => FStore2,
+            Opcode::FStore3 => FStore3,
+            Opcode::FSub => FSub,
+            Opcode::GetField => GetField { index: decoder.read()? },
+            Opcode::GetStatic => GetStatic { index: decoder.read()? },
+            Opcode::Goto => Goto {
                 offset: decoder.read()?,
             },
-            0xc8 => GotoW {
+            Opcode::GotoW => GotoW {
                 offset: decoder.read()?,
             },
-            0x91 => I2B,
-            0x92 => I2C,
-            0x87 => I2D,
-            0x86 => I2F,
-            0x85 => I2L,
-            0x93 => I2S,
-            0x60 => IAdd,
-            0x2e => IALoad,
-            0x7e => IAnd,
-            0x4f => IAStore,
-            0x02 => IConstM1,
-            0x03 => IConst0,
-            0x04 => IConst1,
-            0x05 => IConst2,
-            0x06 => IConst3,
-            0x07 => IConst4,
-            0x08 => IConst5,
-            0x6c => IDiv,
-            0xa5 => IfACmpEq {
+            Opcode::I2B => I2B,
+            Opcode::I2C => I2C,
+            Opcode::I2D => I2D,
+            Opcode::I2F => I2F,
+            Opcode::I2L => I2L,
+            Opcode::I2S => I2S,
+            Opcode::IAdd => IAdd,
+            Opcode::IALoad => IALoad,
+            Opcode::IAnd => IAnd,
+            Opcode::IAStore => IAStore,
+            Opcode::IConstM1 => IConstM1,
+            Opcode::IConst0 => IConst0,
+            Opcode::IConst1 => IConst1,
+            Opcode::IConst2 => IConst2,
+            Opcode::IConst3 => IConst3,
+            Opcode::IConst4 => IConst4,
+            Opcode::IConst5 => IConst5,
+            Opcode::IDiv => IDiv,
+            Opcode::IfACmpEq => IfACmpEq {
                 offset: decoder.read()?,
             },
-            0xa6 => IfACmpNe {
+            Opcode::IfACmpNe => IfACmpNe {
                 offset: decoder.read()?,
             },
-            0x9f => IfICmpEq {
+            Opcode::IfICmpEq => IfICmpEq {
                 offset: decoder.read()?,
             },
-            0xa0 => IfICmpNe {
+            Opcode::IfICmpNe => IfICmpNe {
                 offset: decoder.read()?,
             },
-            0xa1 => IfICmpLt {
+            Opcode::IfICmpLt => IfICmpLt {
                 offset: decoder.read()?,
             },
-            0xa2 => IfICmpGe {
+            Opcode::IfICmpGe => IfICmpGe {
                 offset: decoder.read()?,
             },
-            0xa3 => IfICmpGt {
+            Opcode::IfICmpGt => IfICmpGt {
                 offset: decoder.read()?,
             },
-            0xa4 => IfICmpLe {
+            Opcode::IfICmpLe => IfICmpLe {
                 offset: decoder.read()?,
             },
-            0x99 => IfEq {
+            Opcode::IfEq => IfEq {
                 offset: decoder.read()?,
             },
-            0x9a => IfNe {
+            Opcode::IfNe => IfNe {
                 offset: decoder.read()?,
             },
-            0x9b => IfLt {
+            Opcode::IfLt => IfLt {
                 offset: decoder.read()?,
             },
-            0x9c => IfGe {
+            Opcode::IfGe => IfGe {
                 offset: decoder.read()?,
             },
-            0x9d => IfGt {
+            Opcode::IfGt => IfGt {
                 offset: decoder.read()?,
             },
-            0x9e => IfLe {
+            Opcode::IfLe => IfLe {
                 offset: decoder.read()?,
             },
-            0xc7 => IfNonNull {
+            Opcode::IfNonNull => IfNonNull {
                 offset: decoder.read()?,
             },
-            0xc6 => IfNull {
+            Opcode::IfNull => IfNull {
                 offset: decoder.read()?,
             },
-            0x84 => IInc {
+            Opcode::IInc => IInc {
                 index: decoder.read()?,
                 value: decoder.read()?,
             },
-            0x15 => ILoad { index: decoder.read()? },
-            0x1a => ILoad0,
-            0x1b => ILoad1,
-            0x1c => ILoad2,
-            0x1d => ILoad3,
-            0x68 => IMul,
-            0x74 => INeg,
-            0xc1 => InstanceOf { index: decoder.read()? },
-            0xba => {
+            Opcode::ILoad => ILoad { index: decoder.read()? },
+            Opcode::ILoad0 => ILoad0,
+            Opcode::ILoad1 => ILoad1,
+            Opcode::ILoad2 => ILoad2,
+            Opcode::ILoad3 => ILoad3,
+            Opcode::IMul => IMul,
+            Opcode::INeg => INeg,
+            Opcode::InstanceOf => InstanceOf { index: decoder.read()? },
+            Opcode::InvokeDynamic => {
                 let index = decoder.read()?;
                 if decoder.read::<u8>()? != 0 || decoder.read::<u8>()? != 0 {
                     return Err(DecodeError::from_decoder(DecodeErrorKind::InvalidInstruction, decoder));
                 }
                 InvokeDynamic { index }
             }
-            0xb9 => {
+            Opcode::InvokeInterface => {
                 let index = decoder.read()?;
                 let count = decoder.read()?;
                 if decoder.read::<u8>()? != 0 {
@@ -746,52 +748,52 @@ impl<'input> RawInstruction<'input> {
                 }
                 InvokeInterface { index, count }
             }
-            0xb7 => InvokeSpecial { index: decoder.read()? },
-            0xb8 => InvokeStatic { index: decoder.read()? },
-            0xb6 => InvokeVirtual { index: decoder.read()? },
-            0x80 => IOr,
-            0x70 => IRem,
-            0xac => IReturn,
-            0x78 => IShL,
-            0x7a => IShR,
-            0x36 => IStore { index: decoder.read()? },
-            0x3b => IStore0,
-            0x3c => IStore1,
-            0x3d => IStore2,
-            0x3e => IStore3,
-            0x64 => ISub,
-            0x7c => IUShR,
-            0x82 => IXor,
-            0xa8 => JSr {
+            Opcode::InvokeSpecial => InvokeSpecial { index: decoder.read()? },
+            Opcode::InvokeStatic => InvokeStatic { index: decoder.read()? },
+            Opcode::InvokeVirtual => InvokeVirtual { index: decoder.read()? },
+            Opcode::IOr => IOr,
+            Opcode::IRem => IRem,
+            Opcode::IReturn => IReturn,
+            Opcode::IShL => IShL,
+            Opcode::IShR => IShR,
+            Opcode::IStore => IStore { index: decoder.read()? },
+            Opcode::IStore0 => IStore0,
+            Opcode::IStore1 => IStore1,
+            Opcode::IStore2 => IStore2,
+            Opcode::IStore3 => IStore3,
+            Opcode::ISub => ISub,
+            Opcode::IUShR => IUShR,
+            Opcode::IXor => IXor,
+            Opcode::JSr => JSr {
                 offset: decoder.read()?,
             },
-            0xc9 => JSrW {
+            Opcode::JSrW => JSrW {
                 offset: decoder.read()?,
             },
-            0x8a => L2D,
-            0x89 => L2F,
-            0x88 => L2I,
-            0x61 => LAdd,
-            0x2f => LALoad,
-            0x7f => LAnd,
-            0x50 => LAStore,
-            0x94 => LCmp,
-            0x09 => LConst0,
-            0x0a => LConst1,
-            0x12 => LdC {
+            Opcode::L2D => L2D,
+            Opcode::L2F => L2F,
+            Opcode::L2I => L2I,
+            Opcode::LAdd => LAdd,
+            Opcode::LALoad => LALoad,
+            Opcode::LAnd => LAnd,
+            Opcode::LAStore => LAStore,
+            Opcode::LCmp => LCmp,
+            Opcode::LConst0 => LConst0,
+            Opcode::LConst1 => LConst1,
+            Opcode::LdC => LdC {
                 index: cpool::Index::new(decoder.read::<u8>()?.into())?,
             },
-            0x13 => LdCW { index: decoder.read()? },
-            0x14 => LdC2W { index: decoder.read()? },
-            0x6d => LDiv,
-            0x16 => LLoad { index: decoder.read()? },
-            0x1e => LLoad0,
-            0x1f => LLoad1,
-            0x20 => LLoad2,
-            0x21 => LLoad3,
-            0x69 => LMul,
-            0x75 => LNeg,
-            0xab => {
+            Opcode::LdCW => LdCW { index: decoder.read()? },
+            Opcode::LdC2W => LdC2W { index: decoder.read()? },
+            Opcode::LDiv => LDiv,
+            Opcode::LLoad => LLoad { index: decoder.read()? },
+            Opcode::LLoad0 => LLoad0,
+            Opcode::LLoad1 => LLoad1,
+            Opcode::LLoad2 => LLoad2,
+            Opcode::LLoad3 => LLoad3,
+            Opcode::LMul => LMul,
+            Opcode::LNeg => LNeg,
+            Opcode::LookupSwitch => {
                 // skip padding
                 let offset = decoder.file_position() - instruction_start - 1;
                 decoder.advance(3 - (offset & 3))?;
@@ -801,39 +803,39 @@ impl<'input> RawInstruction<'input> {
                     pairs: decoder.read()?,
                 })
             }
-            0x81 => LOr,
-            0x71 => LRem,
-            0xad => LReturn,
-            0x79 => LShL,
-            0x7b => LShR,
-            0x37 => LStore { index: decoder.read()? },
-            0x3f => LStore0,
-            0x40 => LStore1,
-            0x41 => LStore2,
-            0x42 => LStore3,
-            0x65 => LSub,
-            0x7d => LUShR,
-            0x83 => LXor,
-            0xc2 => MonitorEnter,
-            0xc3 => MonitorExit,
-            0xc5 => MultiANewArray {
+            Opcode::LOr => LOr,
+            Opcode::LRem => LRem,
+            Opcode::LReturn => LReturn,
+            Opcode::LShL => LShL,
+            Opcode::LShR => LShR,
+            Opcode::LStore => LStore { index: decoder.read()? },
+            Opcode::LStore0 => LStore0,
+            Opcode::LStore1 => LStore1,
+            Opcode::LStore2 => LStore2,
+            Opcode::LStore3 => LStore3,
+            Opcode::LSub => LSub,
+            Opcode::LUShR => LUShR,
+            Opcode::LXor => LXor,
+            Opcode::MonitorEnter => MonitorEnter,
+            Opcode::MonitorExit => MonitorExit,
+            Opcode::MultiANewArray => MultiANewArray {
                 index: decoder.read()?,
                 dimensions: decoder.read()?,
             },
-            0xbb => New { index: decoder.read()? },
-            0xbc => NewArray { atype: decoder.read()? },
-            0x00 => Nop,
-            0x57 => Pop,
-            0x58 => Pop2,
-            0xb5 => PutField { index: decoder.read()? },
-            0xb3 => PutStatic { index: decoder.read()? },
-            0xa9 => Ret { index: decoder.read()? },
-            0xb1 => Return,
-            0x35 => SALoad,
-            0x56 => SAStore,
-            0x11 => SIPush { value: decoder.read()? },
-            0x5f => Swap,
-            0xaa => {
+            Opcode::New => New { index: decoder.read()? },
+            Opcode::NewArray => NewArray { atype: decoder.read()? },
+            Opcode::Nop => Nop,
+            Opcode::Pop => Pop,
+            Opcode::Pop2 => Pop2,
+            Opcode::PutField => PutField { index: decoder.read()? },
+            Opcode::PutStatic => PutStatic { index: decoder.read()? },
+            Opcode::Ret => Ret { index: decoder.read()? },
+            Opcode::Return => Return,
+            Opcode::SALoad => SALoad,
+            Opcode::SAStore => SAStore,
+            Opcode::SIPush => SIPush { value: decoder.read()? },
+            Opcode::Swap => Swap,
+            Opcode::TableSwitch => {
                 // skip padding
                 let offset = decoder.file_position() - instruction_start - 1;
                 decoder.advance(3 - (offset & 3))?;
@@ -843,28 +845,27 @@ impl<'input> RawInstruction<'input> {
                     pairs: decoder.read()?,
                 })
             }
-            0xc4 => {
-                let opcode: u8 = decoder.read()?;
+            Opcode::Wide => {
+                let opcode = Opcode::from_u8(decoder.read()?);
                 match opcode {
-                    0x19 => ALoadW { index: decoder.read()? },
-                    0x3a => AStoreW { index: decoder.read()? },
-                    0x18 => DLoadW { index: decoder.read()? },
-                    0x39 => DStoreW { index: decoder.read()? },
-                    0x17 => FLoadW { index: decoder.read()? },
-                    0x38 => FStoreW { index: decoder.read()? },
-                    0x15 => ILoadW { index: decoder.read()? },
-                    0x36 => IStoreW { index: decoder.read()? },
-                    0x16 => LLoadW { index: decoder.read()? },
-                    0x37 => LStoreW { index: decoder.read()? },
-                    0xa9 => RetW { index: decoder.read()? },
-                    0x84 => IIncW {
+                    Some(Opcode::ALoad) => ALoadW { index: decoder.read()? },
+                    Some(Opcode::AStore) => AStoreW { index: decoder.read()? },
+                    Some(Opcode::DLoad) => DLoadW { index: decoder.read()? },
+                    Some(Opcode::DStore) => DStoreW { index: decoder.read()? },
+                    Some(Opcode::FLoad) => FLoadW { index: decoder.read()? },
+                    Some(Opcode::FStore) => FStoreW { index: decoder.read()? },
+                    Some(Opcode::ILoad) => ILoadW { index: decoder.read()? },
+                    Some(Opcode::IStore) => IStoreW { index: decoder.read()? },
+                    Some(Opcode::LLoad) => LLoadW { index: decoder.read()? },
+                    Some(Opcode::LStore) => LStoreW { index: decoder.read()? },
+                    Some(Opcode::Ret) => RetW { index: decoder.read()? },
+                    Some(Opcode::IInc) => IIncW {
                         index: decoder.read()?,
                         value: decoder.read()?,
                     },
                     _ => return Err(DecodeError::from_decoder(DecodeErrorKind::InvalidInstruction, decoder)),
                 }
             }
-            _ => return Err(DecodeError::from_decoder(DecodeErrorKind::InvalidInstruction, decoder)),
         };
         Ok(instruction)
     }
@@ -0,0 +1,651 @@
+use crate::descriptor::{BaseType, MethodDescriptor, TypeDescriptor};
+use crate::error::*;
+use crate::reader::attributes::code::{self, RawInstruction};
+use crate::reader::cpool::{self, value, ConstantPool};
+use crate::{MStr, Opcode};
+
+/// How an instruction changes the operand stack, counted in slots where `long` and `double` values take up two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackEffect {
+    pub popped: u16,
+    pub pushed: u16,
+}
+
+impl<'input> RawInstruction<'input> {
+    /// The opcode of the instruction.
+    ///
+    /// The wide forms return the opcode they modify, as in `iload` for `wide iload`.
+    #[must_use]
+    pub fn opcode(&self) -> Opcode {
+        use RawInstruction::*;
+
+        match self {
+            AALoad => Opcode::AALoad,
+            AAStore => Opcode::AAStore,
+            AConstNull => Opcode::AConstNull,
+            ALoad { .. } | ALoadW { .. } => Opcode::ALoad,
+            ALoad0 => Opcode::ALoad0,
+            ALoad1 => Opcode::ALoad1,
+            ALoad2 => Opcode::ALoad2,
+            ALoad3 => Opcode::ALoad3,
+            ANewArray { .. } => Opcode::ANewArray,
+            AReturn => Opcode::AReturn,
+            ArrayLength => Opcode::ArrayLength,
+            AStore { .. } | AStoreW { .. } => Opcode::AStore,
+            AStore0 => Opcode::AStore0,
+            AStore1 => Opcode::AStore1,
+            AStore2 => Opcode::AStore2,
+            AStore3 => Opcode::AStore3,
+            AThrow => Opcode::AThrow,
+            BALoad => Opcode::BALoad,
+            BAStore => Opcode::BAStore,
+            BIPush { .. } => Opcode::BIPush,
+            CALoad => Opcode::CALoad,
+            CAStore => Opcode::CAStore,
+            CheckCast { .. } => Opcode::CheckCast,
+            D2F => Opcode::D2F,
+            D2I => Opcode::D2I,
+            D2L => Opcode::D2L,
+            DAdd => Opcode::DAdd,
+            DALoad => Opcode::DALoad,
+            DAStore => Opcode::DAStore,
+            DCmpG => Opcode::DCmpG,
+            DCmpL => Opcode::DCmpL,
+            DConst0 => Opcode::DConst0,
+            DConst1 => Opcode::DConst1,
+            DDiv => Opcode::DDiv,
+            DLoad { .. } | DLoadW { .. } => Opcode::DLoad,
+            DLoad0 => Opcode::DLoad0,
+            DLoad1 => Opcode::DLoad1,
+            DLoad2 => Opcode::DLoad2,
+            DLoad3 => Opcode::DLoad3,
+            DMul => Opcode::DMul,
+            DNeg => Opcode::DNeg,
+            DRem => Opcode::DRem,
+            DReturn => Opcode::DReturn,
+            DStore { .. } | DStoreW { .. } => Opcode::DStore,
+            DStore0 => Opcode::DStore0,
+            DStore1 => Opcode::DStore1,
+            DStore2 => Opcode::DStore2,
+            DStore3 => Opcode::DStore3,
+            DSub => Opcode::DSub,
+            Dup => Opcode::Dup,
+            DupX1 => Opcode::DupX1,
+            DupX2 => Opcode::DupX2,
+            Dup2 => Opcode::Dup2,
+            Dup2X1 => Opcode::Dup2X1,
+            Dup2X2 => Opcode::Dup2X2,
+            F2D => Opcode::F2D,
+            F2I => Opcode::F2I,
+            F2L => Opcode::F2L,
+            FAdd => Opcode::FAdd,
+            FALoad => Opcode::FALoad,
+            FAStore => Opcode::FAStore,
+            FCmpG => Opcode::FCmpG,
+            FCmpL => Opcode::FCmpL,
+            FConst0 => Opcode::FConst0,
+            FConst1 => Opcode::FConst1,
+            FConst2 => Opcode::FConst2,
+            FDiv => Opcode::FDiv,
+            FLoad { .. } | FLoadW { .. } => Opcode::FLoad,
+            FLoad0 => Opcode::FLoad0,
+            FLoad1 => Opcode::FLoad1,
+            FLoad2 => Opcode::FLoad2,
+            FLoad3 => Opcode::FLoad3,
+            FMul => Opcode::FMul,
+            FNeg => Opcode::FNeg,
+            FRem => Opcode::FRem,
+            FReturn => Opcode::FReturn,
+            FStore { .. } | FStoreW { .. } => Opcode::FStore,
+            FStore0 => Opcode::FStore0,
+            FStore1 => Opcode::FStore1,
+            FStore2 => Opcode::FStore2,
+            FStore3 => Opcode::FStore3,
+            FSub => Opcode::FSub,
+            GetField { .. } => Opcode::GetField,
+            GetStatic { .. } => Opcode::GetStatic,
+            Goto { .. } => Opcode::Goto,
+            GotoW { .. } => Opcode::GotoW,
+            I2B => Opcode::I2B,
+            I2C => Opcode::I2C,
+            I2D => Opcode::I2D,
+            I2F => Opcode::I2F,
+            I2L => Opcode::I2L,
+            I2S => Opcode::I2S,
+            IAdd => Opcode::IAdd,
+            IALoad => Opcode::IALoad,
+            IAnd => Opcode::IAnd,
+            IAStore => Opcode::IAStore,
+            IConstM1 => Opcode::IConstM1,
+            IConst0 => Opcode::IConst0,
+            IConst1 => Opcode::IConst1,
+            IConst2 => Opcode::IConst2,
+            IConst3 => Opcode::IConst3,
+            IConst4 => Opcode::IConst4,
+            IConst5 => Opcode::IConst5,
+            IDiv => Opcode::IDiv,
+            IfACmpEq { .. } => Opcode::IfACmpEq,
+            IfACmpNe { .. } => Opcode::IfACmpNe,
+            IfICmpEq { .. } => Opcode::IfICmpEq,
+            IfICmpNe { .. } => Opcode::IfICmpNe,
+            IfICmpLt { .. } => Opcode::IfICmpLt,
+            IfICmpGe { .. } => Opcode::IfICmpGe,
+            IfICmpGt { .. } => Opcode::IfICmpGt,
+            IfICmpLe { .. } => Opcode::IfICmpLe,
+            IfEq { .. } => Opcode::IfEq,
+            IfNe { .. } => Opcode::IfNe,
+            IfLt { .. } => Opcode::IfLt,
+            IfGe { .. } => Opcode::IfGe,
+            IfGt { .. } => Opcode::IfGt,
+            IfLe { .. } => Opcode::IfLe,
+            IfNonNull { .. } => Opcode::IfNonNull,
+            IfNull { .. } => Opcode::IfNull,
+            IInc { .. } | IIncW { .. } => Opcode::IInc,
+            ILoad { .. } | ILoadW { .. } => Opcode::ILoad,
+            ILoad0 => Opcode::ILoad0,
+            ILoad1 => Opcode::ILoad1,
+            ILoad2 => Opcode::ILoad2,
+            ILoad3 => Opcode::ILoad3,
+            IMul => Opcode::IMul,
+            INeg => Opcode::INeg,
+            InstanceOf { .. } => Opcode::InstanceOf,
+            InvokeDynamic { .. } => Opcode::InvokeDynamic,
+            InvokeInterface { .. } => Opcode::InvokeInterface,
+            InvokeSpecial { .. } => Opcode::InvokeSpecial,
+            InvokeStatic { .. } => Opcode::InvokeStatic,
+            InvokeVirtual { .. } => Opcode::InvokeVirtual,
+            IOr => Opcode::IOr,
+            IRem => Opcode::IRem,
+            IReturn => Opcode::IReturn,
+            IShL => Opcode::IShL,
+            IShR => Opcode::IShR,
+            IStore { .. } | IStoreW { .. } => Opcode::IStore,
+            IStore0 => Opcode::IStore0,
+            IStore1 => Opcode::IStore1,
+            IStore2 => Opcode::IStore2,
+            IStore3 => Opcode::IStore3,
+            ISub => Opcode::ISub,
+            IUShR => Opcode::IUShR,
+            IXor => Opcode::IXor,
+            JSr { .. } => Opcode::JSr,
+            JSrW { .. } => Opcode::JSrW,
+            L2D => Opcode::L2D,
+            L2F => Opcode::L2F,
+            L2I => Opcode::L2I,
+            LAdd => Opcode::LAdd,
+            LALoad => Opcode::LALoad,
+            LAnd => Opcode::LAnd,
+            LAStore => Opcode::LAStore,
+            LCmp => Opcode::LCmp,
+            LConst0 => Opcode::LConst0,
+            LConst1 => Opcode::LConst1,
+            LdC { .. } => Opcode::LdC,
+            LdCW { .. } => Opcode::LdCW,
+            LdC2W { .. } => Opcode::LdC2W,
+            LDiv => Opcode::LDiv,
+            LLoad { .. } | LLoadW { .. } => Opcode::LLoad,
+            LLoad0 => Opcode::LLoad0,
+            LLoad1 => Opcode::LLoad1,
+            LLoad2 => Opcode::LLoad2,
+            LLoad3 => Opcode::LLoad3,
+            LMul => Opcode::LMul,
+            LNeg => Opcode::LNeg,
+            LookupSwitch(_) => Opcode::LookupSwitch,
+            LOr => Opcode::LOr,
+            LRem => Opcode::LRem,
+            LReturn => Opcode::LReturn,
+            LShL => Opcode::LShL,
+            LShR => Opcode::LShR,
+            LStore { .. } | LStoreW { .. } => Opcode::LStore,
+            LStore0 => Opcode::LStore0,
+            LStore1 => Opcode::LStore1,
+            LStore2 => Opcode::LStore2,
+            LStore3 => Opcode::LStore3,
+            LSub => Opcode::LSub,
+            LUShR => Opcode::LUShR,
+            LXor => Opcode::LXor,
+            MonitorEnter => Opcode::MonitorEnter,
+            MonitorExit => Opcode::MonitorExit,
+            MultiANewArray { .. } => Opcode::MultiANewArray,
+            New { .. } => Opcode::New,
+            NewArray { .. } => Opcode::NewArray,
+            Nop => Opcode::Nop,
+            Pop => Opcode::Pop,
+            Pop2 => Opcode::Pop2,
+            PutField { .. } => Opcode::PutField,
+            PutStatic { .. } => Opcode::PutStatic,
+            Ret { .. } | RetW { .. } => Opcode::Ret,
+            Return => Opcode::Return,
+            SALoad => Opcode::SALoad,
+            SAStore => Opcode::SAStore,
+            SIPush { .. } => Opcode::SIPush,
+            Swap => Opcode::Swap,
+            TableSwitch(_) => Opcode::TableSwitch,
+        }
+    }
+
+    /// The name of the instruction as used by the specification, which is the same for the wide forms.
+    #[must_use]
+    pub fn mnemonic(&self) -> &'static str {
+        self.opcode().mnemonic()
+    }
+
+    /// The number of bytes the instruction takes up when it starts at `position`,
+    /// which only matters for the padding of switches.
+    #[must_use]
+    pub fn encoded_length(&self, position: code::Index) -> u32 {
+        use RawInstruction::*;
+
+        let padding = 3 - (position.as_u32() & 3);
+        match self {
+            IIncW { .. } => 6,
+            ALoadW { .. }
+            | AStoreW { .. }
+            | DLoadW { .. }
+            | DStoreW { .. }
+            | FLoadW { .. }
+            | FStoreW { .. }
+            | ILoadW { .. }
+            | IStoreW { .. }
+            | LLoadW { .. }
+            | LStoreW { .. }
+            | RetW { .. } => 4,
+            InvokeInterface { .. } | InvokeDynamic { .. } | GotoW { .. } | JSrW { .. } => 5,
+            MultiANewArray { .. } => 4,
+            ANewArray { .. }
+            | CheckCast { .. }
+            | GetField { .. }
+            | GetStatic { .. }
+            | InstanceOf { .. }
+            | InvokeSpecial { .. }
+            | InvokeStatic { .. }
+            | InvokeVirtual { .. }
+            | LdCW { .. }
+            | LdC2W { .. }
+            | New { .. }
+            | PutField { .. }
+            | PutStatic { .. }
+            | SIPush { .. }
+            | IInc { .. } => 3,
+            Goto { .. }
+            | JSr { .. }
+            | IfACmpEq { .. }
+            | IfACmpNe { .. }
+            | IfICmpEq { .. }
+            | IfICmpNe { .. }
+            | IfICmpLt { .. }
+            | IfICmpGe { .. }
+            | IfICmpGt { .. }
+            | IfICmpLe { .. }
+            | IfEq { .. }
+            | IfNe { .. }
+            | IfLt { .. }
+            | IfGe { .. }
+            | IfGt { .. }
+            | IfLe { .. }
+            | IfNonNull { .. }
+            | IfNull { .. } => 3,
+            ALoad { .. }
+            | AStore { .. }
+            | DLoad { .. }
+            | DStore { .. }
+            | FLoad { .. }
+            | FStore { .. }
+            | ILoad { .. }
+            | IStore { .. }
+            | LLoad { .. }
+            | LStore { .. }
+            | Ret { .. }
+            | BIPush { .. }
+            | LdC { .. }
+            | NewArray { .. } => 2,
+            LookupSwitch(switch) => 1 + padding + 8 + 8 * switch.pairs().count() as u32,
+            TableSwitch(switch) => {
+                let count = (i64::from(switch.high()) - i64::from(switch.low()) + 1) as u32;
+                1 + padding + 12 + 4 * count
+            }
+            _ => 1,
+        }
+    }
+
+    /// Returns how the instruction changes the operand stack.
+    ///
+    /// The descriptors of fields, methods and call sites are looked up in the constant pool.
+    /// Like for the verifier, `jsr` pushes the return address.
+    pub fn stack_effect(&self, pool: &ConstantPool<'input>) -> Result<StackEffect, DecodeError> {
+        let descriptor = match self.member_index() {
+            Some(index) => Some(member_descriptor(pool, index)?),
+            None => None,
+        };
+        self.stack_effect_with(descriptor)
+    }
+
+    /// The index into the constant pool of the field, method or call site whose descriptor the stack effect depends on.
+    pub(crate) fn member_index(&self) -> Option<u16> {
+        use RawInstruction::*;
+
+        match self {
+            GetField { index } | GetStatic { index } | PutField { index } | PutStatic { index } => Some(index.as_u16()),
+            InvokeSpecial { index } | InvokeStatic { index } => Some(index.as_u16()),
+            InvokeVirtual { index } => Some(index.as_u16()),
+            InvokeInterface { index, .. } => Some(index.as_u16()),
+            InvokeDynamic { index } => Some(index.as_u16()),
+            _ => None,
+        }
+    }
+
+    /// The first local variable slot accessed by the instruction and the amount of slots accessed.
+    pub(crate) fn local_variable(&self) -> Option<(u16, u16)> {
+        use RawInstruction::*;
+
+        Some(match *self {
+            ALoad0 | AStore0 | FLoad0 | FStore0 | ILoad0 | IStore0 => (0, 1),
+            ALoad1 | AStore1 | FLoad1 | FStore1 | ILoad1 | IStore1 => (1, 1),
+            ALoad2 | AStore2 | FLoad2 | FStore2 | ILoad2 | IStore2 => (2, 1),
+            ALoad3 | AStore3 | FLoad3 | FStore3 | ILoad3 | IStore3 => (3, 1),
+            DLoad0 | DStore0 | LLoad0 | LStore0 => (0, 2),
+            DLoad1 | DStore1 | LLoad1 | LStore1 => (1, 2),
+            DLoad2 | DStore2 | LLoad2 | LStore2 => (2, 2),
+            DLoad3 | DStore3 | LLoad3 | LStore3 => (3, 2),
+            ALoad { index }
+            | AStore { index }
+            | FLoad { index }
+            | FStore { index }
+            | ILoad { index }
+            | IStore { index }
+            | IInc { index, .. }
+            | Ret { index } => (index.into(), 1),
+            ALoadW { index }
+            | AStoreW { index }
+            | FLoadW { index }
+            | FStoreW { index }
+            | ILoadW { index }
+            | IStoreW { index }
+            | IIncW { index, .. }
+            | RetW { index } => (index, 1),
+            DLoad { index } | DStore { index } | LLoad { index } | LStore { index } => (index.into(), 2),
+            DLoadW { index } | DStoreW { index } | LLoadW { index } | LStoreW { index } => (index, 2),
+            _ => return None,
+        })
+    }
+
+    /// Returns the stack effect, given the descriptor of the member referenced by the instruction if there is one.
+    pub(crate) fn stack_effect_with(&self, descriptor: Option<&MStr>) -> Result<StackEffect, DecodeError> {
+        use RawInstruction::*;
+
+        let (popped, pushed) = match self {
+            Nop | IInc { .. } | IIncW { .. } | Goto { .. } | GotoW { .. } | Ret { .. } | RetW { .. } | Return => (0, 0),
+            AConstNull | IConstM1 | IConst0 | IConst1 | IConst2 | IConst3 | IConst4 | IConst5 => (0, 1),
+            FConst0 | FConst1 | FConst2 | BIPush { .. } | SIPush { .. } | LdC { .. } | LdCW { .. } => (0, 1),
+            ALoad { .. } | ALoadW { .. } | ALoad0 | ALoad1 | ALoad2 | ALoad3 => (0, 1),
+            ILoad { .. } | ILoadW { .. } | ILoad0 | ILoad1 | ILoad2 | ILoad3 => (0, 1),
+            FLoad { .. } | FLoadW { .. } | FLoad0 | FLoad1 | FLoad2 | FLoad3 => (0, 1),
+            New { .. } | JSr { .. } | JSrW { .. } => (0, 1),
+            LConst0 | LConst1 | DConst0 | DConst1 | LdC2W { .. } => (0, 2),
+            LLoad { .. } | LLoadW { .. } | LLoad0 | LLoad1 | LLoad2 | LLoad3 => (0, 2),
+            DLoad { .. } | DLoadW { .. } | DLoad0 | DLoad1 | DLoad2 | DLoad3 => (0, 2),
+            AStore { .. } | AStoreW { .. } | AStore0 | AStore1 | AStore2 | AStore3 => (1, 0),
+            IStore { .. } | IStoreW { .. } | IStore0 | IStore1 | IStore2 | IStore3 => (1, 0),
+            FStore { .. } | FStoreW { .. } | FStore0 | FStore1 | FStore2 | FStore3 => (1, 0),
+            IfEq { .. } | IfNe { .. } | IfLt { .. } | IfGe { .. } | IfGt { .. } | IfLe { .. } => (1, 0),
+            IfNull { .. } | IfNonNull { .. } | LookupSwitch(_) | TableSwitch(_) => (1, 0),
+            IReturn | FReturn | AReturn | AThrow | MonitorEnter | MonitorExit | Pop => (1, 0),
+            INeg | FNeg | I2F | F2I | I2B | I2C | I2S => (1, 1),
+            ArrayLength | InstanceOf { .. } | CheckCast { .. } | NewArray { .. } | ANewArray { .. } => (1, 1),
+            I2L | I2D | F2L | F2D => (1, 2),
+            Dup => (1, 2),
+            LStore { .. } | LStoreW { .. } | LStore0 | LStore1 | LStore2 | LStore3 => (2, 0),
+            DStore { .. } | DStoreW { .. } | DStore0 | DStore1 | DStore2 | DStore3 => (2, 0),
+            IfICmpEq { .. }
+            | IfICmpNe { .. }
+            | IfICmpLt { .. }
+            | IfICmpGe { .. }
+            | IfICmpGt { .. }
+            | IfICmpLe { .. } => (2, 0),
+            IfACmpEq { .. } | IfACmpNe { .. } | LReturn | DReturn | Pop2 => (2, 0),
+            AALoad | BALoad | CALoad | SALoad | IALoad | FALoad => (2, 1),
+            IAdd | ISub | IMul | IDiv | IRem | IAnd | IOr | IXor | IShL | IShR | IUShR => (2, 1),
+            FAdd | FSub | FMul | FDiv | FRem | FCmpL | FCmpG => (2, 1),
+            L2I | L2F | D2I | D2F => (2, 1),
+            LNeg | DNeg | L2D | D2L | LALoad | DALoad | Swap => (2, 2),
+            DupX1 => (2, 3),
+            Dup2 => (2, 4),
+            LShL | LShR | LUShR => (3, 2),
+            AAStore | BAStore | CAStore | SAStore | IAStore | FAStore => (3, 0),
+            DupX2 => (3, 4),
+            Dup2X1 => (3, 5),
+            LAStore | DAStore => (4, 0),
+            LCmp | DCmpL | DCmpG => (4, 1),
+            LAdd | LSub | LMul | LDiv | LRem | LAnd | LOr | LXor => (4, 2),
+            DAdd | DSub | DMul | DDiv | DRem => (4, 2),
+            Dup2X2 => (4, 6),
+            MultiANewArray { dimensions, .. } => (u16::from(*dimensions), 1),
+            GetStatic { .. } => (0, field_size(descriptor)?),
+            PutStatic { .. } => (field_size(descriptor)?, 0),
+            GetField { .. } => (1, field_size(descriptor)?),
+            PutField { .. } => (1 + field_size(descriptor)?, 0),
+            InvokeVirtual { .. } | InvokeSpecial { .. } | InvokeInterface { .. } => {
+                let (parameters, returned) = method_sizes(descriptor)?;
+                (1 + parameters, returned)
+            }
+            InvokeStatic { .. } | InvokeDynamic { .. } => method_sizes(descriptor)?,
+        };
+        Ok(StackEffect { popped, pushed })
+    }
+
+    /// Whether the instruction may throw an exception, apart from errors of the virtual machine itself.
+    ///
+    /// This is answered conservatively: `ldc` may fail to resolve its constant
+    /// and returning may throw an `IllegalMonitorStateException`.
+    #[must_use]
+    pub fn can_throw(&self) -> bool {
+        use RawInstruction::*;
+
+        matches!(
+            self,
+            AALoad
+                | AAStore
+                | BALoad
+                | BAStore
+                | CALoad
+                | CAStore
+                | DALoad
+                | DAStore
+                | FALoad
+                | FAStore
+                | IALoad
+                | IAStore
+                | LALoad
+                | LAStore
+                | SALoad
+                | SAStore
+                | IDiv
+                | IRem
+                | LDiv
+                | LRem
+                | ANewArray { .. }
+                | NewArray { .. }
+                | MultiANewArray { .. }
+                | ArrayLength
+                | New { .. }
+                | CheckCast { .. }
+                | InstanceOf { .. }
+                | GetField { .. }
+                | GetStatic { .. }
+                | PutField { .. }
+                | PutStatic { .. }
+                | InvokeDynamic { .. }
+                | InvokeInterface { .. }
+                | InvokeSpecial { .. }
+                | InvokeStatic { .. }
+                | InvokeVirtual { .. }
+                | LdC { .. }
+                | LdCW { .. }
+                | LdC2W { .. }
+                | MonitorEnter
+                | MonitorExit
+                | AThrow
+                | AReturn
+                | DReturn
+                | FReturn
+                | IReturn
+                | LReturn
+                | Return
+        )
+    }
+
+    /// Whether the instruction transfers control somewhere else than the next instruction,
+    /// so that it is the last one of a basic block.
+    #[must_use]
+    pub fn ends_block(&self) -> bool {
+        use RawInstruction::*;
+
+        matches!(
+            self,
+            Goto { .. }
+                | GotoW { .. }
+                | JSr { .. }
+                | JSrW { .. }
+                | Ret { .. }
+                | RetW { .. }
+                | IfACmpEq { .. }
+                | IfACmpNe { .. }
+                | IfICmpEq { .. }
+                | IfICmpNe { .. }
+                | IfICmpLt { .. }
+                | IfICmpGe { .. }
+                | IfICmpGt { .. }
+                | IfICmpLe { .. }
+                | IfEq { .. }
+                | IfNe { .. }
+                | IfLt { .. }
+                | IfGe { .. }
+                | IfGt { .. }
+                | IfLe { .. }
+                | IfNonNull { .. }
+                | IfNull { .. }
+                | LookupSwitch(_)
+                | TableSwitch(_)
+                | AReturn
+                | DReturn
+                | FReturn
+                | IReturn
+                | LReturn
+                | Return
+                | AThrow
+        )
+    }
+
+    /// Whether execution may continue with the next instruction.
+    ///
+    /// This is the case for `jsr`, as the subroutine returns to the instruction after it.
+    #[must_use]
+    pub fn falls_through(&self) -> bool {
+        self.opcode().falls_through()
+    }
+}
+
+/// Returns the descriptor of a field or method reference or of a call site.
+fn member_descriptor<'input>(pool: &ConstantPool<'input>, index: u16) -> Result<&'input MStr, DecodeError> {
+    Ok(member_name_and_type(pool, index)?.descriptor)
+}
+
+/// Returns the name and the descriptor of a field or method reference or of a call site.
+pub(crate) fn member_name_and_type<'input>(
+    pool: &ConstantPool<'input>,
+    index: u16,
+) -> Result<value::NameAndType<'input>, DecodeError> {
+    let name_and_type = match pool.get(cpool::Index::<cpool::Item<'input>>::new(index)?)? {
+        cpool::Item::FieldRef(field) => field.name_and_type,
+        cpool::Item::MethodRef(method) => method.name_and_type,
+        cpool::Item::InterfaceMethodRef(method) => method.name_and_type,
+        cpool::Item::InvokeDynamic(invoke_dynamic) => invoke_dynamic.name_and_type,
+        _ => return Err(DecodeError::with_context(DecodeErrorKind::TagMismatch, Context::Code)),
+    };
+    pool.retrieve(name_and_type)
+}
+
+fn missing_descriptor() -> DecodeError {
+    DecodeError::with_context(DecodeErrorKind::InvalidDescriptor, Context::Code)
+}
+
+fn type_size(descriptor: &TypeDescriptor<'_>) -> u16 {
+    match descriptor.base {
+        BaseType::Long | BaseType::Double if descriptor.dimensions == 0 => 2,
+        _ => 1,
+    }
+}
+
+fn field_size(descriptor: Option<&MStr>) -> Result<u16, DecodeError> {
+    let descriptor = TypeDescriptor::parse(descriptor.ok_or_else(missing_descriptor)?)?;
+    Ok(type_size(&descriptor))
+}
+
+/// Returns the slots taken up by the parameters and by the return value of a method.
+fn method_sizes(descriptor: Option<&MStr>) -> Result<(u16, u16), DecodeError> {
+    let descriptor = MethodDescriptor::parse(descriptor.ok_or_else(missing_descriptor)?)?;
+    let parameters = descriptor.parameters().map(|parameter| type_size(&parameter)).sum();
+    let returned = descriptor
+        .return_type()
+        .map_or(0, |return_type| type_size(&return_type));
+    Ok((parameters, returned))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assembler::assemble;
+    use crate::reader::attributes::AttributeContent;
+    use crate::reader::Class;
+
+    #[test]
+    fn describes_instructions() {
+        let bytes = assemble(concat!(
+            ".class public super A\n",
+            ".super java/lang/Object\n",
+            ".method m (JD)I\n",
+            "    .limit stack 7\n",
+            "    .limit locals 300\n",
+            "    aload_0\n",
+            "    lload_1\n",
+            "    dload_3\n",
+            "    invokevirtual A/m(JD)I\n",
+            "    lookupswitch\n",
+            "        1 : a\n",
+            "        default : a\n",
+            "a:  wide iinc 299 1\n",
+            "    iconst_0\n",
+            "    ireturn\n",
+            ".end method\n",
+        ))
+        .unwrap();
+        let class = Class::new(&bytes).unwrap();
+        let method = class.methods().iter().next().unwrap().unwrap();
+        let attribute = method.attributes().iter().next().unwrap().unwrap();
+        let Ok(AttributeContent::Code(code)) = attribute.read_content(class.pool()) else {
+            panic!("expected a code attribute");
+        };
+
+        let instructions = code.raw_instructions().collect::<Result<Vec<_>, _>>().unwrap();
+        let mut end = 0;
+        for (position, instruction) in &instructions {
+            assert_eq!(position.as_u32(), end);
+            end += instruction.encoded_length(*position);
+        }
+        assert_eq!(end, code.code_length());
+
+        let (_, invoke) = &instructions[3];
+        assert_eq!(invoke.opcode(), Opcode::InvokeVirtual);
+        assert_eq!(
+            invoke.stack_effect(class.pool()).unwrap(),
+            StackEffect { popped: 5, pushed: 1 }
+        );
+        assert!(invoke.can_throw() && !invoke.ends_block() && invoke.falls_through());
+
+        let (_, switch) = &instructions[4];
+        assert!(switch.ends_block() && !switch.falls_through());
+        let (_, iinc) = &instructions[5];
+        assert_eq!(iinc.mnemonic(), "iinc");
+        assert!(!iinc.can_throw());
+    }
+}
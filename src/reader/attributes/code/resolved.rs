@@ -0,0 +1,783 @@
+use crate::error::*;
+use crate::reader::attributes::code::{self, ArrayType, RawInstruction, RawInstructions};
+use crate::reader::cpool::{value, ConstantPool};
+use std::fmt;
+
+/// An iterator over the instructions of a code table, with their operands resolved.
+#[derive(Clone)]
+pub struct Instructions<'a, 'input> {
+    pub(crate) raw_instructions: RawInstructions<'input>,
+    pub(crate) pool: &'a ConstantPool<'input>,
+}
+
+impl<'a, 'input> Iterator for Instructions<'a, 'input> {
+    type Item = Result<(code::Index, Instruction<'input>), DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (position, instruction) = match self.raw_instructions.next()? {
+            Ok(next) => next,
+            Err(err) => return Some(Err(err)),
+        };
+        Some(
+            instruction
+                .resolve(position, self.pool)
+                .map(|instruction| (position, instruction)),
+        )
+    }
+}
+
+impl<'a, 'input> fmt::Debug for Instructions<'a, 'input> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Instructions").finish()
+    }
+}
+
+/// An instruction whose jump targets are indices into the code table
+/// and whose references to the constant pool are replaced by the values they point to.
+///
+/// The variants correspond to the ones of [`RawInstruction`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction<'input> {
+    AALoad,
+    AAStore,
+    AConstNull,
+    ALoad {
+        index: u8,
+    },
+    ALoadW {
+        index: u16,
+    },
+    ALoad0,
+    ALoad1,
+    ALoad2,
+    ALoad3,
+    ANewArray {
+        class: value::Class<'input>,
+    },
+    AReturn,
+    ArrayLength,
+    AStore {
+        index: u8,
+    },
+    AStoreW {
+        index: u16,
+    },
+    AStore0,
+    AStore1,
+    AStore2,
+    AStore3,
+    AThrow,
+    BALoad,
+    BAStore,
+    BIPush {
+        value: i8,
+    },
+    CALoad,
+    CAStore,
+    CheckCast {
+        class: value::Class<'input>,
+    },
+    D2F,
+    D2I,
+    D2L,
+    DAdd,
+    DALoad,
+    DAStore,
+    DCmpG,
+    DCmpL,
+    DConst0,
+    DConst1,
+    DDiv,
+    DLoad {
+        index: u8,
+    },
+    DLoadW {
+        index: u16,
+    },
+    DLoad0,
+    DLoad1,
+    DLoad2,
+    DLoad3,
+    DMul,
+    DNeg,
+    DRem,
+    DReturn,
+    DStore {
+        index: u8,
+    },
+    DStoreW {
+        index: u16,
+    },
+    DStore0,
+    DStore1,
+    DStore2,
+    DStore3,
+    DSub,
+    Dup,
+    DupX1,
+    DupX2,
+    Dup2,
+    Dup2X1,
+    Dup2X2,
+    F2D,
+    F2I,
+    F2L,
+    FAdd,
+    FALoad,
+    FAStore,
+    FCmpG,
+    FCmpL,
+    FConst0,
+    FConst1,
+    FConst2,
+    FDiv,
+    FLoad {
+        index: u8,
+    },
+    FLoadW {
+        index: u16,
+    },
+    FLoad0,
+    FLoad1,
+    FLoad2,
+    FLoad3,
+    FMul,
+    FNeg,
+    FRem,
+    FReturn,
+    FStore {
+        index: u8,
+    },
+    FStoreW {
+        index: u16,
+    },
+    FStore0,
+    FStore1,
+    FStore2,
+    FStore3,
+    FSub,
+    GetField {
+        field: value::FieldRef<'input>,
+    },
+    GetStatic {
+        field: value::FieldRef<'input>,
+    },
+    Goto {
+        target: code::Index,
+    },
+    GotoW {
+        target: code::Index,
+    },
+    I2B,
+    I2C,
+    I2D,
+    I2F,
+    I2L,
+    I2S,
+    IAdd,
+    IALoad,
+    IAnd,
+    IAStore,
+    IConstM1,
+    IConst0,
+    IConst1,
+    IConst2,
+    IConst3,
+    IConst4,
+    IConst5,
+    IDiv,
+    IfACmpEq {
+        target: code::Index,
+    },
+    IfACmpNe {
+        target: code::Index,
+    },
+    IfICmpEq {
+        target: code::Index,
+    },
+    IfICmpNe {
+        target: code::Index,
+    },
+    IfICmpLt {
+        target: code::Index,
+    },
+    IfICmpGe {
+        target: code::Index,
+    },
+    IfICmpGt {
+        target: code::Index,
+    },
+    IfICmpLe {
+        target: code::Index,
+    },
+    IfEq {
+        target: code::Index,
+    },
+    IfNe {
+        target: code::Index,
+    },
+    IfLt {
+        target: code::Index,
+    },
+    IfGe {
+        target: code::Index,
+    },
+    IfGt {
+        target: code::Index,
+    },
+    IfLe {
+        target: code::Index,
+    },
+    IfNonNull {
+        target: code::Index,
+    },
+    IfNull {
+        target: code::Index,
+    },
+    IInc {
+        index: u8,
+        value: i8,
+    },
+    IIncW {
+        index: u16,
+        value: i16,
+    },
+    ILoad {
+        index: u8,
+    },
+    ILoadW {
+        index: u16,
+    },
+    ILoad0,
+    ILoad1,
+    ILoad2,
+    ILoad3,
+    IMul,
+    INeg,
+    InstanceOf {
+        class: value::Class<'input>,
+    },
+    InvokeDynamic {
+        call_site: value::InvokeDynamic<'input>,
+    },
+    InvokeInterface {
+        method: value::InterfaceMethodRef<'input>,
+        count: u8,
+    },
+    InvokeSpecial {
+        method: value::Item<'input>,
+    },
+    InvokeStatic {
+        method: value::Item<'input>,
+    },
+    InvokeVirtual {
+        method: value::MethodRef<'input>,
+    },
+    IOr,
+    IRem,
+    IReturn,
+    IShL,
+    IShR,
+    IStore {
+        index: u8,
+    },
+    IStoreW {
+        index: u16,
+    },
+    IStore0,
+    IStore1,
+    IStore2,
+    IStore3,
+    ISub,
+    IUShR,
+    IXor,
+    JSr {
+        target: code::Index,
+    },
+    JSrW {
+        target: code::Index,
+    },
+    L2D,
+    L2F,
+    L2I,
+    LAdd,
+    LALoad,
+    LAnd,
+    LAStore,
+    LCmp,
+    LConst0,
+    LConst1,
+    LdC {
+        constant: value::Item<'input>,
+    },
+    LdCW {
+        constant: value::Item<'input>,
+    },
+    LdC2W {
+        constant: value::Item<'input>,
+    },
+    LDiv,
+    LLoad {
+        index: u8,
+    },
+    LLoadW {
+        index: u16,
+    },
+    LLoad0,
+    LLoad1,
+    LLoad2,
+    LLoad3,
+    LMul,
+    LNeg,
+    LookupSwitch {
+        default: code::Index,
+        pairs: Vec<(i32, code::Index)>,
+    },
+    LOr,
+    LRem,
+    LReturn,
+    LShL,
+    LShR,
+    LStore {
+        index: u8,
+    },
+    LStoreW {
+        index: u16,
+    },
+    LStore0,
+    LStore1,
+    LStore2,
+    LStore3,
+    LSub,
+    LUShR,
+    LXor,
+    MonitorEnter,
+    MonitorExit,
+    MultiANewArray {
+        class: value::Class<'input>,
+        dimensions: u8,
+    },
+    New {
+        class: value::Class<'input>,
+    },
+    NewArray {
+        atype: ArrayType,
+    },
+    Nop,
+    Pop,
+    Pop2,
+    PutField {
+        field: value::FieldRef<'input>,
+    },
+    PutStatic {
+        field: value::FieldRef<'input>,
+    },
+    Ret {
+        index: u8,
+    },
+    RetW {
+        index: u16,
+    },
+    Return,
+    SALoad,
+    SAStore,
+    SIPush {
+        value: i16,
+    },
+    Swap,
+    TableSwitch {
+        low: i32,
+        high: i32,
+        default: code::Index,
+        targets: Vec<code::Index>,
+    },
+}
+
+impl<'input> RawInstruction<'input> {
+    /// Resolves the operands of an instruction starting at `position`.
+    pub fn resolve(
+        self,
+        position: code::Index,
+        pool: &ConstantPool<'input>,
+    ) -> Result<Instruction<'input>, DecodeError> {
+        use RawInstruction::*;
+
+        let instruction = match self {
+            AALoad => Instruction::AALoad,
+            AAStore => Instruction::AAStore,
+            AConstNull => Instruction::AConstNull,
+            ALoad { index } => Instruction::ALoad { index },
+            ALoadW { index } => Instruction::ALoadW { index },
+            ALoad0 => Instruction::ALoad0,
+            ALoad1 => Instruction::ALoad1,
+            ALoad2 => Instruction::ALoad2,
+            ALoad3 => Instruction::ALoad3,
+            ANewArray { index } => Instruction::ANewArray {
+                class: pool.retrieve(index)?,
+            },
+            AReturn => Instruction::AReturn,
+            ArrayLength => Instruction::ArrayLength,
+            AStore { index } => Instruction::AStore { index },
+            AStoreW { index } => Instruction::AStoreW { index },
+            AStore0 => Instruction::AStore0,
+            AStore1 => Instruction::AStore1,
+            AStore2 => Instruction::AStore2,
+            AStore3 => Instruction::AStore3,
+            AThrow => Instruction::AThrow,
+            BALoad => Instruction::BALoad,
+            BAStore => Instruction::BAStore,
+            BIPush { value } => Instruction::BIPush { value },
+            CALoad => Instruction::CALoad,
+            CAStore => Instruction::CAStore,
+            CheckCast { index } => Instruction::CheckCast {
+                class: pool.retrieve(index)?,
+            },
+            D2F => Instruction::D2F,
+            D2I => Instruction::D2I,
+            D2L => Instruction::D2L,
+            DAdd => Instruction::DAdd,
+            DALoad => Instruction::DALoad,
+            DAStore => Instruction::DAStore,
+            DCmpG => Instruction::DCmpG,
+            DCmpL => Instruction::DCmpL,
+            DConst0 => Instruction::DConst0,
+            DConst1 => Instruction::DConst1,
+            DDiv => Instruction::DDiv,
+            DLoad { index } => Instruction::DLoad { index },
+            DLoadW { index } => Instruction::DLoadW { index },
+            DLoad0 => Instruction::DLoad0,
+            DLoad1 => Instruction::DLoad1,
+            DLoad2 => Instruction::DLoad2,
+            DLoad3 => Instruction::DLoad3,
+            DMul => Instruction::DMul,
+            DNeg => Instruction::DNeg,
+            DRem => Instruction::DRem,
+            DReturn => Instruction::DReturn,
+            DStore { index } => Instruction::DStore { index },
+            DStoreW { index } => Instruction::DStoreW { index },
+            DStore0 => Instruction::DStore0,
+            DStore1 => Instruction::DStore1,
+            DStore2 => Instruction::DStore2,
+            DStore3 => Instruction::DStore3,
+            DSub => Instruction::DSub,
+            Dup => Instruction::Dup,
+            DupX1 => Instruction::DupX1,
+            DupX2 => Instruction::DupX2,
+            Dup2 => Instruction::Dup2,
+            Dup2X1 => Instruction::Dup2X1,
+            Dup2X2 => Instruction::Dup2X2,
+            F2D => Instruction::F2D,
+            F2I => Instruction::F2I,
+            F2L => Instruction::F2L,
+            FAdd => Instruction::FAdd,
+            FALoad => Instruction::FALoad,
+            FAStore => Instruction::FAStore,
+            FCmpG => Instruction::FCmpG,
+            FCmpL => Instruction::FCmpL,
+            FConst0 => Instruction::FConst0,
+            FConst1 => Instruction::FConst1,
+            FConst2 => Instruction::FConst2,
+            FDiv => Instruction::FDiv,
+            FLoad { index } => Instruction::FLoad { index },
+            FLoadW { index } => Instruction::FLoadW { index },
+            FLoad0 => Instruction::FLoad0,
+            FLoad1 => Instruction::FLoad1,
+            FLoad2 => Instruction::FLoad2,
+            FLoad3 => Instruction::FLoad3,
+            FMul => Instruction::FMul,
+            FNeg => Instruction::FNeg,
+            FRem => Instruction::FRem,
+            FReturn => Instruction::FReturn,
+            FStore { index } => Instruction::FStore { index },
+            FStoreW { index } => Instruction::FStoreW { index },
+            FStore0 => Instruction::FStore0,
+            FStore1 => Instruction::FStore1,
+            FStore2 => Instruction::FStore2,
+            FStore3 => Instruction::FStore3,
+            FSub => Instruction::FSub,
+            GetField { index } => Instruction::GetField {
+                field: pool.retrieve(index)?,
+            },
+            GetStatic { index } => Instruction::GetStatic {
+                field: pool.retrieve(index)?,
+            },
+            Goto { offset } => Instruction::Goto {
+                target: target(position, offset.into())?,
+            },
+            GotoW { offset } => Instruction::GotoW {
+                target: target(position, offset)?,
+            },
+            I2B => Instruction::I2B,
+            I2C => Instruction::I2C,
+            I2D => Instruction::I2D,
+            I2F => Instruction::I2F,
+            I2L => Instruction::I2L,
+            I2S => Instruction::I2S,
+            IAdd => Instruction::IAdd,
+            IALoad => Instruction::IALoad,
+            IAnd => Instruction::IAnd,
+            IAStore => Instruction::IAStore,
+            IConstM1 => Instruction::IConstM1,
+            IConst0 => Instruction::IConst0,
+            IConst1 => Instruction::IConst1,
+            IConst2 => Instruction::IConst2,
+            IConst3 => Instruction::IConst3,
+            IConst4 => Instruction::IConst4,
+            IConst5 => Instruction::IConst5,
+            IDiv => Instruction::IDiv,
+            IfACmpEq { offset } => Instruction::IfACmpEq {
+                target: target(position, offset.into())?,
+            },
+            IfACmpNe { offset } => Instruction::IfACmpNe {
+                target: target(position, offset.into())?,
+            },
+            IfICmpEq { offset } => Instruction::IfICmpEq {
+                target: target(position, offset.into())?,
+            },
+            IfICmpNe { offset } => Instruction::IfICmpNe {
+                target: target(position, offset.into())?,
+            },
+            IfICmpLt { offset } => Instruction::IfICmpLt {
+                target: target(position, offset.into())?,
+            },
+            IfICmpGe { offset } => Instruction::IfICmpGe {
+                target: target(position, offset.into())?,
+            },
+            IfICmpGt { offset } => Instruction::IfICmpGt {
+                target: target(position, offset.into())?,
+            },
+            IfICmpLe { offset } => Instruction::IfICmpLe {
+                target: target(position, offset.into())?,
+            },
+            IfEq { offset } => Instruction::IfEq {
+                target: target(position, offset.into())?,
+            },
+            IfNe { offset } => Instruction::IfNe {
+                target: target(position, offset.into())?,
+            },
+            IfLt { offset } => Instruction::IfLt {
+                target: target(position, offset.into())?,
+            },
+            IfGe { offset } => Instruction::IfGe {
+                target: target(position, offset.into())?,
+            },
+            IfGt { offset } => Instruction::IfGt {
+                target: target(position, offset.into())?,
+            },
+            IfLe { offset } => Instruction::IfLe {
+                target: target(position, offset.into())?,
+            },
+            IfNonNull { offset } => Instruction::IfNonNull {
+                target: target(position, offset.into())?,
+            },
+            IfNull { offset } => Instruction::IfNull {
+                target: target(position, offset.into())?,
+            },
+            IInc { index, value } => Instruction::IInc { index, value },
+            IIncW { index, value } => Instruction::IIncW { index, value },
+            ILoad { index } => Instruction::ILoad { index },
+            ILoadW { index } => Instruction::ILoadW { index },
+            ILoad0 => Instruction::ILoad0,
+            ILoad1 => Instruction::ILoad1,
+            ILoad2 => Instruction::ILoad2,
+            ILoad3 => Instruction::ILoad3,
+            IMul => Instruction::IMul,
+            INeg => Instruction::INeg,
+            InstanceOf { index } => Instruction::InstanceOf {
+                class: pool.retrieve(index)?,
+            },
+            InvokeDynamic { index } => Instruction::InvokeDynamic {
+                call_site: pool.retrieve(index)?,
+            },
+            InvokeInterface { index, count } => Instruction::InvokeInterface {
+                method: pool.retrieve(index)?,
+                count,
+            },
+            InvokeSpecial { index } => Instruction::InvokeSpecial {
+                method: pool.retrieve(index)?,
+            },
+            InvokeStatic { index } => Instruction::InvokeStatic {
+                method: pool.retrieve(index)?,
+            },
+            InvokeVirtual { index } => Instruction::InvokeVirtual {
+                method: pool.retrieve(index)?,
+            },
+            IOr => Instruction::IOr,
+            IRem => Instruction::IRem,
+            IReturn => Instruction::IReturn,
+            IShL => Instruction::IShL,
+            IShR => Instruction::IShR,
+            IStore { index } => Instruction::IStore { index },
+            IStoreW { index } => Instruction::IStoreW { index },
+            IStore0 => Instruction::IStore0,
+            IStore1 => Instruction::IStore1,
+            IStore2 => Instruction::IStore2,
+            IStore3 => Instruction::IStore3,
+            ISub => Instruction::ISub,
+            IUShR => Instruction::IUShR,
+            IXor => Instruction::IXor,
+            JSr { offset } => Instruction::JSr {
+                target: target(position, offset.into())?,
+            },
+            JSrW { offset } => Instruction::JSrW {
+                target: target(position, offset)?,
+            },
+            L2D => Instruction::L2D,
+            L2F => Instruction::L2F,
+            L2I => Instruction::L2I,
+            LAdd => Instruction::LAdd,
+            LALoad => Instruction::LALoad,
+            LAnd => Instruction::LAnd,
+            LAStore => Instruction::LAStore,
+            LCmp => Instruction::LCmp,
+            LConst0 => Instruction::LConst0,
+            LConst1 => Instruction::LConst1,
+            LdC { index } => Instruction::LdC {
+                constant: pool.retrieve(index)?,
+            },
+            LdCW { index } => Instruction::LdCW {
+                constant: pool.retrieve(index)?,
+            },
+            LdC2W { index } => Instruction::LdC2W {
+                constant: pool.retrieve(index)?,
+            },
+            LDiv => Instruction::LDiv,
+            LLoad { index } => Instruction::LLoad { index },
+            LLoadW { index } => Instruction::LLoadW { index },
+            LLoad0 => Instruction::LLoad0,
+            LLoad1 => Instruction::LLoad1,
+            LLoad2 => Instruction::LLoad2,
+            LLoad3 => Instruction::LLoad3,
+            LMul => Instruction::LMul,
+            LNeg => Instruction::LNeg,
+            LookupSwitch(switch) => Instruction::LookupSwitch {
+                default: target(position, switch.default_offset())?,
+                pairs: switch
+                    .pairs()
+                    .map(|pair| Ok((pair.key(), target(position, pair.offset())?)))
+                    .collect::<Result<_, DecodeError>>()?,
+            },
+            LOr => Instruction::LOr,
+            LRem => Instruction::LRem,
+            LReturn => Instruction::LReturn,
+            LShL => Instruction::LShL,
+            LShR => Instruction::LShR,
+            LStore { index } => Instruction::LStore { index },
+            LStoreW { index } => Instruction::LStoreW { index },
+            LStore0 => Instruction::LStore0,
+            LStore1 => Instruction::LStore1,
+            LStore2 => Instruction::LStore2,
+            LStore3 => Instruction::LStore3,
+            LSub => Instruction::LSub,
+            LUShR => Instruction::LUShR,
+            LXor => Instruction::LXor,
+            MonitorEnter => Instruction::MonitorEnter,
+            MonitorExit => Instruction::MonitorExit,
+            MultiANewArray { index, dimensions } => Instruction::MultiANewArray {
+                class: pool.retrieve(index)?,
+                dimensions,
+            },
+            New { index } => Instruction::New {
+                class: pool.retrieve(index)?,
+            },
+            NewArray { atype } => Instruction::NewArray { atype },
+            Nop => Instruction::Nop,
+            Pop => Instruction::Pop,
+            Pop2 => Instruction::Pop2,
+            PutField { index } => Instruction::PutField {
+                field: pool.retrieve(index)?,
+            },
+            PutStatic { index } => Instruction::PutStatic {
+                field: pool.retrieve(index)?,
+            },
+            Ret { index } => Instruction::Ret { index },
+            RetW { index } => Instruction::RetW { index },
+            Return => Instruction::Return,
+            SALoad => Instruction::SALoad,
+            SAStore => Instruction::SAStore,
+            SIPush { value } => Instruction::SIPush { value },
+            Swap => Instruction::Swap,
+            TableSwitch(switch) => Instruction::TableSwitch {
+                low: switch.low(),
+                high: switch.high(),
+                default: target(position, switch.default_offset())?,
+                targets: switch
+                    .pairs()
+                    .map(|pair| target(position, pair.offset()))
+                    .collect::<Result<_, DecodeError>>()?,
+            },
+        };
+        Ok(instruction)
+    }
+}
+
+/// Computes the index a jump at `position` leads to.
+pub(crate) fn target(position: code::Index, offset: i32) -> Result<code::Index, DecodeError> {
+    u32::try_from(i64::from(position.as_u32()) + i64::from(offset))
+        .map(code::Index::new)
+        .map_err(|_| DecodeError::with_context(DecodeErrorKind::InvalidInstruction, Context::Code))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assembler::assemble;
+    use crate::mutf8;
+    use crate::reader::attributes::AttributeContent;
+    use crate::reader::Class;
+
+    #[test]
+    fn resolves_operands() {
+        let bytes = assemble(concat!(
+            ".class public super A\n",
+            ".super java/lang/Object\n",
+            ".method static m (I)V\n",
+            "    .limit stack 1\n",
+            "    .limit locals 1\n",
+            "a:  ldc \"text\"\n",
+            "    invokestatic B/s(Ljava/lang/String;)V\n",
+            "    iload_0\n",
+            "    lookupswitch\n",
+            "        5 : a\n",
+            "        default : b\n",
+            "b:  goto a\n",
+            ".end method\n",
+        ))
+        .unwrap();
+        let class = Class::new(&bytes).unwrap();
+        let method = class.methods().iter().next().unwrap().unwrap();
+        let attribute = method.attributes().iter().next().unwrap().unwrap();
+        let Ok(AttributeContent::Code(code)) = attribute.read_content(class.pool()) else {
+            panic!("expected a code attribute");
+        };
+
+        let instructions = code.instructions(class.pool()).collect::<Result<Vec<_>, _>>().unwrap();
+        let start = code::Index::new(0);
+        let end = code::Index::new(24);
+        assert_eq!(
+            instructions[0],
+            (
+                start,
+                Instruction::LdC {
+                    constant: value::Item::String(value::String { string: mutf8!("text") })
+                }
+            )
+        );
+        let Instruction::InvokeStatic {
+            method: value::Item::MethodRef(method),
+        } = &instructions[1].1
+        else {
+            panic!("expected a method reference");
+        };
+        assert_eq!(method.class.name, mutf8!("B"));
+        assert_eq!(method.name_and_type.name, mutf8!("s"));
+        assert_eq!(
+            instructions[3].1,
+            Instruction::LookupSwitch {
+                default: end,
+                pairs: vec![(5, start)],
+            }
+        );
+        assert_eq!(instructions[4], (end, Instruction::Goto { target: start }));
+    }
+}
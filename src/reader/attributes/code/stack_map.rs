@@ -27,7 +27,7 @@ impl<'input> DecodeInto<'input> for StackMapTable<'input> {
             iter: StackMapIter {
                 decoder,
                 remaining: count,
-                current_offset: 0,
+                previous_offset: None,
             },
         })
     }
@@ -47,7 +47,8 @@ impl<'input> fmt::Debug for StackMapTable<'input> {
 pub struct StackMapIter<'input> {
     decoder: Decoder<'input>,
     remaining: u16,
-    current_offset: u32,
+    /// The offset of the previous frame, as the offsets of all but the first frame are relative to it.
+    previous_offset: Option<u32>,
 }
 
 impl<'input> Iterator for StackMapIter<'input> {
@@ -58,10 +59,15 @@ impl<'input> Iterator for StackMapIter<'input> {
             None
         } else {
             self.remaining -= 1;
-            let bytes_remaining = self.decoder.bytes_remaining() as u32;
-            let stack_map_frame = decode_stack_map_frame(&mut self.decoder, self.current_offset);
-            self.current_offset += bytes_remaining - self.decoder.bytes_remaining() as u32;
-            Some(stack_map_frame)
+            let frame = decode_stack_map_frame(&mut self.decoder).map(|(delta, frame)| {
+                let offset = match self.previous_offset {
+                    Some(previous) => previous + u32::from(delta) + 1,
+                    None => u32::from(delta),
+                };
+                self.previous_offset = Some(offset);
+                (code::Index::new(offset), frame)
+            });
+            Some(frame)
         }
     }
 }
@@ -96,50 +102,40 @@ pub enum StackMapFrame<'input> {
     },
 }
 
-fn decode_stack_map_frame<'input>(
-    decoder: &mut Decoder<'input>,
-    current_offset: u32,
-) -> Result<(code::Index, StackMapFrame<'input>), DecodeError> {
+/// Decodes a frame and returns its offset delta.
+fn decode_stack_map_frame<'input>(decoder: &mut Decoder<'input>) -> Result<(u16, StackMapFrame<'input>), DecodeError> {
     let frame_type: u8 = decoder.read()?;
     match frame_type {
-        0..=63 => {
-            let index = code::Index::new(frame_type.into());
-            Ok((index, StackMapFrame::Same))
-        }
+        0..=63 => Ok((frame_type.into(), StackMapFrame::Same)),
         64..=127 => {
-            let index = code::Index::new(u32::from(frame_type - 64) + current_offset);
-            let stack = decode_verification_type(decoder, current_offset)?;
-            Ok((index, StackMapFrame::Same1 { stack }))
+            let stack = decode_verification_type(decoder)?;
+            Ok(((frame_type - 64).into(), StackMapFrame::Same1 { stack }))
         }
         247 => {
-            let index = code::Index::new(u32::from(decoder.read::<u16>()?) + current_offset);
-            let stack = decode_verification_type(decoder, current_offset)?;
-            Ok((index, StackMapFrame::Same1 { stack }))
+            let delta = decoder.read()?;
+            let stack = decode_verification_type(decoder)?;
+            Ok((delta, StackMapFrame::Same1Extended { stack }))
         }
         248..=250 => {
             let to_chop = 251 - frame_type;
-            let index = code::Index::new(u32::from(decoder.read::<u16>()?) + current_offset);
-            Ok((index, StackMapFrame::Chop { to_chop }))
-        }
-        251 => {
-            let index = code::Index::new(u32::from(decoder.read::<u16>()?) + current_offset);
-            Ok((index, StackMapFrame::SameExtended))
+            Ok((decoder.read()?, StackMapFrame::Chop { to_chop }))
         }
+        251 => Ok((decoder.read()?, StackMapFrame::SameExtended)),
         252..=254 => {
-            let index = code::Index::new(u32::from(decoder.read::<u16>()?) + current_offset);
-            let locals = VerificationTypeIter::new(decoder, (frame_type - 251).into(), current_offset)?;
-            Ok((index, StackMapFrame::Append { locals }))
+            let delta = decoder.read()?;
+            let locals = VerificationTypeIter::new(decoder, (frame_type - 251).into())?;
+            Ok((delta, StackMapFrame::Append { locals }))
         }
         255 => {
-            let index = code::Index::new(u32::from(decoder.read::<u16>()?) + current_offset);
+            let delta = decoder.read()?;
 
             let local_count = decoder.read()?;
-            let locals = VerificationTypeIter::new(decoder, local_count, current_offset)?;
+            let locals = VerificationTypeIter::new(decoder, local_count)?;
 
             let stack_count = decoder.read()?;
-            let stack = VerificationTypeIter::new(decoder, stack_count, current_offset)?;
+            let stack = VerificationTypeIter::new(decoder, stack_count)?;
 
-            Ok((index, StackMapFrame::Full { locals, stack }))
+            Ok((delta, StackMapFrame::Full { locals, stack }))
         }
         _ => Err(DecodeError::from_decoder(DecodeErrorKind::TagReserved, decoder)),
     }
@@ -151,6 +147,7 @@ pub enum VerificationType<'input> {
     Null,
     UninitializedThis,
     Object(cpool::Index<cpool::Class<'input>>),
+    /// An object created by the `new` instruction at the index, whose constructor wasn't called yet.
     UninitializedVariable(code::Index),
     Integer,
     Long,
@@ -158,10 +155,7 @@ pub enum VerificationType<'input> {
     Double,
 }
 
-fn decode_verification_type<'input>(
-    decoder: &mut Decoder<'input>,
-    current_offset: u32,
-) -> Result<VerificationType<'input>, DecodeError> {
+fn decode_verification_type<'input>(decoder: &mut Decoder<'input>) -> Result<VerificationType<'input>, DecodeError> {
     let tag: u8 = decoder.read()?;
     match tag {
         0x00 => Ok(VerificationType::Top),
@@ -173,7 +167,7 @@ fn decode_verification_type<'input>(
         0x06 => Ok(VerificationType::UninitializedThis),
         0x07 => Ok(VerificationType::Object(decoder.read()?)),
         0x08 => {
-            let index = code::Index::new(current_offset + u32::from(decoder.read::<u16>()?));
+            let index = code::Index::new(decoder.read::<u16>()?.into());
             Ok(VerificationType::UninitializedVariable(index))
         }
         _ => Err(DecodeError::from_decoder(DecodeErrorKind::InvalidTag, decoder)),
@@ -200,15 +194,10 @@ fn skip_verification_type<'input>(decoder: &mut Decoder<'input>) -> Result<(), D
 pub struct VerificationTypeIter<'input> {
     decoder: Decoder<'input>,
     remaining: u16,
-    current_offset: u32,
 }
 
 impl<'input> VerificationTypeIter<'input> {
-    fn new(
-        decoder: &mut Decoder<'input>,
-        count: u16,
-        current_offset: u32,
-    ) -> Result<VerificationTypeIter<'input>, DecodeError> {
+    fn new(decoder: &mut Decoder<'input>, count: u16) -> Result<VerificationTypeIter<'input>, DecodeError> {
         let old_decoder = decoder.clone();
         for _ in 0..count {
             skip_verification_type(decoder)?;
@@ -216,7 +205,6 @@ impl<'input> VerificationTypeIter<'input> {
         Ok(VerificationTypeIter {
             decoder: old_decoder,
             remaining: count,
-            current_offset,
         })
     }
 }
@@ -229,10 +217,7 @@ impl<'input> Iterator for VerificationTypeIter<'input> {
             None
         } else {
             self.remaining -= 1;
-            let bytes_remaining = self.decoder.bytes_remaining() as u32;
-            let verification_type = decode_verification_type(&mut self.decoder, self.current_offset);
-            self.current_offset += bytes_remaining - self.decoder.bytes_remaining() as u32;
-            Some(verification_type)
+            Some(decode_verification_type(&mut self.decoder))
         }
     }
 }
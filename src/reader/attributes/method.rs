@@ -28,7 +28,7 @@ impl<'input> FromAttribute<'input> for MethodParameters<'input> {
 
 dec_structure! {
     pub struct MethodParameter<'input> {
-        name: cpool::Index<cpool::Utf8<'input>>,
+        name: Option<cpool::Index<cpool::Utf8<'input>>>,
         access_flags: AccessFlags,
     }
 }
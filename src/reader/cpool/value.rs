@@ -280,3 +280,64 @@ impl<'input> ToValue<'input> for Index<cpool::Package<'input>> {
         })
     }
 }
+
+/// Any item of the constant pool with its references resolved.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum Item<'input> {
+    Class(Class<'input>),
+    FieldRef(FieldRef<'input>),
+    MethodRef(MethodRef<'input>),
+    InterfaceMethodRef(InterfaceMethodRef<'input>),
+    String(String<'input>),
+    Integer(Integer),
+    Long(Long),
+    Float(Float),
+    Double(Double),
+    NameAndType(NameAndType<'input>),
+    Utf8(&'input MStr),
+    MethodHandle(MethodHandle<'input>),
+    MethodType(MethodType<'input>),
+    Dynamic(Dynamic<'input>),
+    InvokeDynamic(InvokeDynamic<'input>),
+    Module(Module<'input>),
+    Package(Package<'input>),
+}
+
+impl<'input> ToValue<'input> for Index<cpool::Item<'input>> {
+    type Target = Item<'input>;
+
+    fn retrieve_from(self, pool: &ConstantPool<'input>) -> Result<Self::Target, DecodeError> {
+        let index = self.as_u16();
+        Ok(match pool.get(self)? {
+            cpool::Item::Class(_) => Item::Class(pool.retrieve(Index::<cpool::Class<'input>>::new(index)?)?),
+            cpool::Item::FieldRef(_) => Item::FieldRef(pool.retrieve(Index::<cpool::FieldRef<'input>>::new(index)?)?),
+            cpool::Item::MethodRef(_) => {
+                Item::MethodRef(pool.retrieve(Index::<cpool::MethodRef<'input>>::new(index)?)?)
+            }
+            cpool::Item::InterfaceMethodRef(_) => {
+                Item::InterfaceMethodRef(pool.retrieve(Index::<cpool::InterfaceMethodRef<'input>>::new(index)?)?)
+            }
+            cpool::Item::String(_) => Item::String(pool.retrieve(Index::<cpool::String<'input>>::new(index)?)?),
+            cpool::Item::Integer(_) => Item::Integer(pool.retrieve(Index::<cpool::Integer>::new(index)?)?),
+            cpool::Item::Long(_) => Item::Long(pool.retrieve(Index::<cpool::Long>::new(index)?)?),
+            cpool::Item::Float(_) => Item::Float(pool.retrieve(Index::<cpool::Float>::new(index)?)?),
+            cpool::Item::Double(_) => Item::Double(pool.retrieve(Index::<cpool::Double>::new(index)?)?),
+            cpool::Item::NameAndType(_) => {
+                Item::NameAndType(pool.retrieve(Index::<cpool::NameAndType<'input>>::new(index)?)?)
+            }
+            cpool::Item::Utf8(utf8) => Item::Utf8(utf8.content),
+            cpool::Item::MethodHandle(_) => {
+                Item::MethodHandle(pool.retrieve(Index::<cpool::MethodHandle<'input>>::new(index)?)?)
+            }
+            cpool::Item::MethodType(_) => {
+                Item::MethodType(pool.retrieve(Index::<cpool::MethodType<'input>>::new(index)?)?)
+            }
+            cpool::Item::Dynamic(_) => Item::Dynamic(pool.retrieve(Index::<cpool::Dynamic<'input>>::new(index)?)?),
+            cpool::Item::InvokeDynamic(_) => {
+                Item::InvokeDynamic(pool.retrieve(Index::<cpool::InvokeDynamic<'input>>::new(index)?)?)
+            }
+            cpool::Item::Module(_) => Item::Module(pool.retrieve(Index::<cpool::Module<'input>>::new(index)?)?),
+            cpool::Item::Package(_) => Item::Package(pool.retrieve(Index::<cpool::Package<'input>>::new(index)?)?),
+        })
+    }
+}
@@ -440,7 +440,7 @@ macro_rules! dec_structure {
     };
     (@decode into => $struct_name:ident; $($field_name:ident),*) => {
         impl<'input> $crate::reader::decoding::DecodeInto<'input> for $struct_name<'input> {
-            #[allow(unused_variables, unused_mut)]
+            #[allow(unused_variables, unused_mut, reason = "structures may have no fields")]
             fn decode_into(mut decoder: $crate::reader::decoding::Decoder<'input>) -> Result<Self, $crate::error::DecodeError> {
                 Ok(Self {
                     $($field_name: decoder.read()?,)*
@@ -451,5 +451,5 @@ macro_rules! dec_structure {
     };
 }
 
-#[allow(unused_imports)]
+#[allow(unused_imports, reason = "macro re-export")]
 pub(crate) use dec_structure;
@@ -0,0 +1,98 @@
+//! Helpers shared by the tests of several modules.
+
+use crate::error::EncodeError;
+use crate::header::{AccessFlags, Version};
+use crate::reader::attributes::{Attribute, AttributeContent, Code};
+use crate::reader::{Class, DecodeMany, Method};
+use crate::writer::attributes::code::{CodeWriter, CodeWriterState};
+use crate::writer::{
+    AttributeWriter, AttributeWriterState, ClassWriter, ClassWriterState, ManyWriter, MethodWriter, MethodWriterState,
+};
+
+pub(crate) type Methods = ManyWriter<MethodWriter<MethodWriterState::AccessFlags>, u16>;
+pub(crate) type ClassAttributes =
+    ManyWriter<AttributeWriter<ClassWriter<ClassWriterState::Attributes>, AttributeWriterState::Start>, u16>;
+
+/// Writes a public class `Test` with the given methods and class attributes.
+pub(crate) fn write_class<M, A>(methods: M, attributes: A) -> Vec<u8>
+where
+    M: FnOnce(&mut Methods) -> Result<(), EncodeError>,
+    A: FnOnce(&mut ClassAttributes) -> Result<(), EncodeError>,
+{
+    try_write_class(methods, attributes).expect("the class should be written")
+}
+
+/// Like [`write_class`], but returns the error instead of panicking.
+pub(crate) fn try_write_class<M, A>(methods: M, attributes: A) -> Result<Vec<u8>, EncodeError>
+where
+    M: FnOnce(&mut Methods) -> Result<(), EncodeError>,
+    A: FnOnce(&mut ClassAttributes) -> Result<(), EncodeError>,
+{
+    ClassWriter::new()
+        .version(Version::latest())
+        .and_then(|writer| writer.access_flags(AccessFlags::PUBLIC | AccessFlags::SUPER))
+        .and_then(|writer| writer.this_class("Test"))
+        .and_then(|writer| writer.super_class("java/lang/Object"))
+        .and_then(|writer| writer.interfaces(|_| Ok(())))
+        .and_then(|writer| writer.fields(|_| Ok(())))
+        .and_then(|writer| writer.methods(methods))
+        .and_then(|writer| writer.attributes(attributes))
+        .and_then(ClassWriter::into_bytes)
+}
+
+/// Reads the content of the attribute with the given name.
+pub(crate) fn attribute<'input>(
+    class: &Class<'input>,
+    attributes: DecodeMany<'input, Attribute<'input>, u16>,
+    name: &str,
+) -> AttributeContent<'input> {
+    for attribute in attributes {
+        let attribute = attribute.unwrap();
+        if class.pool().retrieve(attribute.name()).unwrap() == name {
+            return attribute.read_content(class.pool()).unwrap();
+        }
+    }
+    panic!("expected a {name} attribute");
+}
+
+pub(crate) type TestCode<State> = CodeWriter<ClassWriter<ClassWriterState::Methods>, State>;
+
+/// Writes a class with a public static method `m` with the given descriptor, whose code is written by `f`.
+pub(crate) fn write_code<F>(descriptor: &str, f: F) -> Vec<u8>
+where
+    F: FnOnce(TestCode<CodeWriterState::MaxStack>) -> Result<TestCode<CodeWriterState::End>, EncodeError>,
+{
+    try_write_code(descriptor, f).expect("the class should be written")
+}
+
+/// Like [`write_code`], but returns the error instead of panicking.
+pub(crate) fn try_write_code<F>(descriptor: &str, f: F) -> Result<Vec<u8>, EncodeError>
+where
+    F: FnOnce(TestCode<CodeWriterState::MaxStack>) -> Result<TestCode<CodeWriterState::End>, EncodeError>,
+{
+    try_write_class(
+        |methods| {
+            methods.begin(|method| {
+                method
+                    .access_flags(AccessFlags::PUBLIC | AccessFlags::STATIC)?
+                    .name("m")?
+                    .descriptor(descriptor)?
+                    .attributes(|attributes| {
+                        attributes.begin(|attribute| attribute.code(f))?;
+                        Ok(())
+                    })
+            })?;
+            Ok(())
+        },
+        |_| Ok(()),
+    )
+}
+
+/// Returns the first method of a class together with its code.
+pub(crate) fn first_code<'input>(class: &Class<'input>) -> (Method<'input>, Code<'input>) {
+    let method = class.methods().iter().next().unwrap().unwrap();
+    let AttributeContent::Code(code) = attribute(class, method.attributes(), "Code") else {
+        unreachable!();
+    };
+    (method, code)
+}
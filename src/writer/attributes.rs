@@ -1,16 +1,22 @@
+mod annotations;
+mod class;
 pub mod code;
 mod debug;
 mod enclosing_method;
 mod field;
 mod inner_classes;
 mod method;
+mod module;
 
 use std::fmt;
 use std::marker::PhantomData;
 
+pub use annotations::*;
+pub use class::*;
 pub use enclosing_method::*;
 pub use inner_classes::*;
 pub use method::*;
+pub use module::*;
 
 use crate::error::*;
 use crate::writer::{cpool, encoding::*};
@@ -0,0 +1,720 @@
+mod type_annotation;
+
+use std::fmt;
+use std::marker::PhantomData;
+
+pub use type_annotation::*;
+
+use crate::error::*;
+use crate::writer::{
+    attributes::{AttributeWriter, AttributeWriterState},
+    cpool,
+    encoding::*,
+};
+
+impl<Ctx: EncoderContext> AttributeWriter<Ctx, AttributeWriterState::Start> {
+    pub fn runtime_visible_annotations<F>(
+        self,
+        f: F,
+    ) -> Result<AttributeWriter<Ctx, AttributeWriterState::End>, EncodeError>
+    where
+        F: FnOnce(&mut ManyWriter<AnnotationWriter<Ctx, AnnotationWriterState::Type>, u16>) -> Result<(), EncodeError>,
+    {
+        self.annotations("RuntimeVisibleAnnotations", f)
+    }
+
+    pub fn runtime_invisible_annotations<F>(
+        self,
+        f: F,
+    ) -> Result<AttributeWriter<Ctx, AttributeWriterState::End>, EncodeError>
+    where
+        F: FnOnce(&mut ManyWriter<AnnotationWriter<Ctx, AnnotationWriterState::Type>, u16>) -> Result<(), EncodeError>,
+    {
+        self.annotations("RuntimeInvisibleAnnotations", f)
+    }
+
+    fn annotations<F>(
+        mut self,
+        name: &str,
+        f: F,
+    ) -> Result<AttributeWriter<Ctx, AttributeWriterState::End>, EncodeError>
+    where
+        F: FnOnce(&mut ManyWriter<AnnotationWriter<Ctx, AnnotationWriterState::Type>, u16>) -> Result<(), EncodeError>,
+    {
+        let length_writer = self.attribute_writer(name)?;
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+        length_writer.finish(&mut self.context)?;
+
+        Ok(AttributeWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn runtime_visible_parameter_annotations<F>(
+        self,
+        f: F,
+    ) -> Result<AttributeWriter<Ctx, AttributeWriterState::End>, EncodeError>
+    where
+        F: FnOnce(
+            &mut ManyWriter<ParameterAnnotationsWriter<Ctx, ParameterAnnotationsWriterState::Start>, u8>,
+        ) -> Result<(), EncodeError>,
+    {
+        self.parameter_annotations("RuntimeVisibleParameterAnnotations", f)
+    }
+
+    pub fn runtime_invisible_parameter_annotations<F>(
+        self,
+        f: F,
+    ) -> Result<AttributeWriter<Ctx, AttributeWriterState::End>, EncodeError>
+    where
+        F: FnOnce(
+            &mut ManyWriter<ParameterAnnotationsWriter<Ctx, ParameterAnnotationsWriterState::Start>, u8>,
+        ) -> Result<(), EncodeError>,
+    {
+        self.parameter_annotations("RuntimeInvisibleParameterAnnotations", f)
+    }
+
+    fn parameter_annotations<F>(
+        mut self,
+        name: &str,
+        f: F,
+    ) -> Result<AttributeWriter<Ctx, AttributeWriterState::End>, EncodeError>
+    where
+        F: FnOnce(
+            &mut ManyWriter<ParameterAnnotationsWriter<Ctx, ParameterAnnotationsWriterState::Start>, u8>,
+        ) -> Result<(), EncodeError>,
+    {
+        let length_writer = self.attribute_writer(name)?;
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+        length_writer.finish(&mut self.context)?;
+
+        Ok(AttributeWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Writes the default value of the annotation element represented by this method.
+    pub fn annotation_default<F>(mut self, f: F) -> Result<AttributeWriter<Ctx, AttributeWriterState::End>, EncodeError>
+    where
+        F: FnOnce(
+            ElementValueWriter<Ctx, ElementValueWriterState::Start>,
+        ) -> Result<ElementValueWriter<Ctx, ElementValueWriterState::End>, EncodeError>,
+    {
+        let length_writer = self.attribute_writer("AnnotationDefault")?;
+        self.context = f(ElementValueWriter::new(self.context)?)?.finish()?;
+        length_writer.finish(&mut self.context)?;
+
+        Ok(AttributeWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+pub struct AnnotationWriter<Ctx, State: AnnotationWriterState::State> {
+    context: Ctx,
+    _marker: PhantomData<State>,
+}
+
+impl<Ctx: EncoderContext> AnnotationWriter<Ctx, AnnotationWriterState::Type> {
+    /// Writes the field descriptor of the annotation type, e.g. `Ljava/lang/Deprecated;`.
+    pub fn type_<I>(mut self, descriptor: I) -> Result<AnnotationWriter<Ctx, AnnotationWriterState::Pairs>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Utf8>,
+    {
+        let index = descriptor.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+
+        Ok(AnnotationWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> AnnotationWriter<Ctx, AnnotationWriterState::Pairs> {
+    pub fn pairs<F>(mut self, f: F) -> Result<AnnotationWriter<Ctx, AnnotationWriterState::End>, EncodeError>
+    where
+        F: FnOnce(
+            &mut ManyWriter<ElementValuePairWriter<Ctx, ElementValuePairWriterState::Name>, u16>,
+        ) -> Result<(), EncodeError>,
+    {
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+
+        Ok(AnnotationWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteAssembler for AnnotationWriter<Ctx, AnnotationWriterState::Type> {
+    type Context = Ctx;
+
+    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+        Ok(AnnotationWriter {
+            context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteDisassembler for AnnotationWriter<Ctx, AnnotationWriterState::End> {
+    type Context = Ctx;
+
+    fn finish(self) -> Result<Self::Context, EncodeError> {
+        Ok(self.context)
+    }
+}
+
+impl<Ctx, State: AnnotationWriterState::State> fmt::Debug for AnnotationWriter<Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnnotationWriter").finish()
+    }
+}
+
+enc_state!(pub mod AnnotationWriterState: Type, Pairs, End);
+
+/// Writes the annotations of a single parameter.
+/// Every parameter of the method has to be written, even those without annotations.
+pub struct ParameterAnnotationsWriter<Ctx, State: ParameterAnnotationsWriterState::State> {
+    context: Ctx,
+    _marker: PhantomData<State>,
+}
+
+impl<Ctx: EncoderContext> ParameterAnnotationsWriter<Ctx, ParameterAnnotationsWriterState::Start> {
+    pub fn annotations<F>(
+        mut self,
+        f: F,
+    ) -> Result<ParameterAnnotationsWriter<Ctx, ParameterAnnotationsWriterState::End>, EncodeError>
+    where
+        F: FnOnce(&mut ManyWriter<AnnotationWriter<Ctx, AnnotationWriterState::Type>, u16>) -> Result<(), EncodeError>,
+    {
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+
+        Ok(ParameterAnnotationsWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteAssembler for ParameterAnnotationsWriter<Ctx, ParameterAnnotationsWriterState::Start> {
+    type Context = Ctx;
+
+    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+        Ok(ParameterAnnotationsWriter {
+            context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteDisassembler for ParameterAnnotationsWriter<Ctx, ParameterAnnotationsWriterState::End> {
+    type Context = Ctx;
+
+    fn finish(self) -> Result<Self::Context, EncodeError> {
+        Ok(self.context)
+    }
+}
+
+impl<Ctx, State: ParameterAnnotationsWriterState::State> fmt::Debug for ParameterAnnotationsWriter<Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParameterAnnotationsWriter").finish()
+    }
+}
+
+enc_state!(pub mod ParameterAnnotationsWriterState: Start, End);
+
+pub struct ElementValuePairWriter<Ctx, State: ElementValuePairWriterState::State> {
+    context: Ctx,
+    _marker: PhantomData<State>,
+}
+
+impl<Ctx: EncoderContext> ElementValuePairWriter<Ctx, ElementValuePairWriterState::Name> {
+    pub fn name<I>(
+        mut self,
+        name: I,
+    ) -> Result<ElementValuePairWriter<Ctx, ElementValuePairWriterState::Value>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Utf8>,
+    {
+        let index = name.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+
+        Ok(ElementValuePairWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> ElementValuePairWriter<Ctx, ElementValuePairWriterState::Value> {
+    pub fn value<F>(
+        mut self,
+        f: F,
+    ) -> Result<ElementValuePairWriter<Ctx, ElementValuePairWriterState::End>, EncodeError>
+    where
+        F: FnOnce(
+            ElementValueWriter<Ctx, ElementValueWriterState::Start>,
+        ) -> Result<ElementValueWriter<Ctx, ElementValueWriterState::End>, EncodeError>,
+    {
+        self.context = f(ElementValueWriter::new(self.context)?)?.finish()?;
+
+        Ok(ElementValuePairWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteAssembler for ElementValuePairWriter<Ctx, ElementValuePairWriterState::Name> {
+    type Context = Ctx;
+
+    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+        Ok(ElementValuePairWriter {
+            context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteDisassembler for ElementValuePairWriter<Ctx, ElementValuePairWriterState::End> {
+    type Context = Ctx;
+
+    fn finish(self) -> Result<Self::Context, EncodeError> {
+        Ok(self.context)
+    }
+}
+
+impl<Ctx, State: ElementValuePairWriterState::State> fmt::Debug for ElementValuePairWriter<Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ElementValuePairWriter").finish()
+    }
+}
+
+enc_state!(pub mod ElementValuePairWriterState: Name, Value, End);
+
+pub struct ElementValueWriter<Ctx, State: ElementValueWriterState::State> {
+    context: Ctx,
+    _marker: PhantomData<State>,
+}
+
+impl<Ctx: EncoderContext> ElementValueWriter<Ctx, ElementValueWriterState::Start> {
+    fn tagged<I>(
+        mut self,
+        tag: u8,
+        value: I,
+    ) -> Result<ElementValueWriter<Ctx, ElementValueWriterState::End>, EncodeError>
+    where
+        I: Into<cpool::Item>,
+    {
+        let index = self.context.insert_constant(value)?;
+        self.context.encoder().write(tag)?.write(index)?;
+
+        Ok(ElementValueWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn boolean(self, value: bool) -> Result<ElementValueWriter<Ctx, ElementValueWriterState::End>, EncodeError> {
+        self.tagged(b'Z', cpool::Integer { value: value.into() })
+    }
+
+    pub fn byte(self, value: i8) -> Result<ElementValueWriter<Ctx, ElementValueWriterState::End>, EncodeError> {
+        self.tagged(b'B', cpool::Integer { value: value.into() })
+    }
+
+    pub fn short(self, value: i16) -> Result<ElementValueWriter<Ctx, ElementValueWriterState::End>, EncodeError> {
+        self.tagged(b'S', cpool::Integer { value: value.into() })
+    }
+
+    pub fn int(self, value: i32) -> Result<ElementValueWriter<Ctx, ElementValueWriterState::End>, EncodeError> {
+        self.tagged(b'I', cpool::Integer { value })
+    }
+
+    pub fn long(self, value: i64) -> Result<ElementValueWriter<Ctx, ElementValueWriterState::End>, EncodeError> {
+        self.tagged(b'J', cpool::Long { value })
+    }
+
+    pub fn float(self, value: f32) -> Result<ElementValueWriter<Ctx, ElementValueWriterState::End>, EncodeError> {
+        self.tagged(b'F', cpool::Float { value })
+    }
+
+    pub fn double(self, value: f64) -> Result<ElementValueWriter<Ctx, ElementValueWriterState::End>, EncodeError> {
+        self.tagged(b'D', cpool::Double { value })
+    }
+
+    /// Writes a `char` value given as a UTF-16 code unit.
+    pub fn char(self, value: u16) -> Result<ElementValueWriter<Ctx, ElementValueWriterState::End>, EncodeError> {
+        self.tagged(b'C', cpool::Integer { value: value.into() })
+    }
+
+    pub fn string<I>(mut self, value: I) -> Result<ElementValueWriter<Ctx, ElementValueWriterState::End>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Utf8>,
+    {
+        let index = value.insert(&mut self.context)?;
+        self.context.encoder().write(b's')?.write(index)?;
+
+        Ok(ElementValueWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Writes an enum constant, the type name being the field descriptor of the enum type.
+    pub fn enum_constant<I, J>(
+        mut self,
+        type_name: I,
+        const_name: J,
+    ) -> Result<ElementValueWriter<Ctx, ElementValueWriterState::End>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Utf8>,
+        J: cpool::Insertable<cpool::Utf8>,
+    {
+        let type_name = type_name.insert(&mut self.context)?;
+        let const_name = const_name.insert(&mut self.context)?;
+        self.context
+            .encoder()
+            .write(b'e')?
+            .write(type_name)?
+            .write(const_name)?;
+
+        Ok(ElementValueWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Writes a class literal given as a return descriptor, e.g. `Ljava/lang/Object;` or `V`.
+    pub fn class<I>(
+        mut self,
+        descriptor: I,
+    ) -> Result<ElementValueWriter<Ctx, ElementValueWriterState::End>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Utf8>,
+    {
+        let index = descriptor.insert(&mut self.context)?;
+        self.context.encoder().write(b'c')?.write(index)?;
+
+        Ok(ElementValueWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn annotation<F>(mut self, f: F) -> Result<ElementValueWriter<Ctx, ElementValueWriterState::End>, EncodeError>
+    where
+        F: FnOnce(
+            AnnotationWriter<Ctx, AnnotationWriterState::Type>,
+        ) -> Result<AnnotationWriter<Ctx, AnnotationWriterState::End>, EncodeError>,
+    {
+        self.context.encoder().write(b'@')?;
+        self.context = f(AnnotationWriter::new(self.context)?)?.finish()?;
+
+        Ok(ElementValueWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn array<F>(mut self, f: F) -> Result<ElementValueWriter<Ctx, ElementValueWriterState::End>, EncodeError>
+    where
+        F: FnOnce(
+            &mut ManyWriter<ElementValueWriter<Ctx, ElementValueWriterState::Start>, u16>,
+        ) -> Result<(), EncodeError>,
+    {
+        self.context.encoder().write(b'[')?;
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+
+        Ok(ElementValueWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteAssembler for ElementValueWriter<Ctx, ElementValueWriterState::Start> {
+    type Context = Ctx;
+
+    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+        Ok(ElementValueWriter {
+            context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteDisassembler for ElementValueWriter<Ctx, ElementValueWriterState::End> {
+    type Context = Ctx;
+
+    fn finish(self) -> Result<Self::Context, EncodeError> {
+        Ok(self.context)
+    }
+}
+
+impl<Ctx, State: ElementValueWriterState::State> fmt::Debug for ElementValueWriter<Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ElementValueWriter").finish()
+    }
+}
+
+enc_state!(pub mod ElementValueWriterState: Start, End);
+
+#[cfg(test)]
+mod test {
+    use crate::header::AccessFlags;
+    use crate::reader::attributes::annotations::{Annotation, ElementValue, ParameterAnnotations};
+    use crate::reader::attributes::AttributeContent;
+    use crate::reader::cpool::ConstantPool;
+    use crate::reader::{Class, DecodeMany};
+    use crate::testing::{attribute, write_class};
+
+    /// Describes an element value like in the Java source code.
+    fn describe(pool: &ConstantPool<'_>, value: &ElementValue<'_>) -> String {
+        match value {
+            ElementValue::Boolean(index) => format!("{}", pool.get(*index).unwrap().value != 0),
+            ElementValue::Byte(index) => format!("(byte) {}", pool.get(*index).unwrap().value),
+            ElementValue::Short(index) => format!("(short) {}", pool.get(*index).unwrap().value),
+            ElementValue::Int(index) => format!("{}", pool.get(*index).unwrap().value),
+            ElementValue::Long(index) => format!("{}L", pool.get(*index).unwrap().value),
+            ElementValue::Float(index) => format!("{}f", pool.get(*index).unwrap().value),
+            ElementValue::Double(index) => format!("{}d", pool.get(*index).unwrap().value),
+            ElementValue::Char(index) => format!("(char) {}", pool.get(*index).unwrap().value),
+            ElementValue::String(index) => format!("{:?}", pool.retrieve(*index).unwrap().display().to_string()),
+            ElementValue::Class(index) => format!("{}.class", pool.retrieve(*index).unwrap().display()),
+            ElementValue::Enum { type_name, const_name } => format!(
+                "{}.{}",
+                pool.retrieve(*type_name).unwrap().display(),
+                pool.retrieve(*const_name).unwrap().display()
+            ),
+            ElementValue::Annotation(annotation) => describe_annotation(pool, annotation),
+            ElementValue::Array(values) => {
+                let values: Vec<_> = values.iter().map(|value| describe(pool, &value.unwrap())).collect();
+                format!("{{{}}}", values.join(", "))
+            }
+        }
+    }
+
+    fn describe_annotation(pool: &ConstantPool<'_>, annotation: &Annotation<'_>) -> String {
+        let pairs: Vec<_> = annotation
+            .pairs()
+            .iter()
+            .map(|pair| {
+                let pair = pair.unwrap();
+                let name = pool.retrieve(pair.name()).unwrap().display().to_string();
+                format!("{name} = {}", describe(pool, &pair.value()))
+            })
+            .collect();
+        let type_ = pool.retrieve(annotation.type_()).unwrap().display().to_string();
+        format!("@{type_}({})", pairs.join(", "))
+    }
+
+    #[test]
+    fn element_values() {
+        let bytes = write_class(
+            |_| Ok(()),
+            |attributes| {
+                attributes.begin(|attribute| {
+                    attribute.runtime_visible_annotations(|annotations| {
+                        annotations.begin(|annotation| {
+                            annotation.type_("LValues;")?.pairs(|pairs| {
+                                pairs.begin(|pair| pair.name("z")?.value(|value| value.boolean(true)))?;
+                                pairs.begin(|pair| pair.name("b")?.value(|value| value.byte(-2)))?;
+                                pairs.begin(|pair| pair.name("s")?.value(|value| value.short(300)))?;
+                                pairs.begin(|pair| pair.name("i")?.value(|value| value.int(70000)))?;
+                                pairs.begin(|pair| pair.name("j")?.value(|value| value.long(1 << 40)))?;
+                                pairs.begin(|pair| pair.name("f")?.value(|value| value.float(1.5)))?;
+                                pairs.begin(|pair| pair.name("d")?.value(|value| value.double(-0.25)))?;
+                                pairs.begin(|pair| pair.name("c")?.value(|value| value.char(u16::from(b'x'))))?;
+                                pairs.begin(|pair| pair.name("text")?.value(|value| value.string("hello")))?;
+                                pairs.begin(|pair| {
+                                    pair.name("e")?.value(|value| {
+                                        value.enum_constant("Ljava/lang/annotation/ElementType;", "TYPE")
+                                    })
+                                })?;
+                                pairs.begin(|pair| pair.name("type")?.value(|value| value.class("V")))?;
+                                pairs.begin(|pair| {
+                                    pair.name("nested")?.value(|value| {
+                                        value.annotation(|annotation| annotation.type_("LNested;")?.pairs(|_| Ok(())))
+                                    })
+                                })?;
+                                pairs.begin(|pair| {
+                                    pair.name("array")?.value(|value| {
+                                        value.array(|values| {
+                                            values.begin(|value| value.int(1))?;
+                                            values.begin(|value| value.int(2))?;
+                                            Ok(())
+                                        })
+                                    })
+                                })?;
+                                Ok(())
+                            })
+                        })?;
+                        Ok(())
+                    })
+                })?;
+                attributes.begin(|attribute| {
+                    attribute.runtime_invisible_annotations(|annotations| {
+                        annotations.begin(|annotation| annotation.type_("LHidden;")?.pairs(|_| Ok(())))?;
+                        Ok(())
+                    })
+                })?;
+                Ok(())
+            },
+        );
+        let class = Class::new(&bytes).unwrap();
+        let pool = class.pool();
+
+        let AttributeContent::RuntimeVisibleAnnotations(visible) =
+            attribute(&class, class.attributes(), "RuntimeVisibleAnnotations")
+        else {
+            panic!("expected visible annotations");
+        };
+        let annotations: Vec<_> = visible.annotations().iter().map(Result::unwrap).collect();
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(
+            describe_annotation(pool, &annotations[0]),
+            concat!(
+                "@LValues;(z = true, b = (byte) -2, s = (short) 300, i = 70000, j = 1099511627776L, f = 1.5f, ",
+                "d = -0.25d, c = (char) 120, text = \"hello\", e = Ljava/lang/annotation/ElementType;.TYPE, ",
+                "type = V.class, nested = @LNested;(), array = {1, 2})"
+            )
+        );
+
+        let AttributeContent::RuntimeInvisibleAnnotations(invisible) =
+            attribute(&class, class.attributes(), "RuntimeInvisibleAnnotations")
+        else {
+            panic!("expected invisible annotations");
+        };
+        let annotations: Vec<_> = invisible.annotations().iter().map(Result::unwrap).collect();
+        assert_eq!(describe_annotation(pool, &annotations[0]), "@LHidden;()");
+    }
+
+    #[test]
+    fn annotation_default() {
+        let bytes = write_class(
+            |methods| {
+                methods.begin(|method| {
+                    method
+                        .access_flags(AccessFlags::PUBLIC | AccessFlags::ABSTRACT)?
+                        .name("values")?
+                        .descriptor("()[Ljava/lang/String;")?
+                        .attributes(|attributes| {
+                            attributes.begin(|attribute| {
+                                attribute.annotation_default(|value| {
+                                    value.array(|values| {
+                                        values.begin(|value| value.string("a"))?;
+                                        Ok(())
+                                    })
+                                })
+                            })?;
+                            Ok(())
+                        })
+                })?;
+                Ok(())
+            },
+            |_| Ok(()),
+        );
+        let class = Class::new(&bytes).unwrap();
+        let method = class.methods().iter().next().unwrap().unwrap();
+
+        let AttributeContent::AnnotationDefault(default) = attribute(&class, method.attributes(), "AnnotationDefault")
+        else {
+            panic!("expected an annotation default");
+        };
+        assert_eq!(describe(class.pool(), &default.value()), "{\"a\"}");
+    }
+
+    #[test]
+    fn parameter_annotations() {
+        let bytes = write_class(
+            |methods| {
+                methods.begin(|method| {
+                    method
+                        .access_flags(AccessFlags::PUBLIC | AccessFlags::ABSTRACT)?
+                        .name("m")?
+                        .descriptor("(II)V")?
+                        .attributes(|attributes| {
+                            attributes.begin(|attribute| {
+                                attribute.runtime_visible_parameter_annotations(|parameters| {
+                                    parameters.begin(|parameter| parameter.annotations(|_| Ok(())))?;
+                                    parameters.begin(|parameter| {
+                                        parameter.annotations(|annotations| {
+                                            annotations
+                                                .begin(|annotation| annotation.type_("LA;")?.pairs(|_| Ok(())))?;
+                                            annotations
+                                                .begin(|annotation| annotation.type_("LB;")?.pairs(|_| Ok(())))?;
+                                            Ok(())
+                                        })
+                                    })?;
+                                    Ok(())
+                                })
+                            })?;
+                            attributes.begin(|attribute| {
+                                attribute.runtime_invisible_parameter_annotations(|parameters| {
+                                    parameters.begin(|parameter| {
+                                        parameter.annotations(|annotations| {
+                                            annotations
+                                                .begin(|annotation| annotation.type_("LC;")?.pairs(|_| Ok(())))?;
+                                            Ok(())
+                                        })
+                                    })?;
+                                    Ok(())
+                                })
+                            })?;
+                            Ok(())
+                        })
+                })?;
+                Ok(())
+            },
+            |_| Ok(()),
+        );
+        let class = Class::new(&bytes).unwrap();
+        let pool = class.pool();
+        let method = class.methods().iter().next().unwrap().unwrap();
+        let describe_parameters = |parameters: DecodeMany<'_, ParameterAnnotations<'_>, u8>| -> Vec<Vec<String>> {
+            parameters
+                .iter()
+                .map(|parameter| {
+                    let annotations = parameter.unwrap().annotations();
+                    annotations
+                        .iter()
+                        .map(|annotation| describe_annotation(pool, &annotation.unwrap()))
+                        .collect()
+                })
+                .collect()
+        };
+
+        let AttributeContent::RuntimeVisibleParameterAnnotations(visible) =
+            attribute(&class, method.attributes(), "RuntimeVisibleParameterAnnotations")
+        else {
+            panic!("expected visible parameter annotations");
+        };
+        assert_eq!(
+            describe_parameters(visible.parameters()),
+            [vec![], vec!["@LA;()".to_owned(), "@LB;()".to_owned()]]
+        );
+
+        let AttributeContent::RuntimeInvisibleParameterAnnotations(invisible) =
+            attribute(&class, method.attributes(), "RuntimeInvisibleParameterAnnotations")
+        else {
+            panic!("expected invisible parameter annotations");
+        };
+        assert_eq!(describe_parameters(invisible.parameters()), [vec!["@LC;()".to_owned()]]);
+    }
+}
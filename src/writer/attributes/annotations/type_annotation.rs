@@ -0,0 +1,778 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::error::*;
+use crate::writer::{
+    attributes::{
+        annotations::{ElementValuePairWriter, ElementValuePairWriterState},
+        code::{CodeWriter, CodeWriterState, LabelRef},
+        AttributeWriter, AttributeWriterState,
+    },
+    cpool,
+    encoding::*,
+};
+
+impl<Ctx: EncoderContext> AttributeWriter<Ctx, AttributeWriterState::Start> {
+    pub fn runtime_visible_type_annotations<F>(
+        self,
+        f: F,
+    ) -> Result<AttributeWriter<Ctx, AttributeWriterState::End>, EncodeError>
+    where
+        F: FnOnce(
+            &mut ManyWriter<TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::Target>, u16>,
+        ) -> Result<(), EncodeError>,
+    {
+        self.type_annotations("RuntimeVisibleTypeAnnotations", f)
+    }
+
+    pub fn runtime_invisible_type_annotations<F>(
+        self,
+        f: F,
+    ) -> Result<AttributeWriter<Ctx, AttributeWriterState::End>, EncodeError>
+    where
+        F: FnOnce(
+            &mut ManyWriter<TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::Target>, u16>,
+        ) -> Result<(), EncodeError>,
+    {
+        self.type_annotations("RuntimeInvisibleTypeAnnotations", f)
+    }
+
+    fn type_annotations<F>(
+        mut self,
+        name: &str,
+        f: F,
+    ) -> Result<AttributeWriter<Ctx, AttributeWriterState::End>, EncodeError>
+    where
+        F: FnOnce(
+            &mut ManyWriter<TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::Target>, u16>,
+        ) -> Result<(), EncodeError>,
+    {
+        let length_writer = self.attribute_writer(name)?;
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+        length_writer.finish(&mut self.context)?;
+
+        Ok(AttributeWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+pub struct TypeAnnotationWriter<Ctx, State: TypeAnnotationWriterState::State> {
+    context: Ctx,
+    _marker: PhantomData<State>,
+}
+
+impl<Ctx: EncoderContext> TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::Target> {
+    fn target(mut self, target_type: u8) -> Result<Self, EncodeError> {
+        self.context.encoder().write(target_type)?;
+        Ok(self)
+    }
+
+    fn next_state(self) -> TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::TargetPath> {
+        TypeAnnotationWriter {
+            context: self.context,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn class_type_parameter(
+        self,
+        parameter_index: u8,
+    ) -> Result<TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::TargetPath>, EncodeError> {
+        let mut writer = self.target(0x00)?;
+        writer.context.encoder().write(parameter_index)?;
+        Ok(writer.next_state())
+    }
+
+    pub fn method_type_parameter(
+        self,
+        parameter_index: u8,
+    ) -> Result<TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::TargetPath>, EncodeError> {
+        let mut writer = self.target(0x01)?;
+        writer.context.encoder().write(parameter_index)?;
+        Ok(writer.next_state())
+    }
+
+    /// Targets the type in the `extends` clause of a class declaration.
+    pub fn class_extends_superclass(
+        self,
+    ) -> Result<TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::TargetPath>, EncodeError> {
+        let mut writer = self.target(0x10)?;
+        writer.context.encoder().write(u16::MAX)?;
+        Ok(writer.next_state())
+    }
+
+    /// Targets the type in the `implements` clause of a class declaration.
+    /// The index is an index into the interfaces of the class.
+    pub fn class_extends_interface(
+        self,
+        interface_index: u16,
+    ) -> Result<TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::TargetPath>, EncodeError> {
+        if interface_index == u16::MAX {
+            return Err(EncodeError::with_context(
+                EncodeErrorKind::IndexNotFitting,
+                Context::AttributeContent,
+            ));
+        }
+
+        let mut writer = self.target(0x10)?;
+        writer.context.encoder().write(interface_index)?;
+        Ok(writer.next_state())
+    }
+
+    pub fn class_type_parameter_bound(
+        self,
+        type_parameter_index: u8,
+        bound_index: u8,
+    ) -> Result<TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::TargetPath>, EncodeError> {
+        let mut writer = self.target(0x11)?;
+        writer
+            .context
+            .encoder()
+            .write(type_parameter_index)?
+            .write(bound_index)?;
+        Ok(writer.next_state())
+    }
+
+    pub fn method_type_parameter_bound(
+        self,
+        type_parameter_index: u8,
+        bound_index: u8,
+    ) -> Result<TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::TargetPath>, EncodeError> {
+        let mut writer = self.target(0x12)?;
+        writer
+            .context
+            .encoder()
+            .write(type_parameter_index)?
+            .write(bound_index)?;
+        Ok(writer.next_state())
+    }
+
+    pub fn field(self) -> Result<TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::TargetPath>, EncodeError> {
+        Ok(self.target(0x13)?.next_state())
+    }
+
+    pub fn method_return(
+        self,
+    ) -> Result<TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::TargetPath>, EncodeError> {
+        Ok(self.target(0x14)?.next_state())
+    }
+
+    pub fn method_receiver(
+        self,
+    ) -> Result<TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::TargetPath>, EncodeError> {
+        Ok(self.target(0x15)?.next_state())
+    }
+
+    pub fn method_formal_parameter(
+        self,
+        formal_parameter_index: u8,
+    ) -> Result<TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::TargetPath>, EncodeError> {
+        let mut writer = self.target(0x16)?;
+        writer.context.encoder().write(formal_parameter_index)?;
+        Ok(writer.next_state())
+    }
+
+    /// Targets a type in the `throws` clause, the index being an index into the `Exceptions` attribute.
+    pub fn throws(
+        self,
+        throws_type_index: u16,
+    ) -> Result<TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::TargetPath>, EncodeError> {
+        let mut writer = self.target(0x17)?;
+        writer.context.encoder().write(throws_type_index)?;
+        Ok(writer.next_state())
+    }
+}
+
+/// Targets which are only valid inside of a `Code` attribute.
+/// Code positions are given as labels and resolved against the enclosing code.
+impl<Ctx: EncoderContext>
+    TypeAnnotationWriter<CodeWriter<Ctx, CodeWriterState::Attributes>, TypeAnnotationWriterState::Target>
+{
+    pub fn local_variable<F>(
+        self,
+        f: F,
+    ) -> Result<
+        TypeAnnotationWriter<CodeWriter<Ctx, CodeWriterState::Attributes>, TypeAnnotationWriterState::TargetPath>,
+        EncodeError,
+    >
+    where
+        F: FnOnce(
+            &mut ManyWriter<LocalVariableTargetWriter<Ctx, LocalVariableTargetWriterState::Start>, u16>,
+        ) -> Result<(), EncodeError>,
+    {
+        self.target(0x40)?.local_variable_table(f)
+    }
+
+    pub fn resource_variable<F>(
+        self,
+        f: F,
+    ) -> Result<
+        TypeAnnotationWriter<CodeWriter<Ctx, CodeWriterState::Attributes>, TypeAnnotationWriterState::TargetPath>,
+        EncodeError,
+    >
+    where
+        F: FnOnce(
+            &mut ManyWriter<LocalVariableTargetWriter<Ctx, LocalVariableTargetWriterState::Start>, u16>,
+        ) -> Result<(), EncodeError>,
+    {
+        self.target(0x41)?.local_variable_table(f)
+    }
+
+    fn local_variable_table<F>(
+        mut self,
+        f: F,
+    ) -> Result<
+        TypeAnnotationWriter<CodeWriter<Ctx, CodeWriterState::Attributes>, TypeAnnotationWriterState::TargetPath>,
+        EncodeError,
+    >
+    where
+        F: FnOnce(
+            &mut ManyWriter<LocalVariableTargetWriter<Ctx, LocalVariableTargetWriterState::Start>, u16>,
+        ) -> Result<(), EncodeError>,
+    {
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+        Ok(self.next_state())
+    }
+
+    /// Targets the type of the exception parameter of a catch clause.
+    /// The first exception table entry with the given handler is referenced.
+    pub fn exception_parameter(
+        self,
+        handler: LabelRef,
+    ) -> Result<
+        TypeAnnotationWriter<CodeWriter<Ctx, CodeWriterState::Attributes>, TypeAnnotationWriterState::TargetPath>,
+        EncodeError,
+    > {
+        let exception_table_index = self.context.get_exception_table_index(handler)?;
+        let mut writer = self.target(0x42)?;
+        writer.context.encoder().write(exception_table_index)?;
+        Ok(writer.next_state())
+    }
+
+    fn offset_target(
+        self,
+        target_type: u8,
+        label: LabelRef,
+    ) -> Result<
+        TypeAnnotationWriter<CodeWriter<Ctx, CodeWriterState::Attributes>, TypeAnnotationWriterState::Target>,
+        EncodeError,
+    > {
+        let offset = self.context.get_label_position_u16(label)?;
+        let mut writer = self.target(target_type)?;
+        writer.context.encoder().write(offset)?;
+        Ok(writer)
+    }
+
+    /// Targets the type of an `instanceof` instruction at the label.
+    pub fn instanceof(
+        self,
+        label: LabelRef,
+    ) -> Result<
+        TypeAnnotationWriter<CodeWriter<Ctx, CodeWriterState::Attributes>, TypeAnnotationWriterState::TargetPath>,
+        EncodeError,
+    > {
+        Ok(self.offset_target(0x43, label)?.next_state())
+    }
+
+    /// Targets the type of a `new` instruction at the label.
+    pub fn new(
+        self,
+        label: LabelRef,
+    ) -> Result<
+        TypeAnnotationWriter<CodeWriter<Ctx, CodeWriterState::Attributes>, TypeAnnotationWriterState::TargetPath>,
+        EncodeError,
+    > {
+        Ok(self.offset_target(0x44, label)?.next_state())
+    }
+
+    pub fn constructor_reference(
+        self,
+        label: LabelRef,
+    ) -> Result<
+        TypeAnnotationWriter<CodeWriter<Ctx, CodeWriterState::Attributes>, TypeAnnotationWriterState::TargetPath>,
+        EncodeError,
+    > {
+        Ok(self.offset_target(0x45, label)?.next_state())
+    }
+
+    pub fn method_reference(
+        self,
+        label: LabelRef,
+    ) -> Result<
+        TypeAnnotationWriter<CodeWriter<Ctx, CodeWriterState::Attributes>, TypeAnnotationWriterState::TargetPath>,
+        EncodeError,
+    > {
+        Ok(self.offset_target(0x46, label)?.next_state())
+    }
+
+    fn type_argument_target(
+        self,
+        target_type: u8,
+        label: LabelRef,
+        type_argument_index: u8,
+    ) -> Result<
+        TypeAnnotationWriter<CodeWriter<Ctx, CodeWriterState::Attributes>, TypeAnnotationWriterState::TargetPath>,
+        EncodeError,
+    > {
+        let mut writer = self.offset_target(target_type, label)?;
+        writer.context.encoder().write(type_argument_index)?;
+        Ok(writer.next_state())
+    }
+
+    /// Targets a type in a cast expression of a `checkcast` instruction at the label.
+    pub fn cast(
+        self,
+        label: LabelRef,
+        type_argument_index: u8,
+    ) -> Result<
+        TypeAnnotationWriter<CodeWriter<Ctx, CodeWriterState::Attributes>, TypeAnnotationWriterState::TargetPath>,
+        EncodeError,
+    > {
+        self.type_argument_target(0x47, label, type_argument_index)
+    }
+
+    pub fn constructor_invocation_type_argument(
+        self,
+        label: LabelRef,
+        type_argument_index: u8,
+    ) -> Result<
+        TypeAnnotationWriter<CodeWriter<Ctx, CodeWriterState::Attributes>, TypeAnnotationWriterState::TargetPath>,
+        EncodeError,
+    > {
+        self.type_argument_target(0x48, label, type_argument_index)
+    }
+
+    pub fn method_invocation_type_argument(
+        self,
+        label: LabelRef,
+        type_argument_index: u8,
+    ) -> Result<
+        TypeAnnotationWriter<CodeWriter<Ctx, CodeWriterState::Attributes>, TypeAnnotationWriterState::TargetPath>,
+        EncodeError,
+    > {
+        self.type_argument_target(0x49, label, type_argument_index)
+    }
+
+    pub fn constructor_reference_type_argument(
+        self,
+        label: LabelRef,
+        type_argument_index: u8,
+    ) -> Result<
+        TypeAnnotationWriter<CodeWriter<Ctx, CodeWriterState::Attributes>, TypeAnnotationWriterState::TargetPath>,
+        EncodeError,
+    > {
+        self.type_argument_target(0x4A, label, type_argument_index)
+    }
+
+    pub fn method_reference_type_argument(
+        self,
+        label: LabelRef,
+        type_argument_index: u8,
+    ) -> Result<
+        TypeAnnotationWriter<CodeWriter<Ctx, CodeWriterState::Attributes>, TypeAnnotationWriterState::TargetPath>,
+        EncodeError,
+    > {
+        self.type_argument_target(0x4B, label, type_argument_index)
+    }
+}
+
+impl<Ctx: EncoderContext> TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::TargetPath> {
+    pub fn target_path<F>(
+        mut self,
+        f: F,
+    ) -> Result<TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::Type>, EncodeError>
+    where
+        F: FnOnce(&mut TypePathWriter<Ctx>) -> Result<(), EncodeError>,
+    {
+        let count_offset = self.context.encoder().position();
+        self.context.encoder().write(0u8)?;
+
+        let mut writer = TypePathWriter {
+            context: self.context,
+            count: 0,
+        };
+        f(&mut writer)?;
+        self.context = writer.context;
+
+        let count = writer.count;
+        self.context.encoder().replacing(count_offset).write(count)?;
+
+        Ok(TypeAnnotationWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::Type> {
+    /// Writes the field descriptor of the annotation type.
+    pub fn type_<I>(
+        mut self,
+        descriptor: I,
+    ) -> Result<TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::Pairs>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Utf8>,
+    {
+        let index = descriptor.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+
+        Ok(TypeAnnotationWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::Pairs> {
+    pub fn pairs<F>(mut self, f: F) -> Result<TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::End>, EncodeError>
+    where
+        F: FnOnce(
+            &mut ManyWriter<ElementValuePairWriter<Ctx, ElementValuePairWriterState::Name>, u16>,
+        ) -> Result<(), EncodeError>,
+    {
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+
+        Ok(TypeAnnotationWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteAssembler for TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::Target> {
+    type Context = Ctx;
+
+    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+        Ok(TypeAnnotationWriter {
+            context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteDisassembler for TypeAnnotationWriter<Ctx, TypeAnnotationWriterState::End> {
+    type Context = Ctx;
+
+    fn finish(self) -> Result<Self::Context, EncodeError> {
+        Ok(self.context)
+    }
+}
+
+impl<Ctx, State: TypeAnnotationWriterState::State> fmt::Debug for TypeAnnotationWriter<Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypeAnnotationWriter").finish()
+    }
+}
+
+enc_state!(pub mod TypeAnnotationWriterState: Target, TargetPath, Type, Pairs, End);
+
+pub struct LocalVariableTargetWriter<Ctx, State: LocalVariableTargetWriterState::State> {
+    context: CodeWriter<Ctx, CodeWriterState::Attributes>,
+    start: u32,
+    _marker: PhantomData<State>,
+}
+
+impl<Ctx: EncoderContext> LocalVariableTargetWriter<Ctx, LocalVariableTargetWriterState::Start> {
+    pub fn start(
+        mut self,
+        label: LabelRef,
+    ) -> Result<LocalVariableTargetWriter<Ctx, LocalVariableTargetWriterState::Length>, EncodeError> {
+        let offset = self.context.get_label_position(label)?;
+        let offset_u16 = u16::try_from(offset)
+            .map_err(|_| EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::AttributeContent))?;
+        self.context.encoder().write(offset_u16)?;
+
+        Ok(LocalVariableTargetWriter {
+            context: self.context,
+            start: offset,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> LocalVariableTargetWriter<Ctx, LocalVariableTargetWriterState::Length> {
+    pub fn end(
+        mut self,
+        label: LabelRef,
+    ) -> Result<LocalVariableTargetWriter<Ctx, LocalVariableTargetWriterState::Index>, EncodeError> {
+        let offset = self.context.get_label_position(label)?;
+
+        if offset < self.start {
+            return Err(EncodeError::with_context(
+                EncodeErrorKind::NegativeOffset,
+                Context::AttributeContent,
+            ));
+        }
+
+        let length = u16::try_from(offset - self.start)
+            .map_err(|_| EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::AttributeContent))?;
+        self.context.encoder().write(length)?;
+
+        Ok(LocalVariableTargetWriter {
+            context: self.context,
+            start: self.start,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> LocalVariableTargetWriter<Ctx, LocalVariableTargetWriterState::Index> {
+    pub fn index(
+        mut self,
+        index: u16,
+    ) -> Result<LocalVariableTargetWriter<Ctx, LocalVariableTargetWriterState::End>, EncodeError> {
+        self.context.encoder().write(index)?;
+
+        Ok(LocalVariableTargetWriter {
+            context: self.context,
+            start: self.start,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteAssembler for LocalVariableTargetWriter<Ctx, LocalVariableTargetWriterState::Start> {
+    type Context = CodeWriter<Ctx, CodeWriterState::Attributes>;
+
+    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+        Ok(LocalVariableTargetWriter {
+            context,
+            start: 0,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteDisassembler for LocalVariableTargetWriter<Ctx, LocalVariableTargetWriterState::End> {
+    type Context = CodeWriter<Ctx, CodeWriterState::Attributes>;
+
+    fn finish(self) -> Result<Self::Context, EncodeError> {
+        Ok(self.context)
+    }
+}
+
+impl<Ctx, State: LocalVariableTargetWriterState::State> fmt::Debug for LocalVariableTargetWriter<Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalVariableTargetWriter").finish()
+    }
+}
+
+enc_state!(pub mod LocalVariableTargetWriterState: Start, Length, Index, End);
+
+/// Writes the path to the annotated part of a type.
+pub struct TypePathWriter<Ctx> {
+    context: Ctx,
+    count: u8,
+}
+
+impl<Ctx: EncoderContext> TypePathWriter<Ctx> {
+    fn segment(&mut self, kind: u8, type_argument_index: u8) -> Result<&mut Self, EncodeError> {
+        self.count.check()?;
+        self.context.encoder().write(kind)?.write(type_argument_index)?;
+        self.count.increment()?;
+        Ok(self)
+    }
+
+    /// Annotation is deeper in an array type.
+    pub fn array_element(&mut self) -> Result<&mut Self, EncodeError> {
+        self.segment(0x00, 0)
+    }
+
+    /// Annotation is deeper in a nested type.
+    pub fn inner_type(&mut self) -> Result<&mut Self, EncodeError> {
+        self.segment(0x01, 0)
+    }
+
+    /// Annotation is on the bound of a wildcard type argument of a parameterized type.
+    pub fn wildcard_bound(&mut self) -> Result<&mut Self, EncodeError> {
+        self.segment(0x02, 0)
+    }
+
+    /// Annotation is on a type argument of a parameterized type.
+    pub fn type_argument(&mut self, type_argument_index: u8) -> Result<&mut Self, EncodeError> {
+        self.segment(0x03, type_argument_index)
+    }
+}
+
+impl<Ctx> fmt::Debug for TypePathWriter<Ctx> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypePathWriter").finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::reader::attributes::annotations::{
+        SuperTypeIndex, TargetInfo, TargetType, TypeAnnotation, TypePathSegmentKind,
+    };
+    use crate::reader::attributes::AttributeContent;
+    use crate::reader::Class;
+    use crate::testing::{attribute, first_code, write_class, write_code};
+    use crate::writer::cpool;
+
+    fn path(annotation: &TypeAnnotation<'_>) -> Vec<(TypePathSegmentKind, u8)> {
+        annotation
+            .target_path()
+            .iter()
+            .map(|segment| {
+                let segment = segment.unwrap();
+                (segment.kind(), segment.type_argument_index())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn class_targets() {
+        let bytes = write_class(
+            |_| Ok(()),
+            |attributes| {
+                attributes.begin(|attribute| {
+                    attribute.runtime_visible_type_annotations(|annotations| {
+                        annotations.begin(|annotation| {
+                            annotation
+                                .class_extends_interface(0)?
+                                .target_path(|path| {
+                                    path.type_argument(1)?.array_element()?;
+                                    Ok(())
+                                })?
+                                .type_("LA;")?
+                                .pairs(|_| Ok(()))
+                        })?;
+                        Ok(())
+                    })
+                })?;
+                attributes.begin(|attribute| {
+                    attribute.runtime_invisible_type_annotations(|annotations| {
+                        annotations.begin(|annotation| {
+                            annotation
+                                .class_extends_superclass()?
+                                .target_path(|_| Ok(()))?
+                                .type_("LB;")?
+                                .pairs(|pairs| {
+                                    pairs.begin(|pair| pair.name("value")?.value(|value| value.int(3)))?;
+                                    Ok(())
+                                })
+                        })?;
+                        Ok(())
+                    })
+                })?;
+                Ok(())
+            },
+        );
+        let class = Class::new(&bytes).unwrap();
+        let pool = class.pool();
+
+        let AttributeContent::RuntimeVisibleTypeAnnotations(visible) =
+            attribute(&class, class.attributes(), "RuntimeVisibleTypeAnnotations")
+        else {
+            panic!("expected visible type annotations");
+        };
+        let annotation = visible.annotations().iter().next().unwrap().unwrap();
+        assert_eq!(annotation.target_type(), TargetType::ClassExtends);
+        assert!(matches!(
+            annotation.target_info(),
+            TargetInfo::SuperType {
+                supertype_index: SuperTypeIndex::Interface { index: 0 }
+            }
+        ));
+        assert_eq!(
+            path(&annotation),
+            [
+                (TypePathSegmentKind::TypeArgument, 1),
+                (TypePathSegmentKind::ArrayElement, 0)
+            ]
+        );
+        assert_eq!(pool.retrieve(annotation.type_()).unwrap(), "LA;");
+
+        let AttributeContent::RuntimeInvisibleTypeAnnotations(invisible) =
+            attribute(&class, class.attributes(), "RuntimeInvisibleTypeAnnotations")
+        else {
+            panic!("expected invisible type annotations");
+        };
+        let annotation = invisible.annotations().iter().next().unwrap().unwrap();
+        assert!(matches!(
+            annotation.target_info(),
+            TargetInfo::SuperType {
+                supertype_index: SuperTypeIndex::Class
+            }
+        ));
+        assert_eq!(path(&annotation), []);
+        assert_eq!(annotation.pairs().iter().count(), 1);
+    }
+
+    #[test]
+    fn code_targets() {
+        let bytes = write_code("()V", |code| {
+            let mut code = code.max_stack(2)?.max_locals(1)?;
+            let (new, new_ref) = code.new_label()?;
+            let (start, start_ref) = code.new_label()?;
+            let (end, end_ref) = code.new_label()?;
+            code.instructions(|instructions| {
+                instructions
+                    .label(new)?
+                    .new("java/lang/Object")?
+                    .dup()?
+                    .invokespecial(cpool::MethodRef::by("java/lang/Object", ("<init>", "()V")))?
+                    .astore0()?
+                    .label(start)?
+                    .aload0()?
+                    .pop()?
+                    .label(end)?
+                    .return_()?;
+                Ok(())
+            })?
+            .exceptions(|_| Ok(()))?
+            .attributes(|attributes| {
+                attributes.begin(|attribute| {
+                    attribute.runtime_visible_type_annotations(|annotations| {
+                        annotations.begin(|annotation| {
+                            annotation
+                                .local_variable(|table| {
+                                    table.begin(|variable| variable.start(start_ref)?.end(end_ref)?.index(0))?;
+                                    Ok(())
+                                })?
+                                .target_path(|_| Ok(()))?
+                                .type_("LA;")?
+                                .pairs(|_| Ok(()))
+                        })?;
+                        annotations.begin(|annotation| {
+                            annotation
+                                .new(new_ref)?
+                                .target_path(|_| Ok(()))?
+                                .type_("LB;")?
+                                .pairs(|_| Ok(()))
+                        })?;
+                        Ok(())
+                    })
+                })?;
+                Ok(())
+            })
+        });
+        let class = Class::new(&bytes).unwrap();
+        let (_, code) = first_code(&class);
+
+        let AttributeContent::RuntimeVisibleTypeAnnotations(visible) =
+            attribute(&class, code.attributes(), "RuntimeVisibleTypeAnnotations")
+        else {
+            panic!("expected visible type annotations");
+        };
+        let annotations: Vec<_> = visible.annotations().iter().map(Result::unwrap).collect();
+        let TargetInfo::LocalVariable { table } = annotations[0].target_info() else {
+            panic!("expected a local variable target");
+        };
+        let variables: Vec<_> = table.iter().map(Result::unwrap).collect();
+        assert_eq!(variables.len(), 1);
+        assert_eq!(variables[0].range().start.as_u32(), 8);
+        assert_eq!(variables[0].range().end.as_u32(), 10);
+        assert_eq!(variables[0].index(), 0);
+        assert!(matches!(annotations[1].target_info(), TargetInfo::Offset { offset } if offset.as_u32() == 0));
+    }
+}
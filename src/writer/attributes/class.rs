@@ -0,0 +1,376 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::error::*;
+use crate::writer::{
+    attributes::{AttributeWriter, AttributeWriterState},
+    cpool,
+    encoding::*,
+};
+
+impl<Ctx: EncoderContext> AttributeWriter<Ctx, AttributeWriterState::Start> {
+    pub fn nest_host<I>(mut self, host_class: I) -> Result<AttributeWriter<Ctx, AttributeWriterState::End>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Class>,
+    {
+        let length_writer = self.attribute_writer("NestHost")?;
+        let index = host_class.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+        length_writer.finish(&mut self.context)?;
+
+        Ok(AttributeWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn nest_members<F>(mut self, f: F) -> Result<AttributeWriter<Ctx, AttributeWriterState::End>, EncodeError>
+    where
+        F: FnOnce(&mut ManyWriter<NestMemberWriter<Ctx, NestMemberWriterState::Start>, u16>) -> Result<(), EncodeError>,
+    {
+        let length_writer = self.attribute_writer("NestMembers")?;
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+        length_writer.finish(&mut self.context)?;
+
+        Ok(AttributeWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn permitted_subclasses<F>(
+        mut self,
+        f: F,
+    ) -> Result<AttributeWriter<Ctx, AttributeWriterState::End>, EncodeError>
+    where
+        F: FnOnce(
+            &mut ManyWriter<PermittedSubclassWriter<Ctx, PermittedSubclassWriterState::Start>, u16>,
+        ) -> Result<(), EncodeError>,
+    {
+        let length_writer = self.attribute_writer("PermittedSubclasses")?;
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+        length_writer.finish(&mut self.context)?;
+
+        Ok(AttributeWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn record<F>(mut self, f: F) -> Result<AttributeWriter<Ctx, AttributeWriterState::End>, EncodeError>
+    where
+        F: FnOnce(
+            &mut ManyWriter<RecordComponentWriter<Ctx, RecordComponentWriterState::Name>, u16>,
+        ) -> Result<(), EncodeError>,
+    {
+        let length_writer = self.attribute_writer("Record")?;
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+        length_writer.finish(&mut self.context)?;
+
+        Ok(AttributeWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+pub struct NestMemberWriter<Ctx, State: NestMemberWriterState::State> {
+    context: Ctx,
+    _marker: PhantomData<State>,
+}
+
+impl<Ctx: EncoderContext> NestMemberWriter<Ctx, NestMemberWriterState::Start> {
+    pub fn member<I>(mut self, class: I) -> Result<NestMemberWriter<Ctx, NestMemberWriterState::End>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Class>,
+    {
+        let index = class.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+
+        Ok(NestMemberWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteAssembler for NestMemberWriter<Ctx, NestMemberWriterState::Start> {
+    type Context = Ctx;
+
+    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+        Ok(NestMemberWriter {
+            context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteDisassembler for NestMemberWriter<Ctx, NestMemberWriterState::End> {
+    type Context = Ctx;
+
+    fn finish(self) -> Result<Self::Context, EncodeError> {
+        Ok(self.context)
+    }
+}
+
+impl<Ctx, State: NestMemberWriterState::State> fmt::Debug for NestMemberWriter<Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NestMemberWriter").finish()
+    }
+}
+
+enc_state!(pub mod NestMemberWriterState: Start, End);
+
+pub struct PermittedSubclassWriter<Ctx, State: PermittedSubclassWriterState::State> {
+    context: Ctx,
+    _marker: PhantomData<State>,
+}
+
+impl<Ctx: EncoderContext> PermittedSubclassWriter<Ctx, PermittedSubclassWriterState::Start> {
+    pub fn subclass<I>(
+        mut self,
+        class: I,
+    ) -> Result<PermittedSubclassWriter<Ctx, PermittedSubclassWriterState::End>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Class>,
+    {
+        let index = class.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+
+        Ok(PermittedSubclassWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteAssembler for PermittedSubclassWriter<Ctx, PermittedSubclassWriterState::Start> {
+    type Context = Ctx;
+
+    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+        Ok(PermittedSubclassWriter {
+            context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteDisassembler for PermittedSubclassWriter<Ctx, PermittedSubclassWriterState::End> {
+    type Context = Ctx;
+
+    fn finish(self) -> Result<Self::Context, EncodeError> {
+        Ok(self.context)
+    }
+}
+
+impl<Ctx, State: PermittedSubclassWriterState::State> fmt::Debug for PermittedSubclassWriter<Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PermittedSubclassWriter").finish()
+    }
+}
+
+enc_state!(pub mod PermittedSubclassWriterState: Start, End);
+
+pub struct RecordComponentWriter<Ctx, State: RecordComponentWriterState::State> {
+    context: Ctx,
+    _marker: PhantomData<State>,
+}
+
+impl<Ctx: EncoderContext> RecordComponentWriter<Ctx, RecordComponentWriterState::Name> {
+    pub fn name<I>(
+        mut self,
+        name: I,
+    ) -> Result<RecordComponentWriter<Ctx, RecordComponentWriterState::Descriptor>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Utf8>,
+    {
+        let index = name.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+
+        Ok(RecordComponentWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> RecordComponentWriter<Ctx, RecordComponentWriterState::Descriptor> {
+    pub fn descriptor<I>(
+        mut self,
+        descriptor: I,
+    ) -> Result<RecordComponentWriter<Ctx, RecordComponentWriterState::Attributes>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Utf8>,
+    {
+        let index = descriptor.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+
+        Ok(RecordComponentWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> RecordComponentWriter<Ctx, RecordComponentWriterState::Attributes> {
+    /// Writes the attributes of the component, such as `Signature` or annotations.
+    pub fn attributes<F>(
+        mut self,
+        f: F,
+    ) -> Result<RecordComponentWriter<Ctx, RecordComponentWriterState::End>, EncodeError>
+    where
+        F: FnOnce(&mut ManyWriter<AttributeWriter<Ctx, AttributeWriterState::Start>, u16>) -> Result<(), EncodeError>,
+    {
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+
+        Ok(RecordComponentWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteAssembler for RecordComponentWriter<Ctx, RecordComponentWriterState::Name> {
+    type Context = Ctx;
+
+    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+        Ok(RecordComponentWriter {
+            context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteDisassembler for RecordComponentWriter<Ctx, RecordComponentWriterState::End> {
+    type Context = Ctx;
+
+    fn finish(self) -> Result<Self::Context, EncodeError> {
+        Ok(self.context)
+    }
+}
+
+impl<Ctx, State: RecordComponentWriterState::State> fmt::Debug for RecordComponentWriter<Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordComponentWriter").finish()
+    }
+}
+
+enc_state!(pub mod RecordComponentWriterState: Name, Descriptor, Attributes, End);
+
+#[cfg(test)]
+mod test {
+    use crate::reader::attributes::AttributeContent;
+    use crate::reader::{cpool, Class};
+    use crate::testing::{attribute, write_class};
+
+    #[test]
+    fn record() {
+        let bytes = write_class(
+            |_| Ok(()),
+            |attributes| {
+                attributes.begin(|attribute| {
+                    attribute.record(|components| {
+                        components.begin(|component| component.name("x")?.descriptor("I")?.attributes(|_| Ok(())))?;
+                        components.begin(|component| {
+                            component
+                                .name("names")?
+                                .descriptor("Ljava/util/List;")?
+                                .attributes(|attributes| {
+                                    attributes.begin(|attribute| {
+                                        attribute.signature("Ljava/util/List<Ljava/lang/String;>;")
+                                    })?;
+                                    Ok(())
+                                })
+                        })?;
+                        Ok(())
+                    })
+                })?;
+                Ok(())
+            },
+        );
+        let class = Class::new(&bytes).unwrap();
+        let pool = class.pool();
+        let AttributeContent::Record(record) = attribute(&class, class.attributes(), "Record") else {
+            panic!("expected a record");
+        };
+        let components: Vec<_> = record.components().iter().map(Result::unwrap).collect();
+        assert_eq!(components.len(), 2);
+
+        assert_eq!(pool.retrieve(components[0].name()).unwrap(), "x");
+        assert_eq!(pool.retrieve(components[0].descriptor()).unwrap(), "I");
+        assert_eq!(components[0].attributes().iter().count(), 0);
+
+        assert_eq!(pool.retrieve(components[1].name()).unwrap(), "names");
+        assert_eq!(pool.retrieve(components[1].descriptor()).unwrap(), "Ljava/util/List;");
+        let AttributeContent::Signature(signature) = attribute(&class, components[1].attributes(), "Signature") else {
+            panic!("expected a signature");
+        };
+        assert_eq!(
+            pool.retrieve(signature.signature()).unwrap(),
+            "Ljava/util/List<Ljava/lang/String;>;"
+        );
+    }
+
+    #[test]
+    fn nests_and_permitted_subclasses() {
+        let bytes = write_class(
+            |_| Ok(()),
+            |attributes| {
+                attributes.begin(|attribute| attribute.nest_host("Outer"))?;
+                attributes.begin(|attribute| {
+                    attribute.nest_members(|members| {
+                        members.begin(|member| member.member("Test$A"))?;
+                        members.begin(|member| member.member("Test$B"))?;
+                        Ok(())
+                    })
+                })?;
+                attributes.begin(|attribute| {
+                    attribute.permitted_subclasses(|subclasses| {
+                        subclasses.begin(|subclass| subclass.subclass("Test$A"))?;
+                        Ok(())
+                    })
+                })?;
+                Ok(())
+            },
+        );
+        let class = Class::new(&bytes).unwrap();
+        let pool = class.pool();
+        let class_name =
+            |index: cpool::Index<cpool::Class<'_>>| pool.retrieve(index).unwrap().name.to_str().unwrap().to_owned();
+
+        let AttributeContent::NestHost(host) = attribute(&class, class.attributes(), "NestHost") else {
+            panic!("expected a nest host");
+        };
+        assert_eq!(class_name(host.host_class()), "Outer");
+
+        let AttributeContent::NestMembers(members) = attribute(&class, class.attributes(), "NestMembers") else {
+            panic!("expected nest members");
+        };
+        let members: Vec<_> = members
+            .classes()
+            .iter()
+            .map(|member| class_name(member.unwrap()))
+            .collect();
+        assert_eq!(members, ["Test$A", "Test$B"]);
+
+        let AttributeContent::PermittedSubclasses(subclasses) =
+            attribute(&class, class.attributes(), "PermittedSubclasses")
+        else {
+            panic!("expected permitted subclasses");
+        };
+        let subclasses: Vec<_> = subclasses
+            .classes()
+            .iter()
+            .map(|subclass| class_name(subclass.unwrap()))
+            .collect();
+        assert_eq!(subclasses, ["Test$A"]);
+    }
+}
@@ -1,23 +1,30 @@
 mod exception_table;
+mod frames;
 pub mod instructions;
 mod line_number_table;
 mod local_variable_table;
 mod local_variable_type_table;
+mod maxs;
 pub mod stack_map;
 
 pub use exception_table::{ExceptionWriter, ExceptionWriterState};
-pub use instructions::InstructionWriter;
+pub use instructions::{InstructionWriter, Local};
 pub use line_number_table::{LineNumberWriter, LineNumberWriterState};
 pub use local_variable_table::{LocalVariableWriter, LocalVariableWriterState};
 pub use local_variable_type_table::{LocalVariableTypeWriter, LocalVariableTypeWriterState};
 pub use stack_map::StackMapTableWriter;
 
+use crate::descriptor::MethodDescriptor;
 use crate::error::*;
+use crate::header::AccessFlags;
+use crate::hierarchy::ClassHierarchy;
+use crate::mutf8::MStr;
 use crate::writer::cpool;
 use crate::writer::{
     attributes::{AttributeWriter, AttributeWriterState},
     encoding::*,
 };
+use crate::Opcode;
 use std::num::NonZeroU32;
 use std::{fmt, marker::PhantomData};
 
@@ -41,7 +48,21 @@ impl<Ctx: EncoderContext> AttributeWriter<Ctx, AttributeWriterState::Start> {
 
 pub struct CodeWriter<Ctx, State: CodeWriterState::State> {
     context: Ctx,
+    /// The offset of `max_stack`.
+    start_offset: Offset,
+    /// The values which are computed automatically once the exception table is written.
+    computation: Option<Computation>,
     label_positions: Vec<Option<NonZeroU32>>,
+    /// The handler positions of the exception table entries written so far.
+    exception_handlers: Vec<u32>,
+    /// The exception table entries recorded by [`InstructionWriter::try_catch`].
+    exception_entries: Vec<ExceptionEntry>,
+    /// The local variables allocated by [`InstructionWriter::begin_local`].
+    local_variables: instructions::LocalVariables,
+    /// The line numbers recorded by [`InstructionWriter::line`] and where they start.
+    line_numbers: Vec<(LabelRef, u16)>,
+    /// Whether the instructions are optimized before the labels are resolved.
+    optimize: bool,
     _marker: PhantomData<State>,
 }
 
@@ -50,12 +71,94 @@ impl<Ctx: EncoderContext> CodeWriter<Ctx, CodeWriterState::MaxStack> {
         self.context.encoder().write(max_stack)?;
         Ok(CodeWriter {
             context: self.context,
+            start_offset: self.start_offset,
+            computation: self.computation,
             label_positions: self.label_positions,
+            exception_handlers: self.exception_handlers,
+            exception_entries: self.exception_entries,
+            local_variables: self.local_variables,
+            line_numbers: self.line_numbers,
+            optimize: self.optimize,
             _marker: PhantomData,
         })
     }
 }
 
+impl<Ctx: EncoderContext> CodeWriter<Ctx, CodeWriterState::MaxStack> {
+    /// Computes `max_stack` and `max_locals` from the instructions, the exception handlers and the method descriptor
+    /// once the exception table is written, instead of taking them up front.
+    pub fn compute_maxs(
+        mut self,
+        access_flags: AccessFlags,
+        descriptor: &MethodDescriptor<'_>,
+    ) -> Result<CodeWriter<Ctx, CodeWriterState::Instructions>, EncodeError> {
+        // both values are replaced later on
+        self.context.encoder().write(0u16)?.write(0u16)?;
+        self.local_variables.reserve_parameters(access_flags, descriptor)?;
+        Ok(CodeWriter {
+            context: self.context,
+            start_offset: self.start_offset,
+            computation: Some(Computation {
+                parameter_slots: maxs::parameter_slots(access_flags, descriptor)?,
+                frames: None,
+                pending: false,
+            }),
+            label_positions: self.label_positions,
+            exception_handlers: self.exception_handlers,
+            exception_entries: self.exception_entries,
+            local_variables: self.local_variables,
+            line_numbers: self.line_numbers,
+            optimize: self.optimize,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Computes the stack map frames in addition to `max_stack` and `max_locals`.
+    ///
+    /// The frames are computed by [`CodeWriter::frames`] once the exception table is written, which has to be called
+    /// before the attributes are written. The `StackMapTable` attribute is written after the other attributes of
+    /// the code.
+    pub fn compute_frames<I>(
+        mut self,
+        this_class: I,
+        access_flags: AccessFlags,
+        name: &MStr,
+        descriptor: &MethodDescriptor<'_>,
+    ) -> Result<CodeWriter<Ctx, CodeWriterState::Instructions>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Class>,
+    {
+        let this_class = this_class.insert(&mut self.context)?;
+        let this_class = maxs::class_name(&self.context, this_class.as_u16())?.to_owned();
+        let frames = frames::FrameComputation::new(&this_class, access_flags, name, descriptor);
+
+        let mut writer = self.compute_maxs(access_flags, descriptor)?;
+        if let Some(computation) = &mut writer.computation {
+            computation.frames = Some(Box::new(frames));
+            computation.pending = true;
+        }
+        Ok(writer)
+    }
+}
+
+/// An exception table entry which is written before the ones of [`CodeWriter::exceptions`].
+struct ExceptionEntry {
+    start: LabelRef,
+    end: LabelRef,
+    handler: LabelRef,
+    /// The caught class, or `None` if every exception is caught.
+    catch_type: Option<cpool::Index<cpool::Class>>,
+}
+
+/// The values which are computed automatically once the exception table is written.
+struct Computation {
+    /// The amount of slots taken up by `this` and the parameters.
+    parameter_slots: u16,
+    frames: Option<Box<frames::FrameComputation>>,
+    /// Whether the frames still have to be computed by [`CodeWriter::frames`].
+    pending: bool,
+}
+
 impl<Ctx: EncoderContext> CodeWriter<Ctx, CodeWriterState::MaxLocals> {
     pub fn max_locals(
         mut self,
@@ -64,13 +167,45 @@ impl<Ctx: EncoderContext> CodeWriter<Ctx, CodeWriterState::MaxLocals> {
         self.context.encoder().write(max_locals)?;
         Ok(CodeWriter {
             context: self.context,
+            start_offset: self.start_offset,
+            computation: self.computation,
             label_positions: self.label_positions,
+            exception_handlers: self.exception_handlers,
+            exception_entries: self.exception_entries,
+            local_variables: self.local_variables,
+            line_numbers: self.line_numbers,
+            optimize: self.optimize,
             _marker: PhantomData,
         })
     }
 }
 
 impl<Ctx: EncoderContext> CodeWriter<Ctx, CodeWriterState::Instructions> {
+    /// Removes unneeded instructions once all instructions are written, such as `nop`s, jumps to the next
+    /// instruction and branches on constants.
+    ///
+    /// Local variables which are stored and then immediately loaded are removed as well, unless they were declared
+    /// by [`InstructionWriter::begin_local`]. This changes the frames at later instructions, so the stack map
+    /// frames should be computed in that case. Writing a `StackMapTable`, `LocalVariableTable` or
+    /// `LocalVariableTypeTable` attribute by hand fails once a local variable was removed.
+    pub fn optimize(mut self) -> Result<Self, EncodeError> {
+        self.optimize = true;
+        Ok(self)
+    }
+
+    /// Reserves the local variable slots of `this` and the parameters of the method, so that
+    /// [`InstructionWriter::begin_local`] allocates local variables after them.
+    ///
+    /// This happens automatically when using [`CodeWriter::compute_maxs`], in which case it may not be done again.
+    pub fn parameters(
+        mut self,
+        access_flags: AccessFlags,
+        descriptor: &MethodDescriptor<'_>,
+    ) -> Result<Self, EncodeError> {
+        self.local_variables.reserve_parameters(access_flags, descriptor)?;
+        Ok(self)
+    }
+
     pub fn instructions<F>(mut self, f: F) -> Result<CodeWriter<Ctx, CodeWriterState::ExceptionTable>, EncodeError>
     where
         F: for<'f> FnOnce(&'f mut InstructionWriter<Ctx>) -> Result<(), EncodeError>,
@@ -83,7 +218,14 @@ impl<Ctx: EncoderContext> CodeWriter<Ctx, CodeWriterState::Instructions> {
 
         Ok(CodeWriter {
             context: self.context,
+            start_offset: self.start_offset,
+            computation: self.computation,
             label_positions: self.label_positions,
+            exception_handlers: self.exception_handlers,
+            exception_entries: self.exception_entries,
+            local_variables: self.local_variables,
+            line_numbers: self.line_numbers,
+            optimize: self.optimize,
             _marker: PhantomData,
         })
     }
@@ -94,18 +236,231 @@ impl<Ctx: EncoderContext> CodeWriter<Ctx, CodeWriterState::ExceptionTable> {
     where
         F: FnOnce(&mut ManyWriter<ExceptionWriter<Ctx, ExceptionWriterState::Start>, u16>) -> Result<(), EncodeError>,
     {
-        let mut builder = ManyWriter::new(self)?;
+        let entries = std::mem::take(&mut self.exception_entries);
+        let mut builder: ManyWriter<ExceptionWriter<Ctx, ExceptionWriterState::Start>, u16> = ManyWriter::new(self)?;
+        for entry in entries {
+            builder.begin(|writer| {
+                let writer = writer.start(entry.start)?.end(entry.end)?.handler(entry.handler)?;
+                match entry.catch_type {
+                    Some(catch_type) => writer.catch_type(catch_type),
+                    None => writer.catch_any(),
+                }
+            })?;
+        }
         f(&mut builder)?;
         self = builder.finish()?;
+
+        if self.optimize {
+            self.compact_exception_table()?;
+        }
+
+        // the frames are computed by `frames`, as they require the class hierarchy
+        if let Some(Computation {
+            parameter_slots,
+            frames: None,
+            ..
+        }) = self.computation
+        {
+            self.write_maxs(parameter_slots, 0)?;
+        }
+
         Ok(CodeWriter {
             context: self.context,
+            start_offset: self.start_offset,
+            computation: self.computation,
             label_positions: self.label_positions,
+            exception_handlers: self.exception_handlers,
+            exception_entries: self.exception_entries,
+            local_variables: self.local_variables,
+            line_numbers: self.line_numbers,
+            optimize: self.optimize,
             _marker: PhantomData,
         })
     }
 }
 
+impl<Ctx: EncoderContext, State: CodeWriterState::State> CodeWriter<Ctx, State> {
+    /// Returns the offset of the instructions and a copy of them.
+    fn code(&mut self) -> (Offset, Vec<u8>) {
+        let buf = self.context.encoder().buf();
+        let length_start = self.start_offset.offset(2 + 2).get();
+        let mut length = [0; 4];
+        length.copy_from_slice(&buf[length_start..length_start + 4]);
+        let code_start = length_start + 4;
+        let code = buf[code_start..code_start + u32::from_be_bytes(length) as usize].to_vec();
+        (Offset::new(code_start), code)
+    }
+
+    /// Computes `max_stack` and `max_locals` and replaces the placeholders written before.
+    fn write_maxs(&mut self, parameter_slots: u16, min_stack: u16) -> Result<(), EncodeError> {
+        let (_, code) = self.code();
+        let (max_stack, max_locals) =
+            maxs::compute_maxs(&self.context, &code, &self.exception_handlers, parameter_slots)?;
+        self.context
+            .encoder()
+            .replacing(self.start_offset)
+            .write(max_stack.max(min_stack))?
+            .write(max_locals)?;
+        Ok(())
+    }
+
+    /// Reads back the exception table, which is written right after the instructions.
+    fn exception_table(&mut self) -> (Offset, Vec<frames::ExceptionHandler>) {
+        let (code_start, code) = self.code();
+        let table_start = code_start.offset(code.len());
+        let table = &self.context.encoder().buf()[table_start.get()..];
+        let read = |at: usize| u16::from_be_bytes([table[at], table[at + 1]]);
+        let handlers = (0..read(0) as usize)
+            .map(|i| frames::ExceptionHandler {
+                start: read(2 + i * 8).into(),
+                end: read(4 + i * 8).into(),
+                handler: read(6 + i * 8).into(),
+                catch_type: read(8 + i * 8),
+            })
+            .collect();
+        (table_start, handlers)
+    }
+
+    /// Replaces the exception table starting at `table_start`, which must be the last thing written.
+    fn rewrite_exception_table(
+        &mut self,
+        table_start: Offset,
+        entries: &[frames::ExceptionHandler],
+    ) -> Result<(), EncodeError> {
+        let count = u16::try_from(entries.len())
+            .map_err(|_| EncodeError::with_context(EncodeErrorKind::TooManyItems, Context::Code))?;
+        self.context.encoder().truncate(table_start);
+        self.context.encoder().write(count)?;
+        self.exception_handlers.clear();
+        for entry in entries {
+            // the offsets were read from the table before, so they fit
+            self.context
+                .encoder()
+                .write(entry.start as u16)?
+                .write(entry.end as u16)?
+                .write(entry.handler as u16)?
+                .write(entry.catch_type)?;
+            self.exception_handlers.push(entry.handler);
+        }
+        Ok(())
+    }
+
+    /// Removes the exception table entries whose protected code was removed entirely when optimizing and merges
+    /// consecutive entries which became adjacent.
+    ///
+    /// This covers both the entries of [`InstructionWriter::try_catch`] and the ones written by hand, as their
+    /// labels were all relocated the same way.
+    fn compact_exception_table(&mut self) -> Result<(), EncodeError> {
+        let (table_start, handlers) = self.exception_table();
+        let mut entries: Vec<frames::ExceptionHandler> = Vec::with_capacity(handlers.len());
+        for handler in &handlers {
+            if handler.start >= handler.end {
+                continue;
+            }
+            match entries.last_mut() {
+                Some(last)
+                    if last.end == handler.start
+                        && last.handler == handler.handler
+                        && last.catch_type == handler.catch_type =>
+                {
+                    last.end = handler.end;
+                }
+                _ => entries.push(handler.clone()),
+            }
+        }
+        if entries.len() != handlers.len() {
+            self.rewrite_exception_table(table_start, &entries)?;
+        }
+        Ok(())
+    }
+
+    /// Computes the stack map frames, replaces unreachable code and removes it from the exception table.
+    ///
+    /// Returns whether any code was replaced.
+    fn write_frames(
+        &mut self,
+        computation: &mut frames::FrameComputation,
+        hierarchy: &dyn ClassHierarchy,
+    ) -> Result<bool, EncodeError> {
+        let (code_start, code) = self.code();
+        let (table_start, handlers) = self.exception_table();
+
+        let result = frames::compute_frames(&self.context, computation, hierarchy, &code, &handlers)?;
+        computation.frames = result.frames;
+        if result.unreachable.is_empty() {
+            return Ok(false);
+        }
+
+        for &(start, end) in &result.unreachable {
+            let mut encoder = self.context.encoder().replacing(code_start.offset(start as usize));
+            for _ in start..end - 1 {
+                encoder.write(Opcode::Nop)?;
+            }
+            encoder.write(Opcode::AThrow)?;
+        }
+
+        // the replaced code must not be covered by an exception handler, as it has a different frame
+        let mut entries = Vec::new();
+        for handler in &handlers {
+            let mut start = handler.start;
+            for &(unreachable_start, unreachable_end) in &result.unreachable {
+                if unreachable_end <= start || unreachable_start >= handler.end {
+                    continue;
+                }
+                if start < unreachable_start {
+                    entries.push(frames::ExceptionHandler {
+                        start,
+                        end: unreachable_start,
+                        ..handler.clone()
+                    });
+                }
+                start = unreachable_end;
+            }
+            if start < handler.end {
+                entries.push(frames::ExceptionHandler {
+                    start,
+                    ..handler.clone()
+                });
+            }
+        }
+        self.rewrite_exception_table(table_start, &entries)?;
+
+        Ok(true)
+    }
+}
+
 impl<Ctx: EncoderContext> CodeWriter<Ctx, CodeWriterState::Attributes> {
+    /// Computes the stack map frames requested by [`CodeWriter::compute_frames`], together with `max_stack` and
+    /// `max_locals`.
+    ///
+    /// Code which can never be reached is replaced by `nop`s followed by `athrow`, as it can't be described by frames.
+    /// The class hierarchy is used to find the common super class of two object types.
+    /// The `jsr` and `ret` instructions are not supported.
+    pub fn frames(mut self, hierarchy: &dyn ClassHierarchy) -> Result<Self, EncodeError> {
+        // the exception table was written last, so it can still be rewritten
+        let mut computation = match self.computation.take() {
+            Some(computation @ Computation { pending: true, .. }) => computation,
+            computation => {
+                self.computation = computation;
+                return Err(EncodeError::with_context(
+                    EncodeErrorKind::CantChangeAnymore,
+                    Context::Code,
+                ));
+            }
+        };
+        let mut min_stack = 0;
+        if let Some(frames) = &mut computation.frames {
+            if self.write_frames(frames, hierarchy)? {
+                // the replaced unreachable code throws the exception on the stack
+                min_stack = 1;
+            }
+        }
+        self.write_maxs(computation.parameter_slots, min_stack)?;
+        computation.pending = false;
+        self.computation = Some(computation);
+        Ok(self)
+    }
+
     pub fn attributes<F>(mut self, f: F) -> Result<CodeWriter<Ctx, CodeWriterState::End>, EncodeError>
     where
         F: FnOnce(
@@ -115,18 +470,90 @@ impl<Ctx: EncoderContext> CodeWriter<Ctx, CodeWriterState::Attributes> {
             >,
         ) -> Result<(), EncodeError>,
     {
+        if matches!(self.computation, Some(Computation { pending: true, .. })) {
+            return Err(EncodeError::with_context(EncodeErrorKind::ValuesMissing, Context::Code));
+        }
+        let count_offset = self.context.encoder().position();
+        let line_numbers = std::mem::take(&mut self.line_numbers);
         let mut builder = ManyWriter::new(self)?;
         f(&mut builder)?;
+        if !line_numbers.is_empty() {
+            builder.begin(|writer| {
+                writer.line_number_table(|table| {
+                    for &(start, line_number) in &line_numbers {
+                        table.begin(|writer| writer.start(start)?.line_number(line_number))?;
+                    }
+                    Ok(())
+                })
+            })?;
+        }
         self = builder.finish()?;
+        if self.local_variables.any_removed() {
+            self.check_local_attributes(count_offset)?;
+        }
+
+        // the attributes which are written automatically
+        let mut added = 0;
+        if let Some(frames) = self.computation.take().and_then(|computation| computation.frames) {
+            if !frames.frames.is_empty() {
+                frames::write_stack_map_table(&mut self.context, &frames)?;
+                added += 1;
+            }
+        }
+        let local_variables = std::mem::take(&mut self.local_variables);
+        added += local_variables.write_tables(&mut self)?;
+
+        if added > 0 {
+            let buf = self.context.encoder().buf();
+            let count = u16::from_be_bytes([buf[count_offset.get()], buf[count_offset.get() + 1]]);
+            let count = count
+                .checked_add(added)
+                .ok_or_else(|| EncodeError::with_context(EncodeErrorKind::TooManyItems, Context::Attributes))?;
+            self.context.encoder().replacing(count_offset).write(count)?;
+        }
 
         Ok(CodeWriter {
             context: self.context,
+            start_offset: self.start_offset,
+            computation: self.computation,
             label_positions: self.label_positions,
+            exception_handlers: self.exception_handlers,
+            exception_entries: self.exception_entries,
+            local_variables: self.local_variables,
+            line_numbers: self.line_numbers,
+            optimize: self.optimize,
             _marker: PhantomData,
         })
     }
 }
 
+impl<Ctx: EncoderContext> CodeWriter<Ctx, CodeWriterState::Attributes> {
+    /// Fails if one of the attributes written after `count_offset` describes the local variables, as they don't
+    /// know about the local variables removed when optimizing.
+    fn check_local_attributes(&mut self, count_offset: Offset) -> Result<(), EncodeError> {
+        let attributes = self.context.encoder().buf()[count_offset.get()..].to_vec();
+        let read = |at: usize| u16::from_be_bytes([attributes[at], attributes[at + 1]]);
+        let mut at = 2;
+        for _ in 0..read(0) {
+            let name = maxs::utf8(&self.context, read(at))?;
+            if name == "StackMapTable" || name == "LocalVariableTable" || name == "LocalVariableTypeTable" {
+                return Err(EncodeError::with_context(
+                    EncodeErrorKind::LocalVariableRemoved,
+                    Context::Attributes,
+                ));
+            }
+            let length = u32::from_be_bytes([
+                attributes[at + 2],
+                attributes[at + 3],
+                attributes[at + 4],
+                attributes[at + 5],
+            ]);
+            at += 6 + length as usize;
+        }
+        Ok(())
+    }
+}
+
 impl<Ctx: EncoderContext, State: CodeWriterState::State> CodeWriter<Ctx, State> {
     pub fn new_label(&mut self) -> Result<(Label, LabelRef), EncodeError> {
         let index = u32::try_from(self.label_positions.len())
@@ -135,7 +562,7 @@ impl<Ctx: EncoderContext, State: CodeWriterState::State> CodeWriter<Ctx, State>
         Ok((Label(index), LabelRef(index)))
     }
 
-    fn get_label_position(&self, label: LabelRef) -> Result<u32, EncodeError> {
+    pub(crate) fn get_label_position(&self, label: LabelRef) -> Result<u32, EncodeError> {
         if let Some(pos) = self.label_positions[label.0 as usize] {
             Ok(pos.get() - 1)
         } else {
@@ -143,7 +570,7 @@ impl<Ctx: EncoderContext, State: CodeWriterState::State> CodeWriter<Ctx, State>
         }
     }
 
-    fn get_label_position_u16(&self, label: LabelRef) -> Result<u16, EncodeError> {
+    pub(crate) fn get_label_position_u16(&self, label: LabelRef) -> Result<u16, EncodeError> {
         let position = self.get_label_position(label)?;
         position
             .try_into()
@@ -151,6 +578,20 @@ impl<Ctx: EncoderContext, State: CodeWriterState::State> CodeWriter<Ctx, State>
     }
 }
 
+impl<Ctx: EncoderContext> CodeWriter<Ctx, CodeWriterState::Attributes> {
+    /// Returns the index of the first exception table entry with the given handler.
+    pub(crate) fn get_exception_table_index(&self, handler: LabelRef) -> Result<u16, EncodeError> {
+        let position = self.get_label_position(handler)?;
+        let index = self
+            .exception_handlers
+            .iter()
+            .position(|&handler| handler == position)
+            .ok_or_else(|| EncodeError::with_context(EncodeErrorKind::LabelNotFound, Context::AttributeContent))?;
+        u16::try_from(index)
+            .map_err(|_| EncodeError::with_context(EncodeErrorKind::IndexNotFitting, Context::AttributeContent))
+    }
+}
+
 impl<Ctx: EncoderContext, State: CodeWriterState::State> InternalEncoderContext for CodeWriter<Ctx, State> {
     fn encoder(&mut self) -> &mut VecEncoder {
         self.context.encoder()
@@ -159,15 +600,31 @@ impl<Ctx: EncoderContext, State: CodeWriterState::State> InternalEncoderContext
     fn insert_constant<I: Into<cpool::Item>>(&mut self, item: I) -> Result<cpool::Index<I>, EncodeError> {
         self.context.insert_constant(item)
     }
+
+    fn insert_bootstrap_method(&mut self, method: cpool::BootstrapMethod) -> Result<u16, EncodeError> {
+        self.context.insert_bootstrap_method(method)
+    }
+
+    fn get_constant(&self, index: u16) -> Option<&cpool::Item> {
+        self.context.get_constant(index)
+    }
 }
 
 impl<Ctx: EncoderContext> WriteAssembler for CodeWriter<Ctx, CodeWriterState::MaxStack> {
     type Context = Ctx;
 
-    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+    fn new(mut context: Self::Context) -> Result<Self, EncodeError> {
+        let start_offset = context.encoder().position();
         Ok(CodeWriter {
             context,
+            start_offset,
+            computation: None,
             label_positions: Vec::new(),
+            exception_handlers: Vec::new(),
+            exception_entries: Vec::new(),
+            local_variables: instructions::LocalVariables::default(),
+            line_numbers: Vec::new(),
+            optimize: false,
             _marker: PhantomData,
         })
     }
@@ -37,6 +37,7 @@ impl<Ctx: EncoderContext> ExceptionWriter<Ctx, ExceptionWriterState::Handler> {
     ) -> Result<ExceptionWriter<Ctx, ExceptionWriterState::CatchType>, EncodeError> {
         let position = self.context.get_label_position_u16(label)?;
         self.context.encoder().write(position)?;
+        self.context.exception_handlers.push(position.into());
 
         Ok(ExceptionWriter {
             context: self.context,
@@ -61,6 +62,16 @@ impl<Ctx: EncoderContext> ExceptionWriter<Ctx, ExceptionWriterState::CatchType>
             _marker: PhantomData,
         })
     }
+
+    /// Catches every exception, as it is done for `finally` blocks.
+    pub fn catch_any(mut self) -> Result<ExceptionWriter<Ctx, ExceptionWriterState::End>, EncodeError> {
+        self.context.encoder().write(0u16)?;
+
+        Ok(ExceptionWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
 }
 
 impl<Ctx: EncoderContext> WriteAssembler for ExceptionWriter<Ctx, ExceptionWriterState::Start> {
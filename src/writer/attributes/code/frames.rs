@@ -0,0 +1,608 @@
+use super::maxs;
+use crate::analysis::interpreter::{initial_frame, Constants, Interpreter, Loadable};
+use crate::analysis::{Frame, VerificationType};
+use crate::descriptor::MethodDescriptor;
+use crate::error::*;
+use crate::header::AccessFlags;
+use crate::hierarchy::ClassHierarchy;
+use crate::mutf8::{MStr, MString};
+use crate::reader::attributes::{Index, RawInstruction};
+use crate::writer::{cpool, encoding::*};
+
+/// An entry of the exception table.
+#[derive(Debug, Clone)]
+pub(super) struct ExceptionHandler {
+    pub(super) start: u32,
+    pub(super) end: u32,
+    pub(super) handler: u32,
+    /// The index of the caught class, or 0 if every exception is caught.
+    pub(super) catch_type: u16,
+}
+
+/// The information required to compute the frames of a method.
+pub(super) struct FrameComputation {
+    this_class: MString,
+    initial: Frame,
+    /// The frames which have to be written to the stack map table, ordered by their offsets.
+    pub(super) frames: Vec<(u32, Frame)>,
+}
+
+impl FrameComputation {
+    pub(super) fn new(
+        this_class: &MStr,
+        access_flags: AccessFlags,
+        name: &MStr,
+        descriptor: &MethodDescriptor<'_>,
+    ) -> FrameComputation {
+        FrameComputation {
+            this_class: this_class.to_owned(),
+            initial: initial_frame(this_class, access_flags, name, descriptor),
+            frames: Vec::new(),
+        }
+    }
+}
+
+/// The result of the frame computation.
+#[derive(Debug)]
+pub(super) struct Frames {
+    pub(super) frames: Vec<(u32, Frame)>,
+    /// The ranges of code which are never executed.
+    pub(super) unreachable: Vec<(u32, u32)>,
+}
+
+/// Computes the frames at the start of each basic block which is either a jump target or an exception handler.
+///
+/// Code which can never be reached does not have a frame, so it has to be replaced by `nop`s followed by `athrow`.
+/// For this reason, those ranges get the frame of a block throwing an exception.
+pub(super) fn compute_frames<Ctx: EncoderContext>(
+    context: &Ctx,
+    computation: &FrameComputation,
+    hierarchy: &dyn ClassHierarchy,
+    code: &[u8],
+    handlers: &[ExceptionHandler],
+) -> Result<Frames, EncodeError> {
+    let instructions: Vec<_> = maxs::decode(code)?
+        .into_iter()
+        .map(|(offset, instruction)| (Index::new(offset), instruction))
+        .collect();
+    let interpreter = Interpreter {
+        constants: &Pool(context),
+        this_class: &computation.this_class,
+        hierarchy,
+        instructions: &instructions,
+    };
+    let position_of = |offset: u32| {
+        interpreter
+            .position_of(Index::new(offset))
+            .ok_or_else(|| EncodeError::with_context(EncodeErrorKind::LabelNotFound, Context::Code))
+    };
+
+    let mut frames: Vec<Option<Frame>> = vec![None; instructions.len()];
+    let mut is_target = vec![false; instructions.len()];
+    let mut queue = Queue {
+        indices: Vec::new(),
+        queued: vec![false; instructions.len()],
+    };
+    merge(&interpreter, &mut frames, &mut queue, 0, computation.initial.clone())?;
+
+    while let Some(index) = queue.pop() {
+        let frame = frames[index].clone().expect("queued instructions have a frame");
+        let (offset, instruction) = &instructions[index];
+        let offset = offset.as_u32();
+        if matches!(
+            instruction,
+            RawInstruction::JSr { .. }
+                | RawInstruction::JSrW { .. }
+                | RawInstruction::Ret { .. }
+                | RawInstruction::RetW { .. }
+        ) {
+            return Err(EncodeError::with_context(
+                EncodeErrorKind::UnsupportedInstruction,
+                Context::Code,
+            ));
+        }
+        let mut next = frame.clone();
+        interpreter.execute(Index::new(offset), instruction, &mut next)?;
+
+        for handler in handlers {
+            if (handler.start..handler.end).contains(&offset) {
+                let catch_type = if handler.catch_type == 0 {
+                    MString::from("java/lang/Throwable")
+                } else {
+                    maxs::class_name(context, handler.catch_type)?.to_owned()
+                };
+                let target = position_of(handler.handler)?;
+                is_target[target] = true;
+
+                // stores are checked against the handler both before and after they are executed
+                for locals in [&frame.locals, &next.locals] {
+                    let handler_frame = Frame {
+                        locals: locals.clone(),
+                        stack: vec![VerificationType::Object(catch_type.clone())],
+                    };
+                    merge(&interpreter, &mut frames, &mut queue, target, handler_frame)?;
+                }
+            }
+        }
+
+        let (targets, falls_through) = maxs::successors(offset, instruction);
+        for target in targets {
+            let target = position_of(target)?;
+            is_target[target] = true;
+            merge(&interpreter, &mut frames, &mut queue, target, next.clone())?;
+        }
+        if falls_through && index + 1 < instructions.len() {
+            merge(&interpreter, &mut frames, &mut queue, index + 1, next)?;
+        }
+    }
+
+    let mut result = Frames {
+        frames: Vec::new(),
+        unreachable: Vec::new(),
+    };
+    let mut index = 0;
+    while index < instructions.len() {
+        let offset = instructions[index].0.as_u32();
+        if let Some(frame) = &frames[index] {
+            if is_target[index] {
+                result.frames.push((offset, frame.clone()));
+            }
+            index += 1;
+        } else {
+            while index < instructions.len() && frames[index].is_none() {
+                index += 1;
+            }
+            let end = instructions
+                .get(index)
+                .map_or(code.len() as u32, |(offset, _)| offset.as_u32());
+            result.unreachable.push((offset, end));
+            result.frames.push((
+                offset,
+                Frame {
+                    locals: Vec::new(),
+                    stack: vec![VerificationType::Object(MString::from("java/lang/Throwable"))],
+                },
+            ));
+        }
+    }
+
+    Ok(result)
+}
+
+/// The instructions whose frames changed and have to be executed again.
+struct Queue {
+    indices: Vec<usize>,
+    /// Whether the instruction at an index is in `indices`.
+    queued: Vec<bool>,
+}
+
+impl Queue {
+    fn push(&mut self, index: usize) {
+        if !self.queued[index] {
+            self.queued[index] = true;
+            self.indices.push(index);
+        }
+    }
+
+    fn pop(&mut self) -> Option<usize> {
+        let index = self.indices.pop()?;
+        self.queued[index] = false;
+        Some(index)
+    }
+}
+
+/// Merges a frame into the frame of the instruction at the index and queues the instruction if it changed.
+fn merge<Ctx: EncoderContext>(
+    interpreter: &Interpreter<'_, Pool<'_, Ctx>>,
+    frames: &mut [Option<Frame>],
+    queue: &mut Queue,
+    index: usize,
+    frame: Frame,
+) -> Result<(), EncodeError> {
+    match &mut frames[index] {
+        Some(current) => {
+            if interpreter.merge(current, &frame)? {
+                queue.push(index);
+            }
+        }
+        None => {
+            frames[index] = Some(frame);
+            queue.push(index);
+        }
+    }
+    Ok(())
+}
+
+/// The constant pool of the class being written.
+struct Pool<'a, Ctx>(&'a Ctx);
+
+impl<Ctx: EncoderContext> Constants for Pool<'_, Ctx> {
+    type Error = EncodeError;
+
+    fn invalid_stack(&self) -> EncodeError {
+        EncodeError::with_context(EncodeErrorKind::InvalidStackState, Context::Code)
+    }
+
+    fn invalid_descriptor(&self, _: DecodeError) -> EncodeError {
+        EncodeError::with_context(EncodeErrorKind::InvalidConstant, Context::Code)
+    }
+
+    fn loadable(&self, index: u16) -> Result<Loadable<'_>, EncodeError> {
+        Ok(match self.0.get_constant(index) {
+            Some(cpool::Item::Integer(_)) => Loadable::Integer,
+            Some(cpool::Item::Float(_)) => Loadable::Float,
+            Some(cpool::Item::Long(_)) => Loadable::Long,
+            Some(cpool::Item::Double(_)) => Loadable::Double,
+            Some(cpool::Item::String(_)) => Loadable::String,
+            Some(cpool::Item::Class(_)) => Loadable::Class,
+            Some(cpool::Item::MethodType(_)) => Loadable::MethodType,
+            Some(cpool::Item::MethodHandle(_)) => Loadable::MethodHandle,
+            Some(cpool::Item::Dynamic(_)) => Loadable::Dynamic(maxs::member(self.0, index)?.1),
+            _ => {
+                return Err(EncodeError::with_context(
+                    EncodeErrorKind::InvalidConstant,
+                    Context::Code,
+                ))
+            }
+        })
+    }
+
+    fn member(&self, index: u16) -> Result<(&MStr, &MStr), EncodeError> {
+        maxs::member(self.0, index)
+    }
+
+    fn class_name(&self, index: u16) -> Result<&MStr, EncodeError> {
+        maxs::class_name(self.0, index)
+    }
+}
+
+/// Writes the `StackMapTable` attribute for the frames, using the most compact frame types.
+pub(super) fn write_stack_map_table<Ctx: EncoderContext>(
+    context: &mut Ctx,
+    computation: &FrameComputation,
+) -> Result<(), EncodeError> {
+    let name = context.insert_constant(cpool::Utf8 {
+        content: MString::from("StackMapTable"),
+    })?;
+    context.encoder().write(name)?;
+    let length_writer = LengthWriter::new(context)?;
+
+    let frames = &computation.frames;
+    let count = u16::try_from(frames.len())
+        .map_err(|_| EncodeError::with_context(EncodeErrorKind::TooManyItems, Context::AttributeContent))?;
+    context.encoder().write(count)?;
+
+    let mut previous_locals = compress_locals(&computation.initial.locals);
+    let mut previous_offset = None;
+    for (offset, frame) in frames {
+        let offset_delta = match previous_offset {
+            Some(previous_offset) => offset - previous_offset - 1,
+            None => *offset,
+        };
+        let offset_delta = u16::try_from(offset_delta)
+            .map_err(|_| EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::AttributeContent))?;
+        previous_offset = Some(*offset);
+
+        let locals = compress_locals(&frame.locals);
+        let stack = compress(&frame.stack);
+        let same_locals = locals == previous_locals;
+
+        if same_locals && stack.is_empty() {
+            if offset_delta < 64 {
+                context.encoder().write(offset_delta as u8)?;
+            } else {
+                context.encoder().write(251u8)?.write(offset_delta)?;
+            }
+        } else if same_locals && stack.len() == 1 {
+            if offset_delta < 64 {
+                context.encoder().write(64 + offset_delta as u8)?;
+            } else {
+                context.encoder().write(247u8)?.write(offset_delta)?;
+            }
+            write_verification_type(context, stack[0])?;
+        } else if stack.is_empty()
+            && locals.len() < previous_locals.len()
+            && previous_locals.len() - locals.len() <= 3
+            && previous_locals.starts_with(&locals)
+        {
+            let chopped = (previous_locals.len() - locals.len()) as u8;
+            context.encoder().write(251 - chopped)?.write(offset_delta)?;
+        } else if stack.is_empty()
+            && locals.len() > previous_locals.len()
+            && locals.len() - previous_locals.len() <= 3
+            && locals.starts_with(&previous_locals)
+        {
+            let appended = &locals[previous_locals.len()..];
+            context
+                .encoder()
+                .write(251 + appended.len() as u8)?
+                .write(offset_delta)?;
+            for ty in appended {
+                write_verification_type(context, ty)?;
+            }
+        } else {
+            context.encoder().write(255u8)?.write(offset_delta)?;
+            for types in [&locals, &stack] {
+                let count = u16::try_from(types.len())
+                    .map_err(|_| EncodeError::with_context(EncodeErrorKind::TooManyItems, Context::AttributeContent))?;
+                context.encoder().write(count)?;
+                for ty in types {
+                    write_verification_type(context, ty)?;
+                }
+            }
+        }
+
+        previous_locals = locals;
+    }
+
+    length_writer.finish(context)?;
+    Ok(())
+}
+
+/// Removes the second slots of `long` and `double` values.
+fn compress(types: &[VerificationType]) -> Vec<&VerificationType> {
+    let mut compressed = Vec::with_capacity(types.len());
+    let mut iter = types.iter();
+    while let Some(ty) = iter.next() {
+        compressed.push(ty);
+        if ty.is_wide() {
+            iter.next();
+        }
+    }
+    compressed
+}
+
+/// Compresses the local variables and removes the trailing unused ones.
+///
+/// Unlike local variables, values of unusable types on the stack are still part of the frame.
+fn compress_locals(types: &[VerificationType]) -> Vec<&VerificationType> {
+    let mut compressed = compress(types);
+    while compressed.last() == Some(&&VerificationType::Top) {
+        compressed.pop();
+    }
+    compressed
+}
+
+fn write_verification_type<Ctx: EncoderContext>(context: &mut Ctx, ty: &VerificationType) -> Result<(), EncodeError> {
+    match ty {
+        VerificationType::Top => context.encoder().write(0u8)?,
+        VerificationType::Integer => context.encoder().write(1u8)?,
+        VerificationType::Float => context.encoder().write(2u8)?,
+        VerificationType::Double => context.encoder().write(3u8)?,
+        VerificationType::Long => context.encoder().write(4u8)?,
+        VerificationType::Null => context.encoder().write(5u8)?,
+        VerificationType::UninitializedThis => context.encoder().write(6u8)?,
+        VerificationType::Object(name) => {
+            let index = cpool::Insertable::<cpool::Class>::insert(name.clone(), context)?;
+            context.encoder().write(7u8)?.write(index)?
+        }
+        VerificationType::UninitializedVariable(offset) => {
+            let offset = u16::try_from(offset.as_u32())
+                .map_err(|_| EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::AttributeContent))?;
+            context.encoder().write(8u8)?.write(offset)?
+        }
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::descriptor::MethodDescriptor;
+    use crate::error::{EncodeError, EncodeErrorKind};
+    use crate::header::AccessFlags;
+    use crate::hierarchy::ClassHierarchy;
+    use crate::mutf8;
+    use crate::mutf8::{MStr, MString};
+    use crate::reader::attributes::{AttributeContent, RawInstruction, StackMapFrame, VerificationType};
+    use crate::reader::Class;
+    use crate::testing::{attribute, first_code, try_write_code, write_code};
+    use crate::writer::attributes::code::InstructionWriter;
+    use crate::writer::cpool::{self, MethodRef};
+    use crate::writer::{ClassWriter, ClassWriterState};
+
+    struct Hierarchy;
+
+    impl ClassHierarchy for Hierarchy {
+        fn super_class(&self, class: &MStr) -> Option<MString> {
+            match class.as_bytes() {
+                b"java/lang/StringBuilder" | b"java/lang/String" => Some(MString::from("java/lang/Object")),
+                _ => None,
+            }
+        }
+
+        fn is_interface(&self, _: &MStr) -> bool {
+            false
+        }
+    }
+
+    /// Writes a static method `m` with computed frames and describes the frames read back from it.
+    fn frames<F>(descriptor: &str, initial_locals: &[&str], f: F) -> (Vec<u8>, Vec<String>)
+    where
+        F: FnOnce(&mut InstructionWriter<ClassWriter<ClassWriterState::Methods>>) -> Result<(), EncodeError>,
+    {
+        let bytes = write_code(descriptor, |code| {
+            let descriptor = MString::from(descriptor);
+            let descriptor = MethodDescriptor::parse(&descriptor).unwrap();
+            code.compute_frames(
+                "Test",
+                AccessFlags::PUBLIC | AccessFlags::STATIC,
+                mutf8!("m"),
+                &descriptor,
+            )?
+            .instructions(f)?
+            .exceptions(|_| Ok(()))?
+            .frames(&Hierarchy)?
+            .attributes(|_| Ok(()))
+        });
+
+        let class = Class::new(&bytes).unwrap();
+        let pool = class.pool();
+        let (_, code) = first_code(&class);
+        let describe = |ty: VerificationType<'_>| match ty {
+            VerificationType::Top => "top".to_owned(),
+            VerificationType::Null => "null".to_owned(),
+            VerificationType::UninitializedThis => "uninitialized this".to_owned(),
+            VerificationType::Object(class) => pool.retrieve(class).unwrap().name.to_str().unwrap().to_owned(),
+            VerificationType::UninitializedVariable(index) => format!("uninitialized {}", index.as_u32()),
+            VerificationType::Integer => "int".to_owned(),
+            VerificationType::Long => "long".to_owned(),
+            VerificationType::Float => "float".to_owned(),
+            VerificationType::Double => "double".to_owned(),
+        };
+        let AttributeContent::StackMapTable(table) = attribute(&class, code.attributes(), "StackMapTable") else {
+            panic!("expected a stack map table");
+        };
+
+        let mut locals: Vec<String> = initial_locals.iter().map(|&local| local.to_owned()).collect();
+        let frames = table
+            .iter()
+            .map(|frame| {
+                let (offset, frame) = frame.unwrap();
+                let stack = match frame {
+                    StackMapFrame::Same | StackMapFrame::SameExtended => Vec::new(),
+                    StackMapFrame::Same1 { stack } | StackMapFrame::Same1Extended { stack } => vec![describe(stack)],
+                    StackMapFrame::Chop { to_chop } => {
+                        locals.truncate(locals.len() - usize::from(to_chop));
+                        Vec::new()
+                    }
+                    StackMapFrame::Append { locals: appended } => {
+                        locals.extend(appended.map(|ty| describe(ty.unwrap())));
+                        Vec::new()
+                    }
+                    StackMapFrame::Full {
+                        locals: full_locals,
+                        stack,
+                    } => {
+                        locals = full_locals.map(|ty| describe(ty.unwrap())).collect();
+                        stack.map(|ty| describe(ty.unwrap())).collect()
+                    }
+                };
+                format!("{}: [{}] [{}]", offset.as_u32(), locals.join(", "), stack.join(", "))
+            })
+            .collect();
+        (bytes, frames)
+    }
+
+    #[test]
+    fn join_points() {
+        let (_, frames) = frames("(Z)Ljava/lang/Object;", &["int"], |instructions| {
+            let (other, other_ref) = instructions.new_label()?;
+            let (join, join_ref) = instructions.new_label()?;
+            instructions
+                .iload0()?
+                .ifeq(other_ref)?
+                .ldc(cpool::String::by("text"))?
+                .goto(join_ref)?
+                .label(other)?
+                .new("java/lang/StringBuilder")?
+                .dup()?
+                .invokespecial(MethodRef::by("java/lang/StringBuilder", ("<init>", "()V")))?
+                .label(join)?
+                .areturn()?;
+            Ok(())
+        });
+        assert_eq!(frames, ["9: [int] []", "16: [int] [java/lang/Object]"]);
+    }
+
+    #[test]
+    fn merged_int_and_float() {
+        let (_, frames) = frames("(Z)V", &["int"], |instructions| {
+            let (other, other_ref) = instructions.new_label()?;
+            let (join, join_ref) = instructions.new_label()?;
+            instructions
+                .iload0()?
+                .ifeq(other_ref)?
+                .iconst1()?
+                .istore1()?
+                .iconst1()?
+                .goto(join_ref)?
+                .label(other)?
+                .fconst1()?
+                .fstore1()?
+                .fconst1()?
+                .label(join)?
+                .return_()?;
+            Ok(())
+        });
+        // the unusable local variable is left out, but the value on the stack is not
+        assert_eq!(frames, ["10: [int] []", "13: [int] [top]"]);
+    }
+
+    #[test]
+    fn uninitialized_objects() {
+        let (_, frames) = frames("(Z)Ljava/lang/Object;", &["int"], |instructions| {
+            let (next, next_ref) = instructions.new_label()?;
+            instructions
+                .new("java/lang/StringBuilder")?
+                .dup()?
+                .iload0()?
+                .ifeq(next_ref)?
+                .label(next)?
+                .invokespecial(MethodRef::by("java/lang/StringBuilder", ("<init>", "()V")))?
+                .areturn()?;
+            Ok(())
+        });
+        assert_eq!(frames, ["8: [int] [uninitialized 0, uninitialized 0]"]);
+    }
+
+    #[test]
+    fn unreachable_code() {
+        let (bytes, frames) = frames("()V", &[], |instructions| {
+            let (end, end_ref) = instructions.new_label()?;
+            instructions.goto(end_ref)?.iconst1()?.pop()?.label(end)?.return_()?;
+            Ok(())
+        });
+        assert_eq!(frames, ["3: [] [java/lang/Throwable]", "5: [] []"]);
+
+        let class = Class::new(&bytes).unwrap();
+        let (_, code) = first_code(&class);
+        let instructions: Vec<_> = code
+            .raw_instructions()
+            .map(|instruction| instruction.unwrap().1)
+            .collect();
+        assert!(matches!(
+            instructions[..],
+            [
+                RawInstruction::Goto { offset: 5 },
+                RawInstruction::Nop,
+                RawInstruction::AThrow,
+                RawInstruction::Return
+            ]
+        ));
+        assert_eq!(code.max_stack(), 1);
+    }
+
+    #[test]
+    fn frames_are_computed_once_requested() {
+        let descriptor = MethodDescriptor::parse(mutf8!("()V")).unwrap();
+        let error = try_write_code("()V", |code| {
+            code.compute_frames(
+                "Test",
+                AccessFlags::PUBLIC | AccessFlags::STATIC,
+                mutf8!("m"),
+                &descriptor,
+            )?
+            .instructions(|instructions| {
+                instructions.return_()?;
+                Ok(())
+            })?
+            .exceptions(|_| Ok(()))?
+            .attributes(|_| Ok(()))
+        })
+        .unwrap_err();
+        assert!(matches!(error.kind(), EncodeErrorKind::ValuesMissing));
+
+        let error = try_write_code("()V", |code| {
+            code.compute_maxs(AccessFlags::PUBLIC | AccessFlags::STATIC, &descriptor)?
+                .instructions(|instructions| {
+                    instructions.return_()?;
+                    Ok(())
+                })?
+                .exceptions(|_| Ok(()))?
+                .frames(&Hierarchy)?
+                .attributes(|_| Ok(()))
+        })
+        .unwrap_err();
+        assert!(matches!(error.kind(), EncodeErrorKind::CantChangeAnymore));
+    }
+}
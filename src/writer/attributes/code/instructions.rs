@@ -1,16 +1,31 @@
+mod layout;
+mod locals;
 mod lookupswitch;
+mod peephole;
+mod switch;
 mod tableswitch;
+mod try_catch;
+mod typed;
 
+pub use locals::Local;
+pub(crate) use locals::LocalVariables;
 pub use lookupswitch::{LookupSwitchWriter, LookupSwitchWriterState};
+pub use switch::{SwitchWriter, SwitchWriterState};
 pub use tableswitch::{TableSwitchWriter, TableSwitchWriterState};
+pub use try_catch::{TryCatchWriter, TryCatchWriterState};
 
 use crate::error::*;
-use crate::reader::{attributes::RawInstruction, decoding::*};
+use crate::reader::attributes::ArrayType;
 use crate::writer::{attributes::code::*, cpool, encoding::*};
+use crate::Opcode;
 
 pub struct InstructionWriter<Ctx> {
     code_writer: CodeWriter<Ctx, CodeWriterState::Instructions>,
     start_offset: Offset,
+    /// The opcode of the instruction written last, or the one modified by `wide`.
+    last_opcode: Option<Opcode>,
+    /// The exits of the blocks with finally code which are currently written, innermost last.
+    finally_scopes: Vec<try_catch::FinallyScope>,
 }
 
 impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
@@ -19,6 +34,66 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
         self.code_writer.encoder().position().sub(self.start_offset)
     }
 
+    /// Starts an instruction by writing its opcode. The operands are written to the returned encoder.
+    pub(super) fn opcode(&mut self, opcode: Opcode) -> Result<&mut VecEncoder, EncodeError> {
+        self.last_opcode = Some(opcode);
+        self.code_writer.encoder().write(opcode)
+    }
+
+    /// Starts the wide form of an instruction.
+    fn wide(&mut self, opcode: Opcode) -> Result<&mut VecEncoder, EncodeError> {
+        self.last_opcode = Some(opcode);
+        self.code_writer.encoder().write(Opcode::Wide)?.write(opcode)
+    }
+
+    /// Writes the placeholder of a jump target, which is replaced by the offset once the labels are resolved.
+    pub(super) fn jump_target(&mut self, label: LabelRef, wide: bool) -> Result<(), EncodeError> {
+        let operand = self.code_writer.encoder().position();
+        if let Some(scope) = self.finally_scopes.last_mut() {
+            scope.jumps.push(try_catch::Jump { operand, label, wide });
+        }
+        if wide {
+            self.code_writer.encoder().write(label.0)?;
+        } else {
+            let index = u16::try_from(label.0)
+                .map_err(|_| EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::Code))?;
+            self.code_writer.encoder().write(index)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a return instruction, or a jump to a copy of the finally code followed by the return instruction
+    /// inside of a block with finally code.
+    fn return_with(&mut self, opcode: Opcode) -> Result<&mut Self, EncodeError> {
+        let Some(scope) = self.finally_scopes.last() else {
+            self.opcode(opcode)?;
+            return Ok(self);
+        };
+        let exit = match scope.returns.iter().find(|&&(returned, _, _)| returned == opcode) {
+            Some(&(_, _, exit)) => exit,
+            None => {
+                let (label, label_ref) = self.new_label()?;
+                if let Some(scope) = self.finally_scopes.last_mut() {
+                    scope.returns.push((opcode, label, label_ref));
+                }
+                label_ref
+            }
+        };
+        self.goto(exit)
+    }
+
+    /// Returns whether the code may continue at the current position, either because the instruction written
+    /// last falls through or because a label was placed at the current position.
+    pub(super) fn is_reachable(&mut self) -> bool {
+        let position = self.current_offset().get() + 1;
+        self.last_opcode.is_none_or(Opcode::falls_through)
+            || self
+                .code_writer
+                .label_positions
+                .iter()
+                .any(|label| label.is_some_and(|label| label.get() as usize == position))
+    }
+
     pub fn new_label(&mut self) -> Result<(Label, LabelRef), EncodeError> {
         self.code_writer.new_label()
     }
@@ -32,51 +107,71 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
         let offset = u32::try_from(offset)
             .map_err(|_| EncodeError::with_context(EncodeErrorKind::TooManyBytes, Context::Code))?;
         self.code_writer.label_positions[label.0 as usize] = NonZeroU32::new(offset);
+        if let Some(scope) = self.finally_scopes.last_mut() {
+            scope.labels.push(label.0);
+        }
+        Ok(self)
+    }
+
+    /// Marks the following instructions as belonging to a source line.
+    /// The `LineNumberTable` attribute is written automatically after the other attributes of the code.
+    pub fn line(&mut self, line_number: u16) -> Result<&mut Self, EncodeError> {
+        let position = self.current_offset().get() as u32;
+        if let Some(&(start, _)) = self.code_writer.line_numbers.last() {
+            // no instruction belongs to the previous line
+            if self.code_writer.get_label_position(start)? == position {
+                self.code_writer.line_numbers.pop();
+            }
+        }
+
+        let (label, label_ref) = self.new_label()?;
+        self.label(label)?;
+        self.code_writer.line_numbers.push((label_ref, line_number));
         Ok(self)
     }
 
     pub fn aaload(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x32u8)?;
+        self.opcode(Opcode::AALoad)?;
         Ok(self)
     }
 
     pub fn aastore(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x53u8)?;
+        self.opcode(Opcode::AAStore)?;
         Ok(self)
     }
 
     pub fn aconstnull(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x01u8)?;
+        self.opcode(Opcode::AConstNull)?;
         Ok(self)
     }
 
     pub fn aload(&mut self, index: u8) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x19u8)?.write(index)?;
+        self.opcode(Opcode::ALoad)?.write(index)?;
         Ok(self)
     }
 
     pub fn aload_wide(&mut self, index: u16) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xc4u8)?.write(0x19u8)?.write(index)?;
+        self.wide(Opcode::ALoad)?.write(index)?;
         Ok(self)
     }
 
     pub fn aload0(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x2au8)?;
+        self.opcode(Opcode::ALoad0)?;
         Ok(self)
     }
 
     pub fn aload1(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x2bu8)?;
+        self.opcode(Opcode::ALoad1)?;
         Ok(self)
     }
 
     pub fn aload2(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x2cu8)?;
+        self.opcode(Opcode::ALoad2)?;
         Ok(self)
     }
 
     pub fn aload3(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x2du8)?;
+        self.opcode(Opcode::ALoad3)?;
         Ok(self)
     }
 
@@ -84,403 +179,400 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
     where
         I: cpool::Insertable<cpool::Class>,
     {
-        self.code_writer.encoder().write(0xbdu8)?;
+        self.opcode(Opcode::ANewArray)?;
         let index = array_type.insert(&mut self.code_writer)?;
         self.code_writer.encoder().write(index)?;
         Ok(self)
     }
 
     pub fn areturn(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xb0u8)?;
-        Ok(self)
+        self.return_with(Opcode::AReturn)
     }
 
     pub fn arraylength(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xbeu8)?;
+        self.opcode(Opcode::ArrayLength)?;
         Ok(self)
     }
 
     pub fn astore(&mut self, index: u8) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x3au8)?.write(index)?;
+        self.opcode(Opcode::AStore)?.write(index)?;
         Ok(self)
     }
 
     pub fn astore_wide(&mut self, index: u16) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xc4u8)?.write(0x3au8)?.write(index)?;
+        self.wide(Opcode::AStore)?.write(index)?;
         Ok(self)
     }
 
     pub fn astore0(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x4bu8)?;
+        self.opcode(Opcode::AStore0)?;
         Ok(self)
     }
 
     pub fn astore1(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x4cu8)?;
+        self.opcode(Opcode::AStore1)?;
         Ok(self)
     }
 
     pub fn astore2(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x4du8)?;
+        self.opcode(Opcode::AStore2)?;
         Ok(self)
     }
 
     pub fn astore3(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x4eu8)?;
+        self.opcode(Opcode::AStore3)?;
         Ok(self)
     }
 
     pub fn athrow(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xbfu8)?;
+        self.opcode(Opcode::AThrow)?;
         Ok(self)
     }
 
     pub fn baload(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x33u8)?;
+        self.opcode(Opcode::BALoad)?;
         Ok(self)
     }
 
     pub fn bastore(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x54u8)?;
+        self.opcode(Opcode::BAStore)?;
         Ok(self)
     }
 
     pub fn bipush(&mut self, value: i8) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x10u8)?.write(value)?;
+        self.opcode(Opcode::BIPush)?.write(value)?;
         Ok(self)
     }
 
     pub fn caload(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x34u8)?;
+        self.opcode(Opcode::CALoad)?;
         Ok(self)
     }
 
     pub fn castore(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x55u8)?;
+        self.opcode(Opcode::CAStore)?;
         Ok(self)
     }
 
-    pub fn checkcast<I>(&mut self, array_type: I) -> Result<&mut Self, EncodeError>
+    pub fn checkcast<I>(&mut self, class: I) -> Result<&mut Self, EncodeError>
     where
         I: cpool::Insertable<cpool::Class>,
     {
-        let index = array_type.insert(&mut self.code_writer)?;
-        self.code_writer.encoder().write(0xbdu8)?.write(index)?;
+        let index = class.insert(&mut self.code_writer)?;
+        self.opcode(Opcode::CheckCast)?.write(index)?;
         Ok(self)
     }
 
     pub fn d2f(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x90u8)?;
+        self.opcode(Opcode::D2F)?;
         Ok(self)
     }
 
     pub fn d2i(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x8eu8)?;
+        self.opcode(Opcode::D2I)?;
         Ok(self)
     }
 
     pub fn d2l(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x8fu8)?;
+        self.opcode(Opcode::D2L)?;
         Ok(self)
     }
 
     pub fn dadd(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x63u8)?;
+        self.opcode(Opcode::DAdd)?;
         Ok(self)
     }
 
     pub fn daload(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x31u8)?;
+        self.opcode(Opcode::DALoad)?;
         Ok(self)
     }
 
     pub fn dastore(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x52u8)?;
+        self.opcode(Opcode::DAStore)?;
         Ok(self)
     }
 
     pub fn dcmpg(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x98u8)?;
+        self.opcode(Opcode::DCmpG)?;
         Ok(self)
     }
 
     pub fn dcmpl(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x97u8)?;
+        self.opcode(Opcode::DCmpL)?;
         Ok(self)
     }
 
     pub fn dconst0(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x0eu8)?;
+        self.opcode(Opcode::DConst0)?;
         Ok(self)
     }
 
     pub fn dconst1(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x0fu8)?;
+        self.opcode(Opcode::DConst1)?;
         Ok(self)
     }
 
     pub fn ddiv(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x6fu8)?;
+        self.opcode(Opcode::DDiv)?;
         Ok(self)
     }
 
     pub fn dload(&mut self, index: u8) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x18u8)?.write(index)?;
+        self.opcode(Opcode::DLoad)?.write(index)?;
         Ok(self)
     }
 
     pub fn dload_wide(&mut self, index: u16) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xc4u8)?.write(0x18u8)?.write(index)?;
+        self.wide(Opcode::DLoad)?.write(index)?;
         Ok(self)
     }
 
     pub fn dload0(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x26u8)?;
+        self.opcode(Opcode::DLoad0)?;
         Ok(self)
     }
 
     pub fn dload1(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x27u8)?;
+        self.opcode(Opcode::DLoad1)?;
         Ok(self)
     }
 
     pub fn dload2(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x28u8)?;
+        self.opcode(Opcode::DLoad2)?;
         Ok(self)
     }
 
     pub fn dload3(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x29u8)?;
+        self.opcode(Opcode::DLoad3)?;
         Ok(self)
     }
 
     pub fn dmul(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x6bu8)?;
+        self.opcode(Opcode::DMul)?;
         Ok(self)
     }
 
     pub fn dneg(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x77u8)?;
+        self.opcode(Opcode::DNeg)?;
         Ok(self)
     }
 
     pub fn drem(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x73u8)?;
+        self.opcode(Opcode::DRem)?;
         Ok(self)
     }
 
     pub fn dreturn(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xafu8)?;
-        Ok(self)
+        self.return_with(Opcode::DReturn)
     }
 
     pub fn dstore(&mut self, index: u8) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x39u8)?.write(index)?;
+        self.opcode(Opcode::DStore)?.write(index)?;
         Ok(self)
     }
 
     pub fn dstore_wide(&mut self, index: u16) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xc4u8)?.write(0x39u8)?.write(index)?;
+        self.wide(Opcode::DStore)?.write(index)?;
         Ok(self)
     }
 
     pub fn dstore0(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x47u8)?;
+        self.opcode(Opcode::DStore0)?;
         Ok(self)
     }
 
     pub fn dstore1(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x48u8)?;
+        self.opcode(Opcode::DStore1)?;
         Ok(self)
     }
 
     pub fn dstore2(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x49u8)?;
+        self.opcode(Opcode::DStore2)?;
         Ok(self)
     }
 
     pub fn dstore3(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x4au8)?;
+        self.opcode(Opcode::DStore3)?;
         Ok(self)
     }
 
     pub fn dsub(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x67u8)?;
+        self.opcode(Opcode::DSub)?;
         Ok(self)
     }
 
     pub fn dup(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x59u8)?;
+        self.opcode(Opcode::Dup)?;
         Ok(self)
     }
 
     pub fn dupx1(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x5au8)?;
+        self.opcode(Opcode::DupX1)?;
         Ok(self)
     }
 
     pub fn dupx2(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x5bu8)?;
+        self.opcode(Opcode::DupX2)?;
         Ok(self)
     }
 
     pub fn dup2(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x5cu8)?;
+        self.opcode(Opcode::Dup2)?;
         Ok(self)
     }
 
     pub fn dup2x1(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x5du8)?;
+        self.opcode(Opcode::Dup2X1)?;
         Ok(self)
     }
 
     pub fn dup2x2(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x5eu8)?;
+        self.opcode(Opcode::Dup2X2)?;
         Ok(self)
     }
 
     pub fn f2d(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x8du8)?;
+        self.opcode(Opcode::F2D)?;
         Ok(self)
     }
 
     pub fn f2i(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x8bu8)?;
+        self.opcode(Opcode::F2I)?;
         Ok(self)
     }
 
     pub fn f2l(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x8cu8)?;
+        self.opcode(Opcode::F2L)?;
         Ok(self)
     }
 
     pub fn fadd(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x62u8)?;
+        self.opcode(Opcode::FAdd)?;
         Ok(self)
     }
 
     pub fn faload(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x30u8)?;
+        self.opcode(Opcode::FALoad)?;
         Ok(self)
     }
 
     pub fn fastore(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x51u8)?;
+        self.opcode(Opcode::FAStore)?;
         Ok(self)
     }
 
     pub fn fcmpg(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x96u8)?;
+        self.opcode(Opcode::FCmpG)?;
         Ok(self)
     }
 
     pub fn fcmpl(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x95u8)?;
+        self.opcode(Opcode::FCmpL)?;
         Ok(self)
     }
 
     pub fn fconst0(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x0bu8)?;
+        self.opcode(Opcode::FConst0)?;
         Ok(self)
     }
 
     pub fn fconst1(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x0cu8)?;
+        self.opcode(Opcode::FConst1)?;
         Ok(self)
     }
 
     pub fn fconst2(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x0du8)?;
+        self.opcode(Opcode::FConst2)?;
         Ok(self)
     }
 
     pub fn fdiv(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x6eu8)?;
+        self.opcode(Opcode::FDiv)?;
         Ok(self)
     }
 
     pub fn fload(&mut self, index: u8) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x17u8)?.write(index)?;
+        self.opcode(Opcode::FLoad)?.write(index)?;
         Ok(self)
     }
 
     pub fn fload_wide(&mut self, index: u16) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xc4u8)?.write(0x17u8)?.write(index)?;
+        self.wide(Opcode::FLoad)?.write(index)?;
         Ok(self)
     }
 
     pub fn fload0(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x22u8)?;
+        self.opcode(Opcode::FLoad0)?;
         Ok(self)
     }
 
     pub fn fload1(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x23u8)?;
+        self.opcode(Opcode::FLoad1)?;
         Ok(self)
     }
 
     pub fn fload2(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x24u8)?;
+        self.opcode(Opcode::FLoad2)?;
         Ok(self)
     }
 
     pub fn fload3(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x25u8)?;
+        self.opcode(Opcode::FLoad3)?;
         Ok(self)
     }
 
     pub fn fmul(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x6au8)?;
+        self.opcode(Opcode::FMul)?;
         Ok(self)
     }
 
     pub fn fneg(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x76u8)?;
+        self.opcode(Opcode::FNeg)?;
         Ok(self)
     }
 
     pub fn frem(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x72u8)?;
+        self.opcode(Opcode::FRem)?;
         Ok(self)
     }
 
     pub fn freturn(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xaeu8)?;
-        Ok(self)
+        self.return_with(Opcode::FReturn)
     }
 
     pub fn fstore(&mut self, index: u8) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x38u8)?.write(index)?;
+        self.opcode(Opcode::FStore)?.write(index)?;
         Ok(self)
     }
 
     pub fn fstore_wide(&mut self, index: u16) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xc4u8)?.write(0x38u8)?.write(index)?;
+        self.wide(Opcode::FStore)?.write(index)?;
         Ok(self)
     }
 
     pub fn fstore0(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x43u8)?;
+        self.opcode(Opcode::FStore0)?;
         Ok(self)
     }
 
     pub fn fstore1(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x44u8)?;
+        self.opcode(Opcode::FStore1)?;
         Ok(self)
     }
 
     pub fn fstore2(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x45u8)?;
+        self.opcode(Opcode::FStore2)?;
         Ok(self)
     }
 
     pub fn fstore3(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x46u8)?;
+        self.opcode(Opcode::FStore3)?;
         Ok(self)
     }
 
     pub fn fsub(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x66u8)?;
+        self.opcode(Opcode::FSub)?;
         Ok(self)
     }
 
@@ -489,7 +581,7 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
         I: cpool::Insertable<cpool::FieldRef>,
     {
         let index = field.insert(&mut self.code_writer)?;
-        self.code_writer.encoder().write(0xb4u8)?.write(index)?;
+        self.opcode(Opcode::GetField)?.write(index)?;
         Ok(self)
     }
 
@@ -498,13 +590,14 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
         I: cpool::Insertable<cpool::FieldRef>,
     {
         let index = field.insert(&mut self.code_writer)?;
-        self.code_writer.encoder().write(0xb2u8)?.write(index)?;
+        self.opcode(Opcode::GetStatic)?.write(index)?;
         Ok(self)
     }
 
     pub fn goto(&mut self, label: LabelRef) -> Result<&mut Self, EncodeError> {
-        if let Ok(i) = u16::try_from(label.0) {
-            self.code_writer.encoder().write(0xa7u8)?.write(i)?;
+        if u16::try_from(label.0).is_ok() {
+            self.opcode(Opcode::Goto)?;
+            self.jump_target(label, false)?;
             Ok(self)
         } else {
             self.gotow(label)
@@ -512,296 +605,244 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
     }
 
     pub fn gotow(&mut self, label: LabelRef) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xc8u8)?.write(label.0)?;
+        self.opcode(Opcode::GotoW)?;
+        self.jump_target(label, true)?;
         Ok(self)
     }
 
     pub fn i2b(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x91u8)?;
+        self.opcode(Opcode::I2B)?;
         Ok(self)
     }
 
     pub fn i2c(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x92u8)?;
+        self.opcode(Opcode::I2C)?;
         Ok(self)
     }
 
     pub fn i2d(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x87u8)?;
+        self.opcode(Opcode::I2D)?;
         Ok(self)
     }
 
     pub fn i2f(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x86u8)?;
+        self.opcode(Opcode::I2F)?;
         Ok(self)
     }
 
     pub fn i2l(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x85u8)?;
+        self.opcode(Opcode::I2L)?;
         Ok(self)
     }
 
     pub fn i2s(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x93u8)?;
+        self.opcode(Opcode::I2S)?;
         Ok(self)
     }
 
     pub fn iadd(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x60u8)?;
+        self.opcode(Opcode::IAdd)?;
         Ok(self)
     }
 
     pub fn iaload(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x2eu8)?;
+        self.opcode(Opcode::IALoad)?;
         Ok(self)
     }
 
     pub fn iand(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x7eu8)?;
+        self.opcode(Opcode::IAnd)?;
         Ok(self)
     }
 
     pub fn iastore(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x4fu8)?;
+        self.opcode(Opcode::IAStore)?;
         Ok(self)
     }
 
     pub fn iconstm1(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x02u8)?;
+        self.opcode(Opcode::IConstM1)?;
         Ok(self)
     }
 
     pub fn iconst0(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x03u8)?;
+        self.opcode(Opcode::IConst0)?;
         Ok(self)
     }
 
     pub fn iconst1(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x04u8)?;
+        self.opcode(Opcode::IConst1)?;
         Ok(self)
     }
 
     pub fn iconst2(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x05u8)?;
+        self.opcode(Opcode::IConst2)?;
         Ok(self)
     }
 
     pub fn iconst3(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x06u8)?;
+        self.opcode(Opcode::IConst3)?;
         Ok(self)
     }
 
     pub fn iconst4(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x07u8)?;
+        self.opcode(Opcode::IConst4)?;
         Ok(self)
     }
 
     pub fn iconst5(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x08u8)?;
+        self.opcode(Opcode::IConst5)?;
         Ok(self)
     }
 
     pub fn idiv(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x6cu8)?;
+        self.opcode(Opcode::IDiv)?;
         Ok(self)
     }
 
     pub fn ifacmpeq(&mut self, label: LabelRef) -> Result<&mut Self, EncodeError> {
-        if let Ok(i) = u16::try_from(label.0) {
-            self.code_writer.encoder().write(0xa5u8)?.write(i)?;
-            Ok(self)
-        } else {
-            Err(EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::Code))
-        }
+        self.opcode(Opcode::IfACmpEq)?;
+        self.jump_target(label, false)?;
+        Ok(self)
     }
 
     pub fn ifacmpne(&mut self, label: LabelRef) -> Result<&mut Self, EncodeError> {
-        if let Ok(i) = u16::try_from(label.0) {
-            self.code_writer.encoder().write(0xa6u8)?.write(i)?;
-            Ok(self)
-        } else {
-            Err(EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::Code))
-        }
+        self.opcode(Opcode::IfACmpNe)?;
+        self.jump_target(label, false)?;
+        Ok(self)
     }
 
     pub fn ificmpeq(&mut self, label: LabelRef) -> Result<&mut Self, EncodeError> {
-        if let Ok(i) = u16::try_from(label.0) {
-            self.code_writer.encoder().write(0x9fu8)?.write(i)?;
-            Ok(self)
-        } else {
-            Err(EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::Code))
-        }
+        self.opcode(Opcode::IfICmpEq)?;
+        self.jump_target(label, false)?;
+        Ok(self)
     }
 
     pub fn ificmpne(&mut self, label: LabelRef) -> Result<&mut Self, EncodeError> {
-        if let Ok(i) = u16::try_from(label.0) {
-            self.code_writer.encoder().write(0xa0u8)?.write(i)?;
-            Ok(self)
-        } else {
-            Err(EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::Code))
-        }
+        self.opcode(Opcode::IfICmpNe)?;
+        self.jump_target(label, false)?;
+        Ok(self)
     }
 
     pub fn ificmplt(&mut self, label: LabelRef) -> Result<&mut Self, EncodeError> {
-        if let Ok(i) = u16::try_from(label.0) {
-            self.code_writer.encoder().write(0xa1u8)?.write(i)?;
-            Ok(self)
-        } else {
-            Err(EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::Code))
-        }
+        self.opcode(Opcode::IfICmpLt)?;
+        self.jump_target(label, false)?;
+        Ok(self)
     }
 
     pub fn ificmpge(&mut self, label: LabelRef) -> Result<&mut Self, EncodeError> {
-        if let Ok(i) = u16::try_from(label.0) {
-            self.code_writer.encoder().write(0xa2u8)?.write(i)?;
-            Ok(self)
-        } else {
-            Err(EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::Code))
-        }
+        self.opcode(Opcode::IfICmpGe)?;
+        self.jump_target(label, false)?;
+        Ok(self)
     }
 
     pub fn ificmpgt(&mut self, label: LabelRef) -> Result<&mut Self, EncodeError> {
-        if let Ok(i) = u16::try_from(label.0) {
-            self.code_writer.encoder().write(0xa3u8)?.write(i)?;
-            Ok(self)
-        } else {
-            Err(EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::Code))
-        }
+        self.opcode(Opcode::IfICmpGt)?;
+        self.jump_target(label, false)?;
+        Ok(self)
     }
 
     pub fn ificmple(&mut self, label: LabelRef) -> Result<&mut Self, EncodeError> {
-        if let Ok(i) = u16::try_from(label.0) {
-            self.code_writer.encoder().write(0xa4u8)?.write(i)?;
-            Ok(self)
-        } else {
-            Err(EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::Code))
-        }
+        self.opcode(Opcode::IfICmpLe)?;
+        self.jump_target(label, false)?;
+        Ok(self)
     }
 
     pub fn ifeq(&mut self, label: LabelRef) -> Result<&mut Self, EncodeError> {
-        if let Ok(i) = u16::try_from(label.0) {
-            self.code_writer.encoder().write(0x99u8)?.write(i)?;
-            Ok(self)
-        } else {
-            Err(EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::Code))
-        }
+        self.opcode(Opcode::IfEq)?;
+        self.jump_target(label, false)?;
+        Ok(self)
     }
 
     pub fn ifne(&mut self, label: LabelRef) -> Result<&mut Self, EncodeError> {
-        if let Ok(i) = u16::try_from(label.0) {
-            self.code_writer.encoder().write(0x9au8)?.write(i)?;
-            Ok(self)
-        } else {
-            Err(EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::Code))
-        }
+        self.opcode(Opcode::IfNe)?;
+        self.jump_target(label, false)?;
+        Ok(self)
     }
 
     pub fn iflt(&mut self, label: LabelRef) -> Result<&mut Self, EncodeError> {
-        if let Ok(i) = u16::try_from(label.0) {
-            self.code_writer.encoder().write(0x9bu8)?.write(i)?;
-            Ok(self)
-        } else {
-            Err(EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::Code))
-        }
+        self.opcode(Opcode::IfLt)?;
+        self.jump_target(label, false)?;
+        Ok(self)
     }
 
     pub fn ifge(&mut self, label: LabelRef) -> Result<&mut Self, EncodeError> {
-        if let Ok(i) = u16::try_from(label.0) {
-            self.code_writer.encoder().write(0x9cu8)?.write(i)?;
-            Ok(self)
-        } else {
-            Err(EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::Code))
-        }
+        self.opcode(Opcode::IfGe)?;
+        self.jump_target(label, false)?;
+        Ok(self)
     }
 
     pub fn ifgt(&mut self, label: LabelRef) -> Result<&mut Self, EncodeError> {
-        if let Ok(i) = u16::try_from(label.0) {
-            self.code_writer.encoder().write(0x9du8)?.write(i)?;
-            Ok(self)
-        } else {
-            Err(EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::Code))
-        }
+        self.opcode(Opcode::IfGt)?;
+        self.jump_target(label, false)?;
+        Ok(self)
     }
 
     pub fn ifle(&mut self, label: LabelRef) -> Result<&mut Self, EncodeError> {
-        if let Ok(i) = u16::try_from(label.0) {
-            self.code_writer.encoder().write(0x9eu8)?.write(i)?;
-            Ok(self)
-        } else {
-            Err(EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::Code))
-        }
+        self.opcode(Opcode::IfLe)?;
+        self.jump_target(label, false)?;
+        Ok(self)
     }
 
     pub fn ifnonnull(&mut self, label: LabelRef) -> Result<&mut Self, EncodeError> {
-        if let Ok(i) = u16::try_from(label.0) {
-            self.code_writer.encoder().write(0xc7u8)?.write(i)?;
-            Ok(self)
-        } else {
-            Err(EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::Code))
-        }
+        self.opcode(Opcode::IfNonNull)?;
+        self.jump_target(label, false)?;
+        Ok(self)
     }
 
     pub fn ifnull(&mut self, label: LabelRef) -> Result<&mut Self, EncodeError> {
-        if let Ok(i) = u16::try_from(label.0) {
-            self.code_writer.encoder().write(0xc6u8)?.write(i)?;
-            Ok(self)
-        } else {
-            Err(EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::Code))
-        }
+        self.opcode(Opcode::IfNull)?;
+        self.jump_target(label, false)?;
+        Ok(self)
     }
 
     pub fn iinc(&mut self, index: u8, value: i8) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x84u8)?.write(index)?.write(value)?;
+        self.opcode(Opcode::IInc)?.write(index)?.write(value)?;
         Ok(self)
     }
 
-    pub fn iinc_wide(&mut self, index: u16, value: i64) -> Result<&mut Self, EncodeError> {
-        self.code_writer
-            .encoder()
-            .write(0x4fu8)?
-            .write(0x84u8)?
-            .write(index)?
-            .write(value)?;
+    pub fn iinc_wide(&mut self, index: u16, value: i16) -> Result<&mut Self, EncodeError> {
+        self.wide(Opcode::IInc)?.write(index)?.write(value)?;
         Ok(self)
     }
 
     pub fn iload(&mut self, index: u8) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x15u8)?.write(index)?;
+        self.opcode(Opcode::ILoad)?.write(index)?;
         Ok(self)
     }
 
     pub fn iload_wide(&mut self, index: u16) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xc4u8)?.write(0x15u8)?.write(index)?;
+        self.wide(Opcode::ILoad)?.write(index)?;
         Ok(self)
     }
 
     pub fn iload0(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x1au8)?;
+        self.opcode(Opcode::ILoad0)?;
         Ok(self)
     }
 
     pub fn iload1(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x1bu8)?;
+        self.opcode(Opcode::ILoad1)?;
         Ok(self)
     }
 
     pub fn iload2(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x1cu8)?;
+        self.opcode(Opcode::ILoad2)?;
         Ok(self)
     }
 
     pub fn iload3(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x1du8)?;
+        self.opcode(Opcode::ILoad3)?;
         Ok(self)
     }
 
     pub fn imul(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x68u8)?;
+        self.opcode(Opcode::IMul)?;
         Ok(self)
     }
 
     pub fn ineg(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x74u8)?;
+        self.opcode(Opcode::INeg)?;
         Ok(self)
     }
 
@@ -810,7 +851,7 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
         I: cpool::Insertable<cpool::Class>,
     {
         let index = type_.insert(&mut self.code_writer)?;
-        self.code_writer.encoder().write(0xc1u8)?.write(index)?;
+        self.opcode(Opcode::InstanceOf)?.write(index)?;
         Ok(self)
     }
 
@@ -819,9 +860,7 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
         I: cpool::Insertable<cpool::InvokeDynamic>,
     {
         let index = invoke_dynamic.insert(&mut self.code_writer)?;
-        self.code_writer
-            .encoder()
-            .write(0xbau8)?
+        self.opcode(Opcode::InvokeDynamic)?
             .write(index)?
             .write(0u8)?
             .write(0u8)?;
@@ -833,9 +872,7 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
         I: cpool::Insertable<cpool::InterfaceMethodRef>,
     {
         let index = method.insert(&mut self.code_writer)?;
-        self.code_writer
-            .encoder()
-            .write(0xb9u8)?
+        self.opcode(Opcode::InvokeInterface)?
             .write(index)?
             .write(count)?
             .write(0u8)?;
@@ -847,7 +884,7 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
         I: cpool::Insertable<cpool::Item>,
     {
         let index = method.insert(&mut self.code_writer)?;
-        self.code_writer.encoder().write(0xb7u8)?.write(index)?;
+        self.opcode(Opcode::InvokeSpecial)?.write(index)?;
         Ok(self)
     }
 
@@ -856,7 +893,7 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
         I: cpool::Insertable<cpool::Item>,
     {
         let index = method.insert(&mut self.code_writer)?;
-        self.code_writer.encoder().write(0xb8u8)?.write(index)?;
+        self.opcode(Opcode::InvokeStatic)?.write(index)?;
         Ok(self)
     }
 
@@ -865,83 +902,82 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
         I: cpool::Insertable<cpool::MethodRef>,
     {
         let index = method.insert(&mut self.code_writer)?;
-        self.code_writer.encoder().write(0xb6u8)?.write(index)?;
+        self.opcode(Opcode::InvokeVirtual)?.write(index)?;
         Ok(self)
     }
 
     pub fn ior(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x80u8)?;
+        self.opcode(Opcode::IOr)?;
         Ok(self)
     }
 
     pub fn irem(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x70u8)?;
+        self.opcode(Opcode::IRem)?;
         Ok(self)
     }
 
     pub fn ireturn(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xacu8)?;
-        Ok(self)
+        self.return_with(Opcode::IReturn)
     }
 
     pub fn ishl(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x78u8)?;
+        self.opcode(Opcode::IShL)?;
         Ok(self)
     }
 
     pub fn ishr(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x7au8)?;
+        self.opcode(Opcode::IShR)?;
         Ok(self)
     }
 
     pub fn istore(&mut self, index: u8) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x36u8)?.write(index)?;
+        self.opcode(Opcode::IStore)?.write(index)?;
         Ok(self)
     }
 
     pub fn istore_wide(&mut self, index: u16) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xc4u8)?.write(0x36u8)?.write(index)?;
+        self.wide(Opcode::IStore)?.write(index)?;
         Ok(self)
     }
 
     pub fn istore0(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x3bu8)?;
+        self.opcode(Opcode::IStore0)?;
         Ok(self)
     }
 
     pub fn istore1(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x3cu8)?;
+        self.opcode(Opcode::IStore1)?;
         Ok(self)
     }
 
     pub fn istore2(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x3du8)?;
+        self.opcode(Opcode::IStore2)?;
         Ok(self)
     }
 
     pub fn istore3(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x3eu8)?;
+        self.opcode(Opcode::IStore3)?;
         Ok(self)
     }
 
     pub fn isub(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x64u8)?;
+        self.opcode(Opcode::ISub)?;
         Ok(self)
     }
 
     pub fn iushr(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x7cu8)?;
+        self.opcode(Opcode::IUShR)?;
         Ok(self)
     }
 
     pub fn ixor(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x82u8)?;
+        self.opcode(Opcode::IXor)?;
         Ok(self)
     }
 
     pub fn jsr(&mut self, label: LabelRef) -> Result<&mut Self, EncodeError> {
         if let Ok(i) = u16::try_from(label.0) {
-            self.code_writer.encoder().write(0xa8u8)?.write(i)?;
+            self.opcode(Opcode::JSr)?.write(i)?;
             Ok(self)
         } else {
             self.jsrw(label)
@@ -949,57 +985,57 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
     }
 
     pub fn jsrw(&mut self, label: LabelRef) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xc9u8)?.write(label.0)?;
+        self.opcode(Opcode::JSrW)?.write(label.0)?;
         Ok(self)
     }
 
     pub fn l2d(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x8au8)?;
+        self.opcode(Opcode::L2D)?;
         Ok(self)
     }
 
     pub fn l2f(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x89u8)?;
+        self.opcode(Opcode::L2F)?;
         Ok(self)
     }
 
     pub fn l2i(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x88u8)?;
+        self.opcode(Opcode::L2I)?;
         Ok(self)
     }
 
     pub fn ladd(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x61u8)?;
+        self.opcode(Opcode::LAdd)?;
         Ok(self)
     }
 
     pub fn laload(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x2fu8)?;
+        self.opcode(Opcode::LALoad)?;
         Ok(self)
     }
 
     pub fn land(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x7fu8)?;
+        self.opcode(Opcode::LAnd)?;
         Ok(self)
     }
 
     pub fn lastore(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x50u8)?;
+        self.opcode(Opcode::LAStore)?;
         Ok(self)
     }
 
     pub fn lcmp(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x94u8)?;
+        self.opcode(Opcode::LCmp)?;
         Ok(self)
     }
 
     pub fn lconst0(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x09u8)?;
+        self.opcode(Opcode::LConst0)?;
         Ok(self)
     }
 
     pub fn lconst1(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x0au8)?;
+        self.opcode(Opcode::LConst1)?;
         Ok(self)
     }
 
@@ -1010,7 +1046,7 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
         let index = constant.insert(&mut self.code_writer)?.as_u16();
         let index = u8::try_from(index)
             .map_err(|_| EncodeError::with_context(EncodeErrorKind::IndexNotFitting, Context::Code))?;
-        self.code_writer.encoder().write(0x12u8)?.write(index)?;
+        self.opcode(Opcode::LdC)?.write(index)?;
         Ok(self)
     }
 
@@ -1019,7 +1055,7 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
         I: cpool::Insertable<cpool::Item>,
     {
         let index = constant.insert(&mut self.code_writer)?;
-        self.code_writer.encoder().write(0x13u8)?.write(index)?;
+        self.opcode(Opcode::LdCW)?.write(index)?;
         Ok(self)
     }
 
@@ -1028,52 +1064,52 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
         I: cpool::Insertable<cpool::Item>,
     {
         let index = constant.insert(&mut self.code_writer)?;
-        self.code_writer.encoder().write(0x14u8)?.write(index)?;
+        self.opcode(Opcode::LdC2W)?.write(index)?;
         Ok(self)
     }
 
     pub fn ldiv(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x6du8)?;
+        self.opcode(Opcode::LDiv)?;
         Ok(self)
     }
 
     pub fn lload(&mut self, index: u8) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x16u8)?.write(index)?;
+        self.opcode(Opcode::LLoad)?.write(index)?;
         Ok(self)
     }
 
     pub fn lload_wide(&mut self, index: u16) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xc4u8)?.write(0x16u8)?.write(index)?;
+        self.wide(Opcode::LLoad)?.write(index)?;
         Ok(self)
     }
 
     pub fn lload0(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x1eu8)?;
+        self.opcode(Opcode::LLoad0)?;
         Ok(self)
     }
 
     pub fn lload1(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x1fu8)?;
+        self.opcode(Opcode::LLoad1)?;
         Ok(self)
     }
 
     pub fn lload2(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x20u8)?;
+        self.opcode(Opcode::LLoad2)?;
         Ok(self)
     }
 
     pub fn lload3(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x21u8)?;
+        self.opcode(Opcode::LLoad3)?;
         Ok(self)
     }
 
     pub fn lmul(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x69u8)?;
+        self.opcode(Opcode::LMul)?;
         Ok(self)
     }
 
     pub fn lneg(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x75u8)?;
+        self.opcode(Opcode::LNeg)?;
         Ok(self)
     }
 
@@ -1089,82 +1125,81 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
     }
 
     pub fn lor(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x81u8)?;
+        self.opcode(Opcode::LOr)?;
         Ok(self)
     }
 
     pub fn lrem(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x71u8)?;
+        self.opcode(Opcode::LRem)?;
         Ok(self)
     }
 
     pub fn lreturn(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xadu8)?;
-        Ok(self)
+        self.return_with(Opcode::LReturn)
     }
 
     pub fn lshl(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x79u8)?;
+        self.opcode(Opcode::LShL)?;
         Ok(self)
     }
 
     pub fn lshr(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x7bu8)?;
+        self.opcode(Opcode::LShR)?;
         Ok(self)
     }
 
     pub fn lstore(&mut self, index: u8) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x37u8)?.write(index)?;
+        self.opcode(Opcode::LStore)?.write(index)?;
         Ok(self)
     }
 
     pub fn lstore_wide(&mut self, index: u16) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xc4u8)?.write(0x37u8)?.write(index)?;
+        self.wide(Opcode::LStore)?.write(index)?;
         Ok(self)
     }
 
     pub fn lstore0(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x3fu8)?;
+        self.opcode(Opcode::LStore0)?;
         Ok(self)
     }
 
     pub fn lstore1(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x40u8)?;
+        self.opcode(Opcode::LStore1)?;
         Ok(self)
     }
 
     pub fn lstore2(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x41u8)?;
+        self.opcode(Opcode::LStore2)?;
         Ok(self)
     }
 
     pub fn lstore3(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x42u8)?;
+        self.opcode(Opcode::LStore3)?;
         Ok(self)
     }
 
     pub fn lsub(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x65u8)?;
+        self.opcode(Opcode::LSub)?;
         Ok(self)
     }
 
     pub fn lushr(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x7du8)?;
+        self.opcode(Opcode::LUShR)?;
         Ok(self)
     }
 
     pub fn lxor(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x83u8)?;
+        self.opcode(Opcode::LXor)?;
         Ok(self)
     }
 
     pub fn monitorenter(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xc2u8)?;
+        self.opcode(Opcode::MonitorEnter)?;
         Ok(self)
     }
 
     pub fn monitorexit(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xc3u8)?;
+        self.opcode(Opcode::MonitorExit)?;
         Ok(self)
     }
 
@@ -1173,11 +1208,7 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
         I: cpool::Insertable<cpool::Class>,
     {
         let index = array_type.insert(&mut self.code_writer)?;
-        self.code_writer
-            .encoder()
-            .write(0xc5u8)?
-            .write(index)?
-            .write(dimensions)?;
+        self.opcode(Opcode::MultiANewArray)?.write(index)?.write(dimensions)?;
         Ok(self)
     }
 
@@ -1186,31 +1217,37 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
         I: cpool::Insertable<cpool::Class>,
     {
         let index = class.insert(&mut self.code_writer)?;
-        self.code_writer.encoder().write(0xbbu8)?.write(index)?;
+        self.opcode(Opcode::New)?.write(index)?;
         Ok(self)
     }
 
-    pub fn newarray<I>(&mut self, array_type: I) -> Result<&mut Self, EncodeError>
-    where
-        I: cpool::Insertable<cpool::Class>,
-    {
-        let index = array_type.insert(&mut self.code_writer)?;
-        self.code_writer.encoder().write(0xbcu8)?.write(index)?;
+    pub fn newarray(&mut self, array_type: ArrayType) -> Result<&mut Self, EncodeError> {
+        let array_type: u8 = match array_type {
+            ArrayType::Boolean => 4,
+            ArrayType::Char => 5,
+            ArrayType::Float => 6,
+            ArrayType::Double => 7,
+            ArrayType::Byte => 8,
+            ArrayType::Short => 9,
+            ArrayType::Int => 10,
+            ArrayType::Long => 11,
+        };
+        self.opcode(Opcode::NewArray)?.write(array_type)?;
         Ok(self)
     }
 
     pub fn nop(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x00u8)?;
+        self.opcode(Opcode::Nop)?;
         Ok(self)
     }
 
     pub fn pop(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x57u8)?;
+        self.opcode(Opcode::Pop)?;
         Ok(self)
     }
 
     pub fn pop2(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x58u8)?;
+        self.opcode(Opcode::Pop2)?;
         Ok(self)
     }
 
@@ -1219,7 +1256,7 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
         I: cpool::Insertable<cpool::FieldRef>,
     {
         let index = field.insert(&mut self.code_writer)?;
-        self.code_writer.encoder().write(0xb5u8)?.write(index)?;
+        self.opcode(Opcode::PutField)?.write(index)?;
         Ok(self)
     }
 
@@ -1228,37 +1265,53 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
         I: cpool::Insertable<cpool::FieldRef>,
     {
         let index = field.insert(&mut self.code_writer)?;
-        self.code_writer.encoder().write(0xb3u8)?.write(index)?;
+        self.opcode(Opcode::PutStatic)?.write(index)?;
         Ok(self)
     }
 
-    pub fn ret<I>(&mut self, index: u8) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xa9u8)?.write(index)?;
+    pub fn ret(&mut self, index: u8) -> Result<&mut Self, EncodeError> {
+        self.opcode(Opcode::Ret)?.write(index)?;
         Ok(self)
     }
 
-    pub fn ret_wide<I>(&mut self, index: u16) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xc4u8)?.write(0xa9u8)?.write(index)?;
+    pub fn ret_wide(&mut self, index: u16) -> Result<&mut Self, EncodeError> {
+        self.wide(Opcode::Ret)?.write(index)?;
         Ok(self)
     }
 
     pub fn return_(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0xb1u8)?;
-        Ok(self)
+        self.return_with(Opcode::Return)
     }
 
     pub fn saload(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x35u8)?;
+        self.opcode(Opcode::SALoad)?;
         Ok(self)
     }
 
     pub fn sastore(&mut self) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x56u8)?;
+        self.opcode(Opcode::SAStore)?;
         Ok(self)
     }
 
     pub fn sipush(&mut self, value: i16) -> Result<&mut Self, EncodeError> {
-        self.code_writer.encoder().write(0x11u8)?.write(value)?;
+        self.opcode(Opcode::SIPush)?.write(value)?;
+        Ok(self)
+    }
+
+    pub fn swap(&mut self) -> Result<&mut Self, EncodeError> {
+        self.opcode(Opcode::Swap)?;
+        Ok(self)
+    }
+
+    /// Writes a `tableswitch` or a `lookupswitch`, whichever is cheaper for the cases.
+    pub fn switch<F>(&mut self, f: F) -> Result<&mut Self, EncodeError>
+    where
+        F: for<'f> FnOnce(
+            SwitchWriter<'f, Ctx, SwitchWriterState::Default>,
+        ) -> Result<SwitchWriter<'f, Ctx, SwitchWriterState::Cases>, EncodeError>,
+    {
+        f(SwitchWriter::new(self)?)?.finish()?;
+
         Ok(self)
     }
 
@@ -1272,6 +1325,36 @@ impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
 
         Ok(self)
     }
+
+    /// Writes a protected body followed by catch clauses, whose exception table entries are written automatically.
+    pub fn try_catch<F>(&mut self, f: F) -> Result<&mut Self, EncodeError>
+    where
+        F: for<'f> FnOnce(
+            TryCatchWriter<'f, Ctx, TryCatchWriterState::Body>,
+        ) -> Result<TryCatchWriter<'f, Ctx, TryCatchWriterState::Catches>, EncodeError>,
+    {
+        f(TryCatchWriter::new(self, None)?)?.finish()?;
+
+        Ok(self)
+    }
+
+    /// Like [`InstructionWriter::try_catch`], but the finally code is executed whenever the body and the catch
+    /// clauses are left, be it by completing normally, by a return, by a jump or by an exception.
+    /// See [`TryCatchWriter`] for how the finally code is inserted.
+    ///
+    /// The local variables keeping returned values and exceptions are allocated like the ones of
+    /// [`InstructionWriter::begin_local`], so the slots of the parameters have to be known.
+    pub fn try_finally<F, G>(&mut self, mut finally: G, f: F) -> Result<&mut Self, EncodeError>
+    where
+        F: for<'f> FnOnce(
+            TryCatchWriter<'f, Ctx, TryCatchWriterState::Body>,
+        ) -> Result<TryCatchWriter<'f, Ctx, TryCatchWriterState::Catches>, EncodeError>,
+        G: FnMut(&mut InstructionWriter<Ctx>) -> Result<(), EncodeError>,
+    {
+        f(TryCatchWriter::new(self, Some(&mut finally))?)?.finish()?;
+
+        Ok(self)
+    }
 }
 
 impl<Ctx: EncoderContext> WriteAssembler for InstructionWriter<Ctx> {
@@ -1282,6 +1365,8 @@ impl<Ctx: EncoderContext> WriteAssembler for InstructionWriter<Ctx> {
         Ok(InstructionWriter {
             code_writer,
             start_offset,
+            last_opcode: None,
+            finally_scopes: Vec::new(),
         })
     }
 }
@@ -1290,151 +1375,11 @@ impl<Ctx: EncoderContext> WriteDisassembler for InstructionWriter<Ctx> {
     type Context = CodeWriter<Ctx, CodeWriterState::Instructions>;
 
     fn finish(mut self) -> Result<Self::Context, EncodeError> {
-        let start_offset = self.start_offset;
-        let len = self.current_offset().get();
-        let mut offset = 0;
-        while offset < len {
-            use RawInstruction::*;
-
-            let instruction_start = start_offset.offset(offset);
-
-            // we need to create a new decoder in each iteration as the bytes are modified later on in this block
-            let mut decoder = Decoder::new(self.code_writer.encoder().buf(), Context::Code);
-            // slicing does not work here because the decoder uses the current offset to compute table- and lookupswitch paddings
-            decoder
-                .advance(instruction_start.get())
-                .expect("decoder failed to read encoded instruction");
-            let prev_rem = decoder.bytes_remaining();
-            let instruction = RawInstruction::decode(&mut decoder, start_offset.get())
-                .expect("decoder failed to read encoded instruction");
-            let diff = prev_rem - decoder.bytes_remaining();
-
-            macro_rules! jmp_i16 {
-                ($jump_offset:expr) => {{
-                    let label_position = self
-                        .code_writer
-                        .get_label_position(LabelRef($jump_offset as u16 as u32))?;
-                    let label_offset = label_position as i64 - offset as i64;
-
-                    if let Ok(i) = i16::try_from(label_offset) {
-                        let mut encoder = self.code_writer.encoder().replacing(instruction_start.offset(1));
-                        encoder.write(i)?;
-                    } else {
-                        unimplemented!("noak does not support changing jump offset sizes yet");
-                    }
-                }};
-            }
-
-            macro_rules! jmp_i32 {
-                ($read_offset:expr) => {{
-                    let bytes = &self.code_writer.encoder().buf()[$read_offset.get()..];
-                    let mut decoder = Decoder::new(bytes, Context::Code);
-                    let label_index = LabelRef(decoder.read::<u32>().unwrap());
-                    let label_position = self.code_writer.get_label_position(label_index)?;
-                    let label_offset = label_position as i64 - offset as i64;
-
-                    let mut encoder = self.code_writer.encoder().replacing($read_offset);
-
-                    encoder.write(label_offset as i32)?;
-                }};
-            }
-
-            match instruction {
-                Goto { offset: jump_offset } => {
-                    jmp_i16!(jump_offset);
-                }
-                GotoW { .. } => {
-                    jmp_i32!(instruction_start.offset(1));
-                }
-                IfACmpEq { offset: jump_offset } => {
-                    jmp_i16!(jump_offset);
-                }
-                IfACmpNe { offset: jump_offset } => {
-                    jmp_i16!(jump_offset);
-                }
-                IfICmpEq { offset: jump_offset } => {
-                    jmp_i16!(jump_offset);
-                }
-                IfICmpNe { offset: jump_offset } => {
-                    jmp_i16!(jump_offset);
-                }
-                IfICmpLt { offset: jump_offset } => {
-                    jmp_i16!(jump_offset);
-                }
-                IfICmpGe { offset: jump_offset } => {
-                    jmp_i16!(jump_offset);
-                }
-                IfICmpGt { offset: jump_offset } => {
-                    jmp_i16!(jump_offset);
-                }
-                IfICmpLe { offset: jump_offset } => {
-                    jmp_i16!(jump_offset);
-                }
-                IfEq { offset: jump_offset } => {
-                    jmp_i16!(jump_offset);
-                }
-                IfNe { offset: jump_offset } => {
-                    jmp_i16!(jump_offset);
-                }
-                IfLt { offset: jump_offset } => {
-                    jmp_i16!(jump_offset);
-                }
-                IfGe { offset: jump_offset } => {
-                    jmp_i16!(jump_offset);
-                }
-                IfGt { offset: jump_offset } => {
-                    jmp_i16!(jump_offset);
-                }
-                IfLe { offset: jump_offset } => {
-                    jmp_i16!(jump_offset);
-                }
-                IfNonNull { offset: jump_offset } => {
-                    jmp_i16!(jump_offset);
-                }
-                IfNull { offset: jump_offset } => {
-                    jmp_i16!(jump_offset);
-                }
-                JSr { offset: jump_offset } => {
-                    jmp_i16!(jump_offset);
-                }
-                JSrW { .. } => {
-                    jmp_i32!(instruction_start.offset(1));
-                }
-                LookupSwitch(lookupswitch) => {
-                    let count = lookupswitch.pairs().count();
-                    // skip opcode and padding
-                    let offset_default = instruction_start.offset(1 + 3 - (offset & 3));
-
-                    // write correct default offset
-                    jmp_i32!(offset_default);
-
-                    // skip default and count
-                    let offset_pair_start = offset_default.offset(4 + 4);
-                    for i in 0..count {
-                        jmp_i32!(offset_pair_start.offset((i * 2 + 1) * 4));
-                    }
-                }
-                TableSwitch(tableswitch) => {
-                    let low = tableswitch.low();
-                    let high = tableswitch.high();
-
-                    // skip opcode and padding
-                    let offset_default = instruction_start.offset(1 + 3 - (offset & 3));
-
-                    // write correct default offset
-                    jmp_i32!(offset_default);
-
-                    // skip default, low and high
-                    let offset_pair_start = offset_default.offset(4 + 4 + 4);
-                    for i in low..=high {
-                        jmp_i32!(offset_pair_start.offset(i as usize * 4));
-                    }
-                }
-                _ => {}
-            }
-
-            offset += diff;
+        self.end_locals()?;
+        if self.code_writer.optimize {
+            peephole::optimize(&mut self.code_writer, self.start_offset)?;
         }
+        layout::resolve_labels(&mut self.code_writer, self.start_offset)?;
         Ok(self.code_writer)
     }
 }
@@ -1447,6 +1392,14 @@ impl<Ctx: EncoderContext> InternalEncoderContext for InstructionWriter<Ctx> {
     fn insert_constant<I: Into<cpool::Item>>(&mut self, item: I) -> Result<cpool::Index<I>, EncodeError> {
         self.code_writer.insert_constant(item)
     }
+
+    fn insert_bootstrap_method(&mut self, method: cpool::BootstrapMethod) -> Result<u16, EncodeError> {
+        self.code_writer.insert_bootstrap_method(method)
+    }
+
+    fn get_constant(&self, index: u16) -> Option<&cpool::Item> {
+        self.code_writer.get_constant(index)
+    }
 }
 
 impl<Ctx> fmt::Debug for InstructionWriter<Ctx> {
@@ -1454,3 +1407,45 @@ impl<Ctx> fmt::Debug for InstructionWriter<Ctx> {
         f.debug_struct("InstructionWriter").finish()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::reader::attributes::AttributeContent;
+    use crate::reader::Class;
+    use crate::testing::{attribute, first_code, write_code};
+
+    #[test]
+    fn line_numbers_follow_widened_branches() {
+        let bytes = write_code("(I)V", |code| {
+            code.max_stack(1)?
+                .max_locals(1)?
+                .instructions(|instructions| {
+                    let (end, end_ref) = instructions.new_label()?;
+                    instructions.line(1)?.iload0()?.ifeq(end_ref)?.line(2)?;
+                    for _ in 0..40000 {
+                        instructions.nop()?;
+                    }
+                    // no instruction belongs to line 3
+                    instructions.line(3)?.line(4)?.label(end)?.return_()?;
+                    Ok(())
+                })?
+                .exceptions(|_| Ok(()))?
+                .attributes(|_| Ok(()))
+        });
+
+        let class = Class::new(&bytes).unwrap();
+        let (_, code) = first_code(&class);
+        let AttributeContent::LineNumberTable(table) = attribute(&class, code.attributes(), "LineNumberTable") else {
+            unreachable!();
+        };
+        let lines = table
+            .lines()
+            .iter()
+            .map(|line| {
+                let line = line.unwrap();
+                (line.start().as_u32(), line.line_number())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(lines, [(0, 1), (9, 2), (40009, 4)]);
+    }
+}
@@ -0,0 +1,315 @@
+use std::num::NonZeroU32;
+use std::ops::Range;
+
+use crate::error::*;
+use crate::reader::attributes::RawInstruction;
+use crate::writer::{attributes::code::*, encoding::*};
+use crate::Opcode;
+
+const GOTO: u8 = Opcode::Goto.as_u8();
+const JSR: u8 = Opcode::JSr.as_u8();
+const TABLESWITCH: u8 = Opcode::TableSwitch.as_u8();
+const LOOKUPSWITCH: u8 = Opcode::LookupSwitch.as_u8();
+const IFNULL: u8 = Opcode::IfNull.as_u8();
+const IFNONNULL: u8 = Opcode::IfNonNull.as_u8();
+const GOTO_W: u8 = Opcode::GotoW.as_u8();
+const JSR_W: u8 = Opcode::JSrW.as_u8();
+
+/// An instruction whose placeholders for labels are yet to be replaced by jump offsets.
+enum Item {
+    /// An instruction without labels, which is copied as is.
+    Copy(Range<usize>),
+    /// A branch, where the opcode is always the one of the 16-bit variant.
+    Branch { opcode: u8, label: LabelRef, wide: bool },
+    TableSwitch {
+        default: LabelRef,
+        low: i32,
+        high: i32,
+        labels: Vec<LabelRef>,
+    },
+    LookupSwitch {
+        default: LabelRef,
+        pairs: Vec<(i32, LabelRef)>,
+    },
+}
+
+impl Item {
+    fn size(&self, offset: u32) -> u32 {
+        let padding = 3 - (offset & 3);
+        match self {
+            Item::Copy(range) => range.len() as u32,
+            Item::Branch { wide: false, .. } => 3,
+            Item::Branch { opcode: GOTO | JSR, .. } => 5,
+            // the inverted branch jumps over the following `goto_w`
+            Item::Branch { .. } => 3 + 5,
+            Item::TableSwitch { labels, .. } => 1 + padding + 12 + labels.len() as u32 * 4,
+            Item::LookupSwitch { pairs, .. } => 1 + padding + 8 + pairs.len() as u32 * 8,
+        }
+    }
+}
+
+/// Replaces the label placeholders of the code by jump offsets.
+///
+/// Branches whose target can't be reached with a 16-bit offset are widened: `goto` and `jsr` are replaced by
+/// `goto_w` and `jsr_w`, and conditional branches are inverted to jump over a `goto_w` to the target.
+/// As this moves the following instructions, the positions of all labels are updated as well.
+pub(super) fn resolve_labels<Ctx: EncoderContext>(
+    code_writer: &mut CodeWriter<Ctx, CodeWriterState::Instructions>,
+    start_offset: Offset,
+) -> Result<(), EncodeError> {
+    let code = code_writer.encoder().buf()[start_offset.get()..].to_vec();
+    let instructions = maxs::decode(&code)?;
+    // the offsets of the instructions as they were written, followed by the length of the code
+    let mut original_offsets: Vec<u32> = instructions.iter().map(|&(offset, _)| offset).collect();
+    original_offsets.push(code.len() as u32);
+    let mut items: Vec<Item> = instructions
+        .iter()
+        .zip(&original_offsets[1..])
+        .map(|((offset, instruction), &end)| to_item(&code, *offset, end, instruction))
+        .collect();
+
+    // widening a branch only ever moves instructions further apart, so this terminates
+    let mut offsets;
+    loop {
+        offsets = layout(&items);
+        let mut changed = false;
+        for (index, item) in items.iter_mut().enumerate() {
+            if let Item::Branch {
+                label,
+                wide: wide @ false,
+                ..
+            } = item
+            {
+                let target = resolve(code_writer, &original_offsets, &offsets, *label)?;
+                if i16::try_from(i64::from(target) - i64::from(offsets[index])).is_err() {
+                    *wide = true;
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut encoder = VecEncoder::new(Vec::with_capacity(code.len()));
+    for (index, item) in items.iter().enumerate() {
+        let offset = offsets[index];
+        let jump = |label: LabelRef, from: u32| -> Result<i32, EncodeError> {
+            let target = resolve(code_writer, &original_offsets, &offsets, label)?;
+            i32::try_from(i64::from(target) - i64::from(from))
+                .map_err(|_| EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::Code))
+        };
+
+        match item {
+            Item::Copy(range) => {
+                encoder.write_bytes(&code[range.clone()])?;
+            }
+            Item::Branch {
+                opcode,
+                label,
+                wide: false,
+            } => {
+                // the offset was checked while laying out the code
+                encoder.write(*opcode)?.write(jump(*label, offset)? as i16)?;
+            }
+            Item::Branch {
+                opcode: opcode @ (GOTO | JSR),
+                label,
+                wide: true,
+            } => {
+                let opcode = if *opcode == GOTO { GOTO_W } else { JSR_W };
+                encoder.write(opcode)?.write(jump(*label, offset)?)?;
+            }
+            Item::Branch { opcode, label, .. } => {
+                encoder
+                    .write(invert(*opcode))?
+                    .write(3i16 + 5)?
+                    .write(GOTO_W)?
+                    .write(jump(*label, offset + 3)?)?;
+            }
+            Item::TableSwitch {
+                default,
+                low,
+                high,
+                labels,
+            } => {
+                encoder.write(TABLESWITCH)?;
+                for _ in 0..3 - (offset & 3) {
+                    encoder.write(0u8)?;
+                }
+                encoder.write(jump(*default, offset)?)?.write(*low)?.write(*high)?;
+                for label in labels {
+                    encoder.write(jump(*label, offset)?)?;
+                }
+            }
+            Item::LookupSwitch { default, pairs } => {
+                encoder.write(LOOKUPSWITCH)?;
+                for _ in 0..3 - (offset & 3) {
+                    encoder.write(0u8)?;
+                }
+                // the amount of pairs was checked while writing the instruction
+                encoder.write(jump(*default, offset)?)?.write(pairs.len() as u32)?;
+                for (key, label) in pairs {
+                    encoder.write(*key)?.write(jump(*label, offset)?)?;
+                }
+            }
+        }
+    }
+
+    for index in 0..code_writer.label_positions.len() {
+        if let Some(position) = code_writer.label_positions[index] {
+            let position = relocate(&original_offsets, &offsets, position.get() - 1)?;
+            code_writer.label_positions[index] = NonZeroU32::new(position + 1);
+        }
+    }
+
+    code_writer.encoder().truncate(start_offset);
+    code_writer.encoder().write_bytes(encoder.inner())?;
+    Ok(())
+}
+
+fn to_item(code: &[u8], offset: u32, end: u32, instruction: &RawInstruction<'_>) -> Item {
+    use RawInstruction::*;
+
+    // the placeholders contain the label indices instead of the offsets
+    let branch = |label: i16| Item::Branch {
+        opcode: code[offset as usize],
+        label: LabelRef(label as u16 as u32),
+        wide: false,
+    };
+    match instruction {
+        Goto { offset } | JSr { offset } => branch(*offset),
+        IfEq { offset } | IfNe { offset } | IfLt { offset } | IfGe { offset } | IfGt { offset } | IfLe { offset } => {
+            branch(*offset)
+        }
+        IfICmpEq { offset }
+        | IfICmpNe { offset }
+        | IfICmpLt { offset }
+        | IfICmpGe { offset }
+        | IfICmpGt { offset }
+        | IfICmpLe { offset } => branch(*offset),
+        IfACmpEq { offset } | IfACmpNe { offset } | IfNull { offset } | IfNonNull { offset } => branch(*offset),
+        GotoW { offset: label } => Item::Branch {
+            opcode: GOTO,
+            label: LabelRef(*label as u32),
+            wide: true,
+        },
+        JSrW { offset: label } => Item::Branch {
+            opcode: JSR,
+            label: LabelRef(*label as u32),
+            wide: true,
+        },
+        TableSwitch(table) => Item::TableSwitch {
+            default: LabelRef(table.default_offset() as u32),
+            low: table.low(),
+            high: table.high(),
+            labels: table.pairs().map(|pair| LabelRef(pair.offset() as u32)).collect(),
+        },
+        LookupSwitch(lookup) => Item::LookupSwitch {
+            default: LabelRef(lookup.default_offset() as u32),
+            pairs: lookup
+                .pairs()
+                .map(|pair| (pair.key(), LabelRef(pair.offset() as u32)))
+                .collect(),
+        },
+        _ => Item::Copy(offset as usize..end as usize),
+    }
+}
+
+/// Computes the offsets of all items, followed by the length of the code.
+fn layout(items: &[Item]) -> Vec<u32> {
+    let mut offsets = Vec::with_capacity(items.len() + 1);
+    let mut offset = 0;
+    for item in items {
+        offsets.push(offset);
+        offset += item.size(offset);
+    }
+    offsets.push(offset);
+    offsets
+}
+
+fn resolve<Ctx: EncoderContext>(
+    code_writer: &CodeWriter<Ctx, CodeWriterState::Instructions>,
+    original_offsets: &[u32],
+    offsets: &[u32],
+    label: LabelRef,
+) -> Result<u32, EncodeError> {
+    let position = code_writer.get_label_position(label)?;
+    relocate(original_offsets, offsets, position)
+}
+
+/// Maps an offset in the written code to the offset after the branches were widened.
+fn relocate(original_offsets: &[u32], offsets: &[u32], position: u32) -> Result<u32, EncodeError> {
+    let index = original_offsets
+        .binary_search(&position)
+        .map_err(|_| EncodeError::with_context(EncodeErrorKind::LabelNotFound, Context::Code))?;
+    Ok(offsets[index])
+}
+
+/// Returns the opcode of the conditional branch which jumps if the given one does not.
+fn invert(opcode: u8) -> u8 {
+    match opcode {
+        IFNULL => IFNONNULL,
+        IFNONNULL => IFNULL,
+        // `ifeq` up to `if_acmpne` come in pairs of opposite conditions, starting at an odd opcode
+        _ if opcode & 1 == 1 => opcode + 1,
+        _ => opcode - 1,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::reader::attributes::{Index, RawInstruction};
+    use crate::reader::Class;
+    use crate::testing::{first_code, write_code};
+
+    #[test]
+    fn far_branches_are_widened() {
+        let bytes = write_code("(I)V", |mut code| {
+            let (top, top_ref) = code.new_label()?;
+            let (end, end_ref) = code.new_label()?;
+            code.max_stack(1)?
+                .max_locals(1)?
+                .instructions(|instructions| {
+                    instructions.label(top)?.iload0()?.ifeq(end_ref)?;
+                    for _ in 0..40000 {
+                        instructions.nop()?;
+                    }
+                    instructions.goto(top_ref)?.label(end)?.return_()?;
+                    Ok(())
+                })?
+                .exceptions(|exceptions| {
+                    exceptions.begin(|exception| {
+                        exception
+                            .start(top_ref)?
+                            .end(end_ref)?
+                            .handler(end_ref)?
+                            .catch_type("java/lang/Throwable")
+                    })?;
+                    Ok(())
+                })?
+                .attributes(|_| Ok(()))
+        });
+
+        let class = Class::new(&bytes).unwrap();
+        let (_, code) = first_code(&class);
+        let instructions = code.raw_instructions().collect::<Result<Vec<_>, _>>().unwrap();
+        let end = Index::new(40014);
+        assert_eq!(code.code_length(), 40015);
+        // the inverted branch skips the goto_w which jumps to the original target
+        assert!(matches!(instructions[1], (index, RawInstruction::IfNe { offset: 8 }) if index == Index::new(1)));
+        assert!(matches!(instructions[2], (index, RawInstruction::GotoW { offset: 40010 }) if index == Index::new(4)));
+        assert!(matches!(
+            instructions[instructions.len() - 2],
+            (index, RawInstruction::GotoW { offset: -40009 }) if index == Index::new(40009)
+        ));
+        assert!(matches!(instructions[instructions.len() - 1], (index, RawInstruction::Return) if index == end));
+
+        let handler = code.exception_handlers().next().unwrap();
+        assert_eq!(
+            (handler.start(), handler.end(), handler.handler()),
+            (Index::new(0), end, end)
+        );
+    }
+}
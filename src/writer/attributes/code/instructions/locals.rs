@@ -0,0 +1,521 @@
+use crate::descriptor::{MethodDescriptor, TypeDescriptor};
+use crate::error::*;
+use crate::header::AccessFlags;
+use crate::writer::{attributes::code::*, cpool, encoding::*};
+
+use super::typed::Kind;
+
+/// A local variable allocated by [`InstructionWriter::begin_local`].
+#[derive(Clone, Copy)]
+pub struct Local {
+    index: u16,
+    /// The index of the entry in the local variable table.
+    entry: usize,
+    kind: Kind,
+}
+
+impl Local {
+    /// The index of the first slot taken up by the local variable.
+    #[must_use]
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+}
+
+impl fmt::Debug for Local {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Local").field("index", &self.index).finish()
+    }
+}
+
+/// The local variables allocated so far, which are written to the `LocalVariableTable` and
+/// `LocalVariableTypeTable` attributes.
+#[derive(Default)]
+pub(crate) struct LocalVariables {
+    /// Whether the slot at an index is taken up by a local variable in scope.
+    in_use: Vec<bool>,
+    /// Whether the slot at an index was allocated at some point, so that its use is tracked by `in_use`.
+    allocated: Vec<bool>,
+    /// The amount of slots taken up by `this` and the parameters, or `None` if they are not known yet.
+    parameter_slots: Option<u16>,
+    /// The first slot of the next parameter declared by [`InstructionWriter::begin_parameter`].
+    next_parameter: u16,
+    entries: Vec<LocalVariableEntry>,
+    /// Whether a local variable was removed when optimizing.
+    removed: bool,
+}
+
+struct LocalVariableEntry {
+    start: LabelRef,
+    /// The end of the scope, or `None` if the local variable is in scope until the end of the code.
+    end: Option<LabelRef>,
+    index: u16,
+    size: u16,
+    name: cpool::Index<cpool::Utf8>,
+    descriptor: cpool::Index<cpool::Utf8>,
+    signature: Option<cpool::Index<cpool::Utf8>>,
+}
+
+impl LocalVariables {
+    /// Reserves the slots of `this` and the parameters, so local variables are allocated after them.
+    pub(crate) fn reserve_parameters(
+        &mut self,
+        access_flags: AccessFlags,
+        descriptor: &MethodDescriptor<'_>,
+    ) -> Result<(), EncodeError> {
+        let slots = maxs::parameter_slots(access_flags, descriptor)?;
+        if self.parameter_slots.is_some() {
+            return Err(EncodeError::with_context(
+                EncodeErrorKind::CantChangeAnymore,
+                Context::Code,
+            ));
+        }
+        self.parameter_slots = Some(slots);
+        if self.in_use.len() < usize::from(slots) {
+            self.in_use.resize(usize::from(slots), false);
+        }
+        self.in_use[..usize::from(slots)].fill(true);
+        Ok(())
+    }
+
+    /// Returns the lowest index after the parameters at which enough slots are free.
+    pub(super) fn allocate(&mut self, size: u16) -> Result<u16, EncodeError> {
+        self.allocate_avoiding(size, &[])
+    }
+
+    /// Returns the lowest index after the parameters at which enough slots are free, which also avoids the slots
+    /// accessed by instructions (given by their first slot and amount of slots) unless they were allocated before.
+    /// Such slots were written by index and are not known to be free.
+    pub(super) fn allocate_avoiding(&mut self, size: u16, accessed: &[(u16, u16)]) -> Result<u16, EncodeError> {
+        if self.parameter_slots.is_none() {
+            return Err(EncodeError::with_context(EncodeErrorKind::ValuesMissing, Context::Code));
+        }
+        let mut taken = self.in_use.clone();
+        for &(index, slots) in accessed {
+            let slots = usize::from(index)..usize::from(index) + usize::from(slots);
+            if !self
+                .allocated
+                .get(slots.clone())
+                .is_some_and(|allocated| allocated.iter().all(|&a| a))
+            {
+                if taken.len() < slots.end {
+                    taken.resize(slots.end, false);
+                }
+                taken[slots].fill(true);
+            }
+        }
+
+        let size = usize::from(size);
+        let mut index = 0;
+        while taken.iter().skip(index).take(size).any(|&taken| taken) {
+            index += 1;
+        }
+        if self.in_use.len() < index + size {
+            self.in_use.resize(index + size, false);
+        }
+        self.in_use[index..index + size].fill(true);
+        if self.allocated.len() < index + size {
+            self.allocated.resize(index + size, false);
+        }
+        self.allocated[index..index + size].fill(true);
+
+        u16::try_from(index + size - 1)
+            .map(|_| index as u16)
+            .map_err(|_| EncodeError::with_context(EncodeErrorKind::TooManyItems, Context::Code))
+    }
+
+    /// Frees slots which were allocated without an entry in the local variable table.
+    pub(super) fn free(&mut self, index: u16, size: u16) {
+        self.in_use[usize::from(index)..usize::from(index + size)].fill(false);
+    }
+
+    /// Returns the labels which only mark the start and end of the scopes.
+    pub(super) fn labels(&self) -> impl Iterator<Item = LabelRef> + '_ {
+        self.entries
+            .iter()
+            .flat_map(|entry| [Some(entry.start), entry.end])
+            .flatten()
+    }
+
+    /// Returns whether the slot is taken up by a local variable in the table.
+    pub(super) fn covers(&self, slot: u16) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| (entry.index..entry.index + entry.size).contains(&slot))
+    }
+
+    /// Records that a local variable was removed when optimizing.
+    pub(super) fn remove(&mut self) {
+        self.removed = true;
+    }
+
+    /// Returns whether a local variable was removed when optimizing.
+    pub(crate) fn any_removed(&self) -> bool {
+        self.removed
+    }
+
+    /// Writes the local variable table and, if any local variable has a signature, the local variable type table.
+    /// Returns the amount of attributes written.
+    pub(crate) fn write_tables<Ctx: EncoderContext>(
+        &self,
+        code_writer: &mut CodeWriter<Ctx, CodeWriterState::Attributes>,
+    ) -> Result<u16, EncodeError> {
+        if self.entries.is_empty() {
+            return Ok(0);
+        }
+
+        self.write_table(code_writer, "LocalVariableTable", |entry| Some(entry.descriptor))?;
+        if self.entries.iter().any(|entry| entry.signature.is_some()) {
+            self.write_table(code_writer, "LocalVariableTypeTable", |entry| entry.signature)?;
+            Ok(2)
+        } else {
+            Ok(1)
+        }
+    }
+
+    fn write_table<Ctx: EncoderContext>(
+        &self,
+        code_writer: &mut CodeWriter<Ctx, CodeWriterState::Attributes>,
+        name: &str,
+        descriptor: impl Fn(&LocalVariableEntry) -> Option<cpool::Index<cpool::Utf8>>,
+    ) -> Result<(), EncodeError> {
+        let name = code_writer.insert_constant(cpool::Utf8 { content: name.into() })?;
+        code_writer.encoder().write(name)?;
+        let length_writer = LengthWriter::new(code_writer)?;
+
+        let entries: Vec<_> = self
+            .entries
+            .iter()
+            .filter_map(|entry| Some((entry, descriptor(entry)?)))
+            .collect();
+        let count = u16::try_from(entries.len())
+            .map_err(|_| EncodeError::with_context(EncodeErrorKind::TooManyItems, Context::AttributeContent))?;
+        code_writer.encoder().write(count)?;
+
+        for (entry, descriptor) in entries {
+            let start = code_writer.get_label_position_u16(entry.start)?;
+            let end = code_writer.get_label_position_u16(entry.end.expect("the scopes were ended"))?;
+            code_writer
+                .encoder()
+                .write(start)?
+                .write(end - start)?
+                .write(entry.name)?
+                .write(descriptor)?
+                .write(entry.index)?;
+        }
+
+        length_writer.finish(code_writer)?;
+        Ok(())
+    }
+}
+
+/// Local variables which are allocated automatically and described in the local variable table.
+///
+/// The slots of `this` and the parameters are taken from the method descriptor passed to
+/// [`CodeWriter::compute_maxs`] or [`CodeWriter::parameters`], which has to happen before local variables are
+/// allocated.
+/// To describe them in the local variable table as well, they are declared by
+/// [`InstructionWriter::begin_parameter`] in their order before the first instruction.
+impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
+    /// Allocates a local variable whose scope starts at the current position,
+    /// reusing the slots of local variables whose scope ended.
+    ///
+    /// Fails with [`EncodeErrorKind::ValuesMissing`] if the slots of the parameters are not known.
+    pub fn begin_local<I>(&mut self, name: I, descriptor: &TypeDescriptor<'_>) -> Result<Local, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Utf8>,
+    {
+        self.begin_local_entry(name, descriptor, None)
+    }
+
+    /// Declares the next parameter, starting with `this` for methods which are not static.
+    ///
+    /// Fails with [`EncodeErrorKind::TooManyItems`] if all slots of the parameters are declared already.
+    pub fn begin_parameter<I>(&mut self, name: I, descriptor: &TypeDescriptor<'_>) -> Result<Local, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Utf8>,
+    {
+        let local_variables = &self.code_writer.local_variables;
+        let Some(parameter_slots) = local_variables.parameter_slots else {
+            return Err(EncodeError::with_context(EncodeErrorKind::ValuesMissing, Context::Code));
+        };
+        let index = local_variables.next_parameter;
+        let size = Kind::of(descriptor).size();
+        if index + size > parameter_slots {
+            return Err(EncodeError::with_context(EncodeErrorKind::TooManyItems, Context::Code));
+        }
+
+        let local = self.begin_entry(name, descriptor, None, index)?;
+        self.code_writer.local_variables.next_parameter = index + size;
+        Ok(local)
+    }
+
+    /// Like [`InstructionWriter::begin_local`], but the local variable is also written to the
+    /// local variable type table with the generic signature.
+    pub fn begin_local_with_signature<I, S>(
+        &mut self,
+        name: I,
+        descriptor: &TypeDescriptor<'_>,
+        signature: S,
+    ) -> Result<Local, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Utf8>,
+        S: cpool::Insertable<cpool::Utf8>,
+    {
+        let signature = signature.insert(&mut self.code_writer)?;
+        self.begin_local_entry(name, descriptor, Some(signature))
+    }
+
+    /// Ends the scope of a local variable at the current position, so its slots may be reused.
+    pub fn end_local(&mut self, local: Local) -> Result<&mut Self, EncodeError> {
+        if self.code_writer.local_variables.entries[local.entry].end.is_some() {
+            return Err(EncodeError::with_context(
+                EncodeErrorKind::CantChangeAnymore,
+                Context::Code,
+            ));
+        }
+
+        let (end, end_ref) = self.new_label()?;
+        self.label(end)?;
+        let local_variables = &mut self.code_writer.local_variables;
+        let entry = &mut local_variables.entries[local.entry];
+        entry.end = Some(end_ref);
+        let slots = usize::from(entry.index)..usize::from(entry.index + entry.size);
+        local_variables.in_use[slots].fill(false);
+        Ok(self)
+    }
+
+    /// Loads a local variable, choosing the instruction by its type.
+    pub fn load_local(&mut self, local: Local) -> Result<&mut Self, EncodeError> {
+        self.local_variable(local.kind.load(), local.index)
+    }
+
+    /// Stores a local variable, choosing the instruction by its type.
+    pub fn store_local(&mut self, local: Local) -> Result<&mut Self, EncodeError> {
+        self.local_variable(local.kind.store(), local.index)
+    }
+
+    fn begin_local_entry<I>(
+        &mut self,
+        name: I,
+        descriptor: &TypeDescriptor<'_>,
+        signature: Option<cpool::Index<cpool::Utf8>>,
+    ) -> Result<Local, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Utf8>,
+    {
+        let index = self.code_writer.local_variables.allocate(Kind::of(descriptor).size())?;
+        self.begin_entry(name, descriptor, signature, index)
+    }
+
+    /// Adds the entry of a local variable whose slots are taken up already.
+    fn begin_entry<I>(
+        &mut self,
+        name: I,
+        descriptor: &TypeDescriptor<'_>,
+        signature: Option<cpool::Index<cpool::Utf8>>,
+        index: u16,
+    ) -> Result<Local, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Utf8>,
+    {
+        let name = name.insert(&mut self.code_writer)?;
+        let descriptor_index =
+            cpool::Insertable::<cpool::Utf8>::insert(descriptor.to_mstring(), &mut self.code_writer)?;
+        let kind = Kind::of(descriptor);
+        let size = kind.size();
+
+        let (start, start_ref) = self.new_label()?;
+        self.label(start)?;
+        let local_variables = &mut self.code_writer.local_variables;
+        local_variables.entries.push(LocalVariableEntry {
+            start: start_ref,
+            end: None,
+            index,
+            size,
+            name,
+            descriptor: descriptor_index,
+            signature,
+        });
+
+        Ok(Local {
+            index,
+            entry: local_variables.entries.len() - 1,
+            kind,
+        })
+    }
+
+    /// Ends the scopes of all local variables which are still in scope at the end of the code.
+    pub(super) fn end_locals(&mut self) -> Result<(), EncodeError> {
+        if self
+            .code_writer
+            .local_variables
+            .entries
+            .iter()
+            .all(|entry| entry.end.is_some())
+        {
+            return Ok(());
+        }
+
+        let (end, end_ref) = self.new_label()?;
+        self.label(end)?;
+        for entry in &mut self.code_writer.local_variables.entries {
+            entry.end.get_or_insert(end_ref);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::descriptor::{MethodDescriptor, TypeDescriptor};
+    use crate::error::EncodeErrorKind;
+    use crate::header::AccessFlags;
+    use crate::mutf8;
+    use crate::mutf8::MStr;
+    use crate::reader::attributes::{AttributeContent, RawInstruction};
+    use crate::reader::Class;
+    use crate::testing::{attribute, first_code, write_code};
+
+    fn ty(descriptor: &MStr) -> TypeDescriptor<'_> {
+        TypeDescriptor::parse(descriptor).unwrap()
+    }
+
+    #[test]
+    fn tables_and_reused_slots() {
+        let bytes = write_code("(I)V", |code| {
+            code.max_stack(2)?
+                .max_locals(4)?
+                .parameters(AccessFlags::STATIC, &MethodDescriptor::parse(mutf8!("(I)V")).unwrap())?
+                .instructions(|instructions| {
+                    let x = instructions.begin_parameter("x", &ty(mutf8!("I")))?;
+                    let error = instructions.begin_parameter("y", &ty(mutf8!("I"))).unwrap_err();
+                    assert!(matches!(error.kind(), EncodeErrorKind::TooManyItems));
+                    let a = instructions.begin_local("a", &ty(mutf8!("J")))?;
+                    instructions.lconst0()?.store_local(a)?;
+                    let b = instructions.begin_local_with_signature(
+                        "b",
+                        &ty(mutf8!("Ljava/util/List;")),
+                        "Ljava/util/List<Ljava/lang/String;>;",
+                    )?;
+                    instructions.aconstnull()?.store_local(b)?.end_local(a)?;
+                    let c = instructions.begin_local("c", &ty(mutf8!("I")))?;
+                    instructions
+                        .load_local(x)?
+                        .store_local(c)?
+                        .load_local(b)?
+                        .pop()?
+                        .end_local(b)?
+                        .return_()?;
+
+                    let error = instructions.end_local(a).unwrap_err();
+                    assert!(matches!(error.kind(), EncodeErrorKind::CantChangeAnymore));
+                    assert_eq!([x, a, b, c].map(|local| local.index()), [0, 1, 3, 1]);
+                    Ok(())
+                })?
+                .exceptions(|_| Ok(()))?
+                .attributes(|_| Ok(()))
+        });
+
+        let class = Class::new(&bytes).unwrap();
+        let pool = class.pool();
+        let (_, code) = first_code(&class);
+        let instructions = code
+            .raw_instructions()
+            .map(|instruction| format!("{:?}", instruction.unwrap().1))
+            .collect::<Vec<_>>();
+        let expected = [
+            RawInstruction::LConst0,
+            RawInstruction::LStore1,
+            RawInstruction::AConstNull,
+            RawInstruction::AStore3,
+            RawInstruction::ILoad0,
+            RawInstruction::IStore1,
+            RawInstruction::ALoad3,
+            RawInstruction::Pop,
+            RawInstruction::Return,
+        ];
+        assert_eq!(instructions, expected.map(|instruction| format!("{instruction:?}")));
+
+        let AttributeContent::LocalVariableTable(table) = attribute(&class, code.attributes(), "LocalVariableTable")
+        else {
+            unreachable!();
+        };
+        let locals = table
+            .locals()
+            .iter()
+            .map(|local| {
+                let local = local.unwrap();
+                let range = local.range();
+                (
+                    pool.retrieve(local.name()).unwrap().to_str().unwrap().to_owned(),
+                    pool.retrieve(local.descriptor()).unwrap().to_str().unwrap().to_owned(),
+                    (range.start.as_u32(), range.end.as_u32()),
+                    local.index(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let local = |name: &str, descriptor: &str, range, index| (name.to_owned(), descriptor.to_owned(), range, index);
+        assert_eq!(
+            locals,
+            [
+                local("x", "I", (0, 9), 0),
+                local("a", "J", (0, 4), 1),
+                local("b", "Ljava/util/List;", (2, 8), 3),
+                local("c", "I", (4, 9), 1),
+            ]
+        );
+
+        let AttributeContent::LocalVariableTypeTable(table) =
+            attribute(&class, code.attributes(), "LocalVariableTypeTable")
+        else {
+            unreachable!();
+        };
+        let types = table
+            .locals()
+            .iter()
+            .map(|local| {
+                let local = local.unwrap();
+                let range = local.range();
+                (
+                    pool.retrieve(local.name()).unwrap().to_str().unwrap().to_owned(),
+                    pool.retrieve(local.signature()).unwrap().to_str().unwrap().to_owned(),
+                    (range.start.as_u32(), range.end.as_u32()),
+                    local.index(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(types, [local("b", "Ljava/util/List<Ljava/lang/String;>;", (2, 8), 3)]);
+    }
+
+    #[test]
+    fn locals_start_after_parameters() {
+        write_code("(JI)V", |code| {
+            code.compute_maxs(
+                AccessFlags::PUBLIC | AccessFlags::STATIC,
+                &MethodDescriptor::parse(mutf8!("(JI)V")).unwrap(),
+            )?
+            .instructions(|instructions| {
+                let local = instructions.begin_local("local", &ty(mutf8!("D")))?;
+                assert_eq!(local.index(), 3);
+                instructions.return_()?;
+                Ok(())
+            })?
+            .exceptions(|_| Ok(()))?
+            .attributes(|_| Ok(()))
+        });
+
+        write_code("()V", |code| {
+            code.max_stack(0)?
+                .max_locals(0)?
+                .instructions(|instructions| {
+                    let error = instructions.begin_local("local", &ty(mutf8!("I"))).unwrap_err();
+                    assert!(matches!(error.kind(), EncodeErrorKind::ValuesMissing));
+                    instructions.return_()?;
+                    Ok(())
+                })?
+                .exceptions(|_| Ok(()))?
+                .attributes(|_| Ok(()))
+        });
+    }
+}
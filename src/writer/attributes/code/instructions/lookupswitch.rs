@@ -2,6 +2,7 @@ use std::marker::PhantomData;
 
 use crate::error::*;
 use crate::writer::{attributes::code::*, encoding::*};
+use crate::Opcode;
 
 pub struct LookupSwitchWriter<'a, Ctx, State: LookupSwitchWriterState::State> {
     context: &'a mut InstructionWriter<Ctx>,
@@ -16,7 +17,9 @@ impl<'a, Ctx: EncoderContext> LookupSwitchWriter<'a, Ctx, LookupSwitchWriterStat
         self,
         label: LabelRef,
     ) -> Result<LookupSwitchWriter<'a, Ctx, LookupSwitchWriterState::Jumps>, EncodeError> {
-        self.context.encoder().write(label.0)?;
+        // the amount of pairs is updated as they are written
+        self.context.jump_target(label, true)?;
+        self.context.encoder().write(0u32)?;
 
         Ok(LookupSwitchWriter {
             context: self.context,
@@ -31,7 +34,7 @@ impl<'a, Ctx: EncoderContext> LookupSwitchWriter<'a, Ctx, LookupSwitchWriterStat
 impl<'a, Ctx: EncoderContext> LookupSwitchWriter<'a, Ctx, LookupSwitchWriterState::Jumps> {
     /// Write a key-label pair, where the keys must be written in an increasing numerical order.
     pub fn pair(mut self, key: i32, label: LabelRef) -> Result<Self, EncodeError> {
-        if self.last_key.map_or(false, |last_key| last_key >= key) {
+        if self.last_key.is_some_and(|last_key| last_key >= key) {
             return Err(EncodeError::with_context(
                 EncodeErrorKind::InvalidKeyOrder,
                 Context::Code,
@@ -42,13 +45,10 @@ impl<'a, Ctx: EncoderContext> LookupSwitchWriter<'a, Ctx, LookupSwitchWriterStat
             .count
             .checked_add(1)
             .ok_or_else(|| EncodeError::with_context(EncodeErrorKind::TooManyItems, Context::Code))?;
-        if self.count == 1 {
-            self.context.encoder().write(self.count)?;
-        } else {
-            self.context.encoder().replacing(self.count_offset).write(self.count)?;
-        }
+        self.context.encoder().replacing(self.count_offset).write(self.count)?;
 
-        self.context.encoder().write(key)?.write(label.0)?;
+        self.context.encoder().write(key)?;
+        self.context.jump_target(label, true)?;
 
         self.last_key = Some(key);
 
@@ -68,7 +68,7 @@ impl<'a, Ctx: EncoderContext> WriteAssembler for LookupSwitchWriter<'a, Ctx, Loo
     fn new(context: Self::Context) -> Result<Self, EncodeError> {
         let offset = context.current_offset();
 
-        context.encoder().write(0xabu8)?;
+        context.opcode(Opcode::LookupSwitch)?;
         for _ in 0..3 - (offset.get() & 3) {
             context.encoder().write(0u8)?;
         }
@@ -0,0 +1,690 @@
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+
+use crate::error::*;
+use crate::writer::{attributes::code::*, encoding::*};
+use crate::Opcode;
+
+const NOP: u8 = Opcode::Nop.as_u8();
+const ACONST_NULL: u8 = Opcode::AConstNull.as_u8();
+const ICONST_M1: u8 = Opcode::IConstM1.as_u8();
+const ICONST_0: u8 = Opcode::IConst0.as_u8();
+const ICONST_5: u8 = Opcode::IConst5.as_u8();
+const ILOAD: u8 = Opcode::ILoad.as_u8();
+const ALOAD: u8 = Opcode::ALoad.as_u8();
+const ILOAD_0: u8 = Opcode::ILoad0.as_u8();
+const ALOAD_3: u8 = Opcode::ALoad3.as_u8();
+const ISTORE: u8 = Opcode::IStore.as_u8();
+const ASTORE: u8 = Opcode::AStore.as_u8();
+const ISTORE_0: u8 = Opcode::IStore0.as_u8();
+const ASTORE_3: u8 = Opcode::AStore3.as_u8();
+const IINC: u8 = Opcode::IInc.as_u8();
+const IFEQ: u8 = Opcode::IfEq.as_u8();
+const IFNE: u8 = Opcode::IfNe.as_u8();
+const IF_ACMPNE: u8 = Opcode::IfACmpNe.as_u8();
+const GOTO: u8 = Opcode::Goto.as_u8();
+const RET: u8 = Opcode::Ret.as_u8();
+const TABLESWITCH: u8 = Opcode::TableSwitch.as_u8();
+const LOOKUPSWITCH: u8 = Opcode::LookupSwitch.as_u8();
+const WIDE: u8 = Opcode::Wide.as_u8();
+const IFNULL: u8 = Opcode::IfNull.as_u8();
+const IFNONNULL: u8 = Opcode::IfNonNull.as_u8();
+const GOTO_W: u8 = Opcode::GotoW.as_u8();
+
+/// An instruction as it was written, where the branches still contain label indices instead of jump offsets.
+struct Item {
+    offset: u32,
+    /// The bytes of the instruction, without the padding of switches.
+    bytes: Vec<u8>,
+    removed: bool,
+}
+
+/// How an instruction accesses a local variable.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Access {
+    /// A load of the kind, numbered like [`super::typed::Kind`].
+    Load(u8),
+    /// A store of the kind, numbered like [`super::typed::Kind`].
+    Store(u8),
+    /// `iinc` or `ret`.
+    Other,
+}
+
+/// Removes obviously unneeded instructions before the labels are resolved:
+///
+/// - `nop`s
+/// - `goto`s to the next instruction
+/// - branches to a `goto`, which jump to its target instead
+/// - conditional branches on a constant pushed right before, which become a `goto` or are removed
+/// - a store directly followed by a load of the same local variable, if the variable is not used anywhere else
+///   and is not described in the local variable table
+///
+/// Labels at removed instructions move to the next instruction, so that exception handlers, line numbers and
+/// the scopes of local variables stay consistent.
+/// Instructions which a label refers to, apart from the ones for line numbers and local variables,
+/// are never merged with the instruction before them, as they may be jumped to.
+pub(super) fn optimize<Ctx: EncoderContext>(
+    code_writer: &mut CodeWriter<Ctx, CodeWriterState::Instructions>,
+    start_offset: Offset,
+) -> Result<(), EncodeError> {
+    let code = code_writer.encoder().buf()[start_offset.get()..].to_vec();
+    let instructions = maxs::decode(&code)?;
+    let mut ends: Vec<u32> = instructions.iter().skip(1).map(|&(offset, _)| offset).collect();
+    ends.push(code.len() as u32);
+    let items: Vec<Item> = instructions
+        .iter()
+        .zip(ends)
+        .map(|(&(offset, _), end)| {
+            let mut bytes = code[offset as usize..end as usize].to_vec();
+            if matches!(bytes[0], TABLESWITCH | LOOKUPSWITCH) {
+                bytes.drain(1..1 + padding(offset) as usize);
+            }
+            Item {
+                offset,
+                bytes,
+                removed: false,
+            }
+        })
+        .collect();
+
+    let internal: Vec<LabelRef> = code_writer
+        .line_numbers
+        .iter()
+        .map(|&(label, _)| label)
+        .chain(code_writer.local_variables.labels())
+        .collect();
+    let mut targets: Vec<u32> = code_writer
+        .label_positions
+        .iter()
+        .enumerate()
+        .filter(|&(index, _)| !internal.contains(&LabelRef(index as u32)))
+        .filter_map(|(_, position)| position.map(|position| position.get() - 1))
+        .collect();
+    targets.sort_unstable();
+
+    let mut optimizer = Optimizer {
+        code_writer: &*code_writer,
+        following: (0..=items.len()).collect(),
+        preceding: (0..=items.len()).collect(),
+        items,
+        targets,
+        removed_local: false,
+    };
+    while optimizer.pass()? {}
+
+    let Optimizer {
+        items, removed_local, ..
+    } = optimizer;
+    if removed_local {
+        code_writer.local_variables.remove();
+    }
+    let mut encoder = VecEncoder::new(Vec::with_capacity(code.len()));
+    // the offset of every instruction, where removed instructions take the one of the next instruction
+    let mut offsets = Vec::with_capacity(items.len() + 1);
+    for item in &items {
+        let offset = encoder.position().get() as u32;
+        offsets.push(offset);
+        if item.removed {
+            continue;
+        }
+
+        encoder.write(item.bytes[0])?;
+        if matches!(item.bytes[0], TABLESWITCH | LOOKUPSWITCH) {
+            for _ in 0..padding(offset) {
+                encoder.write(0u8)?;
+            }
+        }
+        encoder.write_bytes(&item.bytes[1..])?;
+    }
+    offsets.push(encoder.position().get() as u32);
+
+    for index in 0..code_writer.label_positions.len() {
+        if let Some(position) = code_writer.label_positions[index] {
+            let position = position.get() - 1;
+            let item = if position == code.len() as u32 {
+                items.len()
+            } else {
+                items
+                    .binary_search_by_key(&position, |item| item.offset)
+                    .map_err(|_| EncodeError::with_context(EncodeErrorKind::LabelNotFound, Context::Code))?
+            };
+            code_writer.label_positions[index] = NonZeroU32::new(offsets[item] + 1);
+        }
+    }
+
+    code_writer.encoder().truncate(start_offset);
+    code_writer.encoder().write_bytes(encoder.inner())?;
+    Ok(())
+}
+
+struct Optimizer<'a, Ctx> {
+    code_writer: &'a CodeWriter<Ctx, CodeWriterState::Instructions>,
+    items: Vec<Item>,
+    /// The sorted positions of the labels which may be jumped to.
+    targets: Vec<u32>,
+    /// Whether a local variable was removed.
+    removed_local: bool,
+    /// For every item and the end of the code, the index of itself if it is not removed, or of a later item
+    /// which is closer to the next one that is not removed.
+    following: Vec<usize>,
+    /// Like `following`, but for the items before, shifted by one so that 0 stands for the start of the code.
+    preceding: Vec<usize>,
+}
+
+impl<'a, Ctx: EncoderContext> Optimizer<'a, Ctx> {
+    /// Applies every rewrite once and returns whether anything changed.
+    fn pass(&mut self) -> Result<bool, EncodeError> {
+        let mut uses: HashMap<u16, usize> = HashMap::new();
+        for item in self.items.iter().filter(|item| !item.removed) {
+            if let Some((access, index)) = local_access(&item.bytes) {
+                if !matches!(access, Access::Store(_)) {
+                    *uses.entry(index).or_default() += 1;
+                }
+            }
+        }
+
+        let mut changed = false;
+        for index in 0..self.items.len() {
+            if self.items[index].removed {
+                continue;
+            }
+            let next = self.next(index);
+            let opcode = self.items[index].bytes[0];
+
+            if opcode == NOP {
+                self.remove(index);
+                changed = true;
+                continue;
+            }
+
+            // a constant followed by a branch on it
+            if let Some(next) = next.filter(|&next| !self.is_target(next)) {
+                let jumps = match (opcode, self.items[next].bytes[0]) {
+                    (ICONST_M1..=ICONST_5, IFEQ) | (ACONST_NULL, IFNULL) => {
+                        Some(matches!(opcode, ICONST_0 | ACONST_NULL))
+                    }
+                    (ICONST_M1..=ICONST_5, IFNE) | (ACONST_NULL, IFNONNULL) => {
+                        Some(!matches!(opcode, ICONST_0 | ACONST_NULL))
+                    }
+                    _ => None,
+                };
+                if let Some(jumps) = jumps {
+                    self.remove(index);
+                    if jumps {
+                        self.items[next].bytes[0] = GOTO;
+                    } else {
+                        self.remove(next);
+                    }
+                    changed = true;
+                    continue;
+                }
+            }
+
+            // a store directly followed by the only load of the local variable
+            if let (Some(next), Some((Access::Store(kind), local))) = (next, local_access(&self.items[index].bytes)) {
+                if local_access(&self.items[next].bytes) == Some((Access::Load(kind), local))
+                    && !self.is_target(next)
+                    && uses.get(&local) == Some(&1)
+                    && !self.code_writer.local_variables.covers(local)
+                {
+                    self.removed_local = true;
+                    self.remove(index);
+                    self.remove(next);
+                    changed = true;
+                    continue;
+                }
+            }
+
+            if let Some(label) = branch_label(&self.items[index].bytes) {
+                let mut target_label = label;
+                // bounded, as `goto`s may jump in a cycle
+                for _ in 0..self.items.len() {
+                    match self.target(target_label)?.and_then(|target| {
+                        branch_label(&self.items[target].bytes)
+                            .filter(|_| matches!(self.items[target].bytes[0], GOTO | GOTO_W))
+                    }) {
+                        Some(label) if label != target_label => target_label = label,
+                        _ => break,
+                    }
+                }
+
+                if matches!(opcode, GOTO | GOTO_W) && self.target(target_label)? == next && next.is_some() {
+                    self.remove(index);
+                    changed = true;
+                } else if target_label != label {
+                    let bytes = &mut self.items[index].bytes;
+                    if opcode == GOTO_W {
+                        bytes[1..5].copy_from_slice(&target_label.to_be_bytes());
+                    } else if let Ok(target_label) = u16::try_from(target_label) {
+                        bytes[1..3].copy_from_slice(&target_label.to_be_bytes());
+                    } else {
+                        continue;
+                    }
+                    changed = true;
+                }
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Removes an item, so that the items around it skip it.
+    fn remove(&mut self, index: usize) {
+        self.items[index].removed = true;
+        self.following[index] = index + 1;
+        self.preceding[index + 1] = index;
+    }
+
+    /// Returns the first item at or after the index which is not removed, or the amount of items if there is none.
+    fn live_from(&mut self, mut index: usize) -> usize {
+        while self.following[index] != index {
+            let next = self.following[index];
+            self.following[index] = self.following[next];
+            index = next;
+        }
+        index
+    }
+
+    /// Returns the last item before the index which is not removed.
+    fn live_before(&mut self, index: usize) -> Option<usize> {
+        let mut position = index;
+        while self.preceding[position] != position {
+            let previous = self.preceding[position];
+            self.preceding[position] = self.preceding[previous];
+            position = previous;
+        }
+        position.checked_sub(1)
+    }
+
+    /// Returns the instruction after an instruction which is not removed.
+    fn next(&mut self, index: usize) -> Option<usize> {
+        Some(self.live_from(index + 1)).filter(|&next| next < self.items.len())
+    }
+
+    /// Returns the instruction the label refers to, or `None` if it refers to the end of the code.
+    fn target(&mut self, label: u32) -> Result<Option<usize>, EncodeError> {
+        let position = self.code_writer.get_label_position(LabelRef(label))?;
+        let index = self.items.partition_point(|item| item.offset < position);
+        let target = self.live_from(index);
+        Ok(Some(target).filter(|&target| target < self.items.len()))
+    }
+
+    /// Returns whether a label which may be jumped to refers to an instruction,
+    /// taking into account that labels at removed instructions move to the next one.
+    fn is_target(&mut self, index: usize) -> bool {
+        let previous = self.live_before(index);
+        let after = previous.map(|previous| self.items[previous].offset + 1).unwrap_or(0);
+        let start = self.targets.partition_point(|&target| target < after);
+        self.targets
+            .get(start)
+            .is_some_and(|&target| target <= self.items[index].offset)
+    }
+}
+
+/// Returns the label of a branch other than `jsr`.
+fn branch_label(bytes: &[u8]) -> Option<u32> {
+    match bytes[0] {
+        IFEQ..=IF_ACMPNE | GOTO | IFNULL | IFNONNULL => Some(u16::from_be_bytes([bytes[1], bytes[2]]).into()),
+        GOTO_W => Some(u32::from_be_bytes([bytes[1], bytes[2], bytes[3], bytes[4]])),
+        _ => None,
+    }
+}
+
+/// Returns how an instruction accesses a local variable, and its index.
+fn local_access(bytes: &[u8]) -> Option<(Access, u16)> {
+    let (opcode, index) = match bytes[0] {
+        WIDE => (bytes[1], u16::from_be_bytes([bytes[2], bytes[3]])),
+        opcode @ (ILOAD..=ALOAD | ISTORE..=ASTORE | IINC | RET) => (opcode, bytes[1].into()),
+        opcode => (opcode, 0),
+    };
+    let access = match opcode {
+        ILOAD..=ALOAD => (Access::Load(opcode - ILOAD), index),
+        ISTORE..=ASTORE => (Access::Store(opcode - ISTORE), index),
+        IINC | RET => (Access::Other, index),
+        ILOAD_0..=ALOAD_3 => (Access::Load((opcode - ILOAD_0) / 4), u16::from((opcode - ILOAD_0) % 4)),
+        ISTORE_0..=ASTORE_3 => (
+            Access::Store((opcode - ISTORE_0) / 4),
+            u16::from((opcode - ISTORE_0) % 4),
+        ),
+        _ => return None,
+    };
+    Some(access)
+}
+
+/// The padding of a switch, which aligns its operands to four bytes.
+fn padding(offset: u32) -> u32 {
+    3 - (offset & 3)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::descriptor::{MethodDescriptor, TypeDescriptor};
+    use crate::error::{EncodeError, EncodeErrorKind};
+    use crate::header::AccessFlags;
+    use crate::mutf8;
+    use crate::reader::attributes::{Index, RawInstruction};
+    use crate::reader::Class;
+    use crate::testing::{first_code, try_write_code, write_code};
+    use crate::writer::attributes::code::InstructionWriter;
+    use crate::writer::{ClassWriter, ClassWriterState};
+
+    /// Writes and optimizes a static method `m`, and passes its instructions read back to `check` together with the
+    /// amount of exception table entries. Each instruction is described by its offset, followed by the target of
+    /// jumps.
+    fn optimize<F, C>(f: F, check: C)
+    where
+        F: FnOnce(&mut InstructionWriter<ClassWriter<ClassWriterState::Methods>>) -> Result<(), EncodeError>,
+        C: FnOnce(Vec<String>, usize),
+    {
+        let bytes = write_code("(I)I", |code| {
+            code.max_stack(1)?
+                .max_locals(2)?
+                .parameters(AccessFlags::STATIC, &MethodDescriptor::parse(mutf8!("(I)I")).unwrap())?
+                .optimize()?
+                .instructions(f)?
+                .exceptions(|_| Ok(()))?
+                .attributes(|_| Ok(()))
+        });
+
+        let class = Class::new(&bytes).unwrap();
+        let (_, code) = first_code(&class);
+        let instructions = code
+            .raw_instructions()
+            .map(|instruction| {
+                let (index, instruction) = instruction.unwrap();
+                let offset = index.as_u32();
+                match instruction {
+                    RawInstruction::Goto { offset: relative } => {
+                        format!("{offset}: Goto -> {}", offset.wrapping_add_signed(relative.into()))
+                    }
+                    RawInstruction::IfEq { offset: relative } => {
+                        format!("{offset}: IfEq -> {}", offset.wrapping_add_signed(relative.into()))
+                    }
+                    instruction => format!("{offset}: {instruction:?}"),
+                }
+            })
+            .collect();
+        check(instructions, code.exception_handlers().count());
+    }
+
+    #[test]
+    fn nops_and_jumps_to_the_next_instruction() {
+        optimize(
+            |instructions| {
+                let (next, next_ref) = instructions.new_label()?;
+                let (end, end_ref) = instructions.new_label()?;
+                instructions
+                    .nop()?
+                    .iload0()?
+                    .ifeq(end_ref)?
+                    .goto(next_ref)?
+                    .label(next)?
+                    .nop()?
+                    .iload0()?
+                    .label(end)?
+                    .ireturn()?;
+                Ok(())
+            },
+            |instructions, _| {
+                assert_eq!(instructions, ["0: ILoad0", "1: IfEq -> 5", "4: ILoad0", "5: IReturn"]);
+            },
+        );
+    }
+
+    #[test]
+    fn labels_skip_long_runs_of_removed_instructions() {
+        optimize(
+            |instructions| {
+                let (middle, middle_ref) = instructions.new_label()?;
+                let (end, end_ref) = instructions.new_label()?;
+                instructions.iload0()?.ifeq(middle_ref)?.goto(end_ref)?;
+                for _ in 0..10000 {
+                    instructions.nop()?;
+                }
+                instructions.label(middle)?;
+                for _ in 0..10000 {
+                    instructions.nop()?;
+                }
+                instructions.iload0()?.label(end)?.ireturn()?;
+                Ok(())
+            },
+            |instructions, _| {
+                assert_eq!(
+                    instructions,
+                    ["0: ILoad0", "1: IfEq -> 7", "4: Goto -> 8", "7: ILoad0", "8: IReturn"]
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn jumps_to_jumps() {
+        optimize(
+            |instructions| {
+                let (first, first_ref) = instructions.new_label()?;
+                let (second, second_ref) = instructions.new_label()?;
+                instructions
+                    .iload0()?
+                    .ifeq(first_ref)?
+                    .iload0()?
+                    .ireturn()?
+                    .label(first)?
+                    .goto(second_ref)?
+                    .iload0()?
+                    .ireturn()?
+                    .label(second)?
+                    .iconst0()?
+                    .ireturn()?;
+                Ok(())
+            },
+            |instructions, _| {
+                assert_eq!(
+                    instructions,
+                    [
+                        "0: ILoad0",
+                        "1: IfEq -> 11",
+                        "4: ILoad0",
+                        "5: IReturn",
+                        "6: Goto -> 11",
+                        "9: ILoad0",
+                        "10: IReturn",
+                        "11: IConst0",
+                        "12: IReturn",
+                    ]
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn branches_on_constants() {
+        optimize(
+            |instructions| {
+                let (end, end_ref) = instructions.new_label()?;
+                instructions
+                    .iconst1()?
+                    .ifeq(end_ref)?
+                    .aconstnull()?
+                    .ifnull(end_ref)?
+                    .iload0()?
+                    .ireturn()?
+                    .label(end)?
+                    .iconst0()?
+                    .ireturn()?;
+                Ok(())
+            },
+            |instructions, _| {
+                assert_eq!(
+                    instructions,
+                    ["0: Goto -> 5", "3: ILoad0", "4: IReturn", "5: IConst0", "6: IReturn"]
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn jump_targets_are_kept() {
+        optimize(
+            |instructions| {
+                let (branch, branch_ref) = instructions.new_label()?;
+                let (end, end_ref) = instructions.new_label()?;
+                instructions
+                    .iconst0()?
+                    .label(branch)?
+                    .ifeq(end_ref)?
+                    .iload0()?
+                    .goto(branch_ref)?
+                    .label(end)?
+                    .iconst0()?
+                    .ireturn()?;
+                Ok(())
+            },
+            |instructions, _| {
+                assert_eq!(
+                    instructions,
+                    [
+                        "0: IConst0",
+                        "1: IfEq -> 8",
+                        "4: ILoad0",
+                        "5: Goto -> 1",
+                        "8: IConst0",
+                        "9: IReturn",
+                    ]
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn stores_followed_by_the_only_load() {
+        optimize(
+            |instructions| {
+                instructions.iload0()?.istore1()?.iload1()?.ireturn()?;
+                Ok(())
+            },
+            |instructions, _| {
+                assert_eq!(instructions, ["0: ILoad0", "1: IReturn"]);
+            },
+        );
+
+        optimize(
+            |instructions| {
+                instructions.iload0()?.istore1()?.iload1()?.iload1()?.ireturn()?;
+                Ok(())
+            },
+            |instructions, _| {
+                assert_eq!(instructions.len(), 5);
+            },
+        );
+
+        optimize(
+            |instructions| {
+                let local = instructions.begin_local("local", &TypeDescriptor::parse(mutf8!("I")).unwrap())?;
+                instructions
+                    .iload0()?
+                    .store_local(local)?
+                    .load_local(local)?
+                    .ireturn()?;
+                Ok(())
+            },
+            |instructions, _| {
+                assert_eq!(instructions.len(), 4);
+            },
+        );
+    }
+
+    #[test]
+    fn empty_protected_ranges_are_dropped() {
+        optimize(
+            |instructions| {
+                instructions
+                    .try_catch(|block| {
+                        block
+                            .body(|body| {
+                                body.nop()?;
+                                Ok(())
+                            })?
+                            .catch("java/lang/Throwable", |catch| {
+                                catch.athrow()?;
+                                Ok(())
+                            })
+                    })?
+                    .iconst0()?
+                    .ireturn()?;
+                Ok(())
+            },
+            |instructions, handlers| {
+                assert_eq!(handlers, 0);
+                assert_eq!(instructions.last().map(String::as_str), Some("5: IReturn"));
+            },
+        );
+    }
+
+    #[test]
+    fn written_exception_entries_are_relocated() {
+        let mut labels = Vec::new();
+        let bytes = write_code("(I)I", |code| {
+            code.max_stack(1)?
+                .max_locals(1)?
+                .parameters(AccessFlags::STATIC, &MethodDescriptor::parse(mutf8!("(I)I")).unwrap())?
+                .optimize()?
+                .instructions(|instructions| {
+                    let mut label = || -> Result<_, EncodeError> {
+                        let (label, label_ref) = instructions.new_label()?;
+                        labels.push(label_ref);
+                        Ok(label)
+                    };
+                    let (nop, load, ret, end, handler) = (label()?, label()?, label()?, label()?, label()?);
+                    instructions
+                        .label(nop)?
+                        .nop()?
+                        .label(load)?
+                        .iload0()?
+                        .label(ret)?
+                        .ireturn()?
+                        .label(end)?
+                        .label(handler)?
+                        .athrow()?;
+                    Ok(())
+                })?
+                .exceptions(|exceptions| {
+                    // the first entry only covers the removed `nop`, the others are adjacent
+                    let handler = labels[4];
+                    for range in labels[..4].windows(2) {
+                        exceptions
+                            .begin(|writer| writer.start(range[0])?.end(range[1])?.handler(handler)?.catch_any())?;
+                    }
+                    Ok(())
+                })?
+                .attributes(|_| Ok(()))
+        });
+
+        let class = Class::new(&bytes).unwrap();
+        let (_, code) = first_code(&class);
+        let handlers: Vec<_> = code
+            .exception_handlers()
+            .map(|handler| [handler.start(), handler.end(), handler.handler()].map(Index::as_u32))
+            .collect();
+        assert_eq!(handlers, [[0, 2, 2]]);
+    }
+
+    #[test]
+    fn local_variable_tables_written_by_hand_are_rejected() {
+        let error = try_write_code("(I)I", |code| {
+            code.max_stack(1)?
+                .max_locals(2)?
+                .parameters(AccessFlags::STATIC, &MethodDescriptor::parse(mutf8!("(I)I")).unwrap())?
+                .optimize()?
+                .instructions(|instructions| {
+                    instructions.iload0()?.istore1()?.iload1()?.ireturn()?;
+                    Ok(())
+                })?
+                .exceptions(|_| Ok(()))?
+                .attributes(|attributes| {
+                    attributes.begin(|attribute| attribute.local_variable_table(|_| Ok(())))?;
+                    Ok(())
+                })
+        })
+        .unwrap_err();
+        assert!(matches!(error.kind(), EncodeErrorKind::LocalVariableRemoved));
+    }
+}
@@ -0,0 +1,224 @@
+use std::marker::PhantomData;
+
+use crate::error::*;
+use crate::writer::{attributes::code::*, encoding::*};
+
+/// Writes a `tableswitch` or a `lookupswitch` for cases in any order.
+///
+/// The instruction is chosen with the same heuristic as javac, which weighs the size of the instruction
+/// against the amount of comparisons needed to find a key.
+/// Keys of a `tableswitch` without a case jump to the default label.
+pub struct SwitchWriter<'a, Ctx, State: SwitchWriterState::State> {
+    context: &'a mut InstructionWriter<Ctx>,
+    default: Option<LabelRef>,
+    cases: Vec<(i32, LabelRef)>,
+    _marker: PhantomData<State>,
+}
+
+impl<'a, Ctx: EncoderContext> SwitchWriter<'a, Ctx, SwitchWriterState::Default> {
+    pub fn default(self, label: LabelRef) -> Result<SwitchWriter<'a, Ctx, SwitchWriterState::Cases>, EncodeError> {
+        Ok(SwitchWriter {
+            context: self.context,
+            default: Some(label),
+            cases: self.cases,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<'a, Ctx: EncoderContext> SwitchWriter<'a, Ctx, SwitchWriterState::Cases> {
+    /// Adds a case, where a key may only be added again with the same label.
+    pub fn case(mut self, key: i32, label: LabelRef) -> Result<Self, EncodeError> {
+        self.cases.push((key, label));
+        Ok(self)
+    }
+}
+
+impl<'a, Ctx: EncoderContext> WriteAssembler for SwitchWriter<'a, Ctx, SwitchWriterState::Default> {
+    type Context = &'a mut InstructionWriter<Ctx>;
+
+    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+        Ok(SwitchWriter {
+            context,
+            default: None,
+            cases: Vec::new(),
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<'a, Ctx: EncoderContext> WriteDisassembler for SwitchWriter<'a, Ctx, SwitchWriterState::Cases> {
+    type Context = &'a mut InstructionWriter<Ctx>;
+
+    fn finish(mut self) -> Result<Self::Context, EncodeError> {
+        let default = self
+            .default
+            .ok_or_else(|| EncodeError::with_context(EncodeErrorKind::ValuesMissing, Context::Code))?;
+
+        self.cases.sort_by_key(|&(key, _)| key);
+        self.cases.dedup();
+        if self.cases.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return Err(EncodeError::with_context(EncodeErrorKind::DuplicateKey, Context::Code));
+        }
+
+        let cases = &self.cases;
+        match (cases.first(), cases.last()) {
+            (Some(&(low, _)), Some(&(high, _))) if prefers_table(low, high, cases.len()) => {
+                self.context.tableswitch(|writer| {
+                    let mut writer = writer.default(default)?.low(low)?.high(high)?;
+                    let mut cases = cases.iter().peekable();
+                    for key in low..=high {
+                        let label = cases
+                            .next_if(|&&(case, _)| case == key)
+                            .map_or(default, |&(_, label)| label);
+                        writer = writer.jump(label)?;
+                    }
+                    Ok(writer)
+                })?;
+            }
+            _ => {
+                self.context.lookupswitch(|writer| {
+                    let mut writer = writer.default(default)?;
+                    for &(key, label) in cases {
+                        writer = writer.pair(key, label)?;
+                    }
+                    Ok(writer)
+                })?;
+            }
+        }
+
+        Ok(self.context)
+    }
+}
+
+/// Compares the costs of both instructions in the same way as javac, where the size is measured in 4-byte words.
+fn prefers_table(low: i32, high: i32, count: usize) -> bool {
+    let table_space_cost = 4 + (i64::from(high) - i64::from(low) + 1);
+    let table_time_cost = 3;
+    let lookup_space_cost = 3 + 2 * count as i64;
+    let lookup_time_cost = count as i64;
+    table_space_cost + 3 * table_time_cost <= lookup_space_cost + 3 * lookup_time_cost
+}
+
+impl<'a, Ctx, State: SwitchWriterState::State> fmt::Debug for SwitchWriter<'a, Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SwitchWriter").finish()
+    }
+}
+
+enc_state!(pub mod SwitchWriterState: Default, Cases);
+
+#[cfg(test)]
+mod test {
+    use crate::error::{EncodeError, EncodeErrorKind};
+    use crate::reader::attributes::RawInstruction;
+    use crate::reader::Class;
+    use crate::testing::{first_code, write_code};
+    use crate::writer::attributes::code::instructions::{SwitchWriter, SwitchWriterState};
+    use crate::writer::attributes::code::LabelRef;
+    use crate::writer::{ClassWriter, ClassWriterState};
+
+    type Writer<'f, State> = SwitchWriter<'f, ClassWriter<ClassWriterState::Methods>, State>;
+
+    /// The name of a switch instruction with its default target and its keys with their targets.
+    type Switch = (&'static str, u32, Vec<(i32, u32)>);
+
+    /// Writes a switch over the first parameter, where `f` gets the labels `a` and `b` and `expected` gets their
+    /// offsets followed by the one of the default label. Each label is followed by a `return`.
+    fn assert_switch<F, E>(f: F, expected: E)
+    where
+        F: for<'f> FnOnce(
+            Writer<'f, SwitchWriterState::Cases>,
+            [LabelRef; 2],
+        ) -> Result<Writer<'f, SwitchWriterState::Cases>, EncodeError>,
+        E: FnOnce([u32; 3]) -> Switch,
+    {
+        let bytes = write_code("(I)V", |code| {
+            code.max_stack(1)?
+                .max_locals(1)?
+                .instructions(|instructions| {
+                    let (a, a_ref) = instructions.new_label()?;
+                    let (b, b_ref) = instructions.new_label()?;
+                    let (default, default_ref) = instructions.new_label()?;
+                    instructions
+                        .iload0()?
+                        .switch(|switch| f(switch.default(default_ref)?, [a_ref, b_ref]))?
+                        .label(a)?
+                        .return_()?
+                        .label(b)?
+                        .return_()?
+                        .label(default)?
+                        .return_()?;
+                    Ok(())
+                })?
+                .exceptions(|_| Ok(()))?
+                .attributes(|_| Ok(()))
+        });
+
+        let class = Class::new(&bytes).unwrap();
+        let (_, code) = first_code(&class);
+        let instructions = code.raw_instructions().collect::<Result<Vec<_>, _>>().unwrap();
+        let labels = [2, 3, 4].map(|index| instructions[index].0.as_u32());
+        let (offset, instruction) = &instructions[1];
+        let target = |relative: i32| offset.as_u32().wrapping_add_signed(relative);
+        let switch = match instruction {
+            RawInstruction::TableSwitch(table) => (
+                "tableswitch",
+                target(table.default_offset()),
+                table.pairs().map(|pair| (pair.key(), target(pair.offset()))).collect(),
+            ),
+            RawInstruction::LookupSwitch(lookup) => (
+                "lookupswitch",
+                target(lookup.default_offset()),
+                lookup.pairs().map(|pair| (pair.key(), target(pair.offset()))).collect(),
+            ),
+            instruction => panic!("expected a switch instead of {instruction:?}"),
+        };
+        assert_eq!(switch, expected(labels));
+    }
+
+    #[test]
+    fn dense_cases_use_a_table() {
+        assert_switch(
+            |switch, [a, b]| switch.case(4, b)?.case(1, a)?.case(2, a)?.case(1, a),
+            |[a, b, default]| ("tableswitch", default, vec![(1, a), (2, a), (3, default), (4, b)]),
+        );
+    }
+
+    #[test]
+    fn sparse_cases_use_a_lookup() {
+        assert_switch(
+            |switch, [a, b]| switch.case(1000, b)?.case(-5, a),
+            |[a, b, default]| ("lookupswitch", default, vec![(-5, a), (1000, b)]),
+        );
+    }
+
+    #[test]
+    fn no_cases() {
+        assert_switch(
+            |switch, _| Ok(switch),
+            |[_, _, default]| ("lookupswitch", default, vec![]),
+        );
+    }
+
+    #[test]
+    fn conflicting_cases() {
+        write_code("(I)V", |code| {
+            code.max_stack(1)?
+                .max_locals(1)?
+                .instructions(|instructions| {
+                    let (a, a_ref) = instructions.new_label()?;
+                    let (b, b_ref) = instructions.new_label()?;
+                    let error = instructions
+                        .iload0()?
+                        .switch(|switch| switch.default(a_ref)?.case(1, a_ref)?.case(1, b_ref))
+                        .unwrap_err();
+                    assert!(matches!(error.kind(), EncodeErrorKind::DuplicateKey));
+                    instructions.label(a)?.label(b)?.return_()?;
+                    Ok(())
+                })?
+                .exceptions(|_| Ok(()))?
+                .attributes(|_| Ok(()))
+        });
+    }
+}
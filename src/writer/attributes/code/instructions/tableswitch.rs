@@ -2,6 +2,7 @@ use std::marker::PhantomData;
 
 use crate::error::*;
 use crate::writer::{attributes::code::*, encoding::*};
+use crate::Opcode;
 
 pub struct TableSwitchWriter<'a, Ctx, State: TableSwitchWriterState::State> {
     context: &'a mut InstructionWriter<Ctx>,
@@ -17,7 +18,7 @@ impl<'a, Ctx: EncoderContext> TableSwitchWriter<'a, Ctx, TableSwitchWriterState:
         self,
         label: LabelRef,
     ) -> Result<TableSwitchWriter<'a, Ctx, TableSwitchWriterState::Low>, EncodeError> {
-        self.context.encoder().write(label.0)?;
+        self.context.jump_target(label, true)?;
 
         Ok(TableSwitchWriter {
             context: self.context,
@@ -69,7 +70,7 @@ impl<'a, Ctx: EncoderContext> TableSwitchWriter<'a, Ctx, TableSwitchWriterState:
         }
 
         self.remaining -= 1;
-        self.context.encoder().write(label.0)?;
+        self.context.jump_target(label, true)?;
 
         Ok(TableSwitchWriter {
             context: self.context,
@@ -85,7 +86,7 @@ impl<'a, Ctx: EncoderContext> WriteAssembler for TableSwitchWriter<'a, Ctx, Tabl
     fn new(context: Self::Context) -> Result<Self, EncodeError> {
         let offset = context.current_offset();
 
-        context.encoder().write(0xaau8)?;
+        context.opcode(Opcode::TableSwitch)?;
         for _ in 0..3 - (offset.get() & 3) {
             context.encoder().write(0u8)?;
         }
@@ -0,0 +1,583 @@
+use std::marker::PhantomData;
+
+use crate::error::*;
+use crate::writer::{attributes::code::*, cpool, encoding::*};
+use crate::Opcode;
+
+use super::typed::Kind;
+
+type Finally<'a, Ctx> = &'a mut dyn FnMut(&mut InstructionWriter<Ctx>) -> Result<(), EncodeError>;
+
+/// Writes a protected block followed by its exception handlers.
+///
+/// The exception table entries are recorded and written before the ones of [`CodeWriter::exceptions`].
+/// If the block has finally code, it is executed on every way out of the body and the catch clauses, as javac does:
+/// - it is inserted whenever the body or a catch clause completes normally
+/// - returns and jumps to labels outside of the block jump to a copy of the finally code instead,
+///   which is followed by the return or the jump; the returned value is kept in a local variable meanwhile
+/// - a handler for all exceptions stores the exception in a local variable, runs the finally code and rethrows it,
+///   which includes exceptions thrown by `athrow`
+///
+/// The copies and the handler are written after the catch clauses and are not protected by the block.
+pub struct TryCatchWriter<'a, Ctx, State: TryCatchWriterState::State> {
+    context: &'a mut InstructionWriter<Ctx>,
+    finally: Option<Finally<'a, Ctx>>,
+    /// The protected range of the body, if it is not empty.
+    body: Option<(LabelRef, LabelRef)>,
+    /// The ranges which are protected by the handler running the finally code.
+    finally_ranges: Vec<(LabelRef, LabelRef)>,
+    /// The returns and jumps written in the body and the catch clauses, if the block has finally code.
+    exits: FinallyScope,
+    /// Whether the instructions written last complete normally and have to jump behind the handlers,
+    /// unless nothing is written after them.
+    pending_exit: bool,
+    after: Label,
+    after_ref: LabelRef,
+    _marker: PhantomData<State>,
+}
+
+/// The ways out of the body and the catch clauses of a block with finally code.
+#[derive(Default)]
+pub(super) struct FinallyScope {
+    /// The jumps whose targets may be outside of the block.
+    pub(super) jumps: Vec<Jump>,
+    /// The indices of the labels placed inside of the block.
+    pub(super) labels: Vec<u32>,
+    /// The return instructions which were replaced by a jump to a label, together with that label.
+    pub(super) returns: Vec<(Opcode, Label, LabelRef)>,
+}
+
+/// A jump target written by [`InstructionWriter::jump_target`].
+pub(super) struct Jump {
+    /// The position of the placeholder of the target.
+    pub(super) operand: Offset,
+    pub(super) label: LabelRef,
+    /// Whether the placeholder takes up four instead of two bytes.
+    pub(super) wide: bool,
+}
+
+impl<'a, Ctx: EncoderContext> TryCatchWriter<'a, Ctx, TryCatchWriterState::Body> {
+    pub fn body<F>(mut self, f: F) -> Result<TryCatchWriter<'a, Ctx, TryCatchWriterState::Catches>, EncodeError>
+    where
+        F: FnOnce(&mut InstructionWriter<Ctx>) -> Result<(), EncodeError>,
+    {
+        let (body, completes_normally) = self.protect(f)?;
+        self.body = body;
+        if self.finally.is_some() {
+            self.finally_ranges.extend(body);
+        }
+        self.exit(completes_normally)?;
+
+        Ok(TryCatchWriter {
+            context: self.context,
+            finally: self.finally,
+            body: self.body,
+            finally_ranges: self.finally_ranges,
+            exits: self.exits,
+            pending_exit: self.pending_exit,
+            after: self.after,
+            after_ref: self.after_ref,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<'a, Ctx: EncoderContext> TryCatchWriter<'a, Ctx, TryCatchWriterState::Catches> {
+    /// Writes a handler for exceptions of the given class thrown in the body.
+    /// The exception is on top of the stack when the handler starts.
+    pub fn catch<I, F>(mut self, catch_type: I, f: F) -> Result<Self, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Class>,
+        F: FnOnce(&mut InstructionWriter<Ctx>) -> Result<(), EncodeError>,
+    {
+        let catch_type = catch_type.insert(self.context)?;
+        self.flush_exit()?;
+        let (handler, handler_ref) = self.context.new_label()?;
+        self.context.label(handler)?;
+        if let Some((start, end)) = self.body {
+            self.context.code_writer.exception_entries.push(ExceptionEntry {
+                start,
+                end,
+                handler: handler_ref,
+                catch_type: Some(catch_type),
+            });
+        }
+
+        let (range, completes_normally) = self.protect(f)?;
+        if self.finally.is_some() {
+            self.finally_ranges.extend(range);
+        }
+        self.exit(completes_normally)?;
+        Ok(self)
+    }
+}
+
+impl<'a, Ctx: EncoderContext, State: TryCatchWriterState::State> TryCatchWriter<'a, Ctx, State> {
+    /// Writes the instructions and returns their range, unless nothing was written,
+    /// and whether the instructions may complete normally.
+    fn protect<F>(&mut self, f: F) -> Result<(Option<(LabelRef, LabelRef)>, bool), EncodeError>
+    where
+        F: FnOnce(&mut InstructionWriter<Ctx>) -> Result<(), EncodeError>,
+    {
+        let (start, start_ref) = self.context.new_label()?;
+        let start_offset = self.context.current_offset();
+        self.context.label(start)?;
+        if self.finally.is_some() {
+            self.context.finally_scopes.push(FinallyScope::default());
+        }
+        f(self.context)?;
+        if self.finally.is_some() {
+            if let Some(scope) = self.context.finally_scopes.pop() {
+                self.exits.jumps.extend(scope.jumps);
+                self.exits.labels.extend(scope.labels);
+                self.exits.returns.extend(scope.returns);
+            }
+        }
+        if self.context.current_offset() == start_offset {
+            return Ok((None, true));
+        }
+
+        let completes_normally = self.context.is_reachable();
+        let (end, end_ref) = self.context.new_label()?;
+        self.context.label(end)?;
+        Ok((Some((start_ref, end_ref)), completes_normally))
+    }
+
+    /// Runs the finally code if the previous instructions complete normally.
+    /// The jump behind the handlers is written once the next instructions are written.
+    fn exit(&mut self, completes_normally: bool) -> Result<(), EncodeError> {
+        if completes_normally {
+            if let Some(finally) = &mut self.finally {
+                finally(self.context)?;
+            }
+            self.pending_exit = true;
+        }
+        Ok(())
+    }
+
+    fn flush_exit(&mut self) -> Result<(), EncodeError> {
+        if std::mem::take(&mut self.pending_exit) {
+            self.context.goto(self.after_ref)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, Ctx: EncoderContext> TryCatchWriter<'a, Ctx, TryCatchWriterState::Body> {
+    pub(super) fn new(
+        context: &'a mut InstructionWriter<Ctx>,
+        finally: Option<Finally<'a, Ctx>>,
+    ) -> Result<Self, EncodeError> {
+        let (after, after_ref) = context.new_label()?;
+        Ok(TryCatchWriter {
+            context,
+            finally,
+            body: None,
+            finally_ranges: Vec::new(),
+            exits: FinallyScope::default(),
+            pending_exit: false,
+            after,
+            after_ref,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<'a, Ctx: EncoderContext> TryCatchWriter<'a, Ctx, TryCatchWriterState::Catches> {
+    pub(super) fn finish(mut self) -> Result<(), EncodeError> {
+        let Some(finally) = self.finally.take() else {
+            self.context.label(self.after)?;
+            return Ok(());
+        };
+
+        let FinallyScope { jumps, labels, returns } = std::mem::take(&mut self.exits);
+        // the labels are inside of an enclosing block as well
+        if let Some(scope) = self.context.finally_scopes.last_mut() {
+            scope.labels.extend(&labels);
+        }
+
+        // the jumps to labels outside of the block jump to a copy of the finally code instead
+        let mut jump_exits: Vec<(LabelRef, Label, LabelRef)> = Vec::new();
+        for jump in jumps {
+            let is_return = returns.iter().any(|&(_, _, exit)| exit == jump.label);
+            if is_return || labels.contains(&jump.label.0) {
+                continue;
+            }
+
+            let exit = match jump_exits.iter().find(|&&(target, _, _)| target == jump.label) {
+                Some(&(_, _, exit)) => exit,
+                None => {
+                    let (exit, exit_ref) = self.context.new_label()?;
+                    jump_exits.push((jump.label, exit, exit_ref));
+                    exit_ref
+                }
+            };
+            let mut encoder = self.context.code_writer.encoder().replacing(jump.operand);
+            if jump.wide {
+                encoder.write(exit.0)?;
+            } else {
+                let index = u16::try_from(exit.0)
+                    .map_err(|_| EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::Code))?;
+                encoder.write(index)?;
+            }
+        }
+
+        if !self.finally_ranges.is_empty() || !returns.is_empty() || !jump_exits.is_empty() {
+            self.flush_exit()?;
+        }
+
+        if !self.finally_ranges.is_empty() {
+            let (handler, handler_ref) = self.context.new_label()?;
+            self.context.label(handler)?;
+            for (start, end) in std::mem::take(&mut self.finally_ranges) {
+                self.context.code_writer.exception_entries.push(ExceptionEntry {
+                    start,
+                    end,
+                    handler: handler_ref,
+                    catch_type: None,
+                });
+            }
+            self.keeping(Kind::Reference, finally)?;
+            self.context.athrow()?;
+        }
+
+        for (opcode, exit, _) in returns {
+            self.context.label(exit)?;
+            match returned_kind(opcode) {
+                Some(kind) => self.keeping(kind, finally)?,
+                None => finally(self.context)?,
+            }
+            self.context.return_with(opcode)?;
+        }
+
+        for (target, exit, _) in jump_exits {
+            self.context.label(exit)?;
+            finally(self.context)?;
+            self.context.goto(target)?;
+        }
+
+        self.context.label(self.after)?;
+        Ok(())
+    }
+
+    /// Runs the finally code while the value on top of the stack is kept in a local variable.
+    /// The local variable avoids the slots written by index so far, as they are not known to the allocator.
+    fn keeping(
+        &mut self,
+        kind: Kind,
+        finally: &mut dyn FnMut(&mut InstructionWriter<Ctx>) -> Result<(), EncodeError>,
+    ) -> Result<(), EncodeError> {
+        let start = self.context.start_offset.get();
+        let code = &self.context.code_writer.encoder().buf()[start..];
+        let accessed: Vec<(u16, u16)> = maxs::decode(code)?
+            .iter()
+            .filter_map(|(_, instruction)| instruction.local_variable())
+            .collect();
+        let index = self
+            .context
+            .code_writer
+            .local_variables
+            .allocate_avoiding(kind.size(), &accessed)?;
+        self.context.local_variable(kind.store(), index)?;
+        finally(self.context)?;
+        self.context.local_variable(kind.load(), index)?;
+        self.context.code_writer.local_variables.free(index, kind.size());
+        Ok(())
+    }
+}
+
+/// Returns the kind of the value returned by a return instruction, if there is one.
+fn returned_kind(opcode: Opcode) -> Option<Kind> {
+    match opcode {
+        Opcode::IReturn => Some(Kind::Int),
+        Opcode::LReturn => Some(Kind::Long),
+        Opcode::FReturn => Some(Kind::Float),
+        Opcode::DReturn => Some(Kind::Double),
+        Opcode::AReturn => Some(Kind::Reference),
+        _ => None,
+    }
+}
+
+impl<'a, Ctx, State: TryCatchWriterState::State> fmt::Debug for TryCatchWriter<'a, Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TryCatchWriter").finish()
+    }
+}
+
+enc_state!(pub mod TryCatchWriterState: Body, Catches);
+
+#[cfg(test)]
+mod test {
+    use crate::descriptor::MethodDescriptor;
+    use crate::error::EncodeError;
+    use crate::header::AccessFlags;
+    use crate::mutf8::MStr;
+    use crate::reader::attributes::{Index, RawInstruction};
+    use crate::reader::Class;
+    use crate::testing::{first_code, write_code};
+    use crate::writer::attributes::code::InstructionWriter;
+    use crate::writer::cpool::MethodRef;
+    use crate::writer::{ClassWriter, ClassWriterState};
+
+    /// The offset and mnemonic of an instruction with the target of `goto` and `ifeq`.
+    type Written = (u32, &'static str, Option<u32>);
+    /// The start, end and handler offsets of an exception table entry with the name of the caught class.
+    type Handler = (u32, u32, u32, Option<String>);
+
+    /// Writes a static method `m` and reads back its instructions, followed by its exception table.
+    fn write<F>(descriptor: &str, f: F) -> (Vec<Written>, Vec<Handler>)
+    where
+        F: FnOnce(&mut InstructionWriter<ClassWriter<ClassWriterState::Methods>>) -> Result<(), EncodeError>,
+    {
+        let bytes = write_code(descriptor, |code| {
+            let descriptor = MethodDescriptor::parse(MStr::from_mutf8(descriptor.as_bytes()).unwrap()).unwrap();
+            code.compute_maxs(AccessFlags::STATIC, &descriptor)?
+                .instructions(f)?
+                .exceptions(|_| Ok(()))?
+                .attributes(|_| Ok(()))
+        });
+
+        let class = Class::new(&bytes).unwrap();
+        let (_, code) = first_code(&class);
+        let instructions = code
+            .raw_instructions()
+            .map(|instruction| {
+                let (index, raw) = instruction.unwrap();
+                let target = match raw {
+                    RawInstruction::Goto { offset } | RawInstruction::IfEq { offset } => {
+                        Some(index.as_u32().wrapping_add_signed(offset.into()))
+                    }
+                    _ => None,
+                };
+                (index.as_u32(), raw.opcode().mnemonic(), target)
+            })
+            .collect();
+        let handlers = code
+            .exception_handlers()
+            .map(|handler| {
+                let catch_type = handler.catch_type().map(|catch_type| {
+                    let class = class.pool().retrieve(catch_type).unwrap();
+                    class.name.to_str().unwrap().to_owned()
+                });
+                let offsets = [handler.start(), handler.end(), handler.handler()].map(Index::as_u32);
+                (offsets[0], offsets[1], offsets[2], catch_type)
+            })
+            .collect();
+        (instructions, handlers)
+    }
+
+    fn call(
+        writer: &mut InstructionWriter<ClassWriter<ClassWriterState::Methods>>,
+        name: &str,
+    ) -> Result<(), EncodeError> {
+        writer.invokestatic(MethodRef::by("Test", (name, "()V")))?;
+        Ok(())
+    }
+
+    #[test]
+    fn finally_runs_after_normal_completion_and_exceptions() {
+        let (instructions, handlers) = write("()V", |instructions| {
+            instructions
+                .try_finally(
+                    |finally| call(finally, "f"),
+                    |block| {
+                        block
+                            .body(|body| call(body, "a"))?
+                            .catch("java/lang/Exception", |catch| {
+                                catch.pop()?;
+                                Ok(())
+                            })
+                    },
+                )?
+                .return_()?;
+            Ok(())
+        });
+
+        assert_eq!(
+            instructions,
+            [
+                (0, "invokestatic", None),
+                (3, "invokestatic", None),
+                (6, "goto", Some(22)),
+                // catch clause
+                (9, "pop", None),
+                (10, "invokestatic", None),
+                (13, "goto", Some(22)),
+                // the exception is kept in the first free slot
+                (16, "astore_0", None),
+                (17, "invokestatic", None),
+                (20, "aload_0", None),
+                (21, "athrow", None),
+                (22, "return", None),
+            ]
+        );
+        assert_eq!(
+            handlers,
+            [
+                (0, 3, 9, Some("java/lang/Exception".to_owned())),
+                (0, 3, 16, None),
+                (9, 10, 16, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn finally_runs_before_early_return() {
+        let (instructions, handlers) = write("(I)I", |instructions| {
+            let (skip, skip_ref) = instructions.new_label()?;
+            instructions
+                .try_finally(
+                    |finally| call(finally, "f"),
+                    |block| {
+                        block.body(|body| {
+                            body.iload0()?.ifeq(skip_ref)?.iconst1()?.ireturn()?.label(skip)?;
+                            call(body, "a")
+                        })
+                    },
+                )?
+                .iconst0()?
+                .ireturn()?;
+            Ok(())
+        });
+
+        assert_eq!(
+            instructions,
+            [
+                (0, "iload_0", None),
+                (1, "ifeq", Some(8)),
+                (4, "iconst_1", None),
+                // the return jumps to a copy of the finally code
+                (5, "goto", Some(23)),
+                (8, "invokestatic", None),
+                (11, "invokestatic", None),
+                (14, "goto", Some(29)),
+                (17, "astore_1", None),
+                (18, "invokestatic", None),
+                (21, "aload_1", None),
+                (22, "athrow", None),
+                // the returned value is kept after the parameter
+                (23, "istore_1", None),
+                (24, "invokestatic", None),
+                (27, "iload_1", None),
+                (28, "ireturn", None),
+                (29, "iconst_0", None),
+                (30, "ireturn", None),
+            ]
+        );
+        assert_eq!(handlers, [(0, 11, 17, None)]);
+    }
+
+    #[test]
+    fn kept_values_do_not_overwrite_slots_written_by_index() {
+        let (instructions, _) = write("(I)I", |instructions| {
+            instructions.iload0()?.istore1()?;
+            instructions
+                .try_finally(
+                    |finally| {
+                        finally.iload1()?.pop()?;
+                        call(finally, "f")
+                    },
+                    |block| {
+                        block.body(|body| {
+                            body.iload0()?.ireturn()?;
+                            Ok(())
+                        })
+                    },
+                )?
+                .iload1()?
+                .ireturn()?;
+            Ok(())
+        });
+
+        assert_eq!(
+            instructions,
+            [
+                (0, "iload_0", None),
+                (1, "istore_1", None),
+                (2, "iload_0", None),
+                (3, "goto", Some(14)),
+                // slot 1 is still read by the finally code, so the exception is kept after it
+                (6, "astore_2", None),
+                (7, "iload_1", None),
+                (8, "pop", None),
+                (9, "invokestatic", None),
+                (12, "aload_2", None),
+                (13, "athrow", None),
+                (14, "istore_2", None),
+                (15, "iload_1", None),
+                (16, "pop", None),
+                (17, "invokestatic", None),
+                (20, "iload_2", None),
+                (21, "ireturn", None),
+                (22, "iload_1", None),
+                (23, "ireturn", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn finally_runs_before_leaving_jumps() {
+        let (instructions, _) = write("()V", |instructions| {
+            let (top, top_ref) = instructions.new_label()?;
+            let (inner, inner_ref) = instructions.new_label()?;
+            instructions.label(top)?;
+            instructions.try_finally(
+                |finally| call(finally, "f"),
+                |block| {
+                    block.body(|body| {
+                        // a jump inside of the block stays as it is
+                        body.goto(inner_ref)?.label(inner)?.goto(top_ref)?;
+                        Ok(())
+                    })
+                },
+            )?;
+            Ok(())
+        });
+
+        assert_eq!(
+            instructions,
+            [
+                (0, "goto", Some(3)),
+                (3, "goto", Some(12)),
+                (6, "astore_0", None),
+                (7, "invokestatic", None),
+                (10, "aload_0", None),
+                (11, "athrow", None),
+                (12, "invokestatic", None),
+                (15, "goto", Some(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_jump_after_abrupt_completion() {
+        let (instructions, handlers) = write("()V", |instructions| {
+            instructions
+                .try_catch(|block| {
+                    block
+                        .body(|body| {
+                            call(body, "a")?;
+                            body.return_()?;
+                            Ok(())
+                        })?
+                        .catch("java/lang/RuntimeException", |catch| {
+                            catch.athrow()?;
+                            Ok(())
+                        })
+                })?
+                .return_()?;
+            Ok(())
+        });
+
+        assert_eq!(
+            instructions,
+            [
+                (0, "invokestatic", None),
+                (3, "return", None),
+                (4, "athrow", None),
+                (5, "return", None),
+            ]
+        );
+        assert_eq!(handlers, [(0, 4, 4, Some("java/lang/RuntimeException".to_owned()))]);
+    }
+}
@@ -0,0 +1,359 @@
+use crate::descriptor::{BaseType, MethodDescriptor, TypeDescriptor};
+use crate::error::*;
+use crate::writer::{attributes::code::*, cpool, encoding::*};
+use crate::Opcode;
+
+/// The kinds of values, which determine the typed variants of instructions.
+#[derive(Clone, Copy)]
+pub(super) enum Kind {
+    Int,
+    Long,
+    Float,
+    Double,
+    Reference,
+}
+
+impl Kind {
+    pub(super) fn of(ty: &TypeDescriptor<'_>) -> Kind {
+        if ty.dimensions > 0 {
+            return Kind::Reference;
+        }
+        match ty.base {
+            BaseType::Boolean | BaseType::Byte | BaseType::Short | BaseType::Char | BaseType::Integer => Kind::Int,
+            BaseType::Long => Kind::Long,
+            BaseType::Float => Kind::Float,
+            BaseType::Double => Kind::Double,
+            BaseType::Object(_) => Kind::Reference,
+        }
+    }
+
+    /// The amount of local variable slots taken up by a value of this kind.
+    pub(super) fn size(self) -> u16 {
+        match self {
+            Kind::Long | Kind::Double => 2,
+            Kind::Int | Kind::Float | Kind::Reference => 1,
+        }
+    }
+
+    /// The `<t>load` opcode followed by the `<t>load_<n>` opcodes.
+    pub(super) fn load(self) -> [Opcode; 5] {
+        use Opcode::*;
+
+        match self {
+            Kind::Int => [ILoad, ILoad0, ILoad1, ILoad2, ILoad3],
+            Kind::Long => [LLoad, LLoad0, LLoad1, LLoad2, LLoad3],
+            Kind::Float => [FLoad, FLoad0, FLoad1, FLoad2, FLoad3],
+            Kind::Double => [DLoad, DLoad0, DLoad1, DLoad2, DLoad3],
+            Kind::Reference => [ALoad, ALoad0, ALoad1, ALoad2, ALoad3],
+        }
+    }
+
+    /// The `<t>store` opcode followed by the `<t>store_<n>` opcodes.
+    pub(super) fn store(self) -> [Opcode; 5] {
+        use Opcode::*;
+
+        match self {
+            Kind::Int => [IStore, IStore0, IStore1, IStore2, IStore3],
+            Kind::Long => [LStore, LStore0, LStore1, LStore2, LStore3],
+            Kind::Float => [FStore, FStore0, FStore1, FStore2, FStore3],
+            Kind::Double => [DStore, DStore0, DStore1, DStore2, DStore3],
+            Kind::Reference => [AStore, AStore0, AStore1, AStore2, AStore3],
+        }
+    }
+
+    fn return_(self) -> Opcode {
+        match self {
+            Kind::Int => Opcode::IReturn,
+            Kind::Long => Opcode::LReturn,
+            Kind::Float => Opcode::FReturn,
+            Kind::Double => Opcode::DReturn,
+            Kind::Reference => Opcode::AReturn,
+        }
+    }
+}
+
+/// Returns the `<t>aload` and `<t>astore` opcodes for arrays whose elements are of the type.
+fn array_access(element_type: &TypeDescriptor<'_>) -> (Opcode, Opcode) {
+    use Opcode::*;
+
+    if element_type.dimensions > 0 {
+        return (AALoad, AAStore);
+    }
+    match element_type.base {
+        BaseType::Boolean | BaseType::Byte => (BALoad, BAStore),
+        BaseType::Char => (CALoad, CAStore),
+        BaseType::Short => (SALoad, SAStore),
+        BaseType::Integer => (IALoad, IAStore),
+        BaseType::Long => (LALoad, LAStore),
+        BaseType::Float => (FALoad, FAStore),
+        BaseType::Double => (DALoad, DAStore),
+        BaseType::Object(_) => (AALoad, AAStore),
+    }
+}
+
+/// Instructions whose opcodes are chosen by type or value.
+impl<Ctx: EncoderContext> InstructionWriter<Ctx> {
+    /// Loads a local variable of the type, using the shortest form for the index.
+    pub fn load(&mut self, ty: &TypeDescriptor<'_>, index: u16) -> Result<&mut Self, EncodeError> {
+        self.local_variable(Kind::of(ty).load(), index)
+    }
+
+    /// Stores a local variable of the type, using the shortest form for the index.
+    pub fn store(&mut self, ty: &TypeDescriptor<'_>, index: u16) -> Result<&mut Self, EncodeError> {
+        self.local_variable(Kind::of(ty).store(), index)
+    }
+
+    /// Returns from a method with the descriptor, taking the return value from the stack if there is one.
+    pub fn return_for(&mut self, descriptor: &MethodDescriptor<'_>) -> Result<&mut Self, EncodeError> {
+        let opcode = match descriptor.return_type() {
+            Some(ty) => Kind::of(&ty).return_(),
+            None => Opcode::Return,
+        };
+        self.return_with(opcode)
+    }
+
+    /// Loads an element from an array whose elements are of the type.
+    pub fn array_load(&mut self, element_type: &TypeDescriptor<'_>) -> Result<&mut Self, EncodeError> {
+        self.opcode(array_access(element_type).0)?;
+        Ok(self)
+    }
+
+    /// Stores an element into an array whose elements are of the type.
+    pub fn array_store(&mut self, element_type: &TypeDescriptor<'_>) -> Result<&mut Self, EncodeError> {
+        self.opcode(array_access(element_type).1)?;
+        Ok(self)
+    }
+
+    /// Pushes an `int`, using `iconst_<i>`, `bipush`, `sipush` or a constant.
+    pub fn push_int(&mut self, value: i32) -> Result<&mut Self, EncodeError> {
+        match value {
+            -1 => self.iconstm1(),
+            0 => self.iconst0(),
+            1 => self.iconst1(),
+            2 => self.iconst2(),
+            3 => self.iconst3(),
+            4 => self.iconst4(),
+            5 => self.iconst5(),
+            _ => {
+                if let Ok(value) = i8::try_from(value) {
+                    self.bipush(value)
+                } else if let Ok(value) = i16::try_from(value) {
+                    self.sipush(value)
+                } else {
+                    let index = cpool::Insertable::<cpool::Integer>::insert(value, &mut self.code_writer)?;
+                    self.push_constant(index.as_u16())
+                }
+            }
+        }
+    }
+
+    /// Pushes a `long`, using `lconst_<l>` or a constant.
+    pub fn push_long(&mut self, value: i64) -> Result<&mut Self, EncodeError> {
+        match value {
+            0 => self.lconst0(),
+            1 => self.lconst1(),
+            _ => {
+                let index = cpool::Insertable::<cpool::Long>::insert(value, &mut self.code_writer)?;
+                self.opcode(Opcode::LdC2W)?.write(index)?;
+                Ok(self)
+            }
+        }
+    }
+
+    /// Pushes a `float`, using `fconst_<f>` or a constant.
+    pub fn push_float(&mut self, value: f32) -> Result<&mut Self, EncodeError> {
+        // compared by their bits, so that -0.0 is not treated as 0.0
+        match value.to_bits() {
+            bits if bits == 0f32.to_bits() => self.fconst0(),
+            bits if bits == 1f32.to_bits() => self.fconst1(),
+            bits if bits == 2f32.to_bits() => self.fconst2(),
+            _ => {
+                let index = cpool::Insertable::<cpool::Float>::insert(value, &mut self.code_writer)?;
+                self.push_constant(index.as_u16())
+            }
+        }
+    }
+
+    /// Pushes a `double`, using `dconst_<d>` or a constant.
+    pub fn push_double(&mut self, value: f64) -> Result<&mut Self, EncodeError> {
+        // compared by their bits, so that -0.0 is not treated as 0.0
+        match value.to_bits() {
+            bits if bits == 0f64.to_bits() => self.dconst0(),
+            bits if bits == 1f64.to_bits() => self.dconst1(),
+            _ => {
+                let index = cpool::Insertable::<cpool::Double>::insert(value, &mut self.code_writer)?;
+                self.opcode(Opcode::LdC2W)?.write(index)?;
+                Ok(self)
+            }
+        }
+    }
+
+    /// Pushes a string constant, using `ldc` if the index fits and `ldc_w` otherwise.
+    pub fn push_string<I>(&mut self, value: I) -> Result<&mut Self, EncodeError>
+    where
+        I: cpool::Insertable<cpool::String>,
+    {
+        let index = value.insert(&mut self.code_writer)?;
+        self.push_constant(index.as_u16())
+    }
+
+    fn push_constant(&mut self, index: u16) -> Result<&mut Self, EncodeError> {
+        match u8::try_from(index) {
+            Ok(index) => self.opcode(Opcode::LdC)?.write(index)?,
+            Err(_) => self.opcode(Opcode::LdCW)?.write(index)?,
+        };
+        Ok(self)
+    }
+
+    /// Writes one of the opcodes returned by [`Kind::load`] or [`Kind::store`] for the index.
+    pub(super) fn local_variable(&mut self, opcodes: [Opcode; 5], index: u16) -> Result<&mut Self, EncodeError> {
+        match u8::try_from(index) {
+            Ok(index @ 0..=3) => self.opcode(opcodes[usize::from(index) + 1])?,
+            Ok(index) => self.opcode(opcodes[0])?.write(index)?,
+            Err(_) => self.wide(opcodes[0])?.write(index)?,
+        };
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::descriptor::{MethodDescriptor, TypeDescriptor};
+    use crate::error::EncodeError;
+    use crate::mutf8;
+    use crate::mutf8::MStr;
+    use crate::reader::attributes::RawInstruction;
+    use crate::reader::cpool::{self, ConstantPool, Item};
+    use crate::reader::Class;
+    use crate::testing::{first_code, write_code};
+    use crate::writer::attributes::code::InstructionWriter;
+    use crate::writer::{ClassWriter, ClassWriterState};
+
+    /// Writes a static method `m` and reads back its instructions.
+    /// They are compared by their debug output with loaded constants resolved, so that the sign of zero is compared
+    /// as well.
+    fn instructions<F>(f: F) -> Vec<String>
+    where
+        F: FnOnce(&mut InstructionWriter<ClassWriter<ClassWriterState::Methods>>) -> Result<(), EncodeError>,
+    {
+        let bytes = write_code("()V", |code| {
+            code.max_stack(2)?
+                .max_locals(400)?
+                .instructions(f)?
+                .exceptions(|_| Ok(()))?
+                .attributes(|_| Ok(()))
+        });
+
+        let class = Class::new(&bytes).unwrap();
+        let (_, code) = first_code(&class);
+        code.raw_instructions()
+            .map(|instruction| match instruction.unwrap().1 {
+                RawInstruction::LdC { index } | RawInstruction::LdCW { index } => {
+                    format!("LdC {}", constant(class.pool(), index))
+                }
+                RawInstruction::LdC2W { index } => format!("LdC2W {}", constant(class.pool(), index)),
+                instruction => format!("{instruction:?}"),
+            })
+            .collect()
+    }
+
+    fn constant(pool: &ConstantPool<'_>, index: cpool::Index<Item<'_>>) -> String {
+        match pool.get(index).unwrap() {
+            Item::Integer(integer) => format!("{:?}", integer.value),
+            Item::Long(long) => format!("{:?}", long.value),
+            Item::Float(float) => format!("{:?}", float.value),
+            Item::Double(double) => format!("{:?}", double.value),
+            Item::String(string) => format!("{:?}", pool.retrieve(string.string).unwrap().display().to_string()),
+            item => panic!("unexpected constant {item:?}"),
+        }
+    }
+
+    fn ty(descriptor: &MStr) -> TypeDescriptor<'_> {
+        TypeDescriptor::parse(descriptor).unwrap()
+    }
+
+    #[test]
+    fn local_variables() {
+        let instructions = instructions(|instructions| {
+            instructions
+                .load(&ty(mutf8!("Z")), 0)?
+                .load(&ty(mutf8!("J")), 3)?
+                .load(&ty(mutf8!("[D")), 4)?
+                .store(&ty(mutf8!("F")), 255)?
+                .store(&ty(mutf8!("D")), 256)?
+                .store(&ty(mutf8!("Ljava/lang/Object;")), 1)?;
+            Ok(())
+        });
+        let expected = [
+            RawInstruction::ILoad0,
+            RawInstruction::LLoad3,
+            RawInstruction::ALoad { index: 4 },
+            RawInstruction::FStore { index: 255 },
+            RawInstruction::DStoreW { index: 256 },
+            RawInstruction::AStore1,
+        ];
+        assert_eq!(instructions, expected.map(|instruction| format!("{instruction:?}")));
+    }
+
+    #[test]
+    fn returns_and_arrays() {
+        let instructions = instructions(|instructions| {
+            instructions
+                .array_load(&ty(mutf8!("Z")))?
+                .array_load(&ty(mutf8!("C")))?
+                .array_store(&ty(mutf8!("S")))?
+                .array_store(&ty(mutf8!("[I")))?
+                .array_load(&ty(mutf8!("J")))?
+                .return_for(&MethodDescriptor::parse(mutf8!("(I)[J")).unwrap())?
+                .return_for(&MethodDescriptor::parse(mutf8!("()B")).unwrap())?
+                .return_for(&MethodDescriptor::parse(mutf8!("()V")).unwrap())?;
+            Ok(())
+        });
+        let expected = [
+            RawInstruction::BALoad,
+            RawInstruction::CALoad,
+            RawInstruction::SAStore,
+            RawInstruction::AAStore,
+            RawInstruction::LALoad,
+            RawInstruction::AReturn,
+            RawInstruction::IReturn,
+            RawInstruction::Return,
+        ];
+        assert_eq!(instructions, expected.map(|instruction| format!("{instruction:?}")));
+    }
+
+    #[test]
+    fn constants() {
+        let instructions = instructions(|instructions| {
+            instructions
+                .push_int(-1)?
+                .push_int(5)?
+                .push_int(-128)?
+                .push_int(1000)?
+                .push_int(100_000)?
+                .push_long(1)?
+                .push_long(2)?
+                .push_float(2.0)?
+                .push_float(-0.0)?
+                .push_double(0.0)?
+                .push_double(0.5)?
+                .push_string("text")?;
+            Ok(())
+        });
+        let expected = [
+            "IConstM1",
+            "IConst5",
+            "BIPush { value: -128 }",
+            "SIPush { value: 1000 }",
+            "LdC 100000",
+            "LConst1",
+            "LdC2W 2",
+            "FConst2",
+            "LdC -0.0",
+            "DConst0",
+            "LdC2W 0.5",
+            "LdC \"text\"",
+        ];
+        assert_eq!(instructions, expected);
+    }
+}
@@ -0,0 +1,317 @@
+use crate::descriptor::{BaseType, MethodDescriptor, TypeDescriptor};
+use crate::error::*;
+use crate::header::AccessFlags;
+use crate::reader::{attributes::RawInstruction, decoding::Decoder};
+use crate::writer::{cpool, encoding::*};
+use crate::MStr;
+
+/// Returns the amount of local variable slots taken up by `this` and the parameters of a method.
+pub(super) fn parameter_slots(
+    access_flags: AccessFlags,
+    descriptor: &MethodDescriptor<'_>,
+) -> Result<u16, EncodeError> {
+    let this_slots = u32::from(!access_flags.contains(AccessFlags::STATIC));
+    let slots = descriptor
+        .parameters()
+        .fold(this_slots, |slots, parameter| slots + type_size(&parameter) as u32);
+    u16::try_from(slots).map_err(|_| EncodeError::with_context(EncodeErrorKind::TooManyItems, Context::Code))
+}
+
+/// Computes `max_stack` and `max_locals` of the given code.
+///
+/// The stack depths are propagated along all paths starting at the first instruction and at each exception handler.
+pub(super) fn compute_maxs<Ctx: EncoderContext>(
+    context: &Ctx,
+    code: &[u8],
+    handlers: &[u32],
+    parameter_slots: u16,
+) -> Result<(u16, u16), EncodeError> {
+    let instructions = decode(code)?;
+
+    let mut max_locals = u32::from(parameter_slots);
+    for (_, instruction) in &instructions {
+        if let Some((index, size)) = instruction.local_variable() {
+            max_locals = max_locals.max(u32::from(index) + u32::from(size));
+        }
+    }
+
+    let mut depths = vec![None; instructions.len()];
+    let mut max_stack = 0;
+    let mut queue = vec![(0, 0)];
+    queue.extend(handlers.iter().map(|&handler| (handler, 1)));
+    while let Some((offset, depth)) = queue.pop() {
+        let Ok(mut index) = instructions.binary_search_by_key(&offset, |&(offset, _)| offset) else {
+            continue;
+        };
+        let mut depth: i32 = depth;
+
+        while index < instructions.len() && depths[index].is_none() {
+            depths[index] = Some(depth);
+            max_stack = max_stack.max(depth);
+
+            let (offset, instruction) = &instructions[index];
+            let target_depth = (depth + stack_delta(context, instruction)?).max(0);
+            // only the subroutine starts with the return address on the stack
+            if !matches!(instruction, RawInstruction::JSr { .. } | RawInstruction::JSrW { .. }) {
+                depth = target_depth;
+            }
+            max_stack = max_stack.max(target_depth);
+
+            let (targets, falls_through) = successors(*offset, instruction);
+            queue.extend(targets.into_iter().map(|target| (target, target_depth)));
+
+            if !falls_through {
+                break;
+            }
+            index += 1;
+        }
+    }
+
+    let max_stack = u16::try_from(max_stack)
+        .map_err(|_| EncodeError::with_context(EncodeErrorKind::TooManyItems, Context::Code))?;
+    let max_locals = u16::try_from(max_locals)
+        .map_err(|_| EncodeError::with_context(EncodeErrorKind::TooManyItems, Context::Code))?;
+    Ok((max_stack, max_locals))
+}
+
+/// Decodes the instructions of the code together with their offsets.
+///
+/// Fails with [`EncodeErrorKind::UnsupportedInstruction`] if the code contains bytes which are not a valid instruction.
+pub(super) fn decode(code: &[u8]) -> Result<Vec<(u32, RawInstruction<'_>)>, EncodeError> {
+    let mut instructions = Vec::new();
+    let mut decoder = Decoder::new(code, Context::Code);
+    while decoder.bytes_remaining() != 0 {
+        let offset = decoder.file_position() as u32;
+        let instruction = RawInstruction::decode(&mut decoder, 0)
+            .map_err(|_| EncodeError::with_context(EncodeErrorKind::UnsupportedInstruction, Context::Code))?;
+        instructions.push((offset, instruction));
+    }
+    Ok(instructions)
+}
+
+/// Returns the branch targets of an instruction and whether it may continue with the next instruction.
+pub(super) fn successors(offset: u32, instruction: &RawInstruction<'_>) -> (Vec<u32>, bool) {
+    use RawInstruction::*;
+
+    let target = |relative: i32| (i64::from(offset) + i64::from(relative)) as u32;
+    let targets = match instruction {
+        Goto { offset } | JSr { offset } => vec![target((*offset).into())],
+        GotoW { offset } | JSrW { offset } => vec![target(*offset)],
+        IfACmpEq { offset }
+        | IfACmpNe { offset }
+        | IfICmpEq { offset }
+        | IfICmpNe { offset }
+        | IfICmpLt { offset }
+        | IfICmpGe { offset }
+        | IfICmpGt { offset }
+        | IfICmpLe { offset }
+        | IfEq { offset }
+        | IfNe { offset }
+        | IfLt { offset }
+        | IfGe { offset }
+        | IfGt { offset }
+        | IfLe { offset }
+        | IfNonNull { offset }
+        | IfNull { offset } => vec![target((*offset).into())],
+        LookupSwitch(lookupswitch) => {
+            let mut targets = vec![target(lookupswitch.default_offset())];
+            targets.extend(lookupswitch.pairs().map(|pair| target(pair.offset())));
+            targets
+        }
+        TableSwitch(tableswitch) => {
+            let mut targets = vec![target(tableswitch.default_offset())];
+            targets.extend(tableswitch.pairs().map(|pair| target(pair.offset())));
+            targets
+        }
+        _ => Vec::new(),
+    };
+    (targets, instruction.falls_through())
+}
+
+/// Returns by how many slots an instruction changes the stack size.
+fn stack_delta<Ctx: EncoderContext>(context: &Ctx, instruction: &RawInstruction<'_>) -> Result<i32, EncodeError> {
+    let descriptor = match instruction.member_index() {
+        Some(index) => Some(descriptor(context, index)?),
+        None => None,
+    };
+    let effect = instruction
+        .stack_effect_with(descriptor)
+        .map_err(|_| EncodeError::with_context(EncodeErrorKind::InvalidConstant, Context::Code))?;
+    Ok(i32::from(effect.pushed) - i32::from(effect.popped))
+}
+
+/// Returns the descriptor of a field or method reference or of a dynamically-computed constant or call site.
+fn descriptor<Ctx: EncoderContext>(context: &Ctx, index: u16) -> Result<&MStr, EncodeError> {
+    Ok(member(context, index)?.1)
+}
+
+/// Returns the name and the descriptor of a field or method reference or of a dynamically-computed constant or call site.
+pub(super) fn member<Ctx: EncoderContext>(context: &Ctx, index: u16) -> Result<(&MStr, &MStr), EncodeError> {
+    let name_and_type = match context.get_constant(index) {
+        Some(cpool::Item::FieldRef(field)) => field.name_and_type,
+        Some(cpool::Item::MethodRef(method)) => method.name_and_type,
+        Some(cpool::Item::InterfaceMethodRef(method)) => method.name_and_type,
+        Some(cpool::Item::Dynamic(dynamic)) => dynamic.name_and_type,
+        Some(cpool::Item::InvokeDynamic(invoke_dynamic)) => invoke_dynamic.name_and_type,
+        _ => {
+            return Err(EncodeError::with_context(
+                EncodeErrorKind::InvalidConstant,
+                Context::Code,
+            ))
+        }
+    };
+    let Some(cpool::Item::NameAndType(name_and_type)) = context.get_constant(name_and_type.as_u16()) else {
+        return Err(EncodeError::with_context(
+            EncodeErrorKind::InvalidConstant,
+            Context::Code,
+        ));
+    };
+    Ok((
+        utf8(context, name_and_type.name.as_u16())?,
+        utf8(context, name_and_type.descriptor.as_u16())?,
+    ))
+}
+
+/// Returns the name of a class constant.
+pub(super) fn class_name<Ctx: EncoderContext>(context: &Ctx, index: u16) -> Result<&MStr, EncodeError> {
+    let Some(cpool::Item::Class(class)) = context.get_constant(index) else {
+        return Err(EncodeError::with_context(
+            EncodeErrorKind::InvalidConstant,
+            Context::Code,
+        ));
+    };
+    utf8(context, class.name.as_u16())
+}
+
+pub(super) fn utf8<Ctx: EncoderContext>(context: &Ctx, index: u16) -> Result<&MStr, EncodeError> {
+    let Some(cpool::Item::Utf8(utf8)) = context.get_constant(index) else {
+        return Err(EncodeError::with_context(
+            EncodeErrorKind::InvalidConstant,
+            Context::Code,
+        ));
+    };
+    Ok(&utf8.content)
+}
+
+pub(super) fn type_size(descriptor: &TypeDescriptor<'_>) -> i32 {
+    match descriptor.base {
+        BaseType::Long | BaseType::Double if descriptor.dimensions == 0 => 2,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::descriptor::MethodDescriptor;
+    use crate::error::EncodeError;
+    use crate::header::AccessFlags;
+    use crate::mutf8::MString;
+    use crate::reader::Class;
+    use crate::testing::{first_code, write_code, TestCode};
+    use crate::writer::attributes::code::CodeWriterState;
+    use crate::writer::cpool::{FieldRef, MethodRef};
+
+    /// Writes a static method with computed maximums and reads back `max_stack` and `max_locals`.
+    fn maxs<F>(descriptor: &str, f: F) -> (u16, u16)
+    where
+        F: FnOnce(TestCode<CodeWriterState::Instructions>) -> Result<TestCode<CodeWriterState::End>, EncodeError>,
+    {
+        let bytes = write_code(descriptor, |code| {
+            let descriptor = MString::from(descriptor);
+            let descriptor = MethodDescriptor::parse(&descriptor).unwrap();
+            f(code.compute_maxs(AccessFlags::PUBLIC | AccessFlags::STATIC, &descriptor)?)
+        });
+        let class = Class::new(&bytes).unwrap();
+        let (_, code) = first_code(&class);
+        (code.max_stack(), code.max_locals())
+    }
+
+    #[test]
+    fn parameters() {
+        let (max_stack, max_locals) = maxs("(JIDLjava/lang/Object;)V", |code| {
+            code.instructions(|instructions| {
+                instructions.return_()?;
+                Ok(())
+            })?
+            .exceptions(|_| Ok(()))?
+            .attributes(|_| Ok(()))
+        });
+        assert_eq!((max_stack, max_locals), (0, 6));
+    }
+
+    #[test]
+    fn wide_values_and_members() {
+        let (max_stack, max_locals) = maxs("(J)J", |code| {
+            code.instructions(|instructions| {
+                instructions
+                    .lload0()?
+                    .getstatic(FieldRef::by("Test", ("f", "J")))?
+                    .invokestatic(MethodRef::by("Test", ("g", "(JJ)J")))?
+                    .lstore(3)?
+                    .lload(3)?
+                    .lreturn()?;
+                Ok(())
+            })?
+            .exceptions(|_| Ok(()))?
+            .attributes(|_| Ok(()))
+        });
+        assert_eq!((max_stack, max_locals), (4, 5));
+    }
+
+    #[test]
+    fn deepest_branch() {
+        let (max_stack, max_locals) = maxs("(I)V", |code| {
+            code.instructions(|instructions| {
+                let (end, end_ref) = instructions.new_label()?;
+                instructions
+                    .iload0()?
+                    .ifeq(end_ref)?
+                    .iconst1()?
+                    .iconst2()?
+                    .iconst3()?
+                    .pop()?
+                    .pop2()?
+                    .label(end)?
+                    .return_()?;
+                Ok(())
+            })?
+            .exceptions(|_| Ok(()))?
+            .attributes(|_| Ok(()))
+        });
+        assert_eq!((max_stack, max_locals), (3, 1));
+    }
+
+    #[test]
+    fn handlers_start_with_the_exception() {
+        let (max_stack, _) = maxs("()V", |mut code| {
+            let (start, start_ref) = code.new_label()?;
+            let (end, end_ref) = code.new_label()?;
+            let (handler, handler_ref) = code.new_label()?;
+            code.instructions(|instructions| {
+                instructions
+                    .label(start)?
+                    .nop()?
+                    .label(end)?
+                    .return_()?
+                    .label(handler)?
+                    .dup()?
+                    .dup()?
+                    .pop2()?
+                    .athrow()?;
+                Ok(())
+            })?
+            .exceptions(|exceptions| {
+                exceptions.begin(|exception| {
+                    exception
+                        .start(start_ref)?
+                        .end(end_ref)?
+                        .handler(handler_ref)?
+                        .catch_type("java/lang/Throwable")
+                })?;
+                Ok(())
+            })?
+            .attributes(|_| Ok(()))
+        });
+        assert_eq!(max_stack, 3);
+    }
+}
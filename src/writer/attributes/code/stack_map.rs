@@ -22,7 +22,7 @@ impl<Ctx: EncoderContext> AttributeWriter<CodeWriter<Ctx, CodeWriterState::Attri
 
         let mut writer = StackMapTableWriter {
             context: self.context,
-            last_position: 0,
+            last_position: None,
             count: 0,
         };
         f(&mut writer)?;
@@ -41,19 +41,20 @@ impl<Ctx: EncoderContext> AttributeWriter<CodeWriter<Ctx, CodeWriterState::Attri
 
 pub struct StackMapTableWriter<Ctx> {
     context: CodeWriter<Ctx, CodeWriterState::Attributes>,
-    last_position: u32,
+    /// The position of the previous frame, as the offsets of all but the first frame are relative to it.
+    last_position: Option<u32>,
     count: u16,
 }
 
 impl<Ctx: EncoderContext> StackMapTableWriter<Ctx> {
     pub fn same(&mut self, label: LabelRef) -> Result<(), EncodeError> {
         let offset = self.get_label_offset(label)?;
-        if offset >= 64 {
-            return self.same_extended(label);
-        }
-
         self.increment_counter()?;
-        self.context.encoder().write(offset as u8)?;
+        if offset < 64 {
+            self.context.encoder().write(offset as u8)?;
+        } else {
+            self.context.encoder().write(251u8)?.write(offset)?;
+        }
         Ok(())
     }
 
@@ -71,12 +72,12 @@ impl<Ctx: EncoderContext> StackMapTableWriter<Ctx> {
         ) -> Result<Same1Writer<'ctx, Ctx, Same1WriterState::End>, EncodeError>,
     {
         let offset = self.get_label_offset(label)?;
-        if offset >= 64 {
-            return self.same1_extended(label, f);
-        }
-
         self.increment_counter()?;
-        self.context.encoder().write(64 + offset as u8)?;
+        if offset < 64 {
+            self.context.encoder().write(64 + offset as u8)?;
+        } else {
+            self.context.encoder().write(247u8)?.write(offset)?;
+        }
 
         f(Same1Writer::new(&mut self.context)?)?.finish()?;
 
@@ -108,7 +109,7 @@ impl<Ctx: EncoderContext> StackMapTableWriter<Ctx> {
 
         let offset = self.get_label_offset(label)?;
         self.increment_counter()?;
-        self.context.encoder().write(251 - count)?.write(offset)?;
+        self.context.encoder().write(251 - count as u8)?.write(offset)?;
 
         Ok(())
     }
@@ -152,17 +153,23 @@ impl<Ctx: EncoderContext> StackMapTableWriter<Ctx> {
         Ok(())
     }
 
-    fn get_label_offset(&self, label: LabelRef) -> Result<u16, EncodeError> {
+    /// Returns the offset delta of a frame at the label and makes it the previous frame.
+    fn get_label_offset(&mut self, label: LabelRef) -> Result<u16, EncodeError> {
         let position = self.context.get_label_position(label)?;
-        if position < self.last_position {
-            return Err(EncodeError::with_context(
-                EncodeErrorKind::NegativeOffset,
-                Context::AttributeContent,
-            ));
-        }
-        let offset = u16::try_from(position - self.last_position)
+        let delta = match self.last_position {
+            Some(last_position) if position <= last_position => {
+                return Err(EncodeError::with_context(
+                    EncodeErrorKind::NegativeOffset,
+                    Context::AttributeContent,
+                ));
+            }
+            Some(last_position) => position - last_position - 1,
+            None => position,
+        };
+        let delta = u16::try_from(delta)
             .map_err(|_| EncodeError::with_context(EncodeErrorKind::LabelTooFar, Context::AttributeContent))?;
-        Ok(offset)
+        self.last_position = Some(position);
+        Ok(delta)
     }
 
     fn increment_counter(&mut self) -> Result<(), EncodeError> {
@@ -272,7 +279,7 @@ impl<'ctx, Ctx: EncoderContext> VerificationTypeWriter<'ctx, Ctx, VerificationTy
         self,
         label: LabelRef,
     ) -> Result<VerificationTypeWriter<'ctx, Ctx, VerificationTypeWriterState::End>, EncodeError> {
-        let offset = self.context.get_label_position(label)?;
+        let offset = self.context.get_label_position_u16(label)?;
         self.context.encoder().write(8u8)?.write(offset)?;
 
         Ok(VerificationTypeWriter {
@@ -422,7 +429,7 @@ pub struct FullWriter<'ctx, Ctx, State: FullWriterState::State> {
 }
 
 impl<'ctx, Ctx: EncoderContext> FullWriter<'ctx, Ctx, FullWriterState::Locals> {
-    pub fn locals<F>(mut self, f: F) -> Result<FullWriter<'ctx, Ctx, FullWriterState::End>, EncodeError>
+    pub fn locals<F>(mut self, f: F) -> Result<FullWriter<'ctx, Ctx, FullWriterState::Stack>, EncodeError>
     where
         F: FnOnce(
             &mut ManyWriter<VerificationTypeWriter<'ctx, Ctx, VerificationTypeWriterState::Start>, u16>,
@@ -12,13 +12,11 @@ impl<Ctx: EncoderContext> AttributeWriter<Ctx, AttributeWriterState::Start> {
     pub fn enclosing_method<F>(mut self, f: F) -> Result<AttributeWriter<Ctx, AttributeWriterState::End>, EncodeError>
     where
         F: FnOnce(
-            &mut ManyWriter<EnclosingMethodWriter<Ctx, EnclosingMethodWriterState::Class>, u16>,
-        ) -> Result<(), EncodeError>,
+            EnclosingMethodWriter<Ctx, EnclosingMethodWriterState::Class>,
+        ) -> Result<EnclosingMethodWriter<Ctx, EnclosingMethodWriterState::End>, EncodeError>,
     {
         let length_writer = self.attribute_writer("EnclosingMethod")?;
-        let mut builder = ManyWriter::new(self.context)?;
-        f(&mut builder)?;
-        self.context = builder.finish()?;
+        self.context = f(EnclosingMethodWriter::new(self.context)?)?.finish()?;
         length_writer.finish(&mut self.context)?;
 
         Ok(AttributeWriter {
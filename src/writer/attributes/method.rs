@@ -2,6 +2,7 @@ use std::fmt;
 use std::marker::PhantomData;
 
 use crate::error::*;
+use crate::header::AccessFlags;
 use crate::writer::{
     attributes::{AttributeWriter, AttributeWriterState},
     cpool,
@@ -24,6 +25,24 @@ impl<Ctx: EncoderContext> AttributeWriter<Ctx, AttributeWriterState::Start> {
             _marker: PhantomData,
         })
     }
+
+    pub fn method_parameters<F>(mut self, f: F) -> Result<AttributeWriter<Ctx, AttributeWriterState::End>, EncodeError>
+    where
+        F: FnOnce(
+            &mut ManyWriter<MethodParameterWriter<Ctx, MethodParameterWriterState::Name>, u8>,
+        ) -> Result<(), EncodeError>,
+    {
+        let length_writer = self.attribute_writer("MethodParameters")?;
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+        length_writer.finish(&mut self.context)?;
+
+        Ok(AttributeWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
 }
 
 pub struct ExceptionWriter<Ctx, State: ExceptionWriterState::State> {
@@ -33,13 +52,16 @@ pub struct ExceptionWriter<Ctx, State: ExceptionWriterState::State> {
 
 impl<Ctx: EncoderContext> ExceptionWriter<Ctx, ExceptionWriterState::Start> {
     /// Writes the index to an exception able to be thrown by this method.
-    pub fn exception<I>(&mut self, name: I) -> Result<&mut Self, EncodeError>
+    pub fn exception<I>(mut self, name: I) -> Result<ExceptionWriter<Ctx, ExceptionWriterState::End>, EncodeError>
     where
         I: cpool::Insertable<cpool::Class>,
     {
         let index = name.insert(&mut self.context)?;
         self.context.encoder().write(index)?;
-        Ok(self)
+        Ok(ExceptionWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
     }
 }
 
@@ -69,3 +91,129 @@ impl<Ctx, State: ExceptionWriterState::State> fmt::Debug for ExceptionWriter<Ctx
 }
 
 enc_state!(pub mod ExceptionWriterState: Start, End);
+
+pub struct MethodParameterWriter<Ctx, State: MethodParameterWriterState::State> {
+    context: Ctx,
+    _marker: PhantomData<State>,
+}
+
+impl<Ctx: EncoderContext> MethodParameterWriter<Ctx, MethodParameterWriterState::Name> {
+    pub fn name<I>(
+        mut self,
+        name: I,
+    ) -> Result<MethodParameterWriter<Ctx, MethodParameterWriterState::AccessFlags>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Utf8>,
+    {
+        let index = name.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+
+        Ok(MethodParameterWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Writes a parameter without a name.
+    pub fn no_name(
+        mut self,
+    ) -> Result<MethodParameterWriter<Ctx, MethodParameterWriterState::AccessFlags>, EncodeError> {
+        self.context.encoder().write(0u16)?;
+
+        Ok(MethodParameterWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> MethodParameterWriter<Ctx, MethodParameterWriterState::AccessFlags> {
+    /// Writes the parameter flags, which may contain `FINAL`, `SYNTHETIC` and `MANDATED`.
+    pub fn access_flags(
+        mut self,
+        flags: AccessFlags,
+    ) -> Result<MethodParameterWriter<Ctx, MethodParameterWriterState::End>, EncodeError> {
+        self.context.encoder().write(flags)?;
+
+        Ok(MethodParameterWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteAssembler for MethodParameterWriter<Ctx, MethodParameterWriterState::Name> {
+    type Context = Ctx;
+
+    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+        Ok(MethodParameterWriter {
+            context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteDisassembler for MethodParameterWriter<Ctx, MethodParameterWriterState::End> {
+    type Context = Ctx;
+
+    fn finish(self) -> Result<Self::Context, EncodeError> {
+        Ok(self.context)
+    }
+}
+
+impl<Ctx, State: MethodParameterWriterState::State> fmt::Debug for MethodParameterWriter<Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MethodParameterWriter").finish()
+    }
+}
+
+enc_state!(pub mod MethodParameterWriterState: Name, AccessFlags, End);
+
+#[cfg(test)]
+mod test {
+    use crate::header::AccessFlags;
+    use crate::reader::attributes::AttributeContent;
+    use crate::reader::Class;
+    use crate::testing::{attribute, write_class};
+
+    #[test]
+    fn method_parameters() {
+        let bytes = write_class(
+            |methods| {
+                methods.begin(|method| {
+                    method
+                        .access_flags(AccessFlags::PUBLIC | AccessFlags::NATIVE)?
+                        .name("m")?
+                        .descriptor("(IJ)V")?
+                        .attributes(|attributes| {
+                            attributes.begin(|attribute| {
+                                attribute.method_parameters(|parameters| {
+                                    parameters
+                                        .begin(|parameter| parameter.name("count")?.access_flags(AccessFlags::FINAL))?;
+                                    parameters
+                                        .begin(|parameter| parameter.no_name()?.access_flags(AccessFlags::SYNTHETIC))?;
+                                    Ok(())
+                                })
+                            })?;
+                            Ok(())
+                        })
+                })?;
+                Ok(())
+            },
+            |_| Ok(()),
+        );
+        let class = Class::new(&bytes).unwrap();
+        let pool = class.pool();
+        let method = class.methods().iter().next().unwrap().unwrap();
+        let AttributeContent::MethodParameters(parameters) = attribute(&class, method.attributes(), "MethodParameters")
+        else {
+            panic!("expected method parameters");
+        };
+        let parameters: Vec<_> = parameters.parameters().iter().map(Result::unwrap).collect();
+        assert_eq!(parameters.len(), 2);
+        assert_eq!(pool.retrieve(parameters[0].name().unwrap()).unwrap(), "count");
+        assert_eq!(parameters[0].access_flags(), AccessFlags::FINAL);
+        assert!(parameters[1].name().is_none());
+        assert_eq!(parameters[1].access_flags(), AccessFlags::SYNTHETIC);
+    }
+}
@@ -0,0 +1,866 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::error::*;
+use crate::header::AccessFlags;
+use crate::writer::{
+    attributes::{AttributeWriter, AttributeWriterState},
+    cpool,
+    encoding::*,
+};
+
+impl<Ctx: EncoderContext> AttributeWriter<Ctx, AttributeWriterState::Start> {
+    pub fn module<F>(mut self, f: F) -> Result<AttributeWriter<Ctx, AttributeWriterState::End>, EncodeError>
+    where
+        F: FnOnce(
+            ModuleWriter<Ctx, ModuleWriterState::Name>,
+        ) -> Result<ModuleWriter<Ctx, ModuleWriterState::End>, EncodeError>,
+    {
+        let length_writer = self.attribute_writer("Module")?;
+        self.context = f(ModuleWriter::new(self.context)?)?.finish()?;
+        length_writer.finish(&mut self.context)?;
+
+        Ok(AttributeWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn module_packages<F>(mut self, f: F) -> Result<AttributeWriter<Ctx, AttributeWriterState::End>, EncodeError>
+    where
+        F: FnOnce(&mut ManyWriter<PackageWriter<Ctx, PackageWriterState::Start>, u16>) -> Result<(), EncodeError>,
+    {
+        let length_writer = self.attribute_writer("ModulePackages")?;
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+        length_writer.finish(&mut self.context)?;
+
+        Ok(AttributeWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn module_main_class<I>(
+        mut self,
+        main_class: I,
+    ) -> Result<AttributeWriter<Ctx, AttributeWriterState::End>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Class>,
+    {
+        let length_writer = self.attribute_writer("ModuleMainClass")?;
+        let index = main_class.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+        length_writer.finish(&mut self.context)?;
+
+        Ok(AttributeWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+pub struct ModuleWriter<Ctx, State: ModuleWriterState::State> {
+    context: Ctx,
+    _marker: PhantomData<State>,
+}
+
+impl<Ctx: EncoderContext> ModuleWriter<Ctx, ModuleWriterState::Name> {
+    pub fn name<I>(mut self, name: I) -> Result<ModuleWriter<Ctx, ModuleWriterState::Flags>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Module>,
+    {
+        let index = name.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+
+        Ok(ModuleWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> ModuleWriter<Ctx, ModuleWriterState::Flags> {
+    /// Writes the module flags, which may contain `OPEN`, `SYNTHETIC` and `MANDATED`.
+    pub fn flags(mut self, flags: AccessFlags) -> Result<ModuleWriter<Ctx, ModuleWriterState::Version>, EncodeError> {
+        self.context.encoder().write(flags)?;
+
+        Ok(ModuleWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> ModuleWriter<Ctx, ModuleWriterState::Version> {
+    pub fn version<I>(mut self, version: I) -> Result<ModuleWriter<Ctx, ModuleWriterState::Requires>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Utf8>,
+    {
+        let index = version.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+
+        Ok(ModuleWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn no_version(mut self) -> Result<ModuleWriter<Ctx, ModuleWriterState::Requires>, EncodeError> {
+        self.context.encoder().write(0u16)?;
+
+        Ok(ModuleWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> ModuleWriter<Ctx, ModuleWriterState::Requires> {
+    pub fn requires<F>(mut self, f: F) -> Result<ModuleWriter<Ctx, ModuleWriterState::Exports>, EncodeError>
+    where
+        F: FnOnce(&mut ManyWriter<RequireWriter<Ctx, RequireWriterState::Module>, u16>) -> Result<(), EncodeError>,
+    {
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+
+        Ok(ModuleWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> ModuleWriter<Ctx, ModuleWriterState::Exports> {
+    pub fn exports<F>(mut self, f: F) -> Result<ModuleWriter<Ctx, ModuleWriterState::Opens>, EncodeError>
+    where
+        F: FnOnce(&mut ManyWriter<ExportWriter<Ctx, ExportWriterState::Package>, u16>) -> Result<(), EncodeError>,
+    {
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+
+        Ok(ModuleWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> ModuleWriter<Ctx, ModuleWriterState::Opens> {
+    pub fn opens<F>(mut self, f: F) -> Result<ModuleWriter<Ctx, ModuleWriterState::Uses>, EncodeError>
+    where
+        F: FnOnce(&mut ManyWriter<OpenWriter<Ctx, OpenWriterState::Package>, u16>) -> Result<(), EncodeError>,
+    {
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+
+        Ok(ModuleWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> ModuleWriter<Ctx, ModuleWriterState::Uses> {
+    pub fn uses<F>(mut self, f: F) -> Result<ModuleWriter<Ctx, ModuleWriterState::Provides>, EncodeError>
+    where
+        F: FnOnce(&mut ManyWriter<UseWriter<Ctx, UseWriterState::Start>, u16>) -> Result<(), EncodeError>,
+    {
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+
+        Ok(ModuleWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> ModuleWriter<Ctx, ModuleWriterState::Provides> {
+    pub fn provides<F>(mut self, f: F) -> Result<ModuleWriter<Ctx, ModuleWriterState::End>, EncodeError>
+    where
+        F: FnOnce(&mut ManyWriter<ProvideWriter<Ctx, ProvideWriterState::Service>, u16>) -> Result<(), EncodeError>,
+    {
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+
+        Ok(ModuleWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteAssembler for ModuleWriter<Ctx, ModuleWriterState::Name> {
+    type Context = Ctx;
+
+    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+        Ok(ModuleWriter {
+            context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteDisassembler for ModuleWriter<Ctx, ModuleWriterState::End> {
+    type Context = Ctx;
+
+    fn finish(self) -> Result<Self::Context, EncodeError> {
+        Ok(self.context)
+    }
+}
+
+impl<Ctx, State: ModuleWriterState::State> fmt::Debug for ModuleWriter<Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ModuleWriter").finish()
+    }
+}
+
+enc_state!(pub mod ModuleWriterState: Name, Flags, Version, Requires, Exports, Opens, Uses, Provides, End);
+
+pub struct RequireWriter<Ctx, State: RequireWriterState::State> {
+    context: Ctx,
+    _marker: PhantomData<State>,
+}
+
+impl<Ctx: EncoderContext> RequireWriter<Ctx, RequireWriterState::Module> {
+    pub fn module<I>(mut self, module: I) -> Result<RequireWriter<Ctx, RequireWriterState::Flags>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Module>,
+    {
+        let index = module.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+
+        Ok(RequireWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> RequireWriter<Ctx, RequireWriterState::Flags> {
+    /// Writes the flags of the dependence, which may contain `TRANSITIVE`, `STATIC_PHASE`, `SYNTHETIC` and
+    /// `MANDATED`.
+    pub fn flags(mut self, flags: AccessFlags) -> Result<RequireWriter<Ctx, RequireWriterState::Version>, EncodeError> {
+        self.context.encoder().write(flags)?;
+
+        Ok(RequireWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> RequireWriter<Ctx, RequireWriterState::Version> {
+    pub fn version<I>(mut self, version: I) -> Result<RequireWriter<Ctx, RequireWriterState::End>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Utf8>,
+    {
+        let index = version.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+
+        Ok(RequireWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn no_version(mut self) -> Result<RequireWriter<Ctx, RequireWriterState::End>, EncodeError> {
+        self.context.encoder().write(0u16)?;
+
+        Ok(RequireWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteAssembler for RequireWriter<Ctx, RequireWriterState::Module> {
+    type Context = Ctx;
+
+    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+        Ok(RequireWriter {
+            context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteDisassembler for RequireWriter<Ctx, RequireWriterState::End> {
+    type Context = Ctx;
+
+    fn finish(self) -> Result<Self::Context, EncodeError> {
+        Ok(self.context)
+    }
+}
+
+impl<Ctx, State: RequireWriterState::State> fmt::Debug for RequireWriter<Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequireWriter").finish()
+    }
+}
+
+enc_state!(pub mod RequireWriterState: Module, Flags, Version, End);
+
+pub struct ExportWriter<Ctx, State: ExportWriterState::State> {
+    context: Ctx,
+    _marker: PhantomData<State>,
+}
+
+impl<Ctx: EncoderContext> ExportWriter<Ctx, ExportWriterState::Package> {
+    pub fn package<I>(mut self, package: I) -> Result<ExportWriter<Ctx, ExportWriterState::Flags>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Package>,
+    {
+        let index = package.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+
+        Ok(ExportWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> ExportWriter<Ctx, ExportWriterState::Flags> {
+    pub fn flags(mut self, flags: AccessFlags) -> Result<ExportWriter<Ctx, ExportWriterState::To>, EncodeError> {
+        self.context.encoder().write(flags)?;
+
+        Ok(ExportWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> ExportWriter<Ctx, ExportWriterState::To> {
+    /// Writes the modules the package is exported to.
+    /// If no module is written, the package is exported to every module.
+    pub fn to<F>(mut self, f: F) -> Result<ExportWriter<Ctx, ExportWriterState::End>, EncodeError>
+    where
+        F: FnOnce(
+            &mut ManyWriter<TargetModuleWriter<Ctx, TargetModuleWriterState::Start>, u16>,
+        ) -> Result<(), EncodeError>,
+    {
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+
+        Ok(ExportWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteAssembler for ExportWriter<Ctx, ExportWriterState::Package> {
+    type Context = Ctx;
+
+    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+        Ok(ExportWriter {
+            context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteDisassembler for ExportWriter<Ctx, ExportWriterState::End> {
+    type Context = Ctx;
+
+    fn finish(self) -> Result<Self::Context, EncodeError> {
+        Ok(self.context)
+    }
+}
+
+impl<Ctx, State: ExportWriterState::State> fmt::Debug for ExportWriter<Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExportWriter").finish()
+    }
+}
+
+enc_state!(pub mod ExportWriterState: Package, Flags, To, End);
+
+pub struct OpenWriter<Ctx, State: OpenWriterState::State> {
+    context: Ctx,
+    _marker: PhantomData<State>,
+}
+
+impl<Ctx: EncoderContext> OpenWriter<Ctx, OpenWriterState::Package> {
+    pub fn package<I>(mut self, package: I) -> Result<OpenWriter<Ctx, OpenWriterState::Flags>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Package>,
+    {
+        let index = package.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+
+        Ok(OpenWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> OpenWriter<Ctx, OpenWriterState::Flags> {
+    pub fn flags(mut self, flags: AccessFlags) -> Result<OpenWriter<Ctx, OpenWriterState::To>, EncodeError> {
+        self.context.encoder().write(flags)?;
+
+        Ok(OpenWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> OpenWriter<Ctx, OpenWriterState::To> {
+    /// Writes the modules the package is opened to.
+    /// If no module is written, the package is opened to every module.
+    pub fn to<F>(mut self, f: F) -> Result<OpenWriter<Ctx, OpenWriterState::End>, EncodeError>
+    where
+        F: FnOnce(
+            &mut ManyWriter<TargetModuleWriter<Ctx, TargetModuleWriterState::Start>, u16>,
+        ) -> Result<(), EncodeError>,
+    {
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+
+        Ok(OpenWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteAssembler for OpenWriter<Ctx, OpenWriterState::Package> {
+    type Context = Ctx;
+
+    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+        Ok(OpenWriter {
+            context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteDisassembler for OpenWriter<Ctx, OpenWriterState::End> {
+    type Context = Ctx;
+
+    fn finish(self) -> Result<Self::Context, EncodeError> {
+        Ok(self.context)
+    }
+}
+
+impl<Ctx, State: OpenWriterState::State> fmt::Debug for OpenWriter<Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OpenWriter").finish()
+    }
+}
+
+enc_state!(pub mod OpenWriterState: Package, Flags, To, End);
+
+pub struct TargetModuleWriter<Ctx, State: TargetModuleWriterState::State> {
+    context: Ctx,
+    _marker: PhantomData<State>,
+}
+
+impl<Ctx: EncoderContext> TargetModuleWriter<Ctx, TargetModuleWriterState::Start> {
+    pub fn module<I>(mut self, module: I) -> Result<TargetModuleWriter<Ctx, TargetModuleWriterState::End>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Module>,
+    {
+        let index = module.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+
+        Ok(TargetModuleWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteAssembler for TargetModuleWriter<Ctx, TargetModuleWriterState::Start> {
+    type Context = Ctx;
+
+    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+        Ok(TargetModuleWriter {
+            context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteDisassembler for TargetModuleWriter<Ctx, TargetModuleWriterState::End> {
+    type Context = Ctx;
+
+    fn finish(self) -> Result<Self::Context, EncodeError> {
+        Ok(self.context)
+    }
+}
+
+impl<Ctx, State: TargetModuleWriterState::State> fmt::Debug for TargetModuleWriter<Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TargetModuleWriter").finish()
+    }
+}
+
+enc_state!(pub mod TargetModuleWriterState: Start, End);
+
+pub struct UseWriter<Ctx, State: UseWriterState::State> {
+    context: Ctx,
+    _marker: PhantomData<State>,
+}
+
+impl<Ctx: EncoderContext> UseWriter<Ctx, UseWriterState::Start> {
+    /// Writes the service interface this module may discover.
+    pub fn service<I>(mut self, service: I) -> Result<UseWriter<Ctx, UseWriterState::End>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Class>,
+    {
+        let index = service.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+
+        Ok(UseWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteAssembler for UseWriter<Ctx, UseWriterState::Start> {
+    type Context = Ctx;
+
+    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+        Ok(UseWriter {
+            context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteDisassembler for UseWriter<Ctx, UseWriterState::End> {
+    type Context = Ctx;
+
+    fn finish(self) -> Result<Self::Context, EncodeError> {
+        Ok(self.context)
+    }
+}
+
+impl<Ctx, State: UseWriterState::State> fmt::Debug for UseWriter<Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UseWriter").finish()
+    }
+}
+
+enc_state!(pub mod UseWriterState: Start, End);
+
+pub struct ProvideWriter<Ctx, State: ProvideWriterState::State> {
+    context: Ctx,
+    _marker: PhantomData<State>,
+}
+
+impl<Ctx: EncoderContext> ProvideWriter<Ctx, ProvideWriterState::Service> {
+    pub fn service<I>(mut self, service: I) -> Result<ProvideWriter<Ctx, ProvideWriterState::With>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Class>,
+    {
+        let index = service.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+
+        Ok(ProvideWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> ProvideWriter<Ctx, ProvideWriterState::With> {
+    /// Writes the implementations of the service provided by this module.
+    pub fn with<F>(mut self, f: F) -> Result<ProvideWriter<Ctx, ProvideWriterState::End>, EncodeError>
+    where
+        F: FnOnce(
+            &mut ManyWriter<ImplementationWriter<Ctx, ImplementationWriterState::Start>, u16>,
+        ) -> Result<(), EncodeError>,
+    {
+        let mut builder = ManyWriter::new(self.context)?;
+        f(&mut builder)?;
+        self.context = builder.finish()?;
+
+        Ok(ProvideWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteAssembler for ProvideWriter<Ctx, ProvideWriterState::Service> {
+    type Context = Ctx;
+
+    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+        Ok(ProvideWriter {
+            context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteDisassembler for ProvideWriter<Ctx, ProvideWriterState::End> {
+    type Context = Ctx;
+
+    fn finish(self) -> Result<Self::Context, EncodeError> {
+        Ok(self.context)
+    }
+}
+
+impl<Ctx, State: ProvideWriterState::State> fmt::Debug for ProvideWriter<Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProvideWriter").finish()
+    }
+}
+
+enc_state!(pub mod ProvideWriterState: Service, With, End);
+
+pub struct ImplementationWriter<Ctx, State: ImplementationWriterState::State> {
+    context: Ctx,
+    _marker: PhantomData<State>,
+}
+
+impl<Ctx: EncoderContext> ImplementationWriter<Ctx, ImplementationWriterState::Start> {
+    pub fn implementation<I>(
+        mut self,
+        implementation: I,
+    ) -> Result<ImplementationWriter<Ctx, ImplementationWriterState::End>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Class>,
+    {
+        let index = implementation.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+
+        Ok(ImplementationWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteAssembler for ImplementationWriter<Ctx, ImplementationWriterState::Start> {
+    type Context = Ctx;
+
+    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+        Ok(ImplementationWriter {
+            context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteDisassembler for ImplementationWriter<Ctx, ImplementationWriterState::End> {
+    type Context = Ctx;
+
+    fn finish(self) -> Result<Self::Context, EncodeError> {
+        Ok(self.context)
+    }
+}
+
+impl<Ctx, State: ImplementationWriterState::State> fmt::Debug for ImplementationWriter<Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImplementationWriter").finish()
+    }
+}
+
+enc_state!(pub mod ImplementationWriterState: Start, End);
+
+pub struct PackageWriter<Ctx, State: PackageWriterState::State> {
+    context: Ctx,
+    _marker: PhantomData<State>,
+}
+
+impl<Ctx: EncoderContext> PackageWriter<Ctx, PackageWriterState::Start> {
+    pub fn package<I>(mut self, package: I) -> Result<PackageWriter<Ctx, PackageWriterState::End>, EncodeError>
+    where
+        I: cpool::Insertable<cpool::Package>,
+    {
+        let index = package.insert(&mut self.context)?;
+        self.context.encoder().write(index)?;
+
+        Ok(PackageWriter {
+            context: self.context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteAssembler for PackageWriter<Ctx, PackageWriterState::Start> {
+    type Context = Ctx;
+
+    fn new(context: Self::Context) -> Result<Self, EncodeError> {
+        Ok(PackageWriter {
+            context,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Ctx: EncoderContext> WriteDisassembler for PackageWriter<Ctx, PackageWriterState::End> {
+    type Context = Ctx;
+
+    fn finish(self) -> Result<Self::Context, EncodeError> {
+        Ok(self.context)
+    }
+}
+
+impl<Ctx, State: PackageWriterState::State> fmt::Debug for PackageWriter<Ctx, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PackageWriter").finish()
+    }
+}
+
+enc_state!(pub mod PackageWriterState: Start, End);
+
+#[cfg(test)]
+mod test {
+    use crate::header::AccessFlags;
+    use crate::reader::attributes::AttributeContent;
+    use crate::reader::{cpool, Class};
+    use crate::testing::{attribute, write_class};
+
+    #[test]
+    fn module() {
+        let bytes = write_class(
+            |_| Ok(()),
+            |attributes| {
+                attributes.begin(|attribute| {
+                    attribute.module(|module| {
+                        module
+                            .name("m")?
+                            .flags(AccessFlags::OPEN)?
+                            .version("1.0")?
+                            .requires(|requires| {
+                                requires.begin(|require| {
+                                    require.module("java.base")?.flags(AccessFlags::MANDATED)?.no_version()
+                                })?;
+                                requires.begin(|require| {
+                                    require.module("other")?.flags(AccessFlags::TRANSITIVE)?.version("2")
+                                })?;
+                                Ok(())
+                            })?
+                            .exports(|exports| {
+                                exports.begin(|export| {
+                                    export.package("a/b")?.flags(AccessFlags::empty())?.to(|targets| {
+                                        targets.begin(|target| target.module("x"))?;
+                                        targets.begin(|target| target.module("y"))?;
+                                        Ok(())
+                                    })
+                                })?;
+                                Ok(())
+                            })?
+                            .opens(|opens| {
+                                opens.begin(|open| open.package("c")?.flags(AccessFlags::SYNTHETIC)?.to(|_| Ok(())))?;
+                                Ok(())
+                            })?
+                            .uses(|uses| {
+                                uses.begin(|use_| use_.service("a/b/Service"))?;
+                                Ok(())
+                            })?
+                            .provides(|provides| {
+                                provides.begin(|provide| {
+                                    provide.service("a/b/Service")?.with(|with| {
+                                        with.begin(|implementation| implementation.implementation("c/Implementation"))?;
+                                        Ok(())
+                                    })
+                                })?;
+                                Ok(())
+                            })
+                    })
+                })?;
+                attributes.begin(|attribute| {
+                    attribute.module_packages(|packages| {
+                        packages.begin(|package| package.package("a/b"))?;
+                        packages.begin(|package| package.package("c"))?;
+                        Ok(())
+                    })
+                })?;
+                attributes.begin(|attribute| attribute.module_main_class("a/b/Main"))?;
+                Ok(())
+            },
+        );
+        let class = Class::new(&bytes).unwrap();
+        let pool = class.pool();
+        let module_name =
+            |index: cpool::Index<cpool::Module<'_>>| pool.retrieve(index).unwrap().name.to_str().unwrap().to_owned();
+        let package_name =
+            |index: cpool::Index<cpool::Package<'_>>| pool.retrieve(index).unwrap().name.to_str().unwrap().to_owned();
+        let class_name =
+            |index: cpool::Index<cpool::Class<'_>>| pool.retrieve(index).unwrap().name.to_str().unwrap().to_owned();
+
+        let AttributeContent::Module(module) = attribute(&class, class.attributes(), "Module") else {
+            panic!("expected a module");
+        };
+        assert_eq!(module_name(module.name()), "m");
+        assert_eq!(module.flags(), AccessFlags::OPEN);
+        assert_eq!(pool.retrieve(module.version().unwrap()).unwrap(), "1.0");
+
+        let requires: Vec<_> = module.requires().iter().map(Result::unwrap).collect();
+        assert_eq!(requires.len(), 2);
+        assert_eq!(module_name(requires[0].index()), "java.base");
+        assert_eq!(requires[0].flags(), AccessFlags::MANDATED);
+        assert!(requires[0].version().is_none());
+        assert_eq!(module_name(requires[1].index()), "other");
+        assert_eq!(requires[1].flags(), AccessFlags::TRANSITIVE);
+        assert_eq!(pool.retrieve(requires[1].version().unwrap()).unwrap(), "2");
+
+        let exports: Vec<_> = module.exports().iter().map(Result::unwrap).collect();
+        assert_eq!(exports.len(), 1);
+        assert_eq!(package_name(exports[0].index()), "a/b");
+        let targets: Vec<_> = exports[0]
+            .exports_to()
+            .iter()
+            .map(|target| module_name(target.unwrap()))
+            .collect();
+        assert_eq!(targets, ["x", "y"]);
+
+        let opens: Vec<_> = module.opens().iter().map(Result::unwrap).collect();
+        assert_eq!(opens.len(), 1);
+        assert_eq!(package_name(opens[0].index()), "c");
+        assert_eq!(opens[0].flags(), AccessFlags::SYNTHETIC);
+        assert_eq!(opens[0].opens_to().iter().count(), 0);
+
+        let uses: Vec<_> = module
+            .uses()
+            .iter()
+            .map(|service| class_name(service.unwrap()))
+            .collect();
+        assert_eq!(uses, ["a/b/Service"]);
+
+        let provides: Vec<_> = module.provides().iter().map(Result::unwrap).collect();
+        assert_eq!(provides.len(), 1);
+        assert_eq!(class_name(provides[0].index()), "a/b/Service");
+        let implementations: Vec<_> = provides[0]
+            .provides_with()
+            .iter()
+            .map(|implementation| class_name(implementation.unwrap()))
+            .collect();
+        assert_eq!(implementations, ["c/Implementation"]);
+
+        let AttributeContent::ModulePackages(packages) = attribute(&class, class.attributes(), "ModulePackages") else {
+            panic!("expected module packages");
+        };
+        let packages: Vec<_> = packages
+            .packages()
+            .iter()
+            .map(|package| package_name(package.unwrap()))
+            .collect();
+        assert_eq!(packages, ["a/b", "c"]);
+
+        let AttributeContent::ModuleMainClass(main_class) = attribute(&class, class.attributes(), "ModuleMainClass")
+        else {
+            panic!("expected a module main class");
+        };
+        assert_eq!(class_name(main_class.main_class()), "a/b/Main");
+    }
+}
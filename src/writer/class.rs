@@ -1,3 +1,4 @@
+use indexmap::IndexSet;
 use std::marker::PhantomData;
 use std::{fmt, io};
 
@@ -40,6 +41,7 @@ pub struct ClassWriter<State: ClassWriterState::State> {
     start_encoder: VecEncoder,
     encoder: VecEncoder,
     pool: ConstantPool,
+    bootstrap_methods: IndexSet<cpool::BootstrapMethod>,
     _marker: PhantomData<State>,
 }
 
@@ -57,6 +59,7 @@ impl ClassWriter<ClassWriterState::Start> {
             start_encoder: VecEncoder::new(Vec::with_capacity(1024)),
             encoder: VecEncoder::new(Vec::with_capacity(1024)),
             pool: ConstantPool::new(),
+            bootstrap_methods: IndexSet::new(),
             _marker: PhantomData,
         }
     }
@@ -73,6 +76,7 @@ impl ClassWriter<ClassWriterState::Start> {
             start_encoder: self.start_encoder,
             encoder: self.encoder,
             pool: self.pool,
+            bootstrap_methods: self.bootstrap_methods,
             _marker: PhantomData,
         })
     }
@@ -86,6 +90,7 @@ impl ClassWriter<ClassWriterState::AccessFlags> {
             start_encoder: self.start_encoder,
             encoder: self.encoder,
             pool: self.pool,
+            bootstrap_methods: self.bootstrap_methods,
             _marker: PhantomData,
         })
     }
@@ -103,6 +108,7 @@ impl ClassWriter<ClassWriterState::ThisClass> {
             start_encoder: self.start_encoder,
             encoder: self.encoder,
             pool: self.pool,
+            bootstrap_methods: self.bootstrap_methods,
             _marker: PhantomData,
         })
     }
@@ -120,6 +126,7 @@ impl ClassWriter<ClassWriterState::SuperClass> {
             start_encoder: self.start_encoder,
             encoder: self.encoder,
             pool: self.pool,
+            bootstrap_methods: self.bootstrap_methods,
             _marker: PhantomData,
         })
     }
@@ -131,6 +138,7 @@ impl ClassWriter<ClassWriterState::SuperClass> {
             start_encoder: self.start_encoder,
             encoder: self.encoder,
             pool: self.pool,
+            bootstrap_methods: self.bootstrap_methods,
             _marker: PhantomData,
         })
     }
@@ -149,6 +157,7 @@ impl ClassWriter<ClassWriterState::Interfaces> {
             start_encoder: self.start_encoder,
             encoder: self.encoder,
             pool: self.pool,
+            bootstrap_methods: self.bootstrap_methods,
             _marker: PhantomData,
         })
     }
@@ -167,6 +176,7 @@ impl ClassWriter<ClassWriterState::Fields> {
             start_encoder: self.start_encoder,
             encoder: self.encoder,
             pool: self.pool,
+            bootstrap_methods: self.bootstrap_methods,
             _marker: PhantomData,
         })
     }
@@ -185,6 +195,7 @@ impl ClassWriter<ClassWriterState::Methods> {
             start_encoder: self.start_encoder,
             encoder: self.encoder,
             pool: self.pool,
+            bootstrap_methods: self.bootstrap_methods,
             _marker: PhantomData,
         })
     }
@@ -200,17 +211,53 @@ impl ClassWriter<ClassWriterState::Attributes> {
             >,
         ) -> Result<(), EncodeError>,
     {
+        let count_offset = self.encoder.position();
         let mut builder = ManyWriter::new(self)?;
         f(&mut builder)?;
         self = builder.finish()?;
 
+        if !self.bootstrap_methods.is_empty() {
+            self.write_bootstrap_methods(count_offset)?;
+        }
+
         Ok(ClassWriter {
             start_encoder: self.start_encoder,
             encoder: self.encoder,
             pool: self.pool,
+            bootstrap_methods: self.bootstrap_methods,
             _marker: PhantomData,
         })
     }
+
+    /// Appends the `BootstrapMethods` attribute and increments the attribute count at the offset.
+    fn write_bootstrap_methods(&mut self, count_offset: Offset) -> Result<(), EncodeError> {
+        let buf = self.encoder.buf();
+        let count = u16::from_be_bytes([buf[count_offset.get()], buf[count_offset.get() + 1]]);
+        let count = count
+            .checked_add(1)
+            .ok_or_else(|| EncodeError::with_context(EncodeErrorKind::TooManyItems, Context::Attributes))?;
+
+        let name = self.insert_constant(cpool::Utf8 {
+            content: "BootstrapMethods".into(),
+        })?;
+        self.encoder.write(name)?;
+        let length_writer = LengthWriter::new(self)?;
+
+        // the amount of bootstrap methods was checked on insertion
+        self.encoder.write(self.bootstrap_methods.len() as u16)?;
+        for method in &self.bootstrap_methods {
+            let argument_count = u16::try_from(method.arguments.len())
+                .map_err(|_| EncodeError::with_context(EncodeErrorKind::TooManyItems, Context::AttributeContent))?;
+            self.encoder.write(method.method_ref)?.write(argument_count)?;
+            for argument in &method.arguments {
+                self.encoder.write(argument)?;
+            }
+        }
+
+        length_writer.finish(self)?;
+        self.encoder.replacing(count_offset).write(count)?;
+        Ok(())
+    }
 }
 
 impl ClassWriter<ClassWriterState::End> {
@@ -239,6 +286,27 @@ impl<State: ClassWriterState::State> InternalEncoderContext for ClassWriter<Stat
             .write(self.pool.len())?;
         Ok(index)
     }
+
+    fn insert_bootstrap_method(&mut self, method: cpool::BootstrapMethod) -> Result<u16, EncodeError> {
+        if let Some(index) = self.bootstrap_methods.get_index_of(&method) {
+            // the amount of bootstrap methods is checked on insertion
+            return Ok(index as u16);
+        }
+
+        let index = self.bootstrap_methods.len();
+        if index >= usize::from(u16::MAX) {
+            return Err(EncodeError::with_context(
+                EncodeErrorKind::TooManyItems,
+                Context::Attributes,
+            ));
+        }
+        self.bootstrap_methods.insert(method);
+        Ok(index as u16)
+    }
+
+    fn get_constant(&self, index: u16) -> Option<&cpool::Item> {
+        self.pool.get(index)
+    }
 }
 
 impl<State: ClassWriterState::State> fmt::Debug for ClassWriter<State> {
@@ -248,3 +316,91 @@ impl<State: ClassWriterState::State> fmt::Debug for ClassWriter<State> {
 }
 
 enc_state!(pub mod ClassWriterState: Start, AccessFlags, ThisClass, SuperClass, Interfaces, Fields, Methods, Attributes, End);
+
+#[cfg(test)]
+mod test {
+    use crate::header::AccessFlags;
+    use crate::reader::attributes::{AttributeContent, RawInstruction};
+    use crate::reader::{self, Class};
+    use crate::testing::{attribute, first_code, write_class};
+    use crate::writer::cpool::{self, BootstrapMethod, InvokeDynamic, MethodHandle, MethodKind};
+
+    #[test]
+    fn bootstrap_methods() {
+        let bootstrap = |argument: &'static str| {
+            BootstrapMethod::by(
+                MethodHandle::by(
+                    MethodKind::InvokeStatic,
+                    cpool::MethodRef::by("Test", ("bootstrap", "()Ljava/lang/invoke/CallSite;")),
+                ),
+                (cpool::String::by(argument),),
+            )
+        };
+        let bytes = write_class(
+            |methods| {
+                methods.begin(|method| {
+                    method
+                        .access_flags(AccessFlags::STATIC)?
+                        .name("m")?
+                        .descriptor("()V")?
+                        .attributes(|attributes| {
+                            attributes.begin(|attribute| {
+                                attribute.code(|code| {
+                                    code.max_stack(0)?
+                                        .max_locals(0)?
+                                        .instructions(|instructions| {
+                                            instructions
+                                                .invokedynamic(InvokeDynamic::by(bootstrap("a"), ("a", "()V")))?
+                                                .invokedynamic(InvokeDynamic::by(bootstrap("b"), ("b", "()V")))?
+                                                .invokedynamic(InvokeDynamic::by(bootstrap("a"), ("c", "()V")))?
+                                                .return_()?;
+                                            Ok(())
+                                        })?
+                                        .exceptions(|_| Ok(()))?
+                                        .attributes(|_| Ok(()))
+                                })
+                            })?;
+                            Ok(())
+                        })
+                })?;
+                Ok(())
+            },
+            |attributes| {
+                attributes.begin(|attribute| attribute.source_file("Test.java"))?;
+                Ok(())
+            },
+        );
+        let class = Class::new(&bytes).unwrap();
+        let pool = class.pool();
+        assert_eq!(class.attributes().iter().count(), 2);
+
+        let AttributeContent::BootstrapMethods(table) = attribute(&class, class.attributes(), "BootstrapMethods")
+        else {
+            panic!("expected bootstrap methods");
+        };
+        let arguments: Vec<_> = table
+            .methods()
+            .iter()
+            .map(|method| {
+                let method = method.unwrap();
+                let arguments: Vec<_> = method.arguments().iter().map(Result::unwrap).collect();
+                assert_eq!(arguments.len(), 1);
+                let reader::cpool::Item::String(string) = pool.get(arguments[0]).unwrap() else {
+                    panic!("expected a string argument");
+                };
+                pool.retrieve(string.string).unwrap().to_str().unwrap()
+            })
+            .collect();
+        assert_eq!(arguments, ["a", "b"]);
+
+        let (_, code) = first_code(&class);
+        let indices: Vec<_> = code
+            .raw_instructions()
+            .filter_map(|instruction| match instruction.unwrap().1 {
+                RawInstruction::InvokeDynamic { index } => Some(pool.get(index).unwrap().bootstrap_method_attr),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(indices, [0, 1, 0]);
+    }
+}
@@ -3,6 +3,7 @@ use crate::mutf8::MString;
 use crate::writer::encoding::*;
 use indexmap::IndexMap;
 use std::{
+    cmp::Ordering,
     fmt,
     hash::{Hash, Hasher},
     marker::PhantomData,
@@ -28,13 +29,20 @@ impl ConstantPool {
         item: I,
         mut encoder: E,
     ) -> Result<Index<I>, EncodeError> {
-        if self.len == u16::max_value() {
+        let item = item.into();
+        if let Some(index) = self.content.get(&item) {
+            return Ok(Index {
+                index: index.index,
+                mark: PhantomData,
+            });
+        }
+
+        if self.len == u16::MAX {
             return Err(EncodeError::with_context(
                 EncodeErrorKind::TooManyItems,
                 Context::ConstantPool,
             ));
         }
-        let item = item.into();
         let index = NonZeroU16::new(self.len).unwrap();
         self.len += if let Item::Long(_) | Item::Double(_) = item {
             2
@@ -61,6 +69,22 @@ impl ConstantPool {
     pub(crate) fn len(&self) -> u16 {
         self.len
     }
+
+    /// Returns the item at the given index, if there is one.
+    pub(crate) fn get(&self, index: u16) -> Option<&Item> {
+        // the items are stored in the order of their indices
+        let (mut low, mut high) = (0, self.content.len());
+        while low < high {
+            let mid = (low + high) / 2;
+            let (item, item_index) = self.content.get_index(mid)?;
+            match item_index.index.get().cmp(&index) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return Some(item),
+            }
+        }
+        None
+    }
 }
 
 impl fmt::Debug for ConstantPool {
@@ -93,10 +117,7 @@ impl<I> Index<I> {
 
 impl<I> Clone for Index<I> {
     fn clone(&self) -> Index<I> {
-        Index {
-            index: self.index,
-            mark: PhantomData,
-        }
+        *self
     }
 }
 
@@ -415,12 +436,16 @@ pub struct Dynamic {
 }
 
 impl Dynamic {
-    pub fn by<I>(bootstrap_method_attr: u16, name_and_type: I) -> DynamicInserter<I>
+    /// Creates a dynamically-computed constant.
+    /// The bootstrap method is either an index into the bootstrap method table or a
+    /// [`BootstrapMethod`] which will be added to the table if necessary.
+    pub fn by<B, I>(bootstrap_method: B, name_and_type: I) -> DynamicInserter<B, I>
     where
+        B: InsertableBootstrapMethod,
         I: Insertable<NameAndType>,
     {
         DynamicInserter {
-            bootstrap_method_attr,
+            bootstrap_method,
             name_and_type,
         }
     }
@@ -434,17 +459,43 @@ pub struct InvokeDynamic {
 }
 
 impl InvokeDynamic {
-    pub fn by<I>(bootstrap_method_attr: u16, name_and_type: I) -> InvokeDynamicInserter<I>
+    /// Creates a dynamically-computed call site.
+    /// The bootstrap method is either an index into the bootstrap method table or a
+    /// [`BootstrapMethod`] which will be added to the table if necessary.
+    pub fn by<B, I>(bootstrap_method: B, name_and_type: I) -> InvokeDynamicInserter<B, I>
     where
+        B: InsertableBootstrapMethod,
         I: Insertable<NameAndType>,
     {
         InvokeDynamicInserter {
-            bootstrap_method_attr,
+            bootstrap_method,
             name_and_type,
         }
     }
 }
 
+/// An entry of the `BootstrapMethods` attribute.
+///
+/// Bootstrap methods are collected by the class writer and deduplicated.
+/// The attribute is written automatically at the end of the class attributes.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct BootstrapMethod {
+    pub method_ref: Index<MethodHandle>,
+    pub arguments: Vec<Index<Item>>,
+}
+
+impl BootstrapMethod {
+    /// Creates a bootstrap method with its static arguments.
+    /// The arguments are either a tuple or a vector of loadable constants.
+    pub fn by<M, A>(method_ref: M, arguments: A) -> BootstrapMethodInserter<M, A>
+    where
+        M: Insertable<MethodHandle>,
+        A: BootstrapArguments,
+    {
+        BootstrapMethodInserter { method_ref, arguments }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct Module {
     pub name: Index<Utf8>,
@@ -606,6 +657,20 @@ impl<I: Insertable<Utf8>> Insertable<Class> for I {
     }
 }
 
+impl<I: Insertable<Utf8>> Insertable<Module> for I {
+    fn insert<Ctx: EncoderContext>(self, context: &mut Ctx) -> Result<Index<Module>, EncodeError> {
+        let name = self.insert(context)?;
+        context.insert_constant(Module { name })
+    }
+}
+
+impl<I: Insertable<Utf8>> Insertable<Package> for I {
+    fn insert<Ctx: EncoderContext>(self, context: &mut Ctx) -> Result<Index<Package>, EncodeError> {
+        let name = self.insert(context)?;
+        context.insert_constant(Package { name })
+    }
+}
+
 impl Insertable<Integer> for i32 {
     fn insert<Ctx: EncoderContext>(self, context: &mut Ctx) -> Result<Index<Integer>, EncodeError> {
         context.insert_constant(Integer { value: self })
@@ -726,60 +791,137 @@ impl<I> fmt::Debug for MethodTypeInserter<I> {
     }
 }
 
-pub struct DynamicInserter<I> {
-    bootstrap_method_attr: u16,
+pub struct DynamicInserter<B, I> {
+    bootstrap_method: B,
     name_and_type: I,
 }
 
-impl<I: Insertable<NameAndType>> Insertable<Dynamic> for DynamicInserter<I> {
+impl<B: InsertableBootstrapMethod, I: Insertable<NameAndType>> Insertable<Dynamic> for DynamicInserter<B, I> {
     fn insert<Ctx: EncoderContext>(self, context: &mut Ctx) -> Result<Index<Dynamic>, EncodeError> {
+        let bootstrap_method_attr = self.bootstrap_method.insert(context)?;
         let name_and_type = self.name_and_type.insert(context)?;
         context.insert_constant(Dynamic {
-            bootstrap_method_attr: self.bootstrap_method_attr,
+            bootstrap_method_attr,
             name_and_type,
         })
     }
 }
 
-impl<I: Insertable<NameAndType>> Insertable<Item> for DynamicInserter<I> {
+impl<B: InsertableBootstrapMethod, I: Insertable<NameAndType>> Insertable<Item> for DynamicInserter<B, I> {
     fn insert<Ctx: EncoderContext>(self, context: &mut Ctx) -> Result<Index<Item>, EncodeError> {
         Ok(<Self as Insertable<Dynamic>>::insert(self, context)?.as_item())
     }
 }
 
-impl<I> fmt::Debug for DynamicInserter<I> {
+impl<B, I> fmt::Debug for DynamicInserter<B, I> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("DynamicInserter").finish()
     }
 }
 
-pub struct InvokeDynamicInserter<I> {
-    bootstrap_method_attr: u16,
+pub struct InvokeDynamicInserter<B, I> {
+    bootstrap_method: B,
     name_and_type: I,
 }
 
-impl<I: Insertable<NameAndType>> Insertable<InvokeDynamic> for InvokeDynamicInserter<I> {
+impl<B: InsertableBootstrapMethod, I: Insertable<NameAndType>> Insertable<InvokeDynamic>
+    for InvokeDynamicInserter<B, I>
+{
     fn insert<Ctx: EncoderContext>(self, context: &mut Ctx) -> Result<Index<InvokeDynamic>, EncodeError> {
+        let bootstrap_method_attr = self.bootstrap_method.insert(context)?;
         let name_and_type = self.name_and_type.insert(context)?;
         context.insert_constant(InvokeDynamic {
-            bootstrap_method_attr: self.bootstrap_method_attr,
+            bootstrap_method_attr,
             name_and_type,
         })
     }
 }
 
-impl<I: Insertable<NameAndType>> Insertable<Item> for InvokeDynamicInserter<I> {
+impl<B: InsertableBootstrapMethod, I: Insertable<NameAndType>> Insertable<Item> for InvokeDynamicInserter<B, I> {
     fn insert<Ctx: EncoderContext>(self, context: &mut Ctx) -> Result<Index<Item>, EncodeError> {
         Ok(<Self as Insertable<InvokeDynamic>>::insert(self, context)?.as_item())
     }
 }
 
-impl<I> fmt::Debug for InvokeDynamicInserter<I> {
+impl<B, I> fmt::Debug for InvokeDynamicInserter<B, I> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("InvokeDynamicInserter").finish()
     }
 }
 
+/// Something which can be turned into an index into the bootstrap method table.
+pub trait InsertableBootstrapMethod {
+    fn insert<Ctx: EncoderContext>(self, context: &mut Ctx) -> Result<u16, EncodeError>;
+}
+
+impl InsertableBootstrapMethod for u16 {
+    fn insert<Ctx: EncoderContext>(self, _: &mut Ctx) -> Result<u16, EncodeError> {
+        Ok(self)
+    }
+}
+
+impl InsertableBootstrapMethod for BootstrapMethod {
+    fn insert<Ctx: EncoderContext>(self, context: &mut Ctx) -> Result<u16, EncodeError> {
+        context.insert_bootstrap_method(self)
+    }
+}
+
+pub struct BootstrapMethodInserter<M, A> {
+    method_ref: M,
+    arguments: A,
+}
+
+impl<M: Insertable<MethodHandle>, A: BootstrapArguments> InsertableBootstrapMethod for BootstrapMethodInserter<M, A> {
+    fn insert<Ctx: EncoderContext>(self, context: &mut Ctx) -> Result<u16, EncodeError> {
+        let method_ref = self.method_ref.insert(context)?;
+        let arguments = self.arguments.insert(context)?;
+        context.insert_bootstrap_method(BootstrapMethod { method_ref, arguments })
+    }
+}
+
+impl<M, A> fmt::Debug for BootstrapMethodInserter<M, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BootstrapMethodInserter").finish()
+    }
+}
+
+/// The static arguments of a bootstrap method.
+pub trait BootstrapArguments {
+    fn insert<Ctx: EncoderContext>(self, context: &mut Ctx) -> Result<Vec<Index<Item>>, EncodeError>;
+}
+
+impl<I: Insertable<Item>> BootstrapArguments for Vec<I> {
+    fn insert<Ctx: EncoderContext>(self, context: &mut Ctx) -> Result<Vec<Index<Item>>, EncodeError> {
+        self.into_iter().map(|argument| argument.insert(context)).collect()
+    }
+}
+
+macro_rules! impl_bootstrap_arguments {
+    ($(($($name:ident),*);)*) => {
+        $(
+            impl<$($name: Insertable<Item>),*> BootstrapArguments for ($($name,)*) {
+                #[allow(non_snake_case, unused_variables, reason = "the type parameters are reused as bindings")]
+                fn insert<Ctx: EncoderContext>(self, context: &mut Ctx) -> Result<Vec<Index<Item>>, EncodeError> {
+                    let ($($name,)*) = self;
+                    Ok(vec![$($name.insert(context)?),*])
+                }
+            }
+        )*
+    }
+}
+
+impl_bootstrap_arguments! {
+    ();
+    (A);
+    (A, B);
+    (A, B, C);
+    (A, B, C, D);
+    (A, B, C, D, E);
+    (A, B, C, D, E, F);
+    (A, B, C, D, E, F, G);
+    (A, B, C, D, E, F, G, H);
+}
+
 pub struct ModuleInserter<I> {
     name: I,
 }
@@ -932,3 +1074,28 @@ macro_rules! ref_inserter {
 ref_inserter!(FieldRefInserter, FieldRef);
 ref_inserter!(MethodRefInserter, MethodRef);
 ref_inserter!(InterfaceMethodRefInserter, InterfaceMethodRef);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn equal_items_are_inserted_once() {
+        let mut pool = ConstantPool::new();
+        let mut encoder = VecEncoder::new(Vec::new());
+        let utf8 = |content: &str| Utf8 {
+            content: MString::from(content),
+        };
+
+        let first = pool.insert(utf8("a"), &mut encoder).unwrap();
+        let long = pool.insert(Long { value: 5 }, &mut encoder).unwrap();
+        let second = pool.insert(utf8("a"), &mut encoder).unwrap();
+        let other = pool.insert(utf8("b"), &mut encoder).unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(long.as_u16(), 2);
+        assert_eq!(other.as_u16(), 4);
+        assert_eq!(pool.len(), 5);
+        assert_eq!(encoder.inner(), b"\x01\x00\x01a\x05\0\0\0\0\0\0\0\x05\x01\x00\x01b");
+    }
+}
@@ -1,4 +1,5 @@
 use crate::error::*;
+use crate::Opcode;
 use std::fmt;
 use std::marker::PhantomData;
 
@@ -65,6 +66,13 @@ impl Encode for f64 {
     }
 }
 
+impl Encode for Opcode {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        encoder.write(self.as_u8())?;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Offset(usize);
 
@@ -117,6 +125,10 @@ impl VecEncoder {
             buf: &mut self.buf[at.0..],
         }
     }
+
+    pub(crate) fn truncate(&mut self, at: Offset) {
+        self.buf.truncate(at.0);
+    }
 }
 
 impl Encoder for VecEncoder {
@@ -177,9 +189,15 @@ pub trait InternalEncoderContext {
     fn encoder(&mut self) -> &mut VecEncoder;
 
     fn insert_constant<I: Into<cpool::Item>>(&mut self, item: I) -> Result<cpool::Index<I>, EncodeError>;
+
+    /// Adds a bootstrap method to the table of the class, returning its index in the table.
+    fn insert_bootstrap_method(&mut self, method: cpool::BootstrapMethod) -> Result<u16, EncodeError>;
+
+    /// Returns the constant pool item at the given index, if there is one.
+    fn get_constant(&self, index: u16) -> Option<&cpool::Item>;
 }
 
-impl<'a, Ctx: InternalEncoderContext> InternalEncoderContext for &'a mut Ctx {
+impl<Ctx: InternalEncoderContext> InternalEncoderContext for &mut Ctx {
     fn encoder(&mut self) -> &mut VecEncoder {
         (**self).encoder()
     }
@@ -187,6 +205,14 @@ impl<'a, Ctx: InternalEncoderContext> InternalEncoderContext for &'a mut Ctx {
     fn insert_constant<I: Into<cpool::Item>>(&mut self, item: I) -> Result<cpool::Index<I>, EncodeError> {
         (**self).insert_constant(item)
     }
+
+    fn insert_bootstrap_method(&mut self, method: cpool::BootstrapMethod) -> Result<u16, EncodeError> {
+        (**self).insert_bootstrap_method(method)
+    }
+
+    fn get_constant(&self, index: u16) -> Option<&cpool::Item> {
+        (**self).get_constant(index)
+    }
 }
 
 pub trait EncoderContext: InternalEncoderContext {}
@@ -292,7 +318,7 @@ macro_rules! impl_counter {
             }
 
             fn check(self) -> Result<(), EncodeError> {
-                if self == $v::max_value() {
+                if self == $v::MAX {
                     Err(EncodeError::with_context(
                         EncodeErrorKind::TooManyItems,
                         Context::None,
@@ -323,7 +349,7 @@ impl_counter!(u16);
 
 macro_rules! enc_state {
     ($vis:vis mod $mod:ident : $($state:ident),* $(,)?) => {
-        #[allow(non_snake_case)]
+        #[allow(non_snake_case, reason = "state modules are named like types")]
         #[doc(hidden)]
         $vis mod $mod {
             pub trait State: sealed::Sealed {}
@@ -344,5 +370,5 @@ macro_rules! enc_state {
     };
 }
 
-#[allow(unused_imports)]
+#[allow(unused_imports, reason = "macro re-export")]
 pub(crate) use enc_state;